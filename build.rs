@@ -1,3 +1,11 @@
 fn main() {
     built::write_built_file().expect("Failed to acquire build-time information");
+
+    // The commands are generated from spec.json, keep its version around so we can tell
+    // when the server runs a different one.
+    let spec: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string("spec.json").expect("Failed to read spec.json"))
+            .expect("Failed to parse spec.json");
+    let version = spec["info"]["version"].as_str().unwrap_or_default();
+    println!("cargo:rustc-env=OXIDE_SPEC_VERSION={}", version);
 }
@@ -1,7 +1,10 @@
 use std::collections::BTreeMap;
 
 use anyhow::Result;
-use inflector::cases::{kebabcase::to_kebab_case, titlecase::to_title_case};
+use inflector::{
+    cases::{kebabcase::to_kebab_case, pascalcase::to_pascal_case, titlecase::to_title_case},
+    string::{pluralize::to_plural, singularize::to_singular},
+};
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 use serde::Deserialize;
@@ -13,9 +16,22 @@ use syn::ItemEnum;
 struct Params {
     /// The name of the tag that the commands are grouped buy.
     tag: String,
+    /// The operations to generate a thin command for, besides create, view, edit, list,
+    /// and delete. See `get_extra_command_name` for how they are named.
+    #[serde(default)]
+    extra: Vec<String>,
 }
 
 pub fn do_gen(attr: TokenStream, item: TokenStream) -> Result<TokenStream> {
+    // Lets get the Open API spec.
+    let api = load_api_spec()?;
+
+    do_gen_with_spec(attr, item, &api)
+}
+
+/// Like `do_gen`, but for the given spec instead of the one the CLI is built with, so the
+/// tests can generate commands for operations the spec does not have yet.
+pub fn do_gen_with_spec(attr: TokenStream, item: TokenStream, api: &openapiv3::OpenAPI) -> Result<TokenStream> {
     // Get the data from the parameters.
     let mut params = from_tokenstream::<Params>(&attr)?;
 
@@ -23,20 +39,31 @@ pub fn do_gen(attr: TokenStream, item: TokenStream) -> Result<TokenStream> {
         params.tag = params.tag.trim_end_matches(":global").to_string();
     }
 
-    // Lets get the Open API spec.
-    let api = load_api_spec()?;
+    let ops = get_operations_with_tag(api, &params.tag)?;
 
-    let ops = get_operations_with_tag(&api, &params.tag)?;
+    // The list operation is used to prompt for a resource when the name was not passed.
+    let list_op = ops.iter().find(|op| op.is_root_list_operation(&params.tag));
+    // The view operation is used to read a resource again when an edit of it conflicts.
+    let view_op = ops
+        .iter()
+        .find(|op| op.is_root_level_operation(&params.tag) && op.method == "GET");
 
     let og_enum: ItemEnum = syn::parse2(item).unwrap();
     let mut variants = og_enum.variants.clone();
     let mut commands = quote!();
 
+    // A command the enum already has is written by hand, so we don't generate it.
+    let has_variant = |name: &str| og_enum.variants.iter().any(|v| v.ident == name);
+
     // Let's iterate over the paths and generate the code.
-    for op in ops {
+    for op in &ops {
         // Let's generate the delete command if it exists.
         if op.is_root_level_operation(&params.tag) && op.method == "DELETE" {
-            let (delete_cmd, delete_enum_item) = op.generate_delete_command(&params.tag)?;
+            if has_variant("Delete") {
+                continue;
+            }
+
+            let (delete_cmd, delete_enum_item) = op.generate_delete_command(&params.tag, list_op)?;
 
             commands = quote! {
                 #commands
@@ -48,7 +75,11 @@ pub fn do_gen(attr: TokenStream, item: TokenStream) -> Result<TokenStream> {
             // the variants on the end.
             variants.push(delete_enum_item);
         } else if op.is_root_level_operation(&params.tag) && op.method == "GET" {
-            let (view_cmd, view_enum_item) = op.generate_view_command(&params.tag)?;
+            if has_variant("View") {
+                continue;
+            }
+
+            let (view_cmd, view_enum_item) = op.generate_view_command(&params.tag, list_op)?;
 
             commands = quote! {
                 #commands
@@ -60,7 +91,21 @@ pub fn do_gen(attr: TokenStream, item: TokenStream) -> Result<TokenStream> {
             // the variants on the end.
             variants.push(view_enum_item);
         } else if op.is_root_level_operation(&params.tag) && op.method == "PUT" {
-            let (edit_cmd, edit_enum_item) = op.generate_edit_command(&params.tag)?;
+            if has_variant("Edit") {
+                continue;
+            }
+
+            if op.has_non_object_request_body()? {
+                let warning = op.get_non_object_request_body_warning(&params.tag, "edit");
+                commands = quote! {
+                    #commands
+
+                    #warning
+                };
+                continue;
+            }
+
+            let (edit_cmd, edit_enum_item) = op.generate_edit_command(&params.tag, list_op, view_op)?;
 
             commands = quote! {
                 #commands
@@ -72,6 +117,20 @@ pub fn do_gen(attr: TokenStream, item: TokenStream) -> Result<TokenStream> {
             // the variants on the end.
             variants.push(edit_enum_item);
         } else if op.is_root_create_operation(&params.tag) {
+            if has_variant("Create") {
+                continue;
+            }
+
+            if op.has_non_object_request_body()? {
+                let warning = op.get_non_object_request_body_warning(&params.tag, "create");
+                commands = quote! {
+                    #commands
+
+                    #warning
+                };
+                continue;
+            }
+
             let (create_cmd, create_enum_item) = op.generate_create_command(&params.tag)?;
 
             commands = quote! {
@@ -84,6 +143,10 @@ pub fn do_gen(attr: TokenStream, item: TokenStream) -> Result<TokenStream> {
             // the variants on the end.
             variants.push(create_enum_item);
         } else if op.is_root_list_operation(&params.tag) {
+            if has_variant("List") {
+                continue;
+            }
+
             let (list_cmd, list_enum_item) = op.generate_list_command(&params.tag)?;
 
             commands = quote! {
@@ -98,6 +161,42 @@ pub fn do_gen(attr: TokenStream, item: TokenStream) -> Result<TokenStream> {
         }
     }
 
+    // Other operations are only generated when they are named in `extra`.
+    for name in &params.extra {
+        let command = get_extra_command_name(name, &params.tag)?;
+        if has_variant(&to_pascal_case(&command)) {
+            continue;
+        }
+
+        let op = match ops
+            .iter()
+            .find(|op| op.id == *name || op.id.ends_with(&format!("_{}", name)))
+        {
+            Some(op) => op,
+            None => anyhow::bail!("no operation `{}` with the tag `{}` in the API spec", name, params.tag),
+        };
+
+        if op.is_paginated() || op.op.request_body.is_some() || !op.has_response_body() {
+            let warning = op.get_extra_not_generated_warning(&params.tag, &command);
+            commands = quote! {
+                #commands
+
+                #warning
+            };
+            continue;
+        }
+
+        let (extra_cmd, extra_enum_item) = op.generate_extra_command(&params.tag, &command, list_op)?;
+
+        commands = quote! {
+            #commands
+
+            #extra_cmd
+        };
+
+        variants.push(extra_enum_item);
+    }
+
     let attrs = og_enum.attrs;
     let code = quote!(
         use num_traits::identities::Zero;
@@ -113,6 +212,20 @@ pub fn do_gen(attr: TokenStream, item: TokenStream) -> Result<TokenStream> {
     Ok(code)
 }
 
+/// A warning for the code the macro is used in. A proc macro cannot emit one itself, but
+/// using a deprecated item makes the compiler print its note.
+fn compile_warning(name: &str, message: &str) -> TokenStream {
+    let ident = format_ident!("{}", name);
+    quote! {
+        const _: () = {
+            #[deprecated(note = #message)]
+            #[allow(non_upper_case_globals)]
+            const #ident: () = ();
+            #ident
+        };
+    }
+}
+
 /// Get the OpenAPI spec from the file.
 fn load_api_spec() -> Result<openapiv3::OpenAPI> {
     let s = include_str!("../../spec.json");
@@ -312,6 +425,7 @@ trait SchemaExt {
 
 impl SchemaExt for openapiv3::Schema {
     // If there is an allOf with only one item, we can just return that.
+    // With more than one, they are objects to be merged into one.
     fn recurse(&self) -> Result<openapiv3::Schema> {
         if let openapiv3::SchemaKind::AllOf { all_of } = &self.schema_kind {
             if all_of.len() == 1 {
@@ -324,6 +438,37 @@ impl SchemaExt for openapiv3::Schema {
 
                 return Ok(r);
             }
+
+            let mut object = openapiv3::ObjectType::default();
+            for member in all_of {
+                let member = match member {
+                    openapiv3::ReferenceOr::Item(i) => i.recurse()?,
+                    openapiv3::ReferenceOr::Reference { reference: _ } => {
+                        member.get_schema_from_reference(true)?.recurse()?
+                    }
+                };
+
+                match member.schema_kind {
+                    openapiv3::SchemaKind::Type(openapiv3::Type::Object(o)) => {
+                        // A property of a later member overrides the same one of an earlier member.
+                        object.properties.extend(o.properties);
+                        for required in o.required {
+                            if !object.required.contains(&required) {
+                                object.required.push(required);
+                            }
+                        }
+                    }
+                    _ => anyhow::bail!(
+                        "allOf with more than one member is only supported for objects: {:#?}",
+                        all_of
+                    ),
+                }
+            }
+
+            return Ok(openapiv3::Schema {
+                schema_data: self.schema_data.clone(),
+                schema_kind: openapiv3::SchemaKind::Type(openapiv3::Type::Object(object)),
+            });
         }
 
         Ok(self.clone())
@@ -574,7 +719,6 @@ impl SchemaExt for openapiv3::Parameter {
 struct Operation {
     op: openapiv3::Operation,
     method: String,
-    #[allow(dead_code)]
     path: String,
     id: String,
 }
@@ -591,6 +735,16 @@ struct Parameter {
     required: bool,
 }
 
+/// The parts of a delete command that `--all` adds, see `Operation::get_bulk_delete`.
+/// They are all empty for the resources that can only be deleted one at a time.
+#[derive(Default)]
+struct BulkDelete {
+    fields: TokenStream,
+    checks: TokenStream,
+    run: TokenStream,
+    method: TokenStream,
+}
+
 impl Parameter {
     fn data(&self) -> Option<openapiv3::ParameterData> {
         self.parameter.data()
@@ -606,14 +760,31 @@ impl Operation {
 
     /// Returns if the given operation is a root list operation on a specific tag.
     fn is_root_list_operation(&self, tag: &str) -> bool {
-        let pagination =
-            if let Some(serde_json::value::Value::Bool(b)) = self.op.extensions.get("x-dropshot-pagination") {
-                *b
-            } else {
-                return false;
-            };
+        self.id.ends_with(&format!("{}_{}", tag, self.method.to_lowercase()))
+            && self.is_paginated()
+            && self.method == "GET"
+    }
 
-        self.id.ends_with(&format!("{}_{}", tag, self.method.to_lowercase())) && pagination && self.method == "GET"
+    /// Returns if the results of the operation come in pages.
+    fn is_paginated(&self) -> bool {
+        matches!(
+            self.op.extensions.get("x-dropshot-pagination"),
+            Some(serde_json::value::Value::Bool(true))
+        )
+    }
+
+    /// Returns if the operation responds with a body when it succeeds.
+    fn has_response_body(&self) -> bool {
+        self.op
+            .responses
+            .responses
+            .iter()
+            .any(|(status, response)| match (status, response) {
+                (openapiv3::StatusCode::Code(code), openapiv3::ReferenceOr::Item(response)) => {
+                    (200..300).contains(code) && !response.content.is_empty()
+                }
+                _ => false,
+            })
     }
 
     /// Returns if the given operation is a root create operation on a specific tag.
@@ -684,23 +855,23 @@ impl Operation {
         schema.reference()
     }
 
-    fn get_request_body_properties(&self) -> Result<BTreeMap<String, Property>> {
-        let mut properties = BTreeMap::new();
-
+    /// Gets the schema of the JSON request body, with the reference resolved if it is one,
+    /// and an allOf merged into a single schema.
+    fn get_request_body_schema(&self) -> Result<Option<openapiv3::Schema>> {
         let request_body = match self.op.request_body.as_ref() {
             Some(r) => r,
-            None => return Ok(properties),
+            None => return Ok(None),
         }
         .item()?;
 
         let content = match request_body.content.get("application/json") {
             Some(c) => c,
-            None => return Ok(properties),
+            None => return Ok(None),
         };
 
         let schema = match content.schema.as_ref() {
             Some(s) => s,
-            None => return Ok(properties),
+            None => return Ok(None),
         };
 
         let schema = match schema.item() {
@@ -714,6 +885,62 @@ impl Operation {
             }
         };
 
+        // A body composed with allOf is the objects it is made of merged together.
+        Ok(Some(schema.recurse()?))
+    }
+
+    /// Returns true if the operation takes a JSON request body that is not an object, like
+    /// an array. The create and edit commands take the properties of the body as flags, so
+    /// they cannot be generated for one.
+    fn has_non_object_request_body(&self) -> Result<bool> {
+        Ok(match self.get_request_body_schema()? {
+            Some(schema) => !matches!(
+                schema.schema_kind,
+                openapiv3::SchemaKind::Type(openapiv3::Type::Object(_))
+            ),
+            None => false,
+        })
+    }
+
+    /// Returns a compile time warning that the command for the operation was not generated,
+    /// since its request body is not an object.
+    fn get_non_object_request_body_warning(&self, tag: &str, command: &str) -> TokenStream {
+        compile_warning(
+            &format!("{}_{}_not_generated", singular(tag), command),
+            &format!(
+                "the request body of `{} {}` is not an object, so `oxide {} {}` was not generated",
+                self.method,
+                self.path,
+                singular(tag),
+                command
+            ),
+        )
+    }
+
+    /// Returns a compile time warning that the command for an operation named in `extra`
+    /// was not generated. Only the operations that take no request body, respond with one,
+    /// and are not paginated have a thin command.
+    fn get_extra_not_generated_warning(&self, tag: &str, command: &str) -> TokenStream {
+        compile_warning(
+            &format!("{}_{}_not_generated", singular(tag), command),
+            &format!(
+                "`{} {}` takes a request body, is paginated, or returns nothing, so `oxide {} {}` was not generated",
+                self.method,
+                self.path,
+                singular(tag),
+                to_kebab_case(command)
+            ),
+        )
+    }
+
+    fn get_request_body_properties(&self) -> Result<BTreeMap<String, Property>> {
+        let mut properties = BTreeMap::new();
+
+        let schema = match self.get_request_body_schema()? {
+            Some(schema) => schema,
+            None => return Ok(properties),
+        };
+
         let obj = match &schema.schema_kind {
             openapiv3::SchemaKind::Type(openapiv3::Type::Object(o)) => o,
             _ => return Ok(properties),
@@ -785,7 +1012,7 @@ impl Operation {
             let mut p = p.to_string();
 
             if p == "page_token" {
-                api_call_params.push(quote!(""));
+                api_call_params.push(quote!(self.page_token.as_deref().unwrap_or_default()));
                 continue;
             }
 
@@ -804,6 +1031,18 @@ impl Operation {
                 continue;
             }
 
+            if is_default_param(&p.to_string(), tag) {
+                // These are resolved into local variables, see `get_default_params`.
+                api_call_params.push(quote!(&#p));
+                continue;
+            }
+
+            if p == singular(tag) {
+                // The name of the resource might be prompted for, see `get_name_prompt`.
+                api_call_params.push(quote!(&#p));
+                continue;
+            }
+
             api_call_params.push(quote!(&self.#p));
         }
 
@@ -843,6 +1082,10 @@ impl Operation {
                     } else {
                         req_body_rendered.push(quote!(#p_og: #p_short.unwrap()));
                     }
+                } else if rendered.starts_with("Vec<") && rendered.ends_with("InstanceDiskAttachment>") {
+                    // The disks to attach come first, then the ones to create.
+                    req_body_rendered
+                        .push(quote!(#p_og: self.disks.iter().chain(self.create_disks.iter()).cloned().collect()));
                 } else if rendered.starts_with("Vec<") {
                     // We parse all Vec's as strings and so now we have to convert them back to the
                     // original type.
@@ -870,10 +1113,14 @@ impl Operation {
             let type_name = self.get_request_body_name()?;
             let type_name = format_ident!("{}", type_name);
 
+            // Any `--set` overrides are applied on top of the flags, see `get_set_param`.
             api_call_params.push(quote! {
-                &oxide_api::types::#type_name {
-                    #(#req_body_rendered),*
-                }
+                &crate::types::apply_overrides(
+                    oxide_api::types::#type_name {
+                        #(#req_body_rendered),*
+                    },
+                    &self.set,
+                )?
             });
         }
 
@@ -1006,16 +1253,51 @@ impl Operation {
             quote!(false)
         };
 
+        // The disks of an instance are passed as two repeatable flags, one to attach
+        // existing disks and one to create new ones, rather than as JSON.
+        if rendered.starts_with("Vec<") && rendered.ends_with("InstanceDiskAttachment>") {
+            return Ok(quote! {
+                /// An existing disk to attach to the instance. Can be passed more than once.
+                #[clap(long = "disk", parse(try_from_str = crate::cmd_instance::parse_disk_attach))]
+                pub disks: Vec<oxide_api::types::InstanceDiskAttachment>,
+
+                /// A blank disk to create and attach to the instance, as `name:size`, for example
+                /// `data:10` or `data:512MiB`. Sizes without a unit are in GiB. Can be passed more
+                /// than once.
+                #[clap(long = "create-disk", parse(try_from_str = crate::cmd_instance::parse_disk_create))]
+                pub create_disks: Vec<oxide_api::types::InstanceDiskAttachment>,
+            });
+        }
+
         if rendered.starts_with("Vec<") {
             type_name = quote!(Vec<String>);
         }
 
+        // Some values are validated as they are parsed, so a malformed one fails before
+        // calling the API. Route destinations and targets are passed as `kind=value`, and
+        // IP blocks must be network addresses.
+        let value_parser = if rendered.ends_with("RouteDestination>") {
+            Some(quote!(crate::cmd_route::parse_route_destination))
+        } else if rendered.ends_with("RouteTarget>") {
+            Some(quote!(crate::cmd_route::parse_route_target))
+        } else if rendered.trim_end_matches('>').ends_with("Ipv4Net") {
+            Some(quote!(crate::cmd_subnet::parse_ipv4_block))
+        } else if rendered.trim_end_matches('>').ends_with("Ipv6Net") {
+            Some(quote!(crate::cmd_subnet::parse_ipv6_block))
+        } else {
+            None
+        };
+
         let clap_line = if (self.method == "POST" || name == "sort_by")
             && !rendered.contains("Ipv6Net")
             && !rendered.contains("Ipv4Net")
         {
             // On create, we want to set default values for the parameters.
-            if rendered.starts_with("Option<") {
+            if let Some(value_parser) = value_parser {
+                quote! {
+                    #[clap(#long_flag, #short_flag parse(try_from_str = #value_parser))]
+                }
+            } else if rendered.starts_with("Option<") {
                 // A default value there is pretty much always going to be None.
                 quote! {
                     #[clap(#long_flag, #short_flag)]
@@ -1044,6 +1326,14 @@ impl Operation {
                 quote! {
                     #[clap(#long_flag, #short_flag #default)]
                 }
+            } else if rendered.ends_with("ByteCount") {
+                // Accept human friendly sizes like `512M` or `1GiB`.
+                quote! {
+                    #[clap(
+                        #long_flag, #short_flag
+                        parse(try_from_str = crate::types::parse_byte_count), default_value_t
+                    )]
+                }
             } else {
                 let default = default
                     .map(|d| d.to_string())
@@ -1054,6 +1344,10 @@ impl Operation {
                     #[clap(#long_flag, #short_flag #default)]
                 }
             }
+        } else if let Some(value_parser) = value_parser {
+            quote! {
+                #[clap(#long_flag, #short_flag required = #requiredq, parse(try_from_str = #value_parser))]
+            }
         } else {
             quote! {
                 #[clap(#long_flag, #short_flag required = #requiredq)]
@@ -1091,8 +1385,68 @@ impl Operation {
         Ok(params)
     }
 
-    /// Generate the create command.
-    fn generate_create_command(&self, tag: &str) -> Result<(TokenStream, syn::Variant)> {
+    /// Get the `--set` parameter for the commands that send a request body. The overrides
+    /// are applied to the body last, so they win over the other flags.
+    fn get_set_param(&self) -> TokenStream {
+        quote! {
+            /// Set a field of the request body, like `--set a.b.c=value`. The value is parsed as
+            /// JSON, or taken as a string if it is not valid JSON. Can be passed more than once.
+            #[clap(long)]
+            pub set: Vec<String>,
+        }
+    }
+
+    /// Gets the api call params for the `get_all` call of a list operation, sorting with
+    /// `sort_by`.
+    fn get_api_call_params_all(&self, tag: &str, sort_by: TokenStream) -> Result<Vec<TokenStream>> {
+        let mut api_call_params_all: Vec<TokenStream> = Vec::new();
+        for p in self.get_all_param_names()? {
+            if p == "limit" || p == "page_token" {
+                continue;
+            }
+
+            if p == "sort_by" {
+                api_call_params_all.push(sort_by.clone());
+                continue;
+            }
+
+            let n = clean_param_name(&p);
+            let ident = format_ident!("{}", n);
+
+            if is_default_param(&n, tag) {
+                api_call_params_all.push(quote!(&#ident));
+                continue;
+            }
+
+            api_call_params_all.push(quote!(&self.#ident));
+        }
+
+        Ok(api_call_params_all)
+    }
+
+    /// Get the ascending sort mode of a list operation, and the field of the items that
+    /// it sorts by. Lists that cannot be sorted, like roles, are in name order.
+    fn get_default_sort_mode(&self) -> Result<(TokenStream, proc_macro2::Ident)> {
+        let params = self.get_parameters()?;
+        let sort_by = match params.get("sort_by").and_then(|p| p.parameter.data()) {
+            Some(data) => data.format.schema()?.reference()?,
+            None => return Ok((quote!(), format_ident!("name"))),
+        };
+
+        let sort_mode = format_ident!("{}", sort_by);
+        if sort_by == "IdSortMode" {
+            Ok((quote!(oxide_api::types::#sort_mode::IdAscending), format_ident!("id")))
+        } else {
+            Ok((
+                quote!(oxide_api::types::#sort_mode::NameAscending),
+                format_ident!("name"),
+            ))
+        }
+    }
+
+    /// Get the name of the resource to act on, prompting with the resources from the list
+    /// operation when it was not passed and we can prompt.
+    fn get_name_prompt(&self, tag: &str, list_op: Option<&Operation>) -> Result<TokenStream> {
         let tag_ident = format_ident!("{}", tag);
         let singular_tag_str = if tag == "vpcs" {
             singular(tag).to_uppercase()
@@ -1100,103 +1454,541 @@ impl Operation {
             singular(tag)
         };
         let singular_tag_lc = format_ident!("{}", singular(tag));
-        let struct_name = format_ident!("Cmd{}Create", to_title_case(&singular(tag)));
 
-        let struct_doc = format!(
-            "Create a new {}.\n\nTo create a {} interactively, use `oxide {} create` with no arguments.",
-            singular_tag_str,
-            singular_tag_str,
-            &singular(tag)
-        );
-        let struct_inner_project_doc = format!("The project that holds the {}.", singular_tag_str);
+        let list_op = match list_op {
+            Some(list_op) => list_op,
+            None => {
+                let error_msg = format!("[{}] required", singular(tag));
+                return Ok(quote! {
+                    let #singular_tag_lc = self.#singular_tag_lc.clone();
+                    if #singular_tag_lc.is_empty() {
+                        return Err(anyhow::anyhow!(#error_msg));
+                    }
+                });
+            }
+        };
 
-        let struct_inner_name_doc = format!("The name of the {} to create.", singular_tag_str);
+        let (sort_mode, field) = list_op.get_default_sort_mode()?;
+        let api_call_params_all = list_op.get_api_call_params_all(tag, sort_mode)?;
+        let list_path = &list_op.path;
+        let error_msg = format!("[{}] required in non-interactive mode", singular(tag));
+        let empty_msg = format!("no {} found", plural(&singular_tag_str));
+        let prompt = format!("Select {}:", singular_tag_str);
 
-        let mut mutable_variables: Vec<TokenStream> = Vec::new();
-        for (p, _) in self.get_all_required_param_names_and_types()? {
-            let mut p = if p == "name" { singular(tag) } else { p };
+        Ok(quote! {
+            let mut #singular_tag_lc = self.#singular_tag_lc.clone();
+            if #singular_tag_lc.is_empty() {
+                if !ctx.io.can_prompt() {
+                    return Err(anyhow::anyhow!(#error_msg));
+                }
 
-            p = clean_param_name(&p);
+                let mut names: Vec<String> = Vec::new();
+                let results = ctx
+                    .timed("GET", #list_path, client.#tag_ident().get_all(#(#api_call_params_all),*))
+                    .await?;
+                for result in results {
+                    names.push(result.#field.to_string());
+                }
 
-            let ident = format_ident!("{}", p);
+                if names.is_empty() {
+                    return Err(anyhow::anyhow!(#empty_msg));
+                }
 
-            mutable_variables.push(quote!(
-                let mut #ident = self.#ident.clone();
-            ));
-        }
+                match dialoguer::Select::new()
+                    .with_prompt(#prompt)
+                    .items(&names)
+                    .interact()
+                {
+                    Ok(index) => #singular_tag_lc = names[index].to_string(),
+                    Err(err) => {
+                        return Err(anyhow::anyhow!("prompt failed: {}", err));
+                    }
+                }
+            }
+        })
+    }
 
-        let api_call_params = self.get_api_call_params(tag)?;
+    /// Get the `--all` and `--filter` flags of a delete command, the checks of those flags,
+    /// the code that calls the `delete_all` method with them, and that method, which
+    /// deletes every matching resource. Resources without a list operation, or that are
+    /// not listed by name, can only be deleted one at a time.
+    fn get_bulk_delete(&self, tag: &str, list_op: Option<&Operation>) -> Result<BulkDelete> {
+        let list_op = match list_op {
+            Some(list_op) => list_op,
+            None => return Ok(BulkDelete::default()),
+        };
 
-        let mut required_checks: Vec<TokenStream> = Vec::new();
-        for (p, t) in self.get_all_required_param_names_and_types()? {
-            let p = if p == "name" { singular(tag) } else { p };
+        let (sort_mode, field) = list_op.get_default_sort_mode()?;
+        if field != "name" {
+            return Ok(BulkDelete::default());
+        }
 
-            let n = clean_param_name(&p);
+        let tag_ident = format_ident!("{}", tag);
+        let api_path = &self.path;
+        let singular_tag_str = if tag == "vpcs" {
+            singular(tag).to_uppercase()
+        } else {
+            singular(tag)
+        };
+        let singular_tag_lc = format_ident!("{}", singular(tag));
+        let plural_tag_str = plural(&singular_tag_str);
 
-            if n == "ipv4_block" {
-                continue;
-            }
+        let api_call_params = self.get_api_call_params(tag)?;
+        let api_call_params_all = list_op.get_api_call_params_all(tag, sort_mode)?;
+        let list_path = &list_op.path;
 
-            let p = format_ident!("{}", n);
+        let all_doc = format!(
+            "Delete all the {} matching `--filter`, instead of a single one.",
+            plural_tag_str
+        );
+        let filter_doc = format!(
+            "Only delete the {} whose name contains a string, for example `name~prefix-`. Requires `--all`.",
+            plural_tag_str
+        );
+        let method_doc = format!("Delete all the {} matching `--filter`.", plural_tag_str);
+        let name_error_msg = format!("[{}] cannot be used with --all", singular(tag));
+        let empty_msg = format!("no {} match the filter", plural_tag_str);
+        let delete_msg = format!("Delete {{}} {}?", plural_tag_str);
+
+        // The organization and project are resolved by `run`, and handed over.
+        let mut params: Vec<TokenStream> = Vec::new();
+        let mut args: Vec<TokenStream> = Vec::new();
+        for p in ["organization", "project"] {
+            if self.is_parameter(p) && is_default_param(p, tag) {
+                let ident = format_ident!("{}", p);
+                params.push(quote!(#ident: String));
+                args.push(quote!(#ident));
+            }
+        }
 
-            let formatted = if n == singular(tag) {
-                // Format like an argument not a flag.
-                format!("[{}]", n)
-            } else {
-                let flags = get_flags(&n)?;
-                flags.format_help()
-            };
+        let fields = quote! {
+            #[doc = #all_doc]
+            #[clap(long)]
+            pub all: bool,
 
-            let error_msg = format!("{} required in non-interactive mode", formatted);
+            #[doc = #filter_doc]
+            #[clap(long, default_value = "")]
+            pub filter: String,
 
-            let is_check = t.get_is_check_fn(true)?;
+            /// Display the result of `--all` in json, jsonl, yaml, table, csv, tsv, or template format.
+            #[clap(long, short)]
+            pub format: Option<crate::types::FormatOutput>,
+        };
 
-            required_checks.push(quote!(
-                if #p.#is_check() && !ctx.io.can_prompt() {
-                    return Err(anyhow::anyhow!(#error_msg));
-                }
-            ));
-        }
+        // These come before anything is resolved, since that can take API calls.
+        let checks = quote! {
+            if !self.filter.is_empty() && !self.all {
+                return Err(anyhow::anyhow!("--filter requires --all"));
+            }
 
-        // We need to check if project is a parameter to this call.
-        let project_param = if self.is_parameter("project") && tag != "projects" {
-            quote! {
-                #[doc = #struct_inner_project_doc]
-                #[clap(long, short, required = true)]
-                pub project: String,
+            if self.all && !self.#singular_tag_lc.is_empty() {
+                return Err(anyhow::anyhow!(#name_error_msg));
             }
-        } else {
-            quote!()
         };
 
-        // We need to check if organization is a parameter to this call.
-        let organization_param = if self.is_parameter("organization") && tag != "organizations" {
-            quote! {
-                /// The organization that holds the project.
-                #[clap(long, short, required = true, env = "OXIDE_ORG")]
-                pub organization: String,
+        let run = quote! {
+            if self.all {
+                return self.delete_all(ctx, &client, #(#args),*).await;
             }
-        } else {
-            quote!()
         };
 
-        // We need to check if project is part of this call for the prompt.
-        let project_prompt = if self.is_parameter("project") && tag != "projects" {
-            quote! {
-                // If they didn't specify a project, prompt for it.
-                if project.is_empty() {
-                    let mut org_projects: Vec<String> = Vec::new();
-                    let projects = client
-                        .projects()
-                        .get_all(&organization, oxide_api::types::NameOrIdSortMode::NameAscending)
-                        .await?;
-                    for project in projects {
-                        org_projects.push(project.name.to_string());
+        let delete = quote! {
+            ctx.timed("DELETE", #api_path, client.#tag_ident().delete(#(#api_call_params),*))
+                .await
+        };
+
+        let method = quote! {
+            #[doc = #method_doc]
+            async fn delete_all(
+                &self,
+                ctx: &mut crate::context::Context,
+                client: &oxide_api::Client,
+                #(#params),*
+            ) -> anyhow::Result<()> {
+                let filter = crate::types::parse_name_filter(&self.filter)?;
+
+                let mut names: Vec<String> = Vec::new();
+                let results = ctx
+                    .timed("GET", #list_path, client.#tag_ident().get_all(#(#api_call_params_all),*))
+                    .await?;
+                for result in results {
+                    if result.name.contains(&filter) {
+                        names.push(result.name.to_string());
                     }
+                }
 
-                    // Select the project from the selected organization.
-                    match dialoguer::Select::new()
-                        .with_prompt("Select project:")
+                if names.is_empty() {
+                    return Err(anyhow::anyhow!(#empty_msg));
+                }
+
+                // Confirm deletion.
+                if !self.confirm {
+                    match dialoguer::Confirm::new()
+                        .with_prompt(format!(#delete_msg, names.len()))
+                        .interact()
+                    {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            return Ok(());
+                        }
+                        Err(err) => {
+                            return Err(anyhow::anyhow!("prompt failed: {}", err));
+                        }
+                    }
+                }
+
+                let mut result = crate::bulk::BulkResult::default();
+                for name in &names {
+                    let #singular_tag_lc = name.to_string();
+                    let deleted = #delete;
+                    match deleted {
+                        Ok(_) => result.succeeded.push(name.to_string()),
+                        Err(err) => result.failed.push((name.to_string(), err.to_string())),
+                    }
+                }
+
+                // Failures are printed even with `--quiet`.
+                if !ctx.quiet || self.format.is_some() || !result.failed.is_empty() {
+                    let format = ctx.format(&self.format, &None)?;
+                    result.write(&mut ctx.io, &format)?;
+                }
+
+                result.into_result()
+            }
+        };
+
+        Ok(BulkDelete {
+            fields,
+            checks,
+            run,
+            method,
+        })
+    }
+
+    /// Add "(deprecated)" to the first line of the help of a command whose operation is
+    /// deprecated in the spec.
+    fn mark_deprecated(&self, doc: &str) -> String {
+        if !self.op.deprecated {
+            return doc.to_string();
+        }
+
+        match doc.split_once('\n') {
+            Some((about, rest)) => format!("{} (deprecated)\n{}", about, rest),
+            None => format!("{} (deprecated)", doc),
+        }
+    }
+
+    /// Returns the hidden flag to silence the deprecation warning and the warning itself,
+    /// which is printed when the command runs. Both are empty if the operation is not
+    /// deprecated.
+    fn get_deprecation_warning(&self) -> (TokenStream, TokenStream) {
+        if !self.op.deprecated {
+            return (quote!(), quote!());
+        }
+
+        let warning = format!(
+            "`{} {}` is deprecated in the API, so this command may stop working in a future release",
+            self.method, self.path
+        );
+
+        (
+            quote! {
+                /// Do not warn that the command is deprecated.
+                #[clap(long, hide = true)]
+                pub no_deprecation_warning: bool,
+            },
+            quote! {
+                if !self.no_deprecation_warning && !ctx.quiet {
+                    let cs = ctx.io.color_scheme();
+                    writeln!(ctx.io.err_out, "{} {}", cs.warning_icon(), #warning)?;
+                }
+            },
+        )
+    }
+
+    /// Returns the example of the request body, if the spec has one.
+    fn get_request_body_example(&self) -> Result<Option<serde_json::Value>> {
+        let request_body = match self.op.request_body.as_ref() {
+            Some(r) => r,
+            None => return Ok(None),
+        }
+        .item()?;
+
+        let content = match request_body.content.get("application/json") {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+
+        if let Some(example) = &content.example {
+            return Ok(Some(example.clone()));
+        }
+
+        for example in content.examples.values() {
+            if let openapiv3::ReferenceOr::Item(openapiv3::Example { value: Some(value), .. }) = example {
+                return Ok(Some(value.clone()));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns an example of the create command, shown after its `--help`. The values come
+    /// from the example of the request body when the spec has one, then from the examples
+    /// of the schemas of the required parameters, and are made up for the rest.
+    fn get_create_example(&self, tag: &str) -> Result<String> {
+        let singular_tag_str = if tag == "vpcs" {
+            singular(tag).to_uppercase()
+        } else {
+            singular(tag)
+        };
+
+        let body_example = self.get_request_body_example()?.unwrap_or_default();
+        let example_value = |p: &str| match body_example.get(p) {
+            Some(serde_json::Value::String(s)) => Some(s.to_string()),
+            Some(v) => Some(v.to_string()),
+            None => None,
+        };
+
+        let name = example_value("name").unwrap_or_else(|| format!("my-{}", singular(tag)));
+        let mut args = vec![shell_quote(&name)];
+        for (p, v) in self.get_all_required_param_names_and_types()? {
+            if p == "name" {
+                continue;
+            }
+
+            let n = clean_param_name(&p);
+            let flags = get_flags(&n)?;
+
+            let schema_example = v
+                .get_schema_from_reference(true)
+                .ok()
+                .and_then(|s| s.schema_data.example)
+                .map(|e| match e {
+                    serde_json::Value::String(s) => s,
+                    e => e.to_string(),
+                });
+
+            let rendered = get_text(&v.render_type(true)?)?;
+            let rendered = rendered
+                .trim_start_matches("Option<")
+                .trim_start_matches("oxide_api::types::")
+                .trim_end_matches('>');
+
+            let value = example_value(&p)
+                .or(schema_example)
+                .or_else(|| match (n.as_str(), rendered) {
+                    ("organization", _) => Some("my-org".to_string()),
+                    ("project", _) => Some("my-project".to_string()),
+                    ("description", _) => Some(format!("My {}", singular_tag_str)),
+                    (_, "ByteCount") => Some("1GiB".to_string()),
+                    (_, "BlockSize") => Some("512".to_string()),
+                    (_, "InstanceCpuCount") => Some("2".to_string()),
+                    (_, "RouteDestination") => Some("ip=192.168.1.20".to_string()),
+                    (_, "RouteTarget") => Some("ip=192.168.1.1".to_string()),
+                    (_, "bool") => Some("true".to_string()),
+                    (_, "String") | (_, "Name") => Some(format!("my-{}", flags.long)),
+                    _ => None,
+                });
+
+            // Values we cannot make up are left as a placeholder.
+            let value = match value {
+                Some(value) => shell_quote(&value),
+                None => format!("<{}>", flags.long),
+            };
+
+            args.push(format!("--{} {}", flags.long, value));
+        }
+
+        // The organization command is `oxide org`.
+        let command = if tag == "organizations" {
+            "org".to_string()
+        } else {
+            singular(tag)
+        };
+
+        Ok(format!("EXAMPLE:\n    $ oxide {} create {}", command, args.join(" ")))
+    }
+
+    /// Get the organization and project for the call, falling back to the defaults from the
+    /// config when they were not passed as flags or set in the environment.
+    fn get_default_params(&self, tag: &str) -> Result<TokenStream> {
+        let mut default_params: Vec<TokenStream> = Vec::new();
+
+        for p in ["organization", "project"] {
+            if !self.is_parameter(p) || !is_default_param(p, tag) {
+                continue;
+            }
+
+            let ident = format_ident!("{}", p);
+            let resolve_fn = format_ident!("resolve_{}", p);
+            let error_msg = format!("{} required", get_flags(p)?.format_help());
+
+            default_params.push(quote!(
+                let #ident = ctx.#resolve_fn(&self.#ident)?;
+                if #ident.is_empty() {
+                    return Err(anyhow::anyhow!(#error_msg));
+                }
+            ));
+        }
+
+        Ok(quote!(#(#default_params)*))
+    }
+
+    /// Turn the organization and project for the call into names, looking them up if they
+    /// were passed as IDs, since the API paths take names. This needs the client, so it
+    /// comes after `get_default_params`.
+    fn get_name_or_id_params(&self, tag: &str, mutable: bool) -> Result<TokenStream> {
+        let mut name_or_id_params: Vec<TokenStream> = Vec::new();
+
+        for p in ["organization", "project"] {
+            if !self.is_parameter(p) || !is_default_param(p, tag) {
+                continue;
+            }
+
+            let ident = format_ident!("{}", p);
+            let name_fn = format_ident!("{}_name", p);
+
+            name_or_id_params.push(if mutable {
+                quote!(#ident = ctx.#name_fn(&client, &#ident).await?;)
+            } else {
+                quote!(let #ident = ctx.#name_fn(&client, &#ident).await?;)
+            });
+        }
+
+        Ok(quote!(#(#name_or_id_params)*))
+    }
+
+    /// Generate the create command.
+    fn generate_create_command(&self, tag: &str) -> Result<(TokenStream, syn::Variant)> {
+        let tag_ident = format_ident!("{}", tag);
+        let api_path = &self.path;
+        let singular_tag_str = if tag == "vpcs" {
+            singular(tag).to_uppercase()
+        } else {
+            singular(tag)
+        };
+        let singular_tag_lc = format_ident!("{}", singular(tag));
+        let struct_name = format_ident!("Cmd{}Create", to_title_case(&singular(tag)));
+
+        let mut struct_doc = format!(
+            "Create a new {}.\n\nTo create a {} interactively, use `oxide {} create` with no arguments.",
+            singular_tag_str,
+            singular_tag_str,
+            &singular(tag)
+        );
+        if tag == "instances" {
+            struct_doc.push_str(
+                "\n\nDisks passed with `--disk` and `--create-disk` are attached, and the IPs passed with \
+                 `--external-ips` allocated, as part of creating the instance. If any of them fails, the \
+                 instance is not created.",
+            );
+        }
+        let struct_inner_project_doc = format!("The project that holds the {}.", singular_tag_str);
+
+        let struct_inner_name_doc = format!("The name of the {} to create.", singular_tag_str);
+
+        let example = self.get_create_example(tag)?;
+
+        let mut mutable_variables: Vec<TokenStream> = Vec::new();
+        for (p, _) in self.get_all_required_param_names_and_types()? {
+            let mut p = if p == "name" { singular(tag) } else { p };
+
+            p = clean_param_name(&p);
+
+            let ident = format_ident!("{}", p);
+
+            if is_default_param(&p, tag) {
+                // Fall back to the defaults from the config, we prompt below if they are empty.
+                let resolve_fn = format_ident!("resolve_{}", p);
+                mutable_variables.push(quote!(
+                    let mut #ident = ctx.#resolve_fn(&self.#ident)?;
+                ));
+                continue;
+            }
+
+            mutable_variables.push(quote!(
+                let mut #ident = self.#ident.clone();
+            ));
+        }
+
+        let api_call_params = self.get_api_call_params(tag)?;
+        let name_or_id_params = self.get_name_or_id_params(tag, true)?;
+
+        let mut required_checks: Vec<TokenStream> = Vec::new();
+        for (p, t) in self.get_all_required_param_names_and_types()? {
+            let p = if p == "name" { singular(tag) } else { p };
+
+            let n = clean_param_name(&p);
+
+            if n == "ipv4_block" {
+                continue;
+            }
+
+            let p = format_ident!("{}", n);
+
+            let formatted = if n == singular(tag) {
+                // Format like an argument not a flag.
+                format!("[{}]", n)
+            } else {
+                let flags = get_flags(&n)?;
+                flags.format_help()
+            };
+
+            let error_msg = format!("{} required in non-interactive mode", formatted);
+
+            let is_check = t.get_is_check_fn(true)?;
+
+            required_checks.push(quote!(
+                if #p.#is_check() && !ctx.io.can_prompt() {
+                    return Err(anyhow::anyhow!(#error_msg));
+                }
+            ));
+        }
+
+        // We need to check if project is a parameter to this call.
+        let project_param = if self.is_parameter("project") && tag != "projects" {
+            quote! {
+                #[doc = #struct_inner_project_doc]
+                #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
+                pub project: String,
+            }
+        } else {
+            quote!()
+        };
+
+        // We need to check if organization is a parameter to this call.
+        let organization_param = if self.is_parameter("organization") && tag != "organizations" {
+            quote! {
+                /// The organization that holds the project.
+                #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
+                pub organization: String,
+            }
+        } else {
+            quote!()
+        };
+
+        // We need to check if project is part of this call for the prompt.
+        let project_prompt = if self.is_parameter("project") && tag != "projects" {
+            quote! {
+                // If they didn't specify a project, prompt for it.
+                if project.is_empty() {
+                    let mut org_projects: Vec<String> = Vec::new();
+                    let projects = ctx
+                        .timed(
+                            "GET",
+                            "/organizations/{organization_name}/projects",
+                            client
+                                .projects()
+                                .get_all(&organization, oxide_api::types::NameOrIdSortMode::NameAscending),
+                        )
+                        .await?;
+                    for project in projects {
+                        org_projects.push(project.name.to_string());
+                    }
+
+                    // Select the project from the selected organization.
+                    match dialoguer::Select::new()
+                        .with_prompt("Select project:")
                         .items(&org_projects)
                         .interact()
                     {
@@ -1217,9 +2009,12 @@ impl Operation {
                 // If they didn't specify an organization, prompt for it.
                 if organization.is_empty() {
                     let mut orgs: Vec<String> = Vec::new();
-                    let resp = client
-                        .organizations()
-                        .get_all(oxide_api::types::NameOrIdSortMode::NameAscending)
+                    let resp = ctx
+                        .timed(
+                            "GET",
+                            "/organizations",
+                            client.organizations().get_all(oxide_api::types::NameOrIdSortMode::NameAscending),
+                        )
                         .await?;
                     for org in resp {
                         orgs.push(org.name.to_string());
@@ -1370,11 +2165,15 @@ impl Operation {
         };
 
         let additional_struct_params = self.get_additional_struct_params(tag)?;
+        let set_param = self.get_set_param();
+
+        let struct_doc = self.mark_deprecated(&struct_doc);
+        let (deprecated_param, deprecation_warning) = self.get_deprecation_warning();
 
         let cmd = quote!(
             #[doc = #struct_doc]
             #[derive(clap::Parser, Debug, Clone)]
-            #[clap(verbatim_doc_comment)]
+            #[clap(verbatim_doc_comment, after_help = #example)]
             pub struct #struct_name {
                 #[doc = #struct_inner_name_doc]
                 #[clap(name = #singular_tag_str, required = true)]
@@ -1385,17 +2184,25 @@ impl Operation {
                 #organization_param
 
                 #(#additional_struct_params)*
+
+                #set_param
+
+                #deprecated_param
             }
 
             #[async_trait::async_trait]
             impl crate::cmd::Command for #struct_name {
                 async fn run(&self, ctx: &mut crate::context::Context) -> anyhow::Result<()> {
+                    #deprecation_warning
+
                     #(#mutable_variables)*
 
                     #(#required_checks)*
 
                     let client = ctx.api_client("")?;
 
+                    #name_or_id_params
+
                     // Prompt for various parameters if we can, and the user passed them as empty.
                     if ctx.io.can_prompt() {
                         #org_prompt
@@ -1407,15 +2214,13 @@ impl Operation {
                         #(#additional_prompts)*
                     }
 
-                    client
-                        .#tag_ident()
-                        .post(
-                            #(#api_call_params),*
-                        )
+                    ctx.timed("POST", #api_path, client.#tag_ident().post(#(#api_call_params),*))
                         .await?;
 
-                    let cs = ctx.io.color_scheme();
-                    #output
+                    if !ctx.quiet {
+                        let cs = ctx.io.color_scheme();
+                        #output
+                    }
 
                     Ok(())
                 }
@@ -1428,8 +2233,14 @@ impl Operation {
     }
 
     /// Generate the edit command.
-    fn generate_edit_command(&self, tag: &str) -> Result<(TokenStream, syn::Variant)> {
+    fn generate_edit_command(
+        &self,
+        tag: &str,
+        list_op: Option<&Operation>,
+        view_op: Option<&Operation>,
+    ) -> Result<(TokenStream, syn::Variant)> {
         let tag_ident = format_ident!("{}", tag);
+        let api_path = &self.path;
         let singular_tag_str = if tag == "vpcs" {
             singular(tag).to_uppercase()
         } else {
@@ -1442,14 +2253,21 @@ impl Operation {
         let struct_inner_project_doc = format!("The project that holds the {}.", singular_tag_str);
 
         let struct_inner_name_doc = format!("The {} to edit. Can be an ID or name.", singular_tag_str);
+        let struct_inner_no_retry_conflict_doc = format!(
+            "Fail if the {} is changed at the same time, instead of reading it again and retrying the edit.",
+            singular_tag_str
+        );
 
         let api_call_params = self.get_api_call_params(tag)?;
+        let default_params = self.get_default_params(tag)?;
+        let name_or_id_params = self.get_name_or_id_params(tag, false)?;
+        let name_prompt = self.get_name_prompt(tag, list_op)?;
 
         // We need to check if project is a parameter to this call.
         let project_param = if self.is_parameter("project") && tag != "projects" {
             quote! {
                 #[doc = #struct_inner_project_doc]
-                #[clap(long, short, required = true)]
+                #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
                 pub project: String,
             }
         } else {
@@ -1460,7 +2278,7 @@ impl Operation {
         let organization_param = if self.is_parameter("organization") && tag != "organizations" {
             quote! {
                 /// The organization that holds the project.
-                #[clap(long, short, required = true, env = "OXIDE_ORG")]
+                #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
                 pub organization: String,
             }
         } else {
@@ -1493,7 +2311,7 @@ impl Operation {
                 };
             } else {
                 check_nothing_to_edit = quote! {
-                    #check_nothing_to_edit {
+                    #check_nothing_to_edit && self.set.is_empty() {
                         return Err(anyhow::anyhow!("nothing to edit"));
                     }
                 };
@@ -1505,7 +2323,7 @@ impl Operation {
         // We need to form the output back to the client.
         let output = if self.is_parameter("organization") && self.is_parameter("project") {
             let start = quote! {
-                let full_name = format!("{}/{}", self.organization, self.project);
+                let full_name = format!("{}/{}", organization, project);
             };
             if tag != "projects" {
                 quote! {
@@ -1516,7 +2334,7 @@ impl Operation {
                             "{} Edited {} {} -> {} in {}",
                             cs.success_icon(),
                             #singular_tag_str,
-                            self.#singular_tag_lc,
+                            #singular_tag_lc,
                             self.new_name,
                             full_name
                         )?;
@@ -1526,7 +2344,7 @@ impl Operation {
                             "{} Edited {} {} in {}",
                             cs.success_icon_with_color(ansi_term::Color::Red),
                             #singular_tag_str,
-                            self.#singular_tag_lc,
+                            #singular_tag_lc,
                             full_name
                         )?;
                     }
@@ -1541,7 +2359,7 @@ impl Operation {
                             cs.success_icon(),
                             #singular_tag_str,
                             full_name,
-                            self.organization,
+                            organization,
                             self.new_name
                         )?;
                     } else {
@@ -1563,7 +2381,7 @@ impl Operation {
                         "{} Edited {} {} -> {}",
                         cs.success_icon(),
                         #singular_tag_str,
-                        self.#singular_tag_lc,
+                        #singular_tag_lc,
                         self.new_name
                     )?;
                 } else {
@@ -1572,13 +2390,32 @@ impl Operation {
                         "{} Edited {} {}",
                         cs.success_icon_with_color(ansi_term::Color::Red),
                         #singular_tag_str,
-                        self.#singular_tag_lc
+                        #singular_tag_lc
                     )?;
                 }
             }
         };
 
         let additional_struct_params = self.get_additional_struct_params(tag)?;
+        let set_param = self.get_set_param();
+
+        let struct_doc = self.mark_deprecated(&struct_doc);
+        let (deprecated_param, deprecation_warning) = self.get_deprecation_warning();
+
+        // On a conflict the resource is read again, so an edit that raced with a delete or a
+        // rename fails there instead of being retried. The PUT only sends the fields that were
+        // passed, so sending it again applies the same changes on top of the new state.
+        let reread = match view_op {
+            Some(view_op) => {
+                let view_path = &view_op.path;
+                let view_call_params = view_op.get_api_call_params(tag)?;
+                quote! {
+                    ctx.timed("GET", #view_path, client.#tag_ident().get(#(#view_call_params),*))
+                        .await?;
+                }
+            }
+            None => quote!(),
+        };
 
         let cmd = quote!(
             #[doc = #struct_doc]
@@ -1586,7 +2423,7 @@ impl Operation {
             #[clap(verbatim_doc_comment)]
             pub struct #struct_name {
                 #[doc = #struct_inner_name_doc]
-                #[clap(name = #singular_tag_str, required = true)]
+                #[clap(name = #singular_tag_str, default_value = "")]
                 pub #singular_tag_lc: String,
 
                 #project_param
@@ -1594,25 +2431,60 @@ impl Operation {
                 #organization_param
 
                 #(#additional_struct_params)*
+
+                #set_param
+
+                #[doc = #struct_inner_no_retry_conflict_doc]
+                #[clap(long)]
+                pub no_retry_conflict: bool,
+
+                #deprecated_param
             }
 
             #[async_trait::async_trait]
             impl crate::cmd::Command for #struct_name {
                 async fn run(&self, ctx: &mut crate::context::Context) -> anyhow::Result<()> {
+                    #deprecation_warning
+
                     #check_nothing_to_edit
 
+                    #default_params
+
                     let client = ctx.api_client("")?;
 
-                    let mut name = self.#singular_tag_lc.clone();
+                    #name_or_id_params
+
+                    #name_prompt
+
+                    let mut name = #singular_tag_lc.clone();
 
                     if !self.new_name.is_empty() {
                         name = self.new_name.to_string();
                     }
 
-                    let result = client.#tag_ident().put(#(#api_call_params),*).await?;
+                    let mut conflicts = 0;
+                    let result = loop {
+                        let result = ctx
+                            .timed("PUT", #api_path, client.#tag_ident().put(#(#api_call_params),*))
+                            .await;
+                        match result {
+                            Err(err)
+                                if !self.no_retry_conflict
+                                    && conflicts < crate::context::CONFLICT_RETRIES
+                                    && crate::context::is_conflict(&err) =>
+                            {
+                                conflicts += 1
+                            }
+                            result => break result?,
+                        }
 
-                    let cs = ctx.io.color_scheme();
-                    #output
+                        #reread
+                    };
+
+                    if !ctx.quiet {
+                        let cs = ctx.io.color_scheme();
+                        #output
+                    }
 
                     Ok(())
                 }
@@ -1627,8 +2499,9 @@ impl Operation {
     }
 
     /// Generate the view command.
-    fn generate_view_command(&self, tag: &str) -> Result<(TokenStream, syn::Variant)> {
+    fn generate_view_command(&self, tag: &str, list_op: Option<&Operation>) -> Result<(TokenStream, syn::Variant)> {
         let tag_ident = format_ident!("{}", tag);
+        let api_path = &self.path;
         let singular_tag_str = if tag == "vpcs" {
             singular(tag).to_uppercase()
         } else {
@@ -1647,12 +2520,44 @@ impl Operation {
         let struct_inner_name_doc = format!("The {} to view. Can be an ID or name.", singular_tag_str);
 
         let api_call_params = self.get_api_call_params(tag)?;
+        let default_params = self.get_default_params(tag)?;
+        let name_or_id_params = self.get_name_or_id_params(tag, false)?;
+        let name_prompt = self.get_name_prompt(tag, list_op)?;
+
+        // Resources with a Console page are looked up first, so the page is built from
+        // their name even if they were given by ID. The others fail before any API call.
+        let (web_no_page, web_open) = match get_console_url(tag) {
+            Some(console_url) => (
+                quote!(),
+                quote! {
+                    if self.web {
+                        let url = #console_url;
+                        ctx.browser("", &url)?;
+                        return Ok(());
+                    }
+                },
+            ),
+            None => {
+                let error_msg = format!(
+                    "there are no Console pages for {}, view them without --web instead",
+                    plural(&singular_tag_str)
+                );
+                (
+                    quote! {
+                        if self.web {
+                            return Err(anyhow::anyhow!(#error_msg));
+                        }
+                    },
+                    quote!(),
+                )
+            }
+        };
 
         // We need to check if project is a parameter to this call.
         let project_param = if self.is_parameter("project") && tag != "projects" {
             quote! {
                 #[doc = #struct_inner_project_doc]
-                #[clap(long, short, required = true)]
+                #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
                 pub project: String,
             }
         } else {
@@ -1663,7 +2568,7 @@ impl Operation {
         let organization_param = if self.is_parameter("organization") && tag != "organizations" {
             quote! {
                 /// The organization that holds the project.
-                #[clap(long, short, required = true, env = "OXIDE_ORG")]
+                #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
                 pub organization: String,
             }
         } else {
@@ -1672,13 +2577,16 @@ impl Operation {
 
         let additional_struct_params = self.get_additional_struct_params(tag)?;
 
+        let struct_doc = self.mark_deprecated(&struct_doc);
+        let (deprecated_param, deprecation_warning) = self.get_deprecation_warning();
+
         let cmd = quote!(
             #[doc = #struct_doc]
             #[derive(clap::Parser, Debug, Clone)]
             #[clap(verbatim_doc_comment)]
             pub struct #struct_name {
                 #[doc = #struct_inner_name_doc]
-                #[clap(name = #singular_tag_str, required = true)]
+                #[clap(name = #singular_tag_str, default_value = "")]
                 pub #singular_tag_lc: String,
 
                 #project_param
@@ -1691,32 +2599,54 @@ impl Operation {
                 #[clap(short, long)]
                 pub web: bool,
 
-                /// Display output in json, yaml, or table format.
+                /// Display output in json, jsonl, yaml, table, csv, tsv, or template format.
                 #[clap(long, short)]
                 pub format: Option<crate::types::FormatOutput>,
+
+                /// The template to render each item with, for example '{{.name}} {{.id}}'.
+                #[clap(long)]
+                pub template: Option<String>,
+
+                /// The columns to display, for example 'name,id'. Defaults to all of them.
+                #[clap(long, use_value_delimiter = true)]
+                pub columns: Vec<String>,
+
+                /// Do not print the header row of table, csv, or tsv output.
+                #[clap(long)]
+                pub no_header: bool,
+
+                /// Write the output to this file instead of standard output.
+                #[clap(long, default_value = "")]
+                pub output_file: String,
+
+                #deprecated_param
             }
 
             #[async_trait::async_trait]
             impl crate::cmd::Command for #struct_name {
                 async fn run(&self, ctx: &mut crate::context::Context) -> anyhow::Result<()> {
-                    if self.web {
-                        // TODO: figure out the right URL.
-                        let url = format!(
-                            "https://{}/{}",
-                            ctx.config.default_host()?,
-                            self.#singular_tag_lc
-                        );
+                    #deprecation_warning
 
-                        ctx.browser("", &url)?;
-                        return Ok(());
-                    }
+                    #web_no_page
+
+                    #default_params
 
                     let client = ctx.api_client("")?;
 
-                    let result = client.#tag_ident().get(#(#api_call_params),*).await?;
+                    #name_or_id_params
 
-                    let format = ctx.format(&self.format)?;
-                    ctx.io.write_output(&format, &result)?;
+                    #name_prompt
+
+                    let result = ctx
+                        .timed("GET", #api_path, client.#tag_ident().get(#(#api_call_params),*))
+                        .await?;
+
+                    #web_open
+
+                    let format = ctx.format(&self.format, &self.template)?;
+                    ctx.io.with_output_file(&self.output_file, |io| {
+                        io.write_output_with_columns(&format, &self.columns, self.no_header, &result)
+                    })?;
                     Ok(())
                 }
             }
@@ -1733,6 +2663,7 @@ impl Operation {
     /// Generate the list command.
     fn generate_list_command(&self, tag: &str) -> Result<(TokenStream, syn::Variant)> {
         let tag_ident = format_ident!("{}", tag);
+        let api_path = &self.path;
         let singular_tag_str = if tag == "vpcs" {
             singular(tag).to_uppercase()
         } else {
@@ -1744,29 +2675,41 @@ impl Operation {
         let struct_inner_project_doc = format!("The project that holds the {}.", plural(&singular_tag_str));
 
         let api_call_params = self.get_api_call_params(tag)?;
+        let default_params = self.get_default_params(tag)?;
+        let name_or_id_params = self.get_name_or_id_params(tag, false)?;
 
-        let mut api_call_params_all: Vec<TokenStream> = Vec::new();
-        for p in self.get_all_param_names()? {
-            if p == "limit" || p == "page_token" {
-                continue;
-            }
+        let api_call_params_all = self.get_api_call_params_all(tag, quote!(self.sort_by.clone()))?;
 
-            if p == "sort_by" {
-                api_call_params_all.push(quote!(self.sort_by.clone()));
-                continue;
-            }
-
-            let n = clean_param_name(&p);
+        // The endpoint of the list, for streaming the pages with `--format jsonl`.
+        let mut path_args: Vec<TokenStream> = Vec::new();
+        let path_re = regex::Regex::new(r"\{([a-z_]+)\}")?;
+        for caps in path_re.captures_iter(&self.path) {
+            let n = clean_param_name(&caps[1]);
             let ident = format_ident!("{}", n);
-
-            api_call_params_all.push(quote!(&self.#ident));
+            if is_default_param(&n, tag) {
+                path_args.push(quote!(#ident));
+            } else {
+                path_args.push(quote!(self.#ident));
+            }
         }
+        let endpoint = if path_args.is_empty() {
+            let path = &self.path;
+            quote!(#path.to_string())
+        } else {
+            let path_fmt = path_re.replace_all(&self.path, "{}").to_string();
+            quote!(format!(#path_fmt, #(#path_args),*))
+        };
+        let sort_by = if self.is_parameter("sort_by") {
+            quote!(&self.sort_by)
+        } else {
+            quote!(&())
+        };
 
         // We need to check if project is a parameter to this call.
         let project_param = if self.is_parameter("project") && tag != "projects" {
             quote! {
                 #[doc = #struct_inner_project_doc]
-                #[clap(long, short, required = true)]
+                #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
                 pub project: String,
             }
         } else {
@@ -1777,7 +2720,7 @@ impl Operation {
         let organization_param = if self.is_parameter("organization") && tag != "organizations" {
             quote! {
                 /// The organization that holds the project.
-                #[clap(long, short, required = true, env = "OXIDE_ORG")]
+                #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
                 pub organization: String,
             }
         } else {
@@ -1786,6 +2729,9 @@ impl Operation {
 
         let additional_struct_params = self.get_additional_struct_params(tag)?;
 
+        let struct_doc = self.mark_deprecated(&struct_doc);
+        let (deprecated_param, deprecation_warning) = self.get_deprecation_warning();
+
         let cmd = quote!(
             #[doc = #struct_doc]
             #[derive(clap::Parser, Debug, Clone)]
@@ -1805,38 +2751,108 @@ impl Operation {
                 #[clap(long)]
                 pub paginate: bool,
 
-                /// Display output in json, yaml, or table format.
+                /// Start at the page with this token, from the `next_page` of an earlier listing. With
+                /// `--format json`, the page is printed as the API returns it, with the token of the
+                /// page after it in `next_page`. Pass an empty token for the first page.
+                #[clap(long, conflicts_with = "paginate")]
+                pub page_token: Option<String>,
+
+                /// Display output in json, jsonl, yaml, table, csv, tsv, or template format.
                 #[clap(long, short)]
                 pub format: Option<crate::types::FormatOutput>,
+
+                /// The template to render each item with, for example '{{.name}} {{.id}}'.
+                #[clap(long)]
+                pub template: Option<String>,
+
+                /// The columns to display, for example 'name,id'. Defaults to all of them.
+                #[clap(long, use_value_delimiter = true)]
+                pub columns: Vec<String>,
+
+                /// Do not print the header row of table, csv, or tsv output.
+                #[clap(long)]
+                pub no_header: bool,
+
+                /// Sort the output by this column, for example 'time_created'. Any column works, the
+                /// items are sorted after they are fetched.
+                #[clap(long, default_value = "")]
+                pub sort: String,
+
+                /// Reverse the order of the output.
+                #[clap(long)]
+                pub reverse: bool,
+
+                /// Only list the items with a field equal to a value, as 'key=value', or containing
+                /// it, as 'key~value'. Can be passed more than once, items have to match all of them.
+                #[clap(long)]
+                pub filter: Vec<String>,
+
+                /// Write the output to this file instead of standard output.
+                #[clap(long, default_value = "")]
+                pub output_file: String,
+
+                #deprecated_param
             }
 
             #[async_trait::async_trait]
             impl crate::cmd::Command for #struct_name {
                 async fn run(&self, ctx: &mut crate::context::Context) -> anyhow::Result<()> {
+                    #deprecation_warning
+
                     if self.limit < 1 {
                     return Err(anyhow::anyhow!("--limit must be greater than 0"));
                 }
 
+                #default_params
+
                 let client = ctx.api_client("")?;
 
+                #name_or_id_params
+
+                let format = ctx.format(&self.format, &self.template)?;
+                if self.paginate
+                    && self.output_file.is_empty()
+                    && self.sort.is_empty()
+                    && !self.reverse
+                    && self.filter.is_empty()
+                    && format == crate::types::FormatOutput::Jsonl
+                {
+                    // Write each page as it comes in, rather than waiting for all of them.
+                    let endpoint = #endpoint;
+                    return crate::cmd_api::write_pages_jsonl(ctx, &client, &endpoint, self.limit, #sort_by).await;
+                }
+
+                if let Some(page_token) = &self.page_token {
+                    if format == crate::types::FormatOutput::Json {
+                        // Get the page as the API returns it, with the token of the next one.
+                        let endpoint = #endpoint;
+                        let page =
+                            crate::cmd_api::get_page(ctx, &client, &endpoint, self.limit, #sort_by, page_token).await?;
+                        let items = crate::iostreams::filter_by_fields(page.items, &self.filter)?;
+                        let items = crate::iostreams::sort_by_column(items, &self.sort, self.reverse)?;
+                        let page = crate::cmd_api::PaginatableResponse {
+                            items: crate::iostreams::select_fields(items, &self.columns)?,
+                            next_page: page.next_page,
+                        };
+                        return ctx.io.with_output_file(&self.output_file, |io| {
+                            io.write_output_json(&serde_json::to_value(&page)?)
+                        });
+                    }
+                }
+
                 let results = if self.paginate {
-                    client
-                        .#tag_ident()
-                        .get_all(
-                            #(#api_call_params_all),*
-                        )
+                    ctx.timed("GET", #api_path, client.#tag_ident().get_all(#(#api_call_params_all),*))
                         .await?
                 } else {
-                    client
-                        .#tag_ident()
-                        .get_page(
-                            #(#api_call_params),*
-                        )
+                    ctx.timed("GET", #api_path, client.#tag_ident().get_page(#(#api_call_params),*))
                         .await?
                 };
+                let results = crate::iostreams::filter_by_fields(results, &self.filter)?;
+                let results = crate::iostreams::sort_by_column(results, &self.sort, self.reverse)?;
 
-                let format = ctx.format(&self.format)?;
-                ctx.io.write_output_for_vec(&format, &results)?;
+                ctx.io.with_output_file(&self.output_file, |io| {
+                    io.write_output_for_vec_with_columns(&format, &self.columns, self.no_header, &results)
+                })?;
                 Ok(())
             }
         }
@@ -1848,8 +2864,9 @@ impl Operation {
     }
 
     /// Generate the delete command.
-    fn generate_delete_command(&self, tag: &str) -> Result<(TokenStream, syn::Variant)> {
+    fn generate_delete_command(&self, tag: &str, list_op: Option<&Operation>) -> Result<(TokenStream, syn::Variant)> {
         let tag_ident = format_ident!("{}", tag);
+        let api_path = &self.path;
         let singular_tag_str = if tag == "vpcs" {
             singular(tag).to_uppercase()
         } else {
@@ -1863,12 +2880,29 @@ impl Operation {
         let struct_inner_project_doc = format!("The project to delete the {} from.", singular_tag_str);
 
         let api_call_params = self.get_api_call_params(tag)?;
+        let default_params = self.get_default_params(tag)?;
+        let name_or_id_params = self.get_name_or_id_params(tag, false)?;
+        let name_prompt = self.get_name_prompt(tag, list_op)?;
+        let bulk_delete = self.get_bulk_delete(tag, list_op)?;
+        let bulk_delete_params = bulk_delete.fields;
+        let bulk_delete_checks = bulk_delete.checks;
+        let bulk_delete_run = bulk_delete.run;
+        let bulk_delete_method = bulk_delete.method;
+        let bulk_delete_impl = if bulk_delete_method.is_empty() {
+            quote!()
+        } else {
+            quote! {
+                impl #struct_name {
+                    #bulk_delete_method
+                }
+            }
+        };
 
         // We need to check if project is a parameter to this call.
         let project_param = if self.is_parameter("project") && tag != "projects" {
             quote! {
                 #[doc = #struct_inner_project_doc]
-                #[clap(long, short, required = true)]
+                #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
                 pub project: String,
             }
         } else {
@@ -1879,7 +2913,7 @@ impl Operation {
         let organization_param = if self.is_parameter("organization") && tag != "organizations" {
             quote! {
                 /// The organization that holds the project.
-                #[clap(long, short, required = true, env = "OXIDE_ORG")]
+                #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
                 pub organization: String,
             }
         } else {
@@ -1891,7 +2925,7 @@ impl Operation {
         // We need to form the output back to the client.
         let output = if self.is_parameter("organization") && self.is_parameter("project") {
             let start = quote! {
-                let full_name = format!("{}/{}", self.organization, self.project);
+                let full_name = format!("{}/{}", organization, project);
             };
             if tag != "projects" {
                 quote! {
@@ -1901,7 +2935,7 @@ impl Operation {
                         "{} Deleted {} {} from {}",
                         cs.success_icon_with_color(ansi_term::Color::Red),
                         #singular_tag_str,
-                        self.#singular_tag_lc,
+                        #singular_tag_lc,
                         full_name
                     )?;
                 }
@@ -1924,18 +2958,21 @@ impl Operation {
                     "{} Deleted {} {}",
                     cs.success_icon_with_color(ansi_term::Color::Red),
                     #singular_tag_str,
-                    self.#singular_tag_lc
+                    #singular_tag_lc
                 )?;
             }
         };
 
+        let struct_doc = self.mark_deprecated(&struct_doc);
+        let (deprecated_param, deprecation_warning) = self.get_deprecation_warning();
+
         let cmd = quote!(
             #[doc = #struct_doc]
             #[derive(clap::Parser, Debug, Clone)]
             #[clap(verbatim_doc_comment)]
             pub struct #struct_name {
                 #[doc = #struct_inner_name_doc]
-                #[clap(name = #singular_tag_str, required = true)]
+                #[clap(name = #singular_tag_str, default_value = "")]
                 pub #singular_tag_lc: String,
 
                 #project_param
@@ -1944,27 +2981,42 @@ impl Operation {
 
                 #(#additional_struct_params)*
 
+                #bulk_delete_params
+
                 /// Confirm deletion without prompting.
-                #[clap(long)]
+                #[clap(long, short = 'y', visible_alias = "yes", env = "OXIDE_ASSUME_YES")]
                 pub confirm: bool,
+
+                #deprecated_param
             }
 
             #[async_trait::async_trait]
             impl crate::cmd::Command for #struct_name {
                 async fn run(&self, ctx: &mut crate::context::Context) -> anyhow::Result<()> {
+                    #deprecation_warning
+
                     if !ctx.io.can_prompt() && !self.confirm {
                         return Err(anyhow::anyhow!("--confirm required when not running interactively"));
                     }
 
+                    #bulk_delete_checks
+
+                    #default_params
+
                     let client = ctx.api_client("")?;
 
+                    #name_or_id_params
+
+                    #bulk_delete_run
+
+                    #name_prompt
 
                     // Confirm deletion.
                     if !self.confirm {
                         if let Err(err) = dialoguer::Input::<String>::new()
-                            .with_prompt(format!("Type {} to confirm deletion:", self.#singular_tag_lc))
+                            .with_prompt(format!("Type {} to confirm deletion:", #singular_tag_lc))
                             .validate_with(|input: &String| -> Result<(), &str> {
-                                if input.trim() == self.#singular_tag_lc {
+                                if input.trim() == #singular_tag_lc {
                                     Ok(())
                                 } else {
                                     Err("mismatched confirmation")
@@ -1977,24 +3029,166 @@ impl Operation {
                     }
 
 
-                    client
-                        .#tag_ident()
-                        .delete(#(#api_call_params),*)
+                    ctx.timed("DELETE", #api_path, client.#tag_ident().delete(#(#api_call_params),*))
                         .await?;
 
-                    let cs = ctx.io.color_scheme();
-
-                    #output
+                    if !ctx.quiet {
+                        let cs = ctx.io.color_scheme();
+                        #output
+                    }
 
                     Ok(())
                 }
             }
+
+            #bulk_delete_impl
         );
 
         let enum_item: syn::Variant = syn::parse2(quote!(Delete(#struct_name)))?;
 
         Ok((cmd, enum_item))
     }
+
+    /// Generate a thin command for an operation named in `extra`, like `oxide instance
+    /// start` for `instance_start`. Its parameters are flags, like for the other commands,
+    /// and it prints what the operation returns.
+    fn generate_extra_command(
+        &self,
+        tag: &str,
+        command: &str,
+        list_op: Option<&Operation>,
+    ) -> Result<(TokenStream, syn::Variant)> {
+        let tag_ident = format_ident!("{}", tag);
+        let api_path = &self.path;
+        let method = &self.method;
+        let command_ident = format_ident!("{}", command);
+        let singular_tag_str = if tag == "vpcs" {
+            singular(tag).to_uppercase()
+        } else {
+            singular(tag)
+        };
+        let singular_tag_lc = format_ident!("{}", singular(tag));
+        let struct_name = format_ident!("Cmd{}{}", to_title_case(&singular(tag)), to_pascal_case(command));
+        let variant_name = format_ident!("{}", to_pascal_case(command));
+
+        let summary = self
+            .op
+            .summary
+            .as_deref()
+            .unwrap_or_else(|| self.op.description.as_deref().unwrap_or_default());
+        let struct_doc = format!(
+            "{}.\n\nThis calls `{} {}` and prints what it returns.",
+            summary.trim_end_matches('.'),
+            method,
+            api_path
+        );
+        let struct_inner_project_doc = format!("The project that holds the {}.", singular_tag_str);
+        let struct_inner_name_doc = format!(
+            "The {} to {}. Can be an ID or name.",
+            singular_tag_str,
+            command.replace('_', " ")
+        );
+
+        let api_call_params = self.get_api_call_params(tag)?;
+        let default_params = self.get_default_params(tag)?;
+        let name_or_id_params = self.get_name_or_id_params(tag, false)?;
+
+        // Operations on a single resource take its name, which is prompted for like for
+        // `view`.
+        let (name_param, name_prompt) = if self.get_parameters()?.contains_key(&format!("{}_name", singular(tag))) {
+            (
+                quote! {
+                    #[doc = #struct_inner_name_doc]
+                    #[clap(name = #singular_tag_str, default_value = "")]
+                    pub #singular_tag_lc: String,
+                },
+                self.get_name_prompt(tag, list_op)?,
+            )
+        } else {
+            (quote!(), quote!())
+        };
+
+        // We need to check if project is a parameter to this call.
+        let project_param = if self.is_parameter("project") && tag != "projects" {
+            quote! {
+                #[doc = #struct_inner_project_doc]
+                #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
+                pub project: String,
+            }
+        } else {
+            quote!()
+        };
+
+        // We need to check if organization is a parameter to this call.
+        let organization_param = if self.is_parameter("organization") && tag != "organizations" {
+            quote! {
+                /// The organization that holds the project.
+                #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
+                pub organization: String,
+            }
+        } else {
+            quote!()
+        };
+
+        let additional_struct_params = self.get_additional_struct_params(tag)?;
+
+        let struct_doc = self.mark_deprecated(&struct_doc);
+        let (deprecated_param, deprecation_warning) = self.get_deprecation_warning();
+
+        let cmd = quote!(
+            #[doc = #struct_doc]
+            #[derive(clap::Parser, Debug, Clone)]
+            #[clap(verbatim_doc_comment)]
+            pub struct #struct_name {
+                #name_param
+
+                #project_param
+
+                #organization_param
+
+                #(#additional_struct_params)*
+
+                /// Display output in json, jsonl, yaml, table, csv, tsv, or template format.
+                #[clap(long, short)]
+                pub format: Option<crate::types::FormatOutput>,
+
+                /// The template to render the result with, for example '{{.name}} {{.id}}'.
+                #[clap(long)]
+                pub template: Option<String>,
+
+                #deprecated_param
+            }
+
+            #[async_trait::async_trait]
+            impl crate::cmd::Command for #struct_name {
+                async fn run(&self, ctx: &mut crate::context::Context) -> anyhow::Result<()> {
+                    #deprecation_warning
+
+                    #default_params
+
+                    let client = ctx.api_client("")?;
+
+                    #name_or_id_params
+
+                    #name_prompt
+
+                    let result = ctx
+                        .timed(#method, #api_path, client.#tag_ident().#command_ident(#(#api_call_params),*))
+                        .await?;
+
+                    let format = ctx.format(&self.format, &self.template)?;
+                    ctx.io.write_output(&format, &result)?;
+                    Ok(())
+                }
+            }
+        );
+
+        let enum_item: syn::Variant = syn::parse2(quote!(
+                #variant_name(#struct_name)
+        ))?;
+
+        Ok((cmd, enum_item))
+    }
 }
 
 /// Get the operations with the tag from the OpenAPI spec.
@@ -2040,26 +3234,95 @@ fn get_operations_with_tag(api: &openapiv3::OpenAPI, tag: &str) -> Result<Vec<Op
     Ok(paths)
 }
 
+/// Singular and plural forms of Oxide terms that are pinned rather than left to
+/// `inflector`, since they are acronyms or jargon.
+const INFLECTIONS: &[(&str, &str)] = &[("vpc", "vpcs"), ("silo", "silos"), ("sled", "sleds"), ("saga", "sagas")];
+
+/// Return the pinned singular and plural forms of a word, if it has them.
+fn get_inflection(s: &str) -> Option<&'static (&'static str, &'static str)> {
+    INFLECTIONS
+        .iter()
+        .find(|(singular, plural)| s == *singular || s == *plural)
+}
+
 /// Return the plural version of a string.
 fn plural(s: &str) -> String {
-    let s = singular(s);
-
-    if s.ends_with('s') {
-        return format!("{}es", s);
-    } else if s.ends_with('y') {
-        return format!("{}ies", s.trim_end_matches('y'));
+    if let Some((_, plural)) = get_inflection(s) {
+        return plural.to_string();
     }
 
-    format!("{}s", s)
+    to_plural(&singular(s))
 }
 
 /// Return the singular version of a string (if it plural).
 fn singular(s: &str) -> String {
-    if let Some(b) = s.strip_suffix('s') {
-        return b.to_string();
+    if let Some((singular, _)) = get_inflection(s) {
+        return singular.to_string();
+    }
+
+    to_singular(s)
+}
+
+/// Returns the command for an operation named in `extra`. The name is the end of the
+/// operation ID, starting with the singular tag, and the rest is the command and the
+/// method of the client that is called: `instance_start` names
+/// `project_instances_instance_start`, which is `oxide instance start` and calls
+/// `client.instances().start()`. A command the enum already has is not generated.
+fn get_extra_command_name(name: &str, tag: &str) -> Result<String> {
+    match name.strip_prefix(&format!("{}_", singular(tag))) {
+        Some(command) if !command.is_empty() => Ok(command.to_string()),
+        _ => anyhow::bail!(
+            "`{}` in `extra` must be named `{}_<command>`, like `{}_start`",
+            name,
+            singular(tag),
+            singular(tag)
+        ),
     }
+}
+
+/// Returns if the parameter is an organization or project flag, which fall back to the
+/// defaults from the config.
+fn is_default_param(n: &str, tag: &str) -> bool {
+    (n == "organization" && tag != "organizations") || (n == "project" && tag != "projects")
+}
 
-    s.to_string()
+/// Quote a value for the shell when it has characters the shell would interpret.
+fn shell_quote(s: &str) -> String {
+    if !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:=@".contains(c)) {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''"))
+    }
+}
+
+/// Returns the URL of the Console page of a resource, built from the resolved organization
+/// and project and the `result` of the view call. Only organizations, projects, instances,
+/// and VPCs have a page of their own.
+fn get_console_url(tag: &str) -> Option<TokenStream> {
+    match tag {
+        "organizations" => Some(quote!(format!("{}/orgs/{}", ctx.console_url()?, result.name))),
+        "projects" => Some(quote!(format!(
+            "{}/orgs/{}/projects/{}",
+            ctx.console_url()?,
+            organization,
+            result.name
+        ))),
+        "instances" => Some(quote!(format!(
+            "{}/orgs/{}/projects/{}/instances/{}",
+            ctx.console_url()?,
+            organization,
+            project,
+            result.name
+        ))),
+        "vpcs" => Some(quote!(format!(
+            "{}/orgs/{}/projects/{}/vpcs/{}",
+            ctx.console_url()?,
+            organization,
+            project,
+            result.name
+        ))),
+        _ => None,
+    }
 }
 
 fn skip_defaults(n: &str, tag: &str) -> bool {
@@ -2163,6 +3426,9 @@ fn get_flags(name: &str) -> Result<Flags> {
     } else if name == "size" || flags.short == 'd' || flags.short == 'h' {
         // 'd' is debug, 'h' is help
         flags.short = '0';
+    } else if flags.long == "vpc" {
+        // 'v' is verbose
+        flags.short = 'V';
     } else if name == "ncpus" {
         flags.short = 'c';
     } else if flags.long == "ipv4-block" {
@@ -2173,3 +3439,211 @@ fn get_flags(name: &str) -> Result<Flags> {
 
     Ok(flags)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_singular_plural() {
+        for (one, many) in [
+            ("vpc", "vpcs"),
+            ("subnet", "subnets"),
+            ("policy", "policies"),
+            ("address", "addresses"),
+            ("gateway", "gateways"),
+            ("instance", "instances"),
+        ] {
+            assert_eq!(singular(many), one);
+            assert_eq!(singular(one), one);
+            assert_eq!(plural(one), many);
+            assert_eq!(plural(many), many);
+        }
+
+        // Display names keep their case.
+        assert_eq!(plural("VPC"), "VPCs");
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("my-instance"), "my-instance");
+        assert_eq!(shell_quote("192.168.1.0/24"), "192.168.1.0/24");
+        assert_eq!(shell_quote("My instance"), "'My instance'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn test_non_object_request_body() {
+        let op = |schema: serde_json::Value| Operation {
+            op: serde_json::from_value(serde_json::json!({
+                "requestBody": {
+                    "content": {
+                        "application/json": { "schema": schema },
+                    },
+                    "required": true,
+                },
+                "responses": {},
+            }))
+            .unwrap(),
+            method: "POST".to_string(),
+            path: "/organizations/{organization_name}/projects/{project_name}/disks".to_string(),
+            id: "disk_create".to_string(),
+        };
+
+        let array = op(serde_json::json!({
+            "type": "array",
+            "items": { "$ref": "#/components/schemas/DiskCreate" },
+        }));
+        assert!(array.has_non_object_request_body().unwrap());
+        assert!(array.get_request_body_properties().unwrap().is_empty());
+        let warning = array.get_non_object_request_body_warning("disks", "create").to_string();
+        assert!(warning.contains("deprecated"));
+        assert!(warning.contains("disk_create_not_generated"));
+        assert!(warning.contains("so `oxide disk create` was not generated"));
+
+        let object = op(serde_json::json!({ "$ref": "#/components/schemas/DiskCreate" }));
+        assert!(!object.has_non_object_request_body().unwrap());
+        assert!(object.get_request_body_properties().unwrap().contains_key("size"));
+
+        let none = Operation {
+            op: openapiv3::Operation::default(),
+            method: "POST".to_string(),
+            path: "/organizations".to_string(),
+            id: "organization_create".to_string(),
+        };
+        assert!(!none.has_non_object_request_body().unwrap());
+    }
+
+    #[test]
+    fn test_all_of_request_body() {
+        let op = Operation {
+            op: serde_json::from_value(serde_json::json!({
+                "requestBody": {
+                    "content": {
+                        "application/json": {
+                            "schema": {
+                                "allOf": [
+                                    { "$ref": "#/components/schemas/DiskCreate" },
+                                    {
+                                        "type": "object",
+                                        "properties": {
+                                            "labels": { "type": "array", "items": { "type": "string" } },
+                                        },
+                                        "required": ["labels"],
+                                    },
+                                ],
+                            },
+                        },
+                    },
+                    "required": true,
+                },
+                "responses": {},
+            }))
+            .unwrap(),
+            method: "POST".to_string(),
+            path: "/organizations/{organization_name}/projects/{project_name}/disks".to_string(),
+            id: "disk_create".to_string(),
+        };
+
+        assert!(!op.has_non_object_request_body().unwrap());
+        let properties = op.get_request_body_properties().unwrap();
+        assert_eq!(
+            properties.keys().collect::<Vec<_>>(),
+            vec!["description", "disk_source", "labels", "name", "size"]
+        );
+        assert!(properties["labels"].required);
+        assert!(properties["size"].required);
+
+        // Only objects can be merged.
+        let schema: openapiv3::Schema = serde_json::from_value(serde_json::json!({
+            "allOf": [
+                { "$ref": "#/components/schemas/DiskCreate" },
+                { "type": "string" },
+            ],
+        }))
+        .unwrap();
+        assert!(schema.recurse().is_err());
+    }
+
+    #[test]
+    fn test_extra_operations() {
+        let item = quote! {
+            #[derive(Parser, Debug, Clone)]
+            enum SubCommand {
+                Create(CmdInstanceCreate),
+                Delete(CmdInstanceDelete),
+                List(CmdInstanceList),
+                Stop(CmdInstanceStop),
+                View(CmdInstanceView),
+            }
+        };
+
+        let code = do_gen(
+            quote! {
+                tag = "instances",
+                extra = ["instance_start", "instance_stop", "instance_disks_get"],
+            },
+            item.clone(),
+        )
+        .unwrap();
+        // rustfmt leaves the call to `start` alone since its path is too long to fit, so
+        // it is looked for without spaces.
+        let call = get_text(&code).unwrap();
+        let code = get_text_fmt(&code).unwrap();
+
+        // `start` is generated, `stop` is written by hand.
+        assert!(code.contains("Start(CmdInstanceStart)"));
+        assert!(code.contains("pub struct CmdInstanceStart {"));
+        assert!(call.contains(".start(&instance,&organization,&project)"));
+        assert!(!code.contains("pub struct CmdInstanceStop {"));
+
+        // `disks_get` is paginated.
+        assert!(!code.contains("CmdInstanceDisksGet"));
+        assert!(code.contains("instance_disks_get_not_generated"));
+
+        let err = do_gen(
+            quote! {
+                tag = "instances",
+                extra = ["instance_teleport"],
+            },
+            item.clone(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("no operation `instance_teleport`"));
+
+        let err = do_gen(
+            quote! {
+                tag = "instances",
+                extra = ["start"],
+            },
+            item,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("must be named `instance_<command>`"));
+    }
+
+    #[test]
+    fn test_deprecated_operation() {
+        let mut op = Operation {
+            op: openapiv3::Operation::default(),
+            method: "GET".to_string(),
+            path: "/organizations".to_string(),
+            id: "organization_list".to_string(),
+        };
+
+        assert_eq!(op.mark_deprecated("List organizations."), "List organizations.");
+        let (param, warning) = op.get_deprecation_warning();
+        assert!(param.is_empty());
+        assert!(warning.is_empty());
+
+        op.op.deprecated = true;
+        assert_eq!(
+            op.mark_deprecated("List organizations.\n\nMore help."),
+            "List organizations. (deprecated)\n\nMore help."
+        );
+        let (param, warning) = op.get_deprecation_warning();
+        assert!(param.to_string().contains("no_deprecation_warning"));
+        assert!(warning.to_string().contains("`GET /organizations` is deprecated"));
+    }
+}
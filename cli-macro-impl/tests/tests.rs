@@ -1,4 +1,4 @@
-use cli_macro_impl::{do_gen, get_text_fmt};
+use cli_macro_impl::{do_gen, do_gen_with_spec, get_text_fmt};
 use quote::quote;
 
 #[test]
@@ -138,3 +138,51 @@ fn test_do_gen() {
 
     expectorate::assert_contents("tests/gen/images_global.rs.gen", &get_text_fmt(&actual).unwrap());
 }
+
+#[test]
+fn test_do_gen_deprecated() {
+    // The spec has no deprecated operations yet, so mark one.
+    let mut api: openapiv3::OpenAPI = serde_json::from_str(include_str!("../../spec.json")).unwrap();
+    match api.paths.paths.get_mut("/hardware/sleds/{sled_id}") {
+        Some(openapiv3::ReferenceOr::Item(item)) => item.get.as_mut().unwrap().deprecated = true,
+        _ => panic!("no sled view operation"),
+    }
+
+    let actual = do_gen_with_spec(
+        quote! {
+            tag = "sleds",
+        },
+        quote! {
+            #[derive(Parser, Debug, Clone)]
+            enum SubCommand {}
+        },
+        &api,
+    )
+    .unwrap();
+
+    expectorate::assert_contents("tests/gen/sleds-deprecated.rs.gen", &get_text_fmt(&actual).unwrap());
+}
+
+#[test]
+fn test_do_gen_hand_written() {
+    // The commands the enum already has are written by hand, so they are not generated.
+    let actual = do_gen(
+        quote! {
+            tag = "snapshots",
+        },
+        quote! {
+            #[derive(Parser, Debug, Clone)]
+            enum SubCommand {
+                Create(CmdSnapshotCreate),
+                Restore(CmdSnapshotRestore),
+            }
+        },
+    )
+    .unwrap();
+
+    let actual = get_text_fmt(&actual).unwrap();
+    assert!(!actual.contains("pub struct CmdSnapshotCreate"));
+    assert_eq!(actual.matches("Create(CmdSnapshotCreate)").count(), 1);
+
+    expectorate::assert_contents("tests/gen/snapshots.rs.gen", &actual);
+}
@@ -1,5 +1,20 @@
 extern crate proc_macro;
 
+/// Generate the create, view, edit, list, and delete commands of a tag of the API, for the
+/// commands the enum does not already have.
+///
+/// Other operations of the tag get a thin command when they are named in `extra`, by the
+/// end of their operation ID starting with the singular tag:
+///
+/// ```ignore
+/// #[crud_gen {
+///     tag = "instances",
+///     extra = ["instance_start", "instance_stop"],
+/// }]
+/// ```
+///
+/// generates `oxide instance start` and `oxide instance stop`, which call
+/// `client.instances().start()` and `client.instances().stop()`.
 #[proc_macro_attribute]
 pub fn crud_gen(attr: proc_macro::TokenStream, item: proc_macro::TokenStream) -> proc_macro::TokenStream {
     cli_macro_impl::do_gen(attr.into(), item.into()).unwrap().into()
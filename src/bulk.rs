@@ -0,0 +1,117 @@
+use anyhow::Result;
+
+use crate::types::FormatOutput;
+
+/// The outcome of an operation on several items, like `delete --all`. Commands collect
+/// it as they go and print it once all the items are done, so every bulk command reports
+/// the same way.
+#[derive(serde::Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct BulkResult {
+    /// The names of the items the operation succeeded for.
+    pub succeeded: Vec<String>,
+    /// The names of the items the operation failed for, with the error.
+    pub failed: Vec<(String, String)>,
+}
+
+/// A row of the table a `BulkResult` is printed as.
+#[derive(serde::Serialize, tabled::Tabled)]
+struct BulkRow {
+    name: String,
+    status: String,
+    error: String,
+}
+
+/// Returned by a command when the operation failed for some of the items of a
+/// `BulkResult`, so `oxide` exits with a non-zero code. The failures themselves are
+/// in the printed result.
+#[derive(Debug)]
+pub struct BulkError {
+    pub failed: usize,
+    pub total: usize,
+}
+
+impl std::fmt::Display for BulkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "failed for {} of {} items", self.failed, self.total)
+    }
+}
+
+impl std::error::Error for BulkError {}
+
+impl BulkResult {
+    fn rows(&self) -> Vec<BulkRow> {
+        let succeeded = self.succeeded.iter().map(|name| BulkRow {
+            name: name.to_string(),
+            status: "succeeded".to_string(),
+            error: String::new(),
+        });
+        let failed = self.failed.iter().map(|(name, error)| BulkRow {
+            name: name.to_string(),
+            status: "failed".to_string(),
+            error: error.to_string(),
+        });
+
+        succeeded.chain(failed).collect()
+    }
+
+    /// Print the result, as a table with a row per item or, for the formats that are
+    /// not tabular, as the `BulkResult` itself.
+    pub fn write(&self, io: &mut crate::iostreams::IoStreams, format: &FormatOutput) -> Result<()> {
+        match format {
+            FormatOutput::Table => io.write_output_table_for_vec(self.rows()),
+            FormatOutput::Json => io.write_output_json(&serde_json::to_value(self)?),
+            FormatOutput::Jsonl => io.write_output_jsonl(&serde_json::to_value(self)?),
+            FormatOutput::Yaml => io.write_output_yaml(self),
+            FormatOutput::Csv => io.write_output_csv_for_vec(&serde_json::to_value(self.rows())?, b','),
+            FormatOutput::Tsv => io.write_output_csv_for_vec(&serde_json::to_value(self.rows())?, b'\t'),
+            FormatOutput::Template(template) => io.write_output_template(template, &serde_json::to_value(self)?),
+        }
+    }
+
+    /// Fail with a `BulkError` if the operation failed for any of the items.
+    pub fn into_result(self) -> Result<()> {
+        if self.failed.is_empty() {
+            return Ok(());
+        }
+
+        Err(BulkError {
+            failed: self.failed.len(),
+            total: self.succeeded.len() + self.failed.len(),
+        }
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_bulk_result() {
+        let result = BulkResult {
+            succeeded: vec!["one".to_string()],
+            failed: vec![("two".to_string(), "Object not found".to_string())],
+        };
+
+        let (mut io, stdout_path, _) = crate::iostreams::IoStreams::test();
+        io.set_color_enabled(false);
+        result.write(&mut io, &FormatOutput::Json).unwrap();
+        let stdout = std::fs::read_to_string(stdout_path).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "succeeded": ["one"],
+                "failed": [["two", "Object not found"]],
+            })
+        );
+
+        let err = result.into_result().unwrap_err();
+        assert!(err.downcast_ref::<BulkError>().is_some());
+        assert_eq!(err.to_string(), "failed for 1 of 2 items");
+
+        assert!(BulkResult::default().into_result().is_ok());
+    }
+}
@@ -1,12 +1,16 @@
-use std::io::Write;
+use std::{collections::BTreeMap, io::Write, path::PathBuf};
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use clap::{Command, CommandFactory, Parser};
+use parse_display::{Display, FromStr};
 
 /// Create command shortcuts.
 ///
 /// Aliases can be used to make shortcuts for oxide commands or to compose multiple commands.
 /// Run "oxide help alias set" to learn more.
+///
+/// An alias named like a built-in command, for example one added to the config file by
+/// hand, takes precedence over it with a warning. Pass `--no-alias` to run the built-in.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdAlias {
@@ -19,6 +23,8 @@ enum SubCommand {
     Set(CmdAliasSet),
     Delete(CmdAliasDelete),
     List(CmdAliasList),
+    Export(CmdAliasExport),
+    Import(CmdAliasImport),
 }
 
 #[async_trait::async_trait]
@@ -28,6 +34,8 @@ impl crate::cmd::Command for CmdAlias {
             SubCommand::Delete(cmd) => cmd.run(ctx).await,
             SubCommand::Set(cmd) => cmd.run(ctx).await,
             SubCommand::List(cmd) => cmd.run(ctx).await,
+            SubCommand::Export(cmd) => cmd.run(ctx).await,
+            SubCommand::Import(cmd) => cmd.run(ctx).await,
         }
     }
 }
@@ -77,8 +85,9 @@ impl crate::cmd::Command for CmdAliasDelete {
 ///
 /// The expansion may specify additional arguments and flags. If the expansion includes
 /// positional placeholders such as "$1", extra arguments that follow the alias will be
-/// inserted appropriately. Otherwise, extra arguments will be appended to the expanded
-/// command.
+/// inserted appropriately. Use "$*" or "$@" to insert all the arguments that are not
+/// used by a numbered placeholder. Otherwise, extra arguments will be appended to the
+/// expanded command.
 ///
 /// Use "-" as expansion argument to read the expansion string from standard input. This
 /// is useful to avoid quoting issues when defining expansions.
@@ -171,13 +180,38 @@ impl crate::cmd::Command for CmdAliasSet {
 /// This command prints out all of the aliases oxide is configured to use.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
-pub struct CmdAliasList {}
+pub struct CmdAliasList {
+    /// Output format. Without it, the aliases are printed for people.
+    #[clap(long, short)]
+    pub format: Option<crate::types::FormatOutput>,
+}
+
+/// An alias, as `alias list --format` prints it.
+#[derive(serde::Serialize, tabled::Tabled, Debug, Clone)]
+pub struct AliasInfo {
+    pub alias: String,
+    pub expansion: String,
+}
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdAliasList {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
         let config_aliases = ctx.config.aliases()?;
 
+        if let Some(format) = &self.format {
+            let mut aliases: Vec<AliasInfo> = config_aliases
+                .list()
+                .into_keys()
+                .map(|alias| AliasInfo {
+                    // Not the value of `list`, that is quoted like in the config file.
+                    expansion: config_aliases.get(&alias).0,
+                    alias,
+                })
+                .collect();
+            aliases.sort_by(|a, b| a.alias.cmp(&b.alias));
+            return ctx.io.write_output_for_vec(format, aliases);
+        }
+
         if config_aliases.map.is_empty() {
             writeln!(ctx.io.out, "no aliases configured")?;
             return Ok(());
@@ -196,6 +230,182 @@ impl crate::cmd::Command for CmdAliasList {
     }
 }
 
+/// The format of an exported set of aliases.
+#[derive(Debug, Clone, PartialEq, Eq, FromStr, Display)]
+#[display(style = "kebab-case")]
+pub enum AliasFormat {
+    Toml,
+    Yaml,
+}
+
+/// Export your aliases.
+///
+/// This command prints all of your aliases as a document that can be shared and
+/// read back with "oxide alias import".
+///
+///     $ oxide alias export > aliases.toml
+///     $ oxide alias export --format yaml > aliases.yaml
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdAliasExport {
+    /// The format of the document: toml or yaml.
+    #[clap(long, short, default_value = "toml")]
+    pub format: AliasFormat,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdAliasExport {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let aliases = alias_map(&ctx.config.aliases()?.map);
+
+        match self.format {
+            AliasFormat::Toml => {
+                let mut doc = toml_edit::Document::new();
+                for (alias, expansion) in &aliases {
+                    doc[alias.as_str()] = toml_edit::value(expansion.as_str());
+                }
+                write!(ctx.io.out, "{}", doc)?;
+            }
+            AliasFormat::Yaml => write!(ctx.io.out, "{}", serde_yaml::to_string(&aliases)?)?,
+        }
+
+        Ok(())
+    }
+}
+
+/// Import aliases from a file.
+///
+/// The file is a document as written by "oxide alias export", in TOML or YAML format
+/// depending on its extension. Use "-" to read a TOML document from standard input.
+///
+/// Aliases that are already set to a different expansion are only replaced after
+/// confirming, or when "--clobber" is given.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdAliasImport {
+    /// The file to import the aliases from.
+    #[clap(name = "file", required = true)]
+    pub file: PathBuf,
+
+    /// Overwrite existing aliases without prompting.
+    #[clap(long)]
+    pub clobber: bool,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdAliasImport {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let cs = ctx.io.color_scheme();
+
+        let contents = if self.file.to_str() == Some("-") {
+            let mut contents = String::new();
+            ctx.io.stdin.read_to_string(&mut contents)?;
+            contents
+        } else {
+            std::fs::read_to_string(&self.file).with_context(|| format!("failed to read {}", self.file.display()))?
+        };
+
+        let is_yaml = matches!(
+            self.file.extension().and_then(|e| e.to_str()),
+            Some("yaml") | Some("yml")
+        );
+        let aliases = parse_aliases(&contents, is_yaml)
+            .map_err(|err| anyhow!("failed to parse {}: {}", self.file.display(), err))?;
+
+        let mut config_aliases = ctx.config.aliases()?;
+
+        // Validate all the aliases before we change anything.
+        for (alias, expansion) in &aliases {
+            if valid_command(alias) {
+                bail!("could not import alias: {} is already an oxide command", alias);
+            }
+
+            if !expansion.starts_with('!') && !valid_command(expansion) {
+                bail!(
+                    "could not import alias {}: {} does not correspond to an oxide command",
+                    alias,
+                    expansion
+                );
+            }
+        }
+
+        let mut imported = 0;
+        for (alias, expansion) in &aliases {
+            let (old_expansion, ok) = config_aliases.get(alias);
+            if ok && old_expansion == *expansion {
+                continue;
+            }
+
+            if ok && !self.clobber {
+                let overwrite = ctx.io.can_prompt()
+                    && match dialoguer::Confirm::new()
+                        .with_prompt(format!(
+                            "Alias {} is already set to {}, overwrite it with {}?",
+                            alias, old_expansion, expansion
+                        ))
+                        .interact()
+                    {
+                        Ok(overwrite) => overwrite,
+                        Err(err) => bail!("prompt failed: {}", err),
+                    };
+
+                if !overwrite {
+                    writeln!(
+                        ctx.io.err_out,
+                        "{} Skipped alias {}, it is already set to {}",
+                        cs.warning_icon(),
+                        cs.bold(alias),
+                        cs.bold(&old_expansion)
+                    )?;
+                    continue;
+                }
+            }
+
+            if let Err(err) = config_aliases.add(alias, expansion) {
+                bail!("could not import alias {}: {}", alias, err);
+            }
+            imported += 1;
+        }
+
+        writeln!(
+            ctx.io.out,
+            "{} Imported {} {}",
+            cs.success_icon(),
+            imported,
+            if imported == 1 { "alias" } else { "aliases" }
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Get the aliases from the config as a sorted map.
+fn alias_map(map: &crate::config_map::ConfigMap) -> BTreeMap<String, String> {
+    map.root
+        .iter()
+        .filter_map(|(alias, expansion)| Some((alias.to_string(), expansion.as_str()?.to_string())))
+        .collect()
+}
+
+/// Parse a set of exported aliases.
+fn parse_aliases(contents: &str, is_yaml: bool) -> Result<BTreeMap<String, String>> {
+    if is_yaml {
+        return Ok(serde_yaml::from_str(contents)?);
+    }
+
+    let doc = contents.parse::<toml_edit::Document>()?;
+
+    let mut aliases = BTreeMap::new();
+    for (alias, expansion) in doc.iter() {
+        match expansion.as_str() {
+            Some(expansion) => aliases.insert(alias.to_string(), expansion.to_string()),
+            None => bail!("the expansion of alias {} is not a string", alias),
+        };
+    }
+
+    Ok(aliases)
+}
+
 fn get_expansion(cmd: &CmdAliasSet) -> Result<String> {
     if cmd.expansion == "-" {
         let mut expansion = String::new();
@@ -307,7 +517,7 @@ mod test {
         let tests: Vec<TestAlias> = vec![
             TestAlias {
                 name: "list empty".to_string(),
-                cmd: crate::cmd_alias::SubCommand::List(crate::cmd_alias::CmdAliasList {}),
+                cmd: crate::cmd_alias::SubCommand::List(crate::cmd_alias::CmdAliasList { format: None }),
                 want_out: "no aliases configured\n".to_string(),
                 want_err: "".to_string(),
             },
@@ -384,12 +594,28 @@ mod test {
                 want_out: "".to_string(),
                 want_err: "could not create alias: dne thing does not correspond to an oxide command".to_string(),
             },
+            TestAlias {
+                name: "export".to_string(),
+                cmd: crate::cmd_alias::SubCommand::Export(crate::cmd_alias::CmdAliasExport {
+                    format: crate::cmd_alias::AliasFormat::Toml,
+                }),
+                want_out: "cp = \"!config list\"\ncs = \"config set $1 $2\"\n".to_string(),
+                want_err: "".to_string(),
+            },
             TestAlias {
                 name: "list all".to_string(),
-                cmd: crate::cmd_alias::SubCommand::List(crate::cmd_alias::CmdAliasList {}),
+                cmd: crate::cmd_alias::SubCommand::List(crate::cmd_alias::CmdAliasList { format: None }),
                 want_out: "\"!config list\"\n".to_string(),
                 want_err: "".to_string(),
             },
+            TestAlias {
+                name: "list json".to_string(),
+                cmd: crate::cmd_alias::SubCommand::List(crate::cmd_alias::CmdAliasList {
+                    format: Some(crate::types::FormatOutput::Json),
+                }),
+                want_out: "\"alias\": \"cp\",\n    \"expansion\": \"!config list\"".to_string(),
+                want_err: "".to_string(),
+            },
             TestAlias {
                 name: "delete an alias".to_string(),
                 cmd: crate::cmd_alias::SubCommand::Delete(crate::cmd_alias::CmdAliasDelete {
@@ -408,7 +634,7 @@ mod test {
             },
             TestAlias {
                 name: "list after delete".to_string(),
-                cmd: crate::cmd_alias::SubCommand::List(crate::cmd_alias::CmdAliasList {}),
+                cmd: crate::cmd_alias::SubCommand::List(crate::cmd_alias::CmdAliasList { format: None }),
                 want_out: "cs:  \"config set $1 $2\"\n".to_string(),
                 want_err: "".to_string(),
             },
@@ -425,6 +651,13 @@ mod test {
                 config: &mut c,
                 io,
                 debug: false,
+                quiet: false,
+                profile: None,
+                api_clients: Default::default(),
+                timeout: None,
+                headers: Default::default(),
+                request_id_sent: Default::default(),
+                endpoint: None,
             };
 
             let cmd_alias = crate::cmd_alias::CmdAlias { subcmd: t.cmd };
@@ -467,4 +700,74 @@ mod test {
             }
         }
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    #[serial_test::serial]
+    async fn test_cmd_alias_import() {
+        use std::io::Write;
+
+        use crate::config::Config;
+
+        let mut file = tempfile::Builder::new().suffix(".yaml").tempfile().unwrap();
+        file.write_all(b"cp: \"!config list | grep editor\"\ncs: config set $1 $2\n")
+            .unwrap();
+
+        let mut config = crate::config::new_blank_config().unwrap();
+        config.aliases().unwrap().add("cs", "config get").unwrap();
+
+        let tests = vec![
+            (
+                "import without clobber",
+                crate::cmd_alias::SubCommand::Import(crate::cmd_alias::CmdAliasImport {
+                    file: file.path().to_path_buf(),
+                    clobber: false,
+                }),
+                "✔ Imported 1 alias\n",
+                "! Skipped alias cs, it is already set to config get\n",
+            ),
+            (
+                "import with clobber",
+                crate::cmd_alias::SubCommand::Import(crate::cmd_alias::CmdAliasImport {
+                    file: file.path().to_path_buf(),
+                    clobber: true,
+                }),
+                "✔ Imported 1 alias\n",
+                "",
+            ),
+            (
+                "export after import",
+                crate::cmd_alias::SubCommand::Export(crate::cmd_alias::CmdAliasExport {
+                    format: crate::cmd_alias::AliasFormat::Toml,
+                }),
+                "cp = \"!config list | grep editor\"\ncs = \"config set $1 $2\"\n",
+                "",
+            ),
+        ];
+
+        for (name, cmd, want_out, want_err) in tests {
+            let (mut io, stdout_path, stderr_path) = crate::iostreams::IoStreams::test();
+            io.set_color_enabled(false);
+            io.set_never_prompt(true);
+            let mut ctx = crate::context::Context {
+                config: &mut config,
+                io,
+                debug: false,
+                quiet: false,
+                profile: None,
+                api_clients: Default::default(),
+                timeout: None,
+                headers: Default::default(),
+                request_id_sent: Default::default(),
+                endpoint: None,
+            };
+
+            let cmd_alias = crate::cmd_alias::CmdAlias { subcmd: cmd };
+            cmd_alias.run(&mut ctx).await.unwrap();
+
+            let stdout = std::fs::read_to_string(stdout_path).unwrap();
+            let stderr = std::fs::read_to_string(stderr_path).unwrap();
+            assert_eq!(stdout, want_out, "test {}", name);
+            assert_eq!(stderr, want_err, "test {}", name);
+        }
+    }
 }
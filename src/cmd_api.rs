@@ -33,6 +33,10 @@ use serde::{Deserialize, Serialize};
 ///
 /// In `--paginate` mode, all pages of results will sequentially be requested until
 /// there are no more pages of results.
+///
+/// With `--include`, the status line and response headers are printed before the body.
+/// Pass `--include-format json` to print them as a JSON object on a single line instead,
+/// like `{"status":200,"version":"HTTP/1.1","headers":{...}}`, for use in scripts.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdApi {
@@ -64,9 +68,58 @@ pub struct CmdApi {
     #[clap(short, long)]
     pub include: bool,
 
+    /// How to print the response headers: human or json. Implies `--include`.
+    #[clap(long)]
+    pub include_format: Option<IncludeFormat>,
+
     /// Add a HTTP request header in `key:value` format.
+    ///
+    /// This is the global `--header`, with a `-H` that only `api` has. If headers are given both
+    /// before and after `api`, only the ones after it are sent.
     #[clap(short = 'H', long)]
     pub header: Vec<String>,
+
+    /// Write the response body to this file instead of standard output.
+    #[clap(long, default_value = "")]
+    pub output_file: String,
+}
+
+/// How `--include` prints the status line and response headers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IncludeFormat {
+    /// The status line and a table of the headers.
+    Human,
+    /// A JSON object with the status, HTTP version, and headers, on a single line.
+    Json,
+}
+
+impl Default for IncludeFormat {
+    fn default() -> IncludeFormat {
+        IncludeFormat::Human
+    }
+}
+
+impl std::fmt::Display for IncludeFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            IncludeFormat::Human => "human",
+            IncludeFormat::Json => "json",
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+impl std::str::FromStr for IncludeFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "human" => Ok(IncludeFormat::Human),
+            "json" => Ok(IncludeFormat::Json),
+            _ => Err(anyhow!("invalid include format: {}, options are: human, json", s)),
+        }
+    }
 }
 
 /// The JSON type for a paginated response.
@@ -156,22 +209,27 @@ impl crate::cmd::Command for CmdApi {
                 Some(reqwest::Body::from(bytes.clone()))
             };
 
-            let mut req = client.request_raw(method.clone(), &endpoint, body).await?;
-
-            // Let's add our headers.
-            let headers = self.parse_headers()?;
-            if !headers.is_empty() {
-                for (key, value) in headers {
-                    req = req.header(key, value);
-                }
-            }
+            // Let's add our headers. When run from the command line these are also the global
+            // `--header`s that `timed_raw` adds, see `Opts::header`.
+            let req = client
+                .request_raw(method.clone(), &endpoint, body)
+                .await?
+                .headers(parse_headers(&self.header)?);
 
-            let resp = req.send().await?;
+            let resp = ctx.timed_raw(method.as_str(), &endpoint, req).await?;
 
             // Print the response headers if requested.
-            if self.include {
-                writeln!(ctx.io.out, "{:?} {}", resp.version(), resp.status())?;
-                print_headers(ctx, resp.headers())?;
+            if self.include || self.include_format.is_some() {
+                match self.include_format.clone().unwrap_or_default() {
+                    IncludeFormat::Human => {
+                        writeln!(ctx.io.out, "{:?} {}", resp.version(), resp.status())?;
+                        print_headers(ctx, resp.headers())?;
+                    }
+                    IncludeFormat::Json => {
+                        let include = headers_json(resp.version(), resp.status(), resp.headers());
+                        writeln!(ctx.io.out, "{}", serde_json::to_string(&include)?)?;
+                    }
+                }
             }
 
             if resp.status() == 204 {
@@ -212,27 +270,37 @@ impl crate::cmd::Command for CmdApi {
             result = serde_json::Value::Array(page_results);
         }
 
-        ctx.io.write_output_json(&result)?;
+        ctx.io
+            .with_output_file(&self.output_file, |io| io.write_output_json(&result))?;
 
         Ok(())
     }
 }
 
-impl CmdApi {
-    fn parse_headers(&self) -> Result<HashMap<String, String>> {
-        let mut headers: HashMap<String, String> = HashMap::new();
+/// Parse headers in `key:value` format, as passed to `--header`. Whitespace around the
+/// key and value is ignored, like curl does.
+pub fn parse_headers(headers: &[String]) -> Result<reqwest::header::HeaderMap> {
+    let mut map = reqwest::header::HeaderMap::new();
 
-        for h in self.header.iter() {
-            let mut parts = h.splitn(2, ':');
-            let key = parts.next().ok_or_else(|| anyhow!("missing key in --header"))?;
-            let value = parts.next().ok_or_else(|| anyhow!("missing value in --header"))?;
+    for h in headers.iter() {
+        let mut parts = h.splitn(2, ':');
+        let key = parts.next().ok_or_else(|| anyhow!("missing key in --header"))?;
+        let value = parts
+            .next()
+            .ok_or_else(|| anyhow!("missing value in --header `{}`, expected key:value", h))?;
 
-            headers.insert(key.to_string(), value.to_string());
-        }
+        let key = reqwest::header::HeaderName::from_bytes(key.trim().as_bytes())
+            .map_err(|_| anyhow!("invalid header name in --header `{}`", h))?;
+        let value = reqwest::header::HeaderValue::from_str(value.trim())
+            .map_err(|_| anyhow!("invalid header value in --header `{}`", h))?;
 
-        Ok(headers)
+        map.insert(key, value);
     }
 
+    Ok(map)
+}
+
+impl CmdApi {
     fn parse_fields(&self, ctx: &mut crate::context::Context) -> Result<HashMap<String, serde_json::Value>> {
         let mut params: HashMap<String, serde_json::Value> = HashMap::new();
 
@@ -321,6 +389,98 @@ fn print_headers(ctx: &mut crate::context::Context, headers: &reqwest::header::H
     Ok(())
 }
 
+/// Returns the status line and headers of a response as a JSON object, for
+/// `--include-format json`. Headers sent more than once are joined with a comma, as
+/// HTTP allows.
+fn headers_json(
+    version: reqwest::Version,
+    status: reqwest::StatusCode,
+    headers: &reqwest::header::HeaderMap,
+) -> serde_json::Value {
+    let mut values: std::collections::BTreeMap<String, String> = std::collections::BTreeMap::new();
+    for name in headers.keys() {
+        let value: Vec<String> = headers
+            .get_all(name)
+            .iter()
+            .map(|v| String::from_utf8_lossy(v.as_bytes()).to_string())
+            .collect();
+        values.insert(name.as_str().to_string(), value.join(", "));
+    }
+
+    serde_json::json!({
+        "status": status.as_u16(),
+        "version": format!("{:?}", version),
+        "headers": values,
+    })
+}
+
+/// Fetch a single page of a list endpoint, as the API returns it, with the token of the
+/// next page. An empty page token is the first page.
+pub async fn get_page(
+    ctx: &crate::context::Context<'_>,
+    client: &oxide_api::Client,
+    endpoint: &str,
+    limit: u32,
+    sort_by: &impl Serialize,
+    page_token: &str,
+) -> Result<PaginatableResponse> {
+    let mut query_string = format!("limit={}", limit);
+    if let serde_json::Value::String(sort_by) = serde_json::to_value(sort_by)? {
+        query_string.push_str(&format!("&sort_by={}", sort_by));
+    }
+    if !page_token.is_empty() {
+        query_string.push_str(&format!("&page_token={}", page_token));
+    }
+    let page_endpoint = add_query_string(endpoint, &query_string);
+
+    let req = client.request_raw(http::Method::GET, &page_endpoint, None).await?;
+    let resp = ctx.timed_raw("GET", &page_endpoint, req).await?;
+
+    if !resp.status().is_success() {
+        return Err(anyhow!(
+            "{} {}",
+            resp.status(),
+            resp.status().canonical_reason().unwrap_or("")
+        ));
+    }
+
+    Ok(resp.json().await?)
+}
+
+/// Fetch all the pages of a list endpoint and write each item as a line of JSON as soon as
+/// its page arrives, rather than buffering all of them. This is what `--paginate --format
+/// jsonl` does for the list commands.
+pub async fn write_pages_jsonl(
+    ctx: &mut crate::context::Context<'_>,
+    client: &oxide_api::Client,
+    endpoint: &str,
+    limit: u32,
+    sort_by: &impl Serialize,
+) -> Result<()> {
+    let mut page_token = String::new();
+    loop {
+        let page = get_page(ctx, client, endpoint, limit, sort_by, &page_token).await?;
+        match ctx.io.write_output_jsonl(&serde_json::Value::Array(page.items)) {
+            Ok(()) => {}
+            // Whoever reads the output went away, like `head` does, so stop fetching.
+            Err(err) if is_broken_pipe(&err) => return Ok(()),
+            Err(err) => return Err(err),
+        }
+
+        match page.next_page {
+            Some(next_page) => page_token = next_page,
+            None => return Ok(()),
+        }
+    }
+}
+
+fn is_broken_pipe(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<std::io::Error>(),
+        Some(err) if err.kind() == std::io::ErrorKind::BrokenPipe
+    )
+}
+
 fn add_query_string(endpoint: &str, query_string: &str) -> String {
     if endpoint.contains('?') {
         format!("{}&{}", endpoint, query_string)
@@ -351,4 +511,40 @@ mod test {
         expected = "https://api.github.com/users/octocat/repos?page=2&per_page=100&foo=bar";
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_parse_headers() {
+        let headers = parse_headers(&["X-Feature: on".to_string(), "accept:application/json".to_string()]).unwrap();
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers.get("x-feature").unwrap(), "on");
+        assert_eq!(headers.get("accept").unwrap(), "application/json");
+
+        let err = parse_headers(&["x-feature".to_string()]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "missing value in --header `x-feature`, expected key:value"
+        );
+
+        let err = parse_headers(&["bad header:on".to_string()]).unwrap_err();
+        assert_eq!(err.to_string(), "invalid header name in --header `bad header:on`");
+
+        let err = parse_headers(&["x-feature:a\nb".to_string()]).unwrap_err();
+        assert_eq!(err.to_string(), "invalid header value in --header `x-feature:a\nb`");
+
+        assert!(parse_headers(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_headers_json() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("content-type", "application/json".parse().unwrap());
+        headers.append("vary", "origin".parse().unwrap());
+        headers.append("vary", "accept-encoding".parse().unwrap());
+
+        let result = headers_json(reqwest::Version::HTTP_11, reqwest::StatusCode::OK, &headers);
+        assert_eq!(
+            serde_json::to_string(&result).unwrap(),
+            r#"{"headers":{"content-type":"application/json","vary":"origin, accept-encoding"},"status":200,"version":"HTTP/1.1"}"#
+        );
+    }
 }
@@ -1,10 +1,10 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, io::Write};
 
 use anyhow::{anyhow, Result};
 use clap::Parser;
 use oauth2::{
     basic::BasicClient, devicecode::StandardDeviceAuthorizationResponse, reqwest::async_http_client, AuthType, AuthUrl,
-    ClientId, DeviceAuthorizationUrl, TokenResponse, TokenUrl,
+    ClientId, DeviceAuthorizationUrl, Scope, TokenResponse, TokenUrl,
 };
 
 /// Login, logout, and get the status of your authentication.
@@ -19,18 +19,22 @@ pub struct CmdAuth {
 
 #[derive(Parser, Debug, Clone)]
 enum SubCommand {
+    List(CmdAuthList),
     Login(CmdAuthLogin),
     Logout(CmdAuthLogout),
     Status(CmdAuthStatus),
+    Switch(CmdAuthSwitch),
 }
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdAuth {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
         match &self.subcmd {
+            SubCommand::List(cmd) => cmd.run(ctx).await,
             SubCommand::Login(cmd) => cmd.run(ctx).await,
             SubCommand::Logout(cmd) => cmd.run(ctx).await,
             SubCommand::Status(cmd) => cmd.run(ctx).await,
+            SubCommand::Switch(cmd) => cmd.run(ctx).await,
         }
     }
 }
@@ -97,19 +101,27 @@ fn parse_host_interactively(ctx: &mut crate::context::Context) -> Result<url::Ur
 
 /// Authenticate with an Oxide host.
 ///
-/// Alternatively, pass in a token on standard input by using `--with-token`.
+/// Alternatively, pass in a token on standard input by using `--with-token`, or
+/// from a file by using `--token-file`.
 ///
 ///     # start interactive setup
 ///     $ oxide auth login
 ///
 ///     # authenticate against a specific Oxide instance by reading the token from a file
 ///     $ oxide auth login --with-token --host oxide.internal < mytoken.txt
+///     $ oxide auth login --token-file mytoken.txt --host oxide.internal
 ///
 ///     # authenticate with a specific Oxide instance
 ///     $ oxide auth login --host oxide.internal
 ///
 ///     # authenticate with an insecure Oxide instance (not recommended)
 ///     $ oxide auth login --host http://oxide.internal
+///
+///     # authenticate with another Oxide instance and store it as a named profile
+///     $ oxide auth login --host rack2.oxide.internal --profile staging
+///
+///     # authenticate with an Oxide instance whose identity provider needs its own client
+///     $ oxide auth login --host oxide.internal --client-id my-client-id --scope openid
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdAuthLogin {
@@ -117,11 +129,24 @@ pub struct CmdAuthLogin {
     #[clap(long)]
     pub with_token: bool,
 
+    /// Read token from a file.
+    #[clap(long)]
+    pub token_file: Option<std::path::PathBuf>,
+
     /// The host of the Oxide instance to authenticate with.
     /// This assumes the instance is an `https://` url, if not otherwise specified
     /// as `http://`.
     #[clap(short = 'H', long, env = "OXIDE_HOST", parse(try_from_str = parse_host))]
     pub host: Option<url::Url>,
+
+    /// The OAuth client ID to log in with, for an identity provider that is set up with
+    /// its own. Defaults to the `client_id` setting.
+    #[clap(long)]
+    pub client_id: Option<String>,
+
+    /// An OAuth scope to ask for when logging in. Can be passed more than once.
+    #[clap(long = "scope")]
+    pub scopes: Vec<String>,
     // Open a browser to authenticate.
     // TODO: Make this work when we have device auth.
     // #[clap(short, long)]
@@ -131,8 +156,14 @@ pub struct CmdAuthLogin {
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdAuthLogin {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
-        if !ctx.io.can_prompt() && !self.with_token {
-            return Err(anyhow!("--with-token required when not running interactively"));
+        if self.with_token && self.token_file.is_some() {
+            return Err(anyhow!("--with-token and --token-file cannot be used together"));
+        }
+
+        if !ctx.io.can_prompt() && !self.with_token && self.token_file.is_none() {
+            return Err(anyhow!(
+                "--with-token or --token-file required when not running interactively"
+            ));
         }
 
         let mut token = String::new();
@@ -141,6 +172,8 @@ impl crate::cmd::Command for CmdAuthLogin {
             // Read from stdin.
             ctx.io.stdin.read_to_string(&mut token)?;
             token = token.trim_end_matches('\n').to_string();
+        } else if let Some(token_file) = &self.token_file {
+            token = read_token_file(ctx, token_file)?;
         }
 
         let mut interactive = false;
@@ -158,7 +191,11 @@ impl crate::cmd::Command for CmdAuthLogin {
             return Err(anyhow!("--host required when not running interactively"));
         };
 
-        if let Err(err) = ctx.config.check_writable(host, "token") {
+        // Credentials are stored under the profile name, if we were given one.
+        // Otherwise they are keyed by the host.
+        let profile = ctx.profile.clone().unwrap_or_else(|| host.to_string());
+
+        if let Err(err) = ctx.config.check_writable(&profile, "token") {
             if let Some(crate::config_from_env::ReadOnlyEnvVarError::Variable(var)) = err.downcast_ref() {
                 writeln!(
                     ctx.io.err_out,
@@ -181,7 +218,7 @@ impl crate::cmd::Command for CmdAuthLogin {
         if token.is_empty() {
             // We don't want to capture the error here just in case we have no host config
             // for this specific host yet.
-            let existing_token = if let Ok(existing_token) = ctx.config.get(host, "token") {
+            let existing_token = if let Ok(existing_token) = ctx.config.get(&profile, "token") {
                 existing_token
             } else {
                 String::new()
@@ -206,7 +243,10 @@ impl crate::cmd::Command for CmdAuthLogin {
 
             // Do an OAuth 2.0 Device Authorization Grant dance to get a token.
             let device_auth_url = DeviceAuthorizationUrl::new(format!("{}device/auth", host))?;
-            let client_id = ctx.config.get("", "client_id")?;
+            let client_id = match &self.client_id {
+                Some(client_id) => client_id.to_string(),
+                None => ctx.config.get("", "client_id")?,
+            };
             let auth_client = BasicClient::new(
                 ClientId::new(client_id),
                 None,
@@ -218,26 +258,37 @@ impl crate::cmd::Command for CmdAuthLogin {
 
             let details: StandardDeviceAuthorizationResponse = auth_client
                 .exchange_device_code()?
+                .add_scopes(self.scopes.iter().map(|scope| Scope::new(scope.to_string())))
                 .request_async(async_http_client)
                 .await?;
 
-            if let Some(uri) = details.verification_uri_complete() {
+            let tty = ctx.io.is_stdout_tty();
+            let uri = if let Some(uri) = details.verification_uri_complete() {
                 writeln!(
                     ctx.io.out,
                     "Opening {} in your browser.\n\
                      Please verify user code: {}\n",
-                    uri.secret(),
+                    hyperlink(uri.secret(), tty),
                     details.user_code().secret()
                 )?;
                 let _ = ctx.browser(host, uri.secret());
+                uri.secret().to_string()
             } else {
                 writeln!(
                     ctx.io.out,
                     "Open this URL in your browser:\n{}\n\
                      And enter the code: {}\n",
-                    **details.verification_uri(),
+                    hyperlink(details.verification_uri().as_str(), tty),
                     details.user_code().secret()
                 )?;
+                details.verification_uri().as_str().to_string()
+            };
+
+            // To verify from a phone instead. Only for terminals, and only if it fits.
+            if tty {
+                if let Some(code) = qr_code(&uri, ctx.io.terminal_width()) {
+                    writeln!(ctx.io.out, "Or scan this code:\n{}", code)?;
+                }
             }
 
             token = auth_client
@@ -250,18 +301,21 @@ impl crate::cmd::Command for CmdAuthLogin {
         }
 
         // Set the token in the config file.
-        ctx.config.set(host, "token", &token)?;
+        ctx.config.set(&profile, "token", &token)?;
+        if profile != host {
+            ctx.config.set(&profile, "host", host)?;
+        }
 
-        let client = ctx.api_client(host)?;
+        let client = ctx.api_client(&profile)?;
 
         // Get the session for the token.
-        let session = client.hidden().session_me().await?;
+        let session = ctx.timed("GET", "/session/me", client.hidden().session_me()).await?;
 
         // Set the user.
         // TODO: This should instead store the email, or some username or something
         // that is human knowable.
         let email = session.id;
-        ctx.config.set(host, "user", &email)?;
+        ctx.config.set(&profile, "user", &email)?;
 
         // Save the config.
         ctx.config.write()?;
@@ -272,29 +326,90 @@ impl crate::cmd::Command for CmdAuthLogin {
     }
 }
 
+/// Make a URL clickable in terminals that support it, with an OSC 8 hyperlink. Terminals
+/// that do not support them print the URL as is.
+fn hyperlink(url: &str, tty: bool) -> String {
+    if !tty {
+        return url.to_string();
+    }
+
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, url)
+}
+
+/// Render a URL as a QR code made of half blocks, light on dark like most terminals.
+/// `None` if it would not fit in the given width.
+fn qr_code(url: &str, width: i32) -> Option<String> {
+    use qrcode::render::unicode::Dense1x2;
+
+    let code = qrcode::QrCode::new(url.as_bytes()).ok()?;
+    let image = code
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .build();
+
+    if image.lines().any(|line| line.chars().count() > width as usize) {
+        return None;
+    }
+
+    Some(image)
+}
+
+/// Read a token from a file, warning if the file can be read by anyone.
+fn read_token_file(ctx: &mut crate::context::Context, path: &std::path::Path) -> Result<String> {
+    let token = std::fs::read_to_string(path)
+        .map_err(|err| anyhow!("failed to read token file {}: {}", path.display(), err))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = std::fs::metadata(path)?.permissions().mode();
+        if mode & 0o004 != 0 {
+            let cs = ctx.io.color_scheme();
+            writeln!(
+                ctx.io.err_out,
+                "{} Token file {} is readable by anyone, consider running `chmod 600 {}`",
+                cs.warning_icon(),
+                path.display(),
+                path.display()
+            )?;
+        }
+    }
+
+    Ok(token.trim_end().to_string())
+}
+
 /// Log out of an Oxide host.
 ///
-/// This command removes the authentication configuration for a host either specified
-/// interactively or via `--host`.
+/// This command revokes and removes the authentication configuration for a host
+/// either specified interactively, via `--host`, or the default host.
 ///
 ///     $ oxide auth logout
 ///     # => select what host to log out of via a prompt
 ///
 ///     $ oxide auth logout --host oxide.internal
 ///     # => log out of specified host
+///
+///     $ oxide auth logout --host oxide.internal --confirm
+///     # => log out of specified host without prompting
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdAuthLogout {
     /// The hostname of the Oxide instance to log out of.
     #[clap(short = 'H', long, env = "OXIDE_HOST", parse(try_from_str = parse_host))]
     pub host: Option<url::Url>,
+
+    /// Confirm logging out without prompting.
+    #[clap(long, short = 'y', visible_alias = "yes", env = "OXIDE_ASSUME_YES")]
+    pub confirm: bool,
 }
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdAuthLogout {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
-        if self.host.is_none() && !ctx.io.can_prompt() {
-            return Err(anyhow!("--host required when not running interactively"));
+        if !ctx.io.can_prompt() && !self.confirm {
+            return Err(anyhow!("--confirm required when not running interactively"));
         }
 
         let candidates = ctx.config.hosts()?;
@@ -302,38 +417,37 @@ impl crate::cmd::Command for CmdAuthLogout {
             return Err(anyhow!("not logged in to any hosts"));
         }
 
-        let hostname = if self.host.is_none() {
-            if candidates.len() == 1 {
-                candidates[0].to_string()
-            } else {
-                let index = dialoguer::Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
-                    .with_prompt("What account do you want to log out of?")
-                    .default(0)
-                    .items(&candidates[..])
-                    .interact();
-
-                match index {
-                    Ok(i) => candidates[i].to_string(),
-                    Err(err) => {
-                        return Err(anyhow!("prompt failed: {}", err));
-                    }
-                }
-            }
-        } else {
-            let hostname = self.host.as_ref().unwrap().to_string();
-            let mut found = false;
+        let hostname = if let Some(host) = &self.host {
+            let host = host.to_string();
+            let mut found = None;
             for c in candidates {
-                if c == hostname {
-                    found = true;
+                if c == host || ctx.config.profile_host(&c)? == host {
+                    found = Some(c);
                     break;
                 }
             }
 
-            if !found {
-                return Err(anyhow!("not logged into {}", hostname));
+            match found {
+                Some(hostname) => hostname,
+                None => return Err(anyhow!("not logged into {}", host)),
             }
-
-            hostname
+        } else if candidates.len() == 1 {
+            candidates[0].to_string()
+        } else if ctx.io.can_prompt() {
+            let index = dialoguer::Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("What account do you want to log out of?")
+                .default(0)
+                .items(&candidates[..])
+                .interact();
+
+            match index {
+                Ok(i) => candidates[i].to_string(),
+                Err(err) => {
+                    return Err(anyhow!("prompt failed: {}", err));
+                }
+            }
+        } else {
+            ctx.config.default_host()?
         };
 
         if let Err(err) = ctx.config.check_writable(&hostname, "token") {
@@ -356,15 +470,18 @@ impl crate::cmd::Command for CmdAuthLogout {
         let client = ctx.api_client(&hostname)?;
 
         // Get the current user.
-        let session = client.hidden().session_me().await?;
-
+        // We don't want to fail here, since the token might already be invalid and
+        // we still want to be able to remove it.
         // TODO: this should be the users email or something better.
         // make it consistent with login.
-        let email = session.id;
+        let email = match ctx.timed("GET", "/session/me", client.hidden().session_me()).await {
+            Ok(session) => session.id,
+            Err(_) => String::new(),
+        };
 
-        if ctx.io.can_prompt() {
+        if !self.confirm {
             match dialoguer::Confirm::new()
-                .with_prompt(format!("Are you sure you want to log out of {}{}?", hostname, email))
+                .with_prompt(format!("Are you sure you want to log out of {} {}?", hostname, email))
                 .interact()
             {
                 Ok(true) => {}
@@ -377,13 +494,25 @@ impl crate::cmd::Command for CmdAuthLogout {
             }
         }
 
+        let cs = ctx.io.color_scheme();
+
+        // Revoke the token, we still remove it locally if this fails.
+        if let Err(err) = ctx.timed("POST", "/logout", client.hidden().logout()).await {
+            writeln!(
+                ctx.io.err_out,
+                "{} Failed to revoke the token for {}: {}",
+                cs.warning_icon(),
+                hostname,
+                err
+            )?;
+        }
+
         // Unset the host.
         ctx.config.unset_host(&hostname)?;
 
         // Write the changes to the config.
         ctx.config.write()?;
 
-        let cs = ctx.io.color_scheme();
         writeln!(
             ctx.io.out,
             "{} Logged out of {} {}",
@@ -399,11 +528,12 @@ impl crate::cmd::Command for CmdAuthLogout {
 /// Verifies and displays information about your authentication state.
 ///
 /// This command will test your authentication state for each Oxide host that `oxide`
-/// knows about and report on any issues.
+/// knows about and report on any issues. It exits with a non-zero status if any
+/// host fails to authenticate.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdAuthStatus {
-    /// Display the auth token.
+    /// Display the auth token, masked to its last 4 characters.
     #[clap(short = 't', long)]
     pub show_token: bool,
 
@@ -434,7 +564,8 @@ impl crate::cmd::Command for CmdAuthStatus {
         let mut hostname_found = false;
 
         for hostname in &hostnames {
-            if matches!(&self.host, Some(host) if host.as_str() != *hostname) {
+            let host = ctx.config.profile_host(hostname)?;
+            if matches!(&self.host, Some(h) if h.as_str() != *hostname && h.as_str() != host) {
                 continue;
             }
 
@@ -446,7 +577,7 @@ impl crate::cmd::Command for CmdAuthStatus {
 
             let mut host_status: Vec<String> = vec![];
 
-            match client.hidden().session_me().await {
+            match ctx.timed("GET", "/session/me", client.hidden().session_me()).await {
                 Ok(session) => {
                     // TODO: this should be the users email or something consistent with login
                     // and logout.
@@ -467,20 +598,17 @@ impl crate::cmd::Command for CmdAuthStatus {
                     host_status.push(format!(
                         "{} Logged in to {} as {} ({})",
                         cs.success_icon(),
-                        hostname,
+                        host,
                         cs.bold(&email),
                         token_source
                     ));
-                    let mut token_display = "*******************".to_string();
                     if self.show_token {
-                        token_display = token.to_string();
+                        host_status.push(format!("{} Token: {}", cs.success_icon(), mask_token(&token)));
                     }
-                    host_status.push(format!("{} Token: {}", cs.success_icon(), token_display));
                 }
                 Err(err) => {
-                    host_status.push(format!("{} {}: api call failed: {}", cs.failure_icon(), hostname, err));
+                    host_status.push(format!("{} {}: api call failed: {}", cs.failure_icon(), host, err));
                     failed = true;
-                    continue;
                 }
             }
 
@@ -518,8 +646,137 @@ impl crate::cmd::Command for CmdAuthStatus {
     }
 }
 
+/// Mask all but the last 4 characters of a token.
+pub(crate) fn mask_token(token: &str) -> String {
+    let chars: Vec<char> = token.chars().collect();
+    let hidden = if chars.len() > 4 { chars.len() - 4 } else { chars.len() };
+
+    format!("{}{}", "*".repeat(hidden), chars[hidden..].iter().collect::<String>())
+}
+
+/// List the profiles you are authenticated with.
+///
+/// The active profile is marked with a `*`. Profiles created without `--profile`
+/// are named after their host.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdAuthList {
+    /// Output format. Without it, the profiles are printed for people.
+    #[clap(long, short)]
+    pub format: Option<crate::types::FormatOutput>,
+}
+
+/// A profile, as `auth list --format` prints it.
+#[derive(serde::Serialize, tabled::Tabled, Debug, Clone)]
+pub struct ProfileInfo {
+    pub profile: String,
+    pub host: String,
+    pub active: bool,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdAuthList {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let profiles = ctx.config.hosts()?;
+
+        if profiles.is_empty() && self.format.is_none() {
+            let cs = ctx.io.color_scheme();
+            writeln!(
+                ctx.io.out,
+                "You are not logged into any Oxide hosts. Run {} to authenticate.",
+                cs.bold("oxide auth login")
+            )?;
+            return Ok(());
+        }
+
+        let active = match &ctx.profile {
+            Some(profile) => profile.to_string(),
+            None => ctx.config.default_host().unwrap_or_default(),
+        };
+
+        let mut infos = Vec::new();
+        for profile in profiles {
+            let host = ctx.config.profile_host(&profile)?;
+            let is_active = profile == active || host == active;
+            infos.push(ProfileInfo {
+                profile,
+                host,
+                active: is_active,
+            });
+        }
+
+        if let Some(format) = &self.format {
+            return ctx.io.write_output_for_vec(format, infos);
+        }
+
+        let mut tw = tabwriter::TabWriter::new(vec![]);
+        for info in infos {
+            let marker = if info.active { "*" } else { " " };
+            writeln!(tw, "{} {}\t{}", marker, info.profile, info.host)?;
+        }
+        tw.flush()?;
+
+        let table = String::from_utf8(tw.into_inner()?)?;
+        write!(ctx.io.out, "{}", table)?;
+
+        Ok(())
+    }
+}
+
+/// Switch the active profile.
+///
+/// The active profile is used by all commands, unless `--profile` or `OXIDE_PROFILE`
+/// is set.
+///
+///     $ oxide auth switch staging
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdAuthSwitch {
+    /// The profile to switch to.
+    #[clap(name = "profile", required = true)]
+    pub profile: String,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdAuthSwitch {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let profiles = ctx.config.hosts()?;
+
+        if !profiles.contains(&self.profile) {
+            return Err(anyhow!(
+                "profile `{}` not found, options are: {}",
+                self.profile,
+                profiles.join(", ")
+            ));
+        }
+
+        for profile in &profiles {
+            let default = if *profile == self.profile { "true" } else { "false" };
+            ctx.config.set(profile, "default", default)?;
+        }
+
+        // Write the changes to the config.
+        ctx.config.write()?;
+
+        let host = ctx.config.profile_host(&self.profile)?;
+
+        let cs = ctx.io.color_scheme();
+        writeln!(
+            ctx.io.out,
+            "{} Switched to profile {} ({})",
+            cs.success_icon(),
+            cs.bold(&self.profile),
+            host
+        )?;
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use std::io::Write;
+
     use pretty_assertions::assert_eq;
 
     use crate::cmd::Command;
@@ -561,16 +818,22 @@ mod test {
                 cmd: crate::cmd_auth::SubCommand::Login(crate::cmd_auth::CmdAuthLogin {
                     host: Some(test_host.clone()),
                     with_token: false,
+                    token_file: None,
+                    client_id: None,
+                    scopes: vec![],
                 }),
                 stdin: test_token.to_string(),
                 want_out: "".to_string(),
-                want_err: "--with-token required when not running interactively".to_string(),
+                want_err: "--with-token or --token-file required when not running interactively".to_string(),
             },
             TestItem {
                 name: "login --with-token=true".to_string(),
                 cmd: crate::cmd_auth::SubCommand::Login(crate::cmd_auth::CmdAuthLogin {
                     host: Some(test_host.clone()),
                     with_token: true,
+                    token_file: None,
+                    client_id: None,
+                    scopes: vec![],
                 }),
                 stdin: test_token.to_string(),
                 want_out: "✔ Logged in as ".to_string(),
@@ -587,16 +850,20 @@ mod test {
                 want_err: "".to_string(),
             },
             TestItem {
-                name: "logout no prompt no host".to_string(),
-                cmd: crate::cmd_auth::SubCommand::Logout(crate::cmd_auth::CmdAuthLogout { host: None }),
+                name: "logout no prompt no confirm".to_string(),
+                cmd: crate::cmd_auth::SubCommand::Logout(crate::cmd_auth::CmdAuthLogout {
+                    host: Some(test_host.clone()),
+                    confirm: false,
+                }),
                 stdin: "".to_string(),
                 want_out: "".to_string(),
-                want_err: "--host required when not running interactively".to_string(),
+                want_err: "--confirm required when not running interactively".to_string(),
             },
             TestItem {
                 name: "logout no prompt with host".to_string(),
                 cmd: crate::cmd_auth::SubCommand::Logout(crate::cmd_auth::CmdAuthLogout {
                     host: Some(test_host.clone()),
+                    confirm: true,
                 }),
                 stdin: "".to_string(),
                 want_out: format!("✔ Logged out of {}", test_host),
@@ -621,6 +888,13 @@ mod test {
                 config: &mut c,
                 io,
                 debug: false,
+                quiet: false,
+                profile: None,
+                api_clients: Default::default(),
+                timeout: None,
+                headers: Default::default(),
+                request_id_sent: Default::default(),
+                endpoint: None,
             };
 
             let cmd_auth = crate::cmd_auth::CmdAuth { subcmd: t.cmd };
@@ -646,6 +920,163 @@ mod test {
         }
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    #[serial_test::serial]
+    async fn test_cmd_auth_profiles() {
+        let tests: Vec<TestItem> = vec![
+            TestItem {
+                name: "list".to_string(),
+                cmd: crate::cmd_auth::SubCommand::List(crate::cmd_auth::CmdAuthList { format: None }),
+                stdin: "".to_string(),
+                want_out: "  https://thing.com/  https://thing.com/\n  staging             https://rack2.thing.com/\n"
+                    .to_string(),
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "switch to a profile that does not exist".to_string(),
+                cmd: crate::cmd_auth::SubCommand::Switch(crate::cmd_auth::CmdAuthSwitch {
+                    profile: "dne".to_string(),
+                }),
+                stdin: "".to_string(),
+                want_out: "".to_string(),
+                want_err: "profile `dne` not found, options are: https://thing.com/, staging".to_string(),
+            },
+            TestItem {
+                name: "switch".to_string(),
+                cmd: crate::cmd_auth::SubCommand::Switch(crate::cmd_auth::CmdAuthSwitch {
+                    profile: "staging".to_string(),
+                }),
+                stdin: "".to_string(),
+                want_out: "✔ Switched to profile staging (https://rack2.thing.com/)\n".to_string(),
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "list after switch".to_string(),
+                cmd: crate::cmd_auth::SubCommand::List(crate::cmd_auth::CmdAuthList { format: None }),
+                stdin: "".to_string(),
+                want_out: "  https://thing.com/  https://thing.com/\n* staging             https://rack2.thing.com/\n"
+                    .to_string(),
+                want_err: "".to_string(),
+            },
+        ];
+
+        let mut config = crate::config::new_blank_config().unwrap();
+        let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+        c.set("https://thing.com/", "token", "MY_TOKEN").unwrap();
+        c.set("staging", "host", "https://rack2.thing.com/").unwrap();
+        c.set("staging", "token", "STAGING_TOKEN").unwrap();
+
+        for t in tests {
+            let (mut io, stdout_path, stderr_path) = crate::iostreams::IoStreams::test();
+            io.set_color_enabled(false);
+            io.set_never_prompt(true);
+            let mut ctx = crate::context::Context {
+                config: &mut c,
+                io,
+                debug: false,
+                quiet: false,
+                profile: None,
+                api_clients: Default::default(),
+                timeout: None,
+                headers: Default::default(),
+                request_id_sent: Default::default(),
+                endpoint: None,
+            };
+
+            let cmd_auth = crate::cmd_auth::CmdAuth { subcmd: t.cmd };
+            let result = cmd_auth.run(&mut ctx).await;
+
+            let stdout = std::fs::read_to_string(stdout_path).unwrap();
+            let stderr = std::fs::read_to_string(stderr_path).unwrap();
+
+            assert_eq!(stdout, t.want_out, "test {}", t.name);
+            assert!(stderr.is_empty(), "test {}: {}", t.name, stderr);
+            match result {
+                Ok(()) => assert!(t.want_err.is_empty(), "test {}", t.name),
+                Err(err) => assert_eq!(err.to_string(), t.want_err, "test {}", t.name),
+            }
+        }
+    }
+
+    #[test]
+    fn test_read_token_file() {
+        let mut config = crate::config::new_blank_config().unwrap();
+        let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "MY_TOKEN  ").unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(file.path(), std::fs::Permissions::from_mode(0o600)).unwrap();
+        }
+
+        let (mut io, _stdout_path, stderr_path) = crate::iostreams::IoStreams::test();
+        io.set_color_enabled(false);
+        let mut ctx = crate::context::Context {
+            config: &mut c,
+            io,
+            debug: false,
+            quiet: false,
+            profile: None,
+            api_clients: Default::default(),
+            timeout: None,
+            headers: Default::default(),
+            request_id_sent: Default::default(),
+            endpoint: None,
+        };
+
+        let token = super::read_token_file(&mut ctx, file.path()).unwrap();
+        assert_eq!(token, "MY_TOKEN");
+        assert_eq!(std::fs::read_to_string(&stderr_path).unwrap(), "");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(file.path(), std::fs::Permissions::from_mode(0o644)).unwrap();
+
+            let token = super::read_token_file(&mut ctx, file.path()).unwrap();
+            assert_eq!(token, "MY_TOKEN");
+            assert!(std::fs::read_to_string(&stderr_path)
+                .unwrap()
+                .contains("is readable by anyone"));
+        }
+
+        let err = super::read_token_file(&mut ctx, std::path::Path::new("/does/not/exist")).unwrap_err();
+        assert!(err.to_string().starts_with("failed to read token file /does/not/exist"));
+    }
+
+    #[test]
+    fn test_hyperlink() {
+        let url = "https://oxide.internal/device/verify";
+        assert_eq!(super::hyperlink(url, false), url);
+        assert_eq!(
+            super::hyperlink(url, true),
+            "\x1b]8;;https://oxide.internal/device/verify\x1b\\https://oxide.internal/device/verify\x1b]8;;\x1b\\"
+        );
+    }
+
+    #[test]
+    fn test_qr_code() {
+        let url = "https://oxide.internal/device/verify?user_code=ABCD-EFGH";
+        let code = super::qr_code(url, 80).unwrap();
+        assert!(code.lines().count() > 1);
+
+        // Too narrow a terminal gets no code.
+        assert!(super::qr_code(url, 20).is_none());
+    }
+
+    #[test]
+    fn test_mask_token() {
+        use super::mask_token;
+
+        assert_eq!(mask_token("oxide-token-abcd"), "************abcd");
+        assert_eq!(mask_token("abcde"), "*bcde");
+        assert_eq!(mask_token("abcd"), "****");
+        assert_eq!(mask_token(""), "");
+    }
+
     #[test]
     fn test_parse_host() {
         use super::parse_host;
@@ -1,17 +1,32 @@
-use anyhow::Result;
-use clap::{Command, CommandFactory, Parser};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context as _, Result};
+use clap::{ArgEnum, Command, CommandFactory, Parser};
 use clap_complete::{generate, Shell};
 
+use crate::config_file::get_env_var;
+
 /// Generate shell completion scripts.
 ///
 /// When installing Oxide CLI through a package manager, it's possible that
 /// no additional shell configuration is necessary to gain completion support. For
 /// Homebrew, see <https://docs.brew.sh/Shell-Completion>.
 ///
+/// For bash, zsh, and fish, `oxide completion --install` writes the completion script
+/// to the conventional location for the shell, detected from `$SHELL` unless `--shell`
+/// is passed. Use `--print-path` to see where that is without writing anything.
+///
 /// If you need to set up completions manually, follow the instructions below. The exact
 /// config file locations might vary based on your system. Make sure to restart your
 /// shell before testing whether completions are working.
 ///
+/// For bash and fish, the completion scripts also complete the names of instances,
+/// disks, projects, and VPCs by calling back into `oxide`. Zsh, PowerShell, and elvish
+/// only complete commands and flags, resource names are not supported for them.
+///
 /// ### bash
 ///
 /// First, ensure that you install `bash-completion` using your package manager.
@@ -48,33 +63,399 @@ use clap_complete::{generate, Shell};
 /// Add the line and save the file:
 ///
 ///     Invoke-Expression -Command $(oxide completion -s powershell | Out-String)
+///
+/// ### elvish
+///
+/// Add this to your `~/.elvish/rc.elv`:
+///
+///     eval (oxide completion -s elvish | slurp)
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdCompletion {
-    /// Shell type: {bash|zsh|fish|powershell}
-    #[clap(short, long, default_value = "bash")]
-    pub shell: Shell,
+    /// Shell type: {bash|zsh|fish|powershell|elvish}
+    #[clap(short, long, parse(try_from_str = parse_shell))]
+    pub shell: Option<Shell>,
+
+    /// Write the completion script to the conventional location for the shell
+    #[clap(long)]
+    pub install: bool,
+
+    /// Print where `--install` would write the completion script, without writing it
+    #[clap(long, conflicts_with = "install")]
+    pub print_path: bool,
+}
+
+/// Parse a shell type, listing the supported shells if it is unknown.
+fn parse_shell(input: &str) -> Result<Shell> {
+    Shell::from_str(input, true).map_err(|_| {
+        let supported: Vec<&str> = Shell::value_variants()
+            .iter()
+            .filter_map(|shell| shell.to_possible_value())
+            .map(|value| value.get_name())
+            .collect();
+
+        anyhow!(
+            "unsupported shell `{}`, supported shells are: {}",
+            input,
+            supported.join(", ")
+        )
+    })
 }
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdCompletion {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
-        // Convert our opts into a clap app.
-        let mut app: Command = crate::Opts::command();
-        let name = app.get_name().to_string();
-        // Generate the completion script.
-        generate(self.shell, &mut app, name, &mut ctx.io.out);
+        if !self.install && !self.print_path {
+            let script = completion_script(self.shell.unwrap_or(Shell::Bash))?;
+            ctx.io.out.write_all(&script)?;
+            return Ok(());
+        }
+
+        // Installing is only useful for the shell the user is in, so detect it rather
+        // than defaulting to bash.
+        let shell = match self.shell {
+            Some(shell) => shell,
+            None => detect_shell(&get_env_var("SHELL"))?,
+        };
+
+        let home = dirs::home_dir().ok_or_else(|| anyhow!("could not find home directory"))?;
+        let path = install_path(
+            shell,
+            &home,
+            &get_env_var("XDG_CONFIG_HOME"),
+            &get_env_var("XDG_DATA_HOME"),
+        )?;
+
+        if self.print_path {
+            writeln!(ctx.io.out, "{}", path.display())?;
+            return Ok(());
+        }
+
+        let parent = path.parent().unwrap();
+        std::fs::create_dir_all(parent).with_context(|| format!("failed to create directory {}", parent.display()))?;
+        std::fs::write(&path, completion_script(shell)?)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+
+        let cs = ctx.io.color_scheme();
+        writeln!(
+            ctx.io.out,
+            "{} Wrote {} completions to {}",
+            cs.success_icon(),
+            shell,
+            path.display()
+        )?;
+
+        if shell == Shell::Zsh {
+            writeln!(
+                ctx.io.out,
+                "Make sure `fpath=({} $fpath)` comes before `compinit` in your ~/.zshrc, then restart your shell.",
+                parent.display()
+            )?;
+        } else {
+            writeln!(ctx.io.out, "Restart your shell for the completions to take effect.")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the completion script for a shell, including the hooks for completing
+/// resource names if the shell supports them.
+fn completion_script(shell: Shell) -> Result<Vec<u8>> {
+    // Convert our opts into a clap app.
+    let mut app: Command = crate::Opts::command();
+    let name = app.get_name().to_string();
+
+    let mut script: Vec<u8> = Vec::new();
+    generate(shell, &mut app, name, &mut script);
+
+    // Add the hooks for completing resource names.
+    if let Some(dynamic) = dynamic_completion_script(shell) {
+        writeln!(script, "{}", dynamic)?;
+    }
+
+    // Add a new line.
+    writeln!(script)?;
+
+    Ok(script)
+}
+
+/// Detect the shell from the value of `$SHELL`, like `/bin/zsh`.
+fn detect_shell(shell_var: &str) -> Result<Shell> {
+    let name = Path::new(shell_var)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    if name.is_empty() {
+        return Err(anyhow!("could not detect the shell from $SHELL, pass it with --shell"));
+    }
+
+    parse_shell(name)
+}
+
+/// Returns where `--install` writes the completion script for a shell. The XDG
+/// directories are passed in as they are in the environment, empty if they are not set.
+fn install_path(shell: Shell, home: &Path, xdg_config_home: &str, xdg_data_home: &str) -> Result<PathBuf> {
+    let xdg_config_home = if xdg_config_home.is_empty() {
+        home.join(".config")
+    } else {
+        PathBuf::from(xdg_config_home)
+    };
+    let xdg_data_home = if xdg_data_home.is_empty() {
+        home.join(".local").join("share")
+    } else {
+        PathBuf::from(xdg_data_home)
+    };
+
+    match shell {
+        Shell::Bash => Ok(xdg_data_home.join("bash-completion").join("completions").join("oxide")),
+        Shell::Zsh => Ok(home.join(".zsh").join("completions").join("_oxide")),
+        Shell::Fish => Ok(xdg_config_home.join("fish").join("completions").join("oxide.fish")),
+        _ => Err(anyhow!(
+            "--install is not supported for {}, see `oxide completion --help` to set up completions manually",
+            shell
+        )),
+    }
+}
+
+/// Returns the script that hooks a shell's completion into `oxide __complete`, if we
+/// support dynamic completion for that shell.
+fn dynamic_completion_script(shell: Shell) -> Option<&'static str> {
+    match shell {
+        Shell::Bash => Some(
+            r#"
+_oxide_dynamic() {
+    local candidates
+    candidates="$(oxide __complete -- "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null)"
+    if [ -n "$candidates" ]; then
+        COMPREPLY=($(compgen -W "$candidates" -- "${COMP_WORDS[COMP_CWORD]}"))
+        return 0
+    fi
+
+    _oxide "$@"
+}
+
+complete -F _oxide_dynamic -o bashdefault -o default oxide"#,
+        ),
+        Shell::Fish => Some(
+            r#"
+function __oxide_dynamic_complete
+    set -l tokens (commandline -opc)
+    oxide __complete -- $tokens[2..-1] (commandline -ct) 2>/dev/null
+end
+
+# Only the names of resources are completed dynamically, so files are still completed
+# for the commands and flags that take a path.
+function __oxide_completing_resource
+    set -l tokens (commandline -opc)
+    set -l resource (oxide __complete --check -- $tokens[2..-1] (commandline -ct) 2>/dev/null)
+    test -n "$resource"
+end
+
+complete -c oxide -n __oxide_completing_resource -f -a '(__oxide_dynamic_complete)'"#,
+        ),
+        _ => None,
+    }
+}
+
+/// Print completion candidates for a partial command line.
+///
+/// This is used by the shell completion scripts to complete resource names and is
+/// not meant to be called directly.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdComplete {
+    /// Only print the kind of resource the word being completed is the name of, without
+    /// calling the API. Nothing is printed if it is not the name of a resource.
+    #[clap(long)]
+    pub check: bool,
+
+    /// The partial command line, without the `oxide` binary name.
+    #[clap(multiple_values = true, allow_hyphen_values = true)]
+    pub args: Vec<String>,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdComplete {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let request = match parse_complete_args(&self.args) {
+            Some(request) => request,
+            None => return Ok(()),
+        };
 
-        // Add a new line.
-        writeln!(ctx.io.out)?;
+        if self.check {
+            writeln!(ctx.io.out, "{}", request.resource.name())?;
+            return Ok(());
+        }
+
+        let organization = if request.organization.is_empty() {
+            get_env_var("OXIDE_ORG")
+        } else {
+            request.organization
+        };
+        let project = if request.project.is_empty() {
+            get_env_var("OXIDE_PROJECT")
+        } else {
+            request.project
+        };
+
+        // Fall back to the defaults from the config.
+        let organization = ctx.resolve_organization(&organization)?;
+        let project = ctx.resolve_project(&project)?;
+
+        // We can't list anything without knowing where to look.
+        if organization.is_empty() || (request.resource != CompleteResource::Project && project.is_empty()) {
+            return Ok(());
+        }
+
+        let client = ctx.api_client("")?;
+
+        let names: Vec<String> = match request.resource {
+            CompleteResource::Instance => ctx
+                .timed(
+                    "GET",
+                    "/organizations/{organization_name}/projects/{project_name}/instances",
+                    client
+                        .instances()
+                        .get_all(&organization, &project, oxide_api::types::NameSortMode::NameAscending),
+                )
+                .await?
+                .into_iter()
+                .map(|instance| instance.name.to_string())
+                .collect(),
+            CompleteResource::Disk => ctx
+                .timed(
+                    "GET",
+                    "/organizations/{organization_name}/projects/{project_name}/disks",
+                    client
+                        .disks()
+                        .get_all(&organization, &project, oxide_api::types::NameSortMode::NameAscending),
+                )
+                .await?
+                .into_iter()
+                .map(|disk| disk.name.to_string())
+                .collect(),
+            CompleteResource::Project => ctx
+                .timed(
+                    "GET",
+                    "/organizations/{organization_name}/projects",
+                    client
+                        .projects()
+                        .get_all(&organization, oxide_api::types::NameOrIdSortMode::NameAscending),
+                )
+                .await?
+                .into_iter()
+                .map(|project| project.name.to_string())
+                .collect(),
+            CompleteResource::Vpc => ctx
+                .timed(
+                    "GET",
+                    "/organizations/{organization_name}/projects/{project_name}/vpcs",
+                    client
+                        .vpcs()
+                        .get_all(&organization, &project, oxide_api::types::NameSortMode::NameAscending),
+                )
+                .await?
+                .into_iter()
+                .map(|vpc| vpc.name.to_string())
+                .collect(),
+        };
+
+        for name in names.iter().filter(|name| name.starts_with(&request.prefix)) {
+            writeln!(ctx.io.out, "{}", name)?;
+        }
 
         Ok(())
     }
 }
 
+/// The resources we can complete the names of.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CompleteResource {
+    Instance,
+    Disk,
+    Project,
+    Vpc,
+}
+
+impl CompleteResource {
+    fn name(&self) -> &'static str {
+        match self {
+            CompleteResource::Instance => "instance",
+            CompleteResource::Disk => "disk",
+            CompleteResource::Project => "project",
+            CompleteResource::Vpc => "vpc",
+        }
+    }
+}
+
+/// A request to complete the name of a resource.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CompleteRequest {
+    resource: CompleteResource,
+    organization: String,
+    project: String,
+    prefix: String,
+}
+
+/// Parse a partial command line, like `instance view -o acme -p web fo`, into a
+/// completion request. Returns `None` if the word being completed is not the name of
+/// a resource we know how to complete.
+fn parse_complete_args(args: &[String]) -> Option<CompleteRequest> {
+    if args.len() < 3 {
+        return None;
+    }
+
+    let resource = match args[0].as_str() {
+        "instance" | "instances" => CompleteResource::Instance,
+        "disk" | "disks" => CompleteResource::Disk,
+        "project" | "projects" => CompleteResource::Project,
+        "vpc" | "vpcs" => CompleteResource::Vpc,
+        _ => return None,
+    };
+
+    // These commands don't take the name of an existing resource.
+    if matches!(args[1].as_str(), "create" | "list" | "help") {
+        return None;
+    }
+
+    let prefix = args[args.len() - 1].to_string();
+    if prefix.starts_with('-') {
+        return None;
+    }
+
+    let mut organization = String::new();
+    let mut project = String::new();
+
+    let mut words = args[2..args.len() - 1].iter();
+    while let Some(word) = words.next() {
+        match word.as_str() {
+            // If there is no next word, we are completing the value of the flag.
+            "-o" | "--organization" => organization = words.next()?.to_string(),
+            "-p" | "--project" => project = words.next()?.to_string(),
+            _ => {
+                if let Some(value) = word.strip_prefix("--organization=") {
+                    organization = value.to_string();
+                } else if let Some(value) = word.strip_prefix("--project=") {
+                    project = value.to_string();
+                } else if !word.starts_with('-') {
+                    // The name was already given.
+                    return None;
+                }
+            }
+        }
+    }
+
+    Some(CompleteRequest {
+        resource,
+        organization,
+        project,
+        prefix,
+    })
+}
+
 #[cfg(test)]
 mod test {
-    use clap::ArgEnum;
     use pretty_assertions::assert_eq;
 
     use crate::cmd::Command;
@@ -86,13 +467,171 @@ mod test {
         want_err: String,
     }
 
+    pub struct TestComplete {
+        name: String,
+        // The words of the command line, an empty last word means we are completing
+        // a new word.
+        cmdline: String,
+        want: Option<super::CompleteRequest>,
+    }
+
+    #[test]
+    fn test_parse_complete_args() {
+        let tests = vec![
+            TestComplete {
+                name: "too short".to_string(),
+                cmdline: "instance view".to_string(),
+                want: None,
+            },
+            TestComplete {
+                name: "unsupported resource".to_string(),
+                cmdline: "route view ".to_string(),
+                want: None,
+            },
+            TestComplete {
+                name: "list does not take a name".to_string(),
+                cmdline: "instance list ".to_string(),
+                want: None,
+            },
+            TestComplete {
+                name: "instance with flags".to_string(),
+                cmdline: "instance view -o acme -p web fo".to_string(),
+                want: Some(super::CompleteRequest {
+                    resource: super::CompleteResource::Instance,
+                    organization: "acme".to_string(),
+                    project: "web".to_string(),
+                    prefix: "fo".to_string(),
+                }),
+            },
+            TestComplete {
+                name: "disk with long flags".to_string(),
+                cmdline: "disks delete --organization=acme --project web ".to_string(),
+                want: Some(super::CompleteRequest {
+                    resource: super::CompleteResource::Disk,
+                    organization: "acme".to_string(),
+                    project: "web".to_string(),
+                    prefix: "".to_string(),
+                }),
+            },
+            TestComplete {
+                name: "project without flags".to_string(),
+                cmdline: "project view ".to_string(),
+                want: Some(super::CompleteRequest {
+                    resource: super::CompleteResource::Project,
+                    organization: "".to_string(),
+                    project: "".to_string(),
+                    prefix: "".to_string(),
+                }),
+            },
+            TestComplete {
+                name: "completing a flag value".to_string(),
+                cmdline: "vpc view -o ".to_string(),
+                want: None,
+            },
+            TestComplete {
+                name: "name already given".to_string(),
+                cmdline: "vpc view my-vpc ".to_string(),
+                want: None,
+            },
+        ];
+
+        for t in tests {
+            let args: Vec<String> = t.cmdline.split(' ').map(String::from).collect();
+            assert_eq!(super::parse_complete_args(&args), t.want, "test {}", t.name);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_cmd_complete_check() {
+        let tests = vec![
+            ("instance view -o acme -p web fo", "instance\n"),
+            ("disks delete --organization=acme --project web ", "disk\n"),
+            ("instance list ", ""),
+            ("image upload ", ""),
+        ];
+
+        for (cmdline, want) in tests {
+            let cmd = crate::cmd_completion::CmdComplete {
+                check: true,
+                args: cmdline.split(' ').map(String::from).collect(),
+            };
+
+            let (io, stdout_path, _) = crate::iostreams::IoStreams::test();
+            let mut config = crate::config::new_blank_config().unwrap();
+            let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+            let mut ctx = crate::context::Context {
+                config: &mut c,
+                io,
+                debug: false,
+                quiet: false,
+                profile: None,
+                api_clients: Default::default(),
+                timeout: None,
+                headers: Default::default(),
+                request_id_sent: Default::default(),
+                endpoint: None,
+            };
+
+            cmd.run(&mut ctx).await.unwrap();
+
+            let stdout = std::fs::read_to_string(&stdout_path).unwrap();
+            assert_eq!(stdout, want, "cmdline {:?}", cmdline);
+        }
+    }
+
+    #[test]
+    fn test_install_path() {
+        let home = std::path::Path::new("/home/oxide");
+
+        assert_eq!(super::detect_shell("/usr/bin/zsh").unwrap(), clap_complete::Shell::Zsh);
+        assert_eq!(
+            super::detect_shell("").unwrap_err().to_string(),
+            "could not detect the shell from $SHELL, pass it with --shell"
+        );
+
+        let tests = vec![
+            (clap_complete::Shell::Zsh, "", "", "/home/oxide/.zsh/completions/_oxide"),
+            (
+                clap_complete::Shell::Fish,
+                "",
+                "",
+                "/home/oxide/.config/fish/completions/oxide.fish",
+            ),
+            (
+                clap_complete::Shell::Fish,
+                "/tmp/xdg-config",
+                "",
+                "/tmp/xdg-config/fish/completions/oxide.fish",
+            ),
+            (
+                clap_complete::Shell::Bash,
+                "",
+                "",
+                "/home/oxide/.local/share/bash-completion/completions/oxide",
+            ),
+            (
+                clap_complete::Shell::Bash,
+                "",
+                "/tmp/xdg-data",
+                "/tmp/xdg-data/bash-completion/completions/oxide",
+            ),
+        ];
+
+        for (shell, xdg_config_home, xdg_data_home, want) in tests {
+            let path = super::install_path(shell, home, xdg_config_home, xdg_data_home).unwrap();
+            assert_eq!(path, std::path::PathBuf::from(want), "shell {}", shell);
+        }
+
+        assert!(super::install_path(clap_complete::Shell::Elvish, home, "", "").is_err());
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn test_cmd_completion_get() {
         let tests = vec![
             TestItem {
                 name: "bash completion".to_string(),
                 input: "bash".to_string(),
-                want_out: "complete -F _oxide -o bashdefault -o default oxide".to_string(),
+                want_out: "complete -F _oxide_dynamic -o bashdefault -o default oxide".to_string(),
                 want_err: "".to_string(),
             },
             TestItem {
@@ -104,7 +643,8 @@ mod test {
             TestItem {
                 name: "fish completion".to_string(),
                 input: "fish".to_string(),
-                want_out: "complete -c oxide ".to_string(),
+                want_out: "complete -c oxide -n __oxide_completing_resource -f -a '(__oxide_dynamic_complete)'"
+                    .to_string(),
                 want_err: "".to_string(),
             },
             TestItem {
@@ -113,22 +653,34 @@ mod test {
                 want_out: "Register-ArgumentCompleter".to_string(),
                 want_err: "".to_string(),
             },
+            TestItem {
+                name: "elvish completion".to_string(),
+                input: "elvish".to_string(),
+                want_out: "edit:completion:arg-completer[oxide]".to_string(),
+                want_err: "".to_string(),
+            },
             TestItem {
                 name: "unsupported shell".to_string(),
                 input: "csh".to_string(),
                 want_out: "".to_string(),
-                want_err: "Invalid variant: csh".to_string(),
+                want_err: "unsupported shell `csh`, supported shells are: bash, elvish, fish, powershell, zsh"
+                    .to_string(),
             },
         ];
 
         for t in tests {
-            if let Err(e) = clap_complete::Shell::from_str(&t.input, true) {
-                assert_eq!(e.to_string(), t.want_err, "test {}", t.name);
-                continue;
-            }
+            let shell = match crate::cmd_completion::parse_shell(&t.input) {
+                Ok(shell) => shell,
+                Err(e) => {
+                    assert_eq!(e.to_string(), t.want_err, "test {}", t.name);
+                    continue;
+                }
+            };
 
             let cmd = crate::cmd_completion::CmdCompletion {
-                shell: clap_complete::Shell::from_str(&t.input, true).unwrap(),
+                shell: Some(shell),
+                install: false,
+                print_path: false,
             };
 
             let (io, stdout_path, stderr_path) = crate::iostreams::IoStreams::test();
@@ -138,6 +690,13 @@ mod test {
                 config: &mut c,
                 io,
                 debug: false,
+                quiet: false,
+                profile: None,
+                api_clients: Default::default(),
+                timeout: None,
+                headers: Default::default(),
+                request_id_sent: Default::default(),
+                endpoint: None,
             };
 
             cmd.run(&mut ctx).await.unwrap();
@@ -1,4 +1,6 @@
-use anyhow::{bail, Result};
+use std::{fs, io::Write};
+
+use anyhow::{anyhow, bail, Context, Result};
 use clap::Parser;
 
 // TODO: make this doc a function that parses from the config the options so it's not hardcoded
@@ -9,6 +11,10 @@ use clap::Parser;
 /// - prompt: toggle interactive prompting in the terminal (default: "enabled")
 /// - browser: the web browser to use for opening URLs
 /// - format: the formatting style for command output
+/// - update_check_interval: how often to check for a new release of oxide (default: "24h")
+///
+/// Keys can also be given in dotted form to refer to aliases or per-host settings,
+/// for example `aliases.foo` or `hosts.rack1.token`.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdConfig {
@@ -21,6 +27,8 @@ enum SubCommand {
     Set(CmdConfigSet),
     List(CmdConfigList),
     Get(CmdConfigGet),
+    Edit(CmdConfigEdit),
+    ClearCache(CmdConfigClearCache),
 }
 
 #[async_trait::async_trait]
@@ -30,11 +38,68 @@ impl crate::cmd::Command for CmdConfig {
             SubCommand::Get(cmd) => cmd.run(ctx).await,
             SubCommand::Set(cmd) => cmd.run(ctx).await,
             SubCommand::List(cmd) => cmd.run(ctx).await,
+            SubCommand::Edit(cmd) => cmd.run(ctx).await,
+            SubCommand::ClearCache(cmd) => cmd.run(ctx).await,
+        }
+    }
+}
+
+/// Keys that are only set per-host, in addition to the global options.
+const HOST_KEYS: &[&str] = &["token", "user", "host", "default", "default_org", "default_project"];
+
+/// Keys that hold secrets, these are masked when listing the configuration.
+const SECRET_KEYS: &[&str] = &["token"];
+
+/// A configuration key, parsed from its dotted form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ConfigKey {
+    /// A global setting, like `editor`.
+    Global(String),
+    /// A per-host setting, like `hosts.rack1.token`.
+    Host { host: String, key: String },
+    /// An alias, like `aliases.foo`.
+    Alias(String),
+}
+
+/// Parse a configuration key. If a host is given with `--host`, the key is always a
+/// setting for that host.
+fn parse_config_key(host: &str, key: &str) -> Result<ConfigKey> {
+    if !host.is_empty() {
+        return Ok(ConfigKey::Host {
+            host: host.to_string(),
+            key: key.to_string(),
+        });
+    }
+
+    if let Some(alias) = key.strip_prefix("aliases.") {
+        if alias.is_empty() {
+            bail!("invalid key: {}", key);
         }
+
+        return Ok(ConfigKey::Alias(alias.to_string()));
+    }
+
+    if let Some(rest) = key.strip_prefix("hosts.") {
+        // Hosts can contain dots themselves, so the key is the last part.
+        return match rest.rsplit_once('.') {
+            Some((host, key)) if !host.is_empty() && !key.is_empty() => Ok(ConfigKey::Host {
+                host: host.trim_matches('"').to_string(),
+                key: key.to_string(),
+            }),
+            _ => Err(anyhow!("invalid key: {}, expected hosts.<host>.<key>", key)),
+        };
     }
+
+    Ok(ConfigKey::Global(key.to_string()))
 }
 
 /// Print the value of a given configuration key.
+///
+/// Use dotted keys to get aliases or per-host settings.
+///
+///     $ oxide config get editor
+///     $ oxide config get aliases.foo
+///     $ oxide config get hosts.rack1.user
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdConfigGet {
@@ -50,7 +115,20 @@ pub struct CmdConfigGet {
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdConfigGet {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
-        match ctx.config.get(&self.host, &self.key) {
+        let value = match parse_config_key(&self.host, &self.key)? {
+            ConfigKey::Global(key) => ctx.config.get("", &key),
+            ConfigKey::Host { host, key } => ctx.config.get(&host, &key),
+            ConfigKey::Alias(alias) => {
+                let (expansion, ok) = ctx.config.aliases()?.get(&alias);
+                if ok {
+                    Ok(expansion)
+                } else {
+                    Err(anyhow!("Key '{}' not found", self.key))
+                }
+            }
+        };
+
+        match value {
             Ok(value) => writeln!(ctx.io.out, "{}", value)?,
             Err(err) => {
                 bail!("{}", err);
@@ -62,6 +140,14 @@ impl crate::cmd::Command for CmdConfigGet {
 }
 
 /// Update configuration with a value for the given key.
+///
+/// Use dotted keys to set aliases or per-host settings.
+///
+///     $ oxide config set editor vim
+///     $ oxide config set aliases.foo "instance list"
+///     $ oxide config set hosts.rack1.default true
+///     $ oxide config set -H rack1 default_org maze-war
+///     $ oxide config set -H rack1 default_project development
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdConfigSet {
@@ -83,25 +169,36 @@ impl crate::cmd::Command for CmdConfigSet {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
         let cs = ctx.io.color_scheme();
 
-        // Validate the key.
-        match crate::config::validate_key(&self.key) {
-            Ok(()) => (),
-            Err(_) => {
-                bail!(
-                    "{} warning: '{}' is not a known configuration key",
-                    cs.warning_icon(),
-                    self.key
-                );
+        let (host, key) = match parse_config_key(&self.host, &self.key)? {
+            ConfigKey::Global(key) => ("".to_string(), key),
+            ConfigKey::Host { host, key } => (host, key),
+            ConfigKey::Alias(alias) => {
+                // Adding the alias also writes the config file.
+                if let Err(err) = ctx.config.aliases()?.add(&alias, &self.value) {
+                    bail!("{}", err);
+                }
+
+                return Ok(());
             }
+        };
+
+        // Validate the key.
+        let is_host_key = !host.is_empty() && HOST_KEYS.contains(&key.as_str());
+        if !is_host_key && crate::config::validate_key(&key).is_err() {
+            bail!(
+                "{} warning: '{}' is not a known configuration key",
+                cs.warning_icon(),
+                key
+            );
         }
 
         // Validate the value.
-        if let Err(err) = crate::config::validate_value(&self.key, &self.value) {
+        if let Err(err) = crate::config::validate_value(&key, &self.value) {
             bail!("{}", err);
         }
 
         // Set the value.
-        if let Err(err) = ctx.config.set(&self.host, &self.key, &self.value) {
+        if let Err(err) = ctx.config.set(&host, &key, &self.value) {
             bail!("{}", err);
         }
 
@@ -115,43 +212,250 @@ impl crate::cmd::Command for CmdConfigSet {
 }
 
 /// Print a list of configuration keys and values.
+///
+/// The configuration is printed in the same format as the config file. Secrets,
+/// like tokens, are masked unless `--show-secrets` is passed.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdConfigList {
     /// Get per-host configuration.
     #[clap(short = 'H', long, default_value = "")]
     pub host: String,
+
+    /// Show secrets, like tokens, instead of masking them.
+    #[clap(long)]
+    pub show_secrets: bool,
 }
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdConfigList {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
-        let host = if self.host.is_empty() {
-            // We don't want to do the default host here since we want to show the default's for
-            // all hosts, even if OXIDE_HOST is set.
-            // TODO: in this case we should print all the hosts configs, not just the default.
-            "".to_string()
-        } else {
-            self.host.to_string()
-        };
+        let mut hosts = ctx
+            .config
+            .hosts_to_string()?
+            .parse::<toml_edit::Document>()?
+            .as_table()
+            .clone();
 
-        for option in crate::config::config_options() {
-            match ctx.config.get(&host, &option.key) {
-                Ok(value) => writeln!(ctx.io.out, "{}={}", option.key, value)?,
-                Err(err) => {
-                    if host.is_empty() {
-                        // Only bail if the host is empty, since some hosts may not have
-                        // all the options.
-                        bail!("{}", err);
+        if !self.show_secrets {
+            for (_, host) in hosts.iter_mut() {
+                if let Some(host) = host.as_table_mut() {
+                    mask_secrets(host);
+                }
+            }
+        }
+
+        let mut doc = toml_edit::Document::new();
+
+        if !self.host.is_empty() {
+            // Print the configuration for the host, or the profile pointing to it.
+            let host = hosts
+                .iter()
+                .find(|(name, table)| {
+                    *name == self.host || table.get("host").and_then(|h| h.as_str()) == Some(self.host.as_str())
+                })
+                .and_then(|(_, table)| table.as_table().cloned());
+
+            match host {
+                Some(host) => {
+                    for (key, value) in host.iter() {
+                        doc[key] = value.clone();
                     }
                 }
+                None => bail!("host `{}` not found", self.host),
+            }
+        } else {
+            // We don't want to do the default host here since we want to show the default's for
+            // all hosts, even if OXIDE_HOST is set.
+            for option in crate::config::config_options() {
+                match ctx.config.get("", &option.key) {
+                    Ok(value) => doc[&option.key] = toml_edit::value(value),
+                    Err(err) => bail!("{}", err),
+                }
+            }
+
+            let aliases = ctx.config.aliases()?;
+            if !aliases.map.is_empty() {
+                doc["aliases"] = toml_edit::Item::Table(aliases.map.root.clone());
+            }
+
+            if !hosts.is_empty() {
+                hosts.set_implicit(true);
+                doc["hosts"] = toml_edit::Item::Table(hosts);
             }
         }
 
+        write!(ctx.io.out, "{}", doc)?;
+
         Ok(())
     }
 }
 
+/// Open the configuration in your editor.
+///
+/// The editor is taken from the `editor` setting, then the `VISUAL` and `EDITOR`
+/// environment variables, falling back to `vi` (`notepad` on Windows).
+///
+/// The edited configuration is validated before it replaces your config, if it
+/// is invalid your config is left untouched.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdConfigEdit {}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdConfigEdit {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let editor = get_editor(
+            &ctx.config.get("", "editor").unwrap_or_default(),
+            &crate::config_file::get_env_var("VISUAL"),
+            &crate::config_file::get_env_var("EDITOR"),
+        );
+
+        // Write the merged config and hosts to a temporary file for editing.
+        let mut doc = crate::config_file::parse(&ctx.config.config_to_string()?)?;
+        let mut hosts = crate::config_file::parse(&ctx.config.hosts_to_string()?)?
+            .as_table()
+            .clone();
+        if !hosts.is_empty() {
+            hosts.set_implicit(true);
+            doc["hosts"] = toml_edit::Item::Table(hosts);
+        }
+
+        // The config has the tokens in it, so the file gets a random name and is only
+        // readable by us.
+        let mut tmp_file = tempfile::Builder::new()
+            .prefix("oxide-config.")
+            .suffix(".toml")
+            .tempfile()
+            .context("failed to create a temporary file for the config")?;
+        tmp_file
+            .write_all(doc.to_string().as_bytes())
+            .and_then(|_| tmp_file.flush())
+            .with_context(|| format!("failed to write to {}", tmp_file.path().display()))?;
+
+        // The temporary file is removed when it is dropped, regardless of whether the
+        // edit succeeded.
+        edit_config(ctx, &editor, tmp_file.path())
+    }
+}
+
+/// Remove the files oxide caches, like the state of the update check.
+///
+/// Your configuration, hosts, and aliases are left untouched. The caches are rebuilt
+/// as they are needed, so the next update check happens on the next run.
+///
+/// Pass `--dry-run` to print what would be removed without removing it.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdConfigClearCache {
+    /// Print what would be removed, without removing anything.
+    #[clap(long)]
+    pub dry_run: bool,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdConfigClearCache {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let files = crate::config_file::cache_files()?;
+        clear_cache(ctx, &files, self.dry_run)
+    }
+}
+
+/// Remove the cache files that exist, printing each one.
+fn clear_cache(ctx: &mut crate::context::Context, files: &[String], dry_run: bool) -> Result<()> {
+    let cs = ctx.io.color_scheme();
+
+    let existing: Vec<&String> = files.iter().filter(|f| std::path::Path::new(f).exists()).collect();
+    if existing.is_empty() {
+        writeln!(ctx.io.out, "Nothing to remove, the cache is empty.")?;
+        return Ok(());
+    }
+
+    for file in existing {
+        if dry_run {
+            writeln!(ctx.io.out, "Would remove {}", file)?;
+            continue;
+        }
+
+        fs::remove_file(file).with_context(|| format!("failed to remove {}", file))?;
+        writeln!(ctx.io.out, "{} Removed {}", cs.success_icon(), file)?;
+    }
+
+    Ok(())
+}
+
+/// Launch the editor on the file until it contains a valid config, then write it.
+fn edit_config(ctx: &mut crate::context::Context, editor: &str, filename: &std::path::Path) -> Result<()> {
+    let cs = ctx.io.color_scheme();
+
+    let args = shlex::split(editor).unwrap_or_default();
+    if args.is_empty() {
+        bail!("invalid editor: `{}`", editor);
+    }
+
+    loop {
+        let status = std::process::Command::new(&args[0])
+            .args(&args[1..])
+            .arg(filename)
+            .stdin(std::process::Stdio::inherit())
+            .stdout(std::process::Stdio::inherit())
+            .stderr(std::process::Stdio::inherit())
+            .status()
+            .with_context(|| format!("failed to launch editor `{}`", editor))?;
+        if !status.success() {
+            bail!("editor `{}` exited with {}, config was not changed", editor, status);
+        }
+
+        let contents =
+            fs::read_to_string(filename).with_context(|| format!("failed to read from {}", filename.display()))?;
+
+        let err = match crate::config_file::parse(&contents) {
+            Ok(doc) => return crate::config::new_config(doc).write(),
+            Err(err) => err,
+        };
+
+        writeln!(ctx.io.err_out, "{} Invalid config: {}", cs.failure_icon(), err)?;
+
+        if !ctx.io.can_prompt() {
+            bail!("config was not changed");
+        }
+
+        match dialoguer::Confirm::new()
+            .with_prompt("Do you want to re-open the editor?")
+            .default(true)
+            .interact()
+        {
+            Ok(true) => {}
+            Ok(false) => bail!("config was not changed"),
+            Err(err) => bail!("prompt failed: {}", err),
+        }
+    }
+}
+
+/// Get the editor to use, preferring the `editor` setting over the environment.
+fn get_editor(config_editor: &str, visual: &str, editor: &str) -> String {
+    for e in [config_editor, visual, editor] {
+        if !e.is_empty() {
+            return e.to_string();
+        }
+    }
+
+    if std::env::consts::OS == "windows" {
+        "notepad".to_string()
+    } else {
+        "vi".to_string()
+    }
+}
+
+/// Mask the secret values in a host's configuration.
+fn mask_secrets(host: &mut toml_edit::Table) {
+    for key in SECRET_KEYS {
+        if let Some(value) = host.get(key).and_then(|v| v.as_str()).map(crate::cmd_auth::mask_token) {
+            host[*key] = toml_edit::value(value);
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use pretty_assertions::assert_eq;
@@ -170,8 +474,11 @@ mod test {
         let tests: Vec<TestItem> = vec![
             TestItem {
                 name: "list empty".to_string(),
-                cmd: crate::cmd_config::SubCommand::List(crate::cmd_config::CmdConfigList { host: "".to_string() }),
-                want_out: "editor=\nprompt=enabled\nbrowser=\nformat=table\n".to_string(),
+                cmd: crate::cmd_config::SubCommand::List(crate::cmd_config::CmdConfigList {
+                    host: "".to_string(),
+                    show_secrets: false,
+                }),
+                want_out: "editor = \"\"\nprompt = \"enabled\"\nbrowser = \"\"\nformat = \"table\"\n".to_string(),
                 want_err: "".to_string(),
             },
             TestItem {
@@ -233,8 +540,77 @@ mod test {
             },
             TestItem {
                 name: "list all default".to_string(),
-                cmd: crate::cmd_config::SubCommand::List(crate::cmd_config::CmdConfigList { host: "".to_string() }),
-                want_out: "editor=\nprompt=enabled\nbrowser=bar\nformat=table\n".to_string(),
+                cmd: crate::cmd_config::SubCommand::List(crate::cmd_config::CmdConfigList {
+                    host: "".to_string(),
+                    show_secrets: false,
+                }),
+                want_out: "editor = \"\"\nprompt = \"enabled\"\nbrowser = \"bar\"\nformat = \"table\"\n".to_string(),
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "set a dotted host key".to_string(),
+                cmd: crate::cmd_config::SubCommand::Set(crate::cmd_config::CmdConfigSet {
+                    key: "hosts.example.org.token".to_string(),
+                    value: "MY_SECRET_TOKEN".to_string(),
+                    host: "".to_string(),
+                }),
+                want_out: "".to_string(),
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "get a dotted host key".to_string(),
+                cmd: crate::cmd_config::SubCommand::Get(crate::cmd_config::CmdConfigGet {
+                    key: "hosts.example.org.prompt".to_string(),
+                    host: "".to_string(),
+                }),
+                want_out: "disabled\n".to_string(),
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "set an unknown dotted host key".to_string(),
+                cmd: crate::cmd_config::SubCommand::Set(crate::cmd_config::CmdConfigSet {
+                    key: "hosts.example.org.foo".to_string(),
+                    value: "bar".to_string(),
+                    host: "".to_string(),
+                }),
+                want_out: "".to_string(),
+                want_err: "warning: 'foo' is not a known configuration key".to_string(),
+            },
+            TestItem {
+                name: "list masks secrets".to_string(),
+                cmd: crate::cmd_config::SubCommand::List(crate::cmd_config::CmdConfigList {
+                    host: "".to_string(),
+                    show_secrets: false,
+                }),
+                want_out: "token = \"***********OKEN\"".to_string(),
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "list host shows secrets".to_string(),
+                cmd: crate::cmd_config::SubCommand::List(crate::cmd_config::CmdConfigList {
+                    host: "example.org".to_string(),
+                    show_secrets: true,
+                }),
+                want_out: "prompt = \"disabled\"\ntoken = \"MY_SECRET_TOKEN\"\n".to_string(),
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "set an alias".to_string(),
+                cmd: crate::cmd_config::SubCommand::Set(crate::cmd_config::CmdConfigSet {
+                    key: "aliases.il".to_string(),
+                    value: "instance list".to_string(),
+                    host: "".to_string(),
+                }),
+                want_out: "".to_string(),
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "get an alias".to_string(),
+                cmd: crate::cmd_config::SubCommand::Get(crate::cmd_config::CmdConfigGet {
+                    key: "aliases.il".to_string(),
+                    host: "".to_string(),
+                }),
+                want_out: "instance list\n".to_string(),
                 want_err: "".to_string(),
             },
         ];
@@ -248,6 +624,13 @@ mod test {
                 config: &mut c,
                 io,
                 debug: false,
+                quiet: false,
+                profile: None,
+                api_clients: Default::default(),
+                timeout: None,
+                headers: Default::default(),
+                request_id_sent: Default::default(),
+                endpoint: None,
             };
 
             let cmd_config = crate::cmd_config::CmdConfig { subcmd: t.cmd };
@@ -268,4 +651,102 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_clear_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = dir.path().join("state.toml").to_str().unwrap().to_string();
+        let missing = dir.path().join("missing.toml").to_str().unwrap().to_string();
+        std::fs::write(&state, "").unwrap();
+
+        let mut config = crate::config::new_blank_config().unwrap();
+        let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+
+        let files = vec![state.to_string(), missing];
+        for (dry_run, want_out) in [
+            (true, format!("Would remove {}\n", state)),
+            (false, format!("✔ Removed {}\n", state)),
+            (false, "Nothing to remove, the cache is empty.\n".to_string()),
+        ] {
+            let (mut io, stdout_path, _) = crate::iostreams::IoStreams::test();
+            io.set_color_enabled(false);
+            let mut ctx = crate::context::Context {
+                config: &mut c,
+                io,
+                debug: false,
+                quiet: false,
+                profile: None,
+                api_clients: Default::default(),
+                timeout: None,
+                headers: Default::default(),
+                request_id_sent: Default::default(),
+                endpoint: None,
+            };
+
+            super::clear_cache(&mut ctx, &files, dry_run).unwrap();
+
+            assert_eq!(std::fs::read_to_string(stdout_path).unwrap(), want_out);
+        }
+
+        assert!(!std::path::Path::new(&state).exists());
+    }
+
+    #[test]
+    fn test_get_editor() {
+        use super::get_editor;
+
+        assert_eq!(get_editor("vim", "code --wait", "nano"), "vim");
+        assert_eq!(get_editor("", "code --wait", "nano"), "code --wait");
+        assert_eq!(get_editor("", "", "nano"), "nano");
+
+        let default = if std::env::consts::OS == "windows" {
+            "notepad"
+        } else {
+            "vi"
+        };
+        assert_eq!(get_editor("", "", ""), default);
+    }
+
+    #[test]
+    fn test_parse_config_key() {
+        use super::{parse_config_key, ConfigKey};
+
+        assert_eq!(
+            parse_config_key("", "editor").unwrap(),
+            ConfigKey::Global("editor".to_string())
+        );
+        assert_eq!(
+            parse_config_key("", "aliases.foo").unwrap(),
+            ConfigKey::Alias("foo".to_string())
+        );
+        assert_eq!(
+            parse_config_key("", "hosts.rack1.token").unwrap(),
+            ConfigKey::Host {
+                host: "rack1".to_string(),
+                key: "token".to_string(),
+            }
+        );
+        assert_eq!(
+            parse_config_key("", "hosts.rack1.example.com.token").unwrap(),
+            ConfigKey::Host {
+                host: "rack1.example.com".to_string(),
+                key: "token".to_string(),
+            }
+        );
+        assert_eq!(
+            parse_config_key("example.org", "prompt").unwrap(),
+            ConfigKey::Host {
+                host: "example.org".to_string(),
+                key: "prompt".to_string(),
+            }
+        );
+        assert_eq!(
+            parse_config_key("", "hosts.rack1").unwrap_err().to_string(),
+            "invalid key: hosts.rack1, expected hosts.<host>.<key>"
+        );
+        assert_eq!(
+            parse_config_key("", "aliases.").unwrap_err().to_string(),
+            "invalid key: aliases."
+        );
+    }
 }
@@ -1,12 +1,12 @@
-use std::io::Write;
+use std::{collections::HashMap, io::Write};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use clap::Parser;
 use cli_macro::crud_gen;
 
 /// Create, list, edit, view, and delete disks.
 ///
-/// Additionally, attach and detach disks to an instance.
+/// Additionally, attach and detach disks to an instance, and snapshot them.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdDisk {
@@ -14,6 +14,9 @@ pub struct CmdDisk {
     subcmd: SubCommand,
 }
 
+// TODO: add `disk import` to upload a local image into a new disk once the API has a
+// bulk write or import endpoint for disks, there is none in spec.json yet.
+// TODO: add `disk resize` once the API has an endpoint to change the size of a disk.
 #[crud_gen {
     tag = "disks",
 }]
@@ -22,6 +25,8 @@ enum SubCommand {
     Attach(CmdDiskAttach),
     Detach(CmdDiskDetach),
     Edit(CmdDiskEdit),
+    List(CmdDiskList),
+    Snapshot(CmdDiskSnapshot),
 }
 
 #[async_trait::async_trait]
@@ -34,6 +39,7 @@ impl crate::cmd::Command for CmdDisk {
             SubCommand::Detach(cmd) => cmd.run(ctx).await,
             SubCommand::Edit(cmd) => cmd.run(ctx).await,
             SubCommand::List(cmd) => cmd.run(ctx).await,
+            SubCommand::Snapshot(cmd) => cmd.run(ctx).await,
             SubCommand::View(cmd) => cmd.run(ctx).await,
         }
     }
@@ -54,33 +60,47 @@ pub struct CmdDiskAttach {
     instance: String,
 
     /// The project that holds the disk and instance.
-    #[clap(long, short, required = true)]
+    #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
     pub project: String,
 
     /// The organization that holds the project.
-    #[clap(long, short, required = true, env = "OXIDE_ORG")]
+    #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
     pub organization: String,
 }
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdDiskAttach {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let organization = ctx.resolve_organization(&self.organization)?;
+        if organization.is_empty() {
+            return Err(anyhow!("-o|--organization required"));
+        }
+
+        let project = ctx.resolve_project(&self.project)?;
+        if project.is_empty() {
+            return Err(anyhow!("-p|--project required"));
+        }
+
         let client = ctx.api_client("")?;
+        let organization = ctx.organization_name(&client, &organization).await?;
+        let project = ctx.project_name(&client, &project).await?;
 
-        let full_name = format!("{}/{}", self.organization, self.project);
+        let full_name = format!("{}/{}", organization, project);
 
         // Attach the disk.
-        client
-            .instances()
-            .disks_attach(
+        ctx.timed(
+            "POST",
+            "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/disks/attach",
+            client.instances().disks_attach(
                 &self.instance,
-                &self.organization,
-                &self.project,
+                &organization,
+                &project,
                 &oxide_api::types::DiskIdentifier {
                     name: self.disk.to_string(),
                 },
-            )
-            .await?;
+            ),
+        )
+        .await?;
 
         let cs = ctx.io.color_scheme();
         writeln!(
@@ -109,33 +129,47 @@ pub struct CmdDiskDetach {
     instance: String,
 
     /// The project that holds the disk and instance.
-    #[clap(long, short, required = true)]
+    #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
     pub project: String,
 
     /// The organization that holds the project.
-    #[clap(long, short, required = true, env = "OXIDE_ORG")]
+    #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
     pub organization: String,
 }
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdDiskDetach {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let organization = ctx.resolve_organization(&self.organization)?;
+        if organization.is_empty() {
+            return Err(anyhow!("-o|--organization required"));
+        }
+
+        let project = ctx.resolve_project(&self.project)?;
+        if project.is_empty() {
+            return Err(anyhow!("-p|--project required"));
+        }
+
         let client = ctx.api_client("")?;
+        let organization = ctx.organization_name(&client, &organization).await?;
+        let project = ctx.project_name(&client, &project).await?;
 
-        let full_name = format!("{}/{}", self.organization, self.project);
+        let full_name = format!("{}/{}", organization, project);
 
         // Detach the disk.
-        client
-            .instances()
-            .disks_detach(
+        ctx.timed(
+            "POST",
+            "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/disks/detach",
+            client.instances().disks_detach(
                 &self.instance,
-                &self.organization,
-                &self.project,
+                &organization,
+                &project,
                 &oxide_api::types::DiskIdentifier {
                     name: self.disk.to_string(),
                 },
-            )
-            .await?;
+            ),
+        )
+        .await?;
 
         let cs = ctx.io.color_scheme();
         writeln!(
@@ -151,6 +185,368 @@ impl crate::cmd::Command for CmdDiskDetach {
     }
 }
 
+/// List disks.
+///
+/// The `attachment` column has the name of the instance the disk is attached to,
+/// or "-" when it is not attached to one.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdDiskList {
+    /// The project that holds the disks.
+    #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
+    pub project: String,
+
+    /// The organization that holds the project.
+    #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
+    pub organization: String,
+
+    /// Only list the disks attached to this instance.
+    #[clap(long, short, default_value = "")]
+    pub instance: String,
+
+    /// The order in which to sort the results.
+    #[clap(long = "sort-by", short = 's', default_value_t)]
+    pub sort_by: oxide_api::types::NameSortMode,
+
+    /// Maximum number of items to list.
+    #[clap(long, short, default_value = "30")]
+    pub limit: u32,
+
+    /// Make additional HTTP requests to fetch all pages.
+    #[clap(long)]
+    pub paginate: bool,
+
+    /// Start at the page with this token, from the `next_page` of an earlier listing. With
+    /// `--format json`, the page is printed as the API returns it, with the token of the
+    /// page after it in `next_page`. Pass an empty token for the first page.
+    #[clap(long, conflicts_with = "paginate")]
+    pub page_token: Option<String>,
+
+    /// Display output in json, jsonl, yaml, table, csv, tsv, or template format.
+    #[clap(long, short)]
+    pub format: Option<crate::types::FormatOutput>,
+
+    /// The template to render each item with, for example '{{.name}} {{.id}}'.
+    #[clap(long)]
+    pub template: Option<String>,
+
+    /// The columns to display, for example 'name,id'. Defaults to all of them.
+    #[clap(long, use_value_delimiter = true)]
+    pub columns: Vec<String>,
+
+    /// Do not print the header row of table, csv, or tsv output.
+    #[clap(long)]
+    pub no_header: bool,
+
+    /// Sort the output by this column, for example 'time_created'. Any column works, the
+    /// items are sorted after they are fetched.
+    #[clap(long, default_value = "")]
+    pub sort: String,
+
+    /// Reverse the order of the output.
+    #[clap(long)]
+    pub reverse: bool,
+
+    /// Only list the items with a field equal to a value, as 'key=value', or containing
+    /// it, as 'key~value'. Can be passed more than once, items have to match all of them.
+    #[clap(long)]
+    pub filter: Vec<String>,
+
+    /// Write the output to this file instead of standard output.
+    #[clap(long, default_value = "")]
+    pub output_file: String,
+}
+
+/// A disk with the name of the instance it is attached to, as `disk list` prints it.
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct DiskAttachment {
+    #[serde(flatten)]
+    pub disk: oxide_api::types::Disk,
+    /// The name of the instance the disk is attached to, or "-".
+    pub attachment: String,
+}
+
+impl tabled::Tabled for DiskAttachment {
+    fn fields(&self) -> Vec<String> {
+        let mut fields = tabled::Tabled::fields(&self.disk);
+        fields.push(self.attachment.to_string());
+        fields
+    }
+
+    fn headers() -> Vec<String> {
+        let mut headers = <oxide_api::types::Disk as tabled::Tabled>::headers();
+        headers.push("attachment".to_string());
+        headers
+    }
+}
+
+/// The ID of the instance a disk is attached to, or being attached to or detached from.
+fn attached_instance(disk: &oxide_api::types::Disk) -> Option<String> {
+    // The state is `{"state": "attached", "instance": "<id>"}` in the API, go through
+    // that rather than match all the states that have an instance.
+    match serde_json::to_value(&disk.state) {
+        Ok(serde_json::Value::Object(state)) => state.get("instance").and_then(|i| i.as_str()).map(String::from),
+        _ => None,
+    }
+}
+
+impl CmdDiskList {
+    /// Add the attachment to each disk. Without `--instance`, this lists the instances of
+    /// the project to get their names, but only if any of the disks is attached.
+    async fn with_attachments(
+        &self,
+        ctx: &crate::context::Context<'_>,
+        client: &oxide_api::Client,
+        organization: &str,
+        project: &str,
+        disks: Vec<oxide_api::types::Disk>,
+    ) -> Result<Vec<DiskAttachment>> {
+        let mut names: HashMap<String, String> = HashMap::new();
+        if self.instance.is_empty() && disks.iter().any(|disk| attached_instance(disk).is_some()) {
+            let instances = ctx
+                .timed(
+                    "GET",
+                    "/organizations/{organization_name}/projects/{project_name}/instances",
+                    client
+                        .instances()
+                        .get_all(organization, project, oxide_api::types::NameSortMode::NameAscending),
+                )
+                .await?;
+            for instance in instances {
+                names.insert(instance.id.to_string(), instance.name.to_string());
+            }
+        }
+
+        Ok(disks
+            .into_iter()
+            .map(|disk| {
+                let attachment = match attached_instance(&disk) {
+                    Some(_) if !self.instance.is_empty() => self.instance.to_string(),
+                    // The instance can be deleted while we list, show its ID then.
+                    Some(id) => names.get(&id).cloned().unwrap_or(id),
+                    None => "-".to_string(),
+                };
+                DiskAttachment { disk, attachment }
+            })
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdDiskList {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        if self.limit < 1 {
+            return Err(anyhow!("--limit must be greater than 0"));
+        }
+
+        let organization = ctx.resolve_organization(&self.organization)?;
+        if organization.is_empty() {
+            return Err(anyhow!("-o|--organization required"));
+        }
+
+        let project = ctx.resolve_project(&self.project)?;
+        if project.is_empty() {
+            return Err(anyhow!("-p|--project required"));
+        }
+
+        let client = ctx.api_client("")?;
+        let organization = ctx.organization_name(&client, &organization).await?;
+        let project = ctx.project_name(&client, &project).await?;
+        let format = ctx.format(&self.format, &self.template)?;
+
+        if let Some(page_token) = &self.page_token {
+            if format == crate::types::FormatOutput::Json {
+                let endpoint = if self.instance.is_empty() {
+                    format!("/organizations/{}/projects/{}/disks", organization, project)
+                } else {
+                    format!(
+                        "/organizations/{}/projects/{}/instances/{}/disks",
+                        organization, project, self.instance
+                    )
+                };
+                let page =
+                    crate::cmd_api::get_page(ctx, &client, &endpoint, self.limit, &self.sort_by, page_token).await?;
+                let disks = serde_json::from_value(serde_json::Value::Array(page.items))?;
+                let items = self
+                    .with_attachments(ctx, &client, &organization, &project, disks)
+                    .await?;
+                let items = crate::iostreams::filter_by_fields(items, &self.filter)?;
+                let items = crate::iostreams::sort_by_column(items, &self.sort, self.reverse)?;
+                let items = items
+                    .into_iter()
+                    .map(serde_json::to_value)
+                    .collect::<serde_json::Result<Vec<_>>>()?;
+                let page = crate::cmd_api::PaginatableResponse {
+                    items: crate::iostreams::select_fields(items, &self.columns)?,
+                    next_page: page.next_page,
+                };
+                return ctx.io.with_output_file(&self.output_file, |io| {
+                    io.write_output_json(&serde_json::to_value(&page)?)
+                });
+            }
+        }
+
+        let page_token = self.page_token.as_deref().unwrap_or_default();
+        let disks = if self.instance.is_empty() {
+            let endpoint = "/organizations/{organization_name}/projects/{project_name}/disks";
+            if self.paginate {
+                ctx.timed(
+                    "GET",
+                    endpoint,
+                    client.disks().get_all(&organization, &project, self.sort_by.clone()),
+                )
+                .await?
+            } else {
+                ctx.timed(
+                    "GET",
+                    endpoint,
+                    client
+                        .disks()
+                        .get_page(self.limit, &organization, page_token, &project, self.sort_by.clone()),
+                )
+                .await?
+            }
+        } else {
+            let endpoint = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/disks";
+            if self.paginate {
+                ctx.timed(
+                    "GET",
+                    endpoint,
+                    client
+                        .instances()
+                        .disks_get_all(&self.instance, &organization, &project, self.sort_by.clone()),
+                )
+                .await?
+            } else {
+                ctx.timed(
+                    "GET",
+                    endpoint,
+                    client.instances().disks_get(
+                        self.limit,
+                        &self.instance,
+                        &organization,
+                        page_token,
+                        &project,
+                        self.sort_by.clone(),
+                    ),
+                )
+                .await?
+            }
+        };
+
+        let results = self
+            .with_attachments(ctx, &client, &organization, &project, disks)
+            .await?;
+        let results = crate::iostreams::filter_by_fields(results, &self.filter)?;
+        let results = crate::iostreams::sort_by_column(results, &self.sort, self.reverse)?;
+        ctx.io.with_output_file(&self.output_file, |io| {
+            io.write_output_for_vec_with_columns(&format, &self.columns, self.no_header, &results)
+        })?;
+        Ok(())
+    }
+}
+
+/// Create a snapshot of a disk.
+///
+/// The snapshot is created in the same project as the disk. Its ID is printed
+/// once it is created, on its own with `--quiet`.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdDiskSnapshot {
+    /// The disk to snapshot.
+    #[clap(name = "disk", required = true)]
+    pub disk: String,
+
+    /// The name of the snapshot to create.
+    #[clap(long, required = true)]
+    pub name: String,
+
+    /// The description for the snapshot.
+    #[clap(long = "description", short = 'D', default_value_t)]
+    pub description: String,
+
+    /// The project that holds the disk.
+    #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
+    pub project: String,
+
+    /// The organization that holds the project.
+    #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
+    pub organization: String,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdDiskSnapshot {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let organization = ctx.resolve_organization(&self.organization)?;
+        if organization.is_empty() {
+            return Err(anyhow!("-o|--organization required"));
+        }
+
+        let project = ctx.resolve_project(&self.project)?;
+        if project.is_empty() {
+            return Err(anyhow!("-p|--project required"));
+        }
+
+        let client = ctx.api_client("")?;
+
+        // Make sure the disk exists, the error from the snapshot create is less clear.
+        if let Err(err) = ctx
+            .timed(
+                "GET",
+                "/organizations/{organization_name}/projects/{project_name}/disks/{disk_name}",
+                client.disks().get(&self.disk, &organization, &project),
+            )
+            .await
+        {
+            if let Some(oxide_api::types::Error::ObjectNotFound { .. }) = err.downcast_ref() {
+                return Err(anyhow!(
+                    "disk {} does not exist in {}/{}",
+                    self.disk,
+                    organization,
+                    project
+                ));
+            }
+
+            return Err(err);
+        }
+
+        // Snapshots have no state in the API, the snapshot is ready once it is created.
+        let snapshot = ctx
+            .timed(
+                "POST",
+                "/organizations/{organization_name}/projects/{project_name}/snapshots",
+                client.snapshots().post(
+                    &organization,
+                    &project,
+                    &oxide_api::types::SnapshotCreate {
+                        description: self.description.to_string(),
+                        disk: self.disk.to_string(),
+                        name: self.name.to_string(),
+                    },
+                ),
+            )
+            .await?;
+
+        if ctx.quiet {
+            writeln!(ctx.io.out, "{}", snapshot.id)?;
+        } else {
+            let cs = ctx.io.color_scheme();
+            writeln!(
+                ctx.io.out,
+                "{} Created snapshot {} of disk {} in {}/{}: {}",
+                cs.success_icon(),
+                self.name,
+                self.disk,
+                organization,
+                project,
+                snapshot.id
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
 /// Edit disk settings.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
@@ -193,6 +589,7 @@ mod test {
                     description: "hi hi".to_string(),
                     size: Default::default(),
                     disk_source: disk_source.clone(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -208,6 +605,7 @@ mod test {
                     description: "foo bar".to_string(),
                     size: Default::default(),
                     disk_source: disk_source.clone(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -223,6 +621,7 @@ mod test {
                     description: "balla".to_string(),
                     size: Default::default(),
                     disk_source: disk_source.clone(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -238,6 +637,7 @@ mod test {
                     description: "".to_string(),
                     size: Default::default(),
                     disk_source: disk_source.clone(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -253,6 +653,7 @@ mod test {
                     description: "blah blah".to_string(),
                     size: Default::default(),
                     disk_source: disk_source.clone(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -268,6 +669,7 @@ mod test {
                     description: "this is a disk".to_string(),
                     size: Default::default(),
                     disk_source: Default::default(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -280,6 +682,8 @@ mod test {
                     disk: "things".to_string(),
                     organization: "".to_string(),
                     project: "".to_string(),
+                    all: false,
+                    filter: "".to_string(),
                     confirm: false,
                 }),
 
@@ -294,14 +698,74 @@ mod test {
                     organization: "".to_string(),
                     project: "".to_string(),
                     paginate: false,
+                    page_token: None,
                     format: None,
+                    template: None,
+                    columns: vec![],
+                    no_header: false,
+                    sort: "".to_string(),
+                    reverse: false,
+                    filter: vec![],
+                    output_file: "".to_string(),
                     sort_by: Default::default(),
+                    instance: "".to_string(),
                 }),
 
                 stdin: "".to_string(),
                 want_out: "".to_string(),
                 want_err: "--limit must be greater than 0".to_string(),
             },
+            TestItem {
+                name: "list no organization".to_string(),
+                cmd: crate::cmd_disk::SubCommand::List(crate::cmd_disk::CmdDiskList {
+                    limit: 30,
+                    organization: "".to_string(),
+                    project: "".to_string(),
+                    paginate: false,
+                    page_token: None,
+                    format: None,
+                    template: None,
+                    columns: vec![],
+                    no_header: false,
+                    sort: "".to_string(),
+                    reverse: false,
+                    filter: vec![],
+                    output_file: "".to_string(),
+                    sort_by: Default::default(),
+                    instance: "".to_string(),
+                }),
+
+                stdin: "".to_string(),
+                want_out: "".to_string(),
+                want_err: "-o|--organization required".to_string(),
+            },
+            TestItem {
+                name: "attach no organization".to_string(),
+                cmd: crate::cmd_disk::SubCommand::Attach(crate::cmd_disk::CmdDiskAttach {
+                    disk: "things".to_string(),
+                    instance: "stuff".to_string(),
+                    organization: "".to_string(),
+                    project: "bar".to_string(),
+                }),
+
+                stdin: "".to_string(),
+                want_out: "".to_string(),
+                want_err: "-o|--organization required".to_string(),
+            },
+            TestItem {
+                name: "snapshot no organization".to_string(),
+                cmd: crate::cmd_disk::SubCommand::Snapshot(crate::cmd_disk::CmdDiskSnapshot {
+                    disk: "things".to_string(),
+                    name: "things-backup".to_string(),
+                    description: "".to_string(),
+                    organization: "".to_string(),
+                    project: "".to_string(),
+                }),
+
+                stdin: "".to_string(),
+                want_out: "".to_string(),
+                want_err: "-o|--organization required".to_string(),
+            },
         ];
 
         let mut config = crate::config::new_blank_config().unwrap();
@@ -320,6 +784,13 @@ mod test {
                 config: &mut c,
                 io,
                 debug: false,
+                quiet: false,
+                profile: None,
+                api_clients: Default::default(),
+                timeout: None,
+                headers: Default::default(),
+                request_id_sent: Default::default(),
+                endpoint: None,
             };
 
             let cmd_disk = crate::cmd_disk::CmdDisk { subcmd: t.cmd };
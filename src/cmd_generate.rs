@@ -1,4 +1,4 @@
-use std::{fs, io::Write};
+use std::{collections::HashMap, fs, io::Write};
 
 use anyhow::{Context, Result};
 use clap::{Command, CommandFactory, Parser};
@@ -15,8 +15,10 @@ pub struct CmdGenerate {
 #[derive(Parser, Debug, Clone)]
 enum SubCommand {
     Markdown(CmdGenerateMarkdown),
+    #[clap(visible_alias = "man")]
     ManPages(CmdGenerateManPages),
     Json(CmdGenerateJson),
+    Schema(CmdGenerateSchema),
 }
 
 #[async_trait::async_trait]
@@ -26,10 +28,129 @@ impl crate::cmd::Command for CmdGenerate {
             SubCommand::Markdown(cmd) => cmd.run(ctx).await,
             SubCommand::ManPages(cmd) => cmd.run(ctx).await,
             SubCommand::Json(cmd) => cmd.run(ctx).await,
+            SubCommand::Schema(cmd) => cmd.run(ctx).await,
         }
     }
 }
 
+/// The command tree to generate the docs from. The commands are compiled in, so with a
+/// `--spec-file` only the help of the arguments that comes from the descriptions in the
+/// spec follows the file.
+fn app_from_spec_file(spec_file: &str) -> Result<Command<'static>> {
+    let mut app: Command = crate::Opts::command();
+
+    if !spec_file.is_empty() {
+        let contents = fs::read_to_string(spec_file).with_context(|| format!("failed to read {}", spec_file))?;
+        let spec: openapiv3::OpenAPI =
+            serde_json::from_str(&contents).with_context(|| format!("{} is not an OpenAPI spec", spec_file))?;
+        let compiled: openapiv3::OpenAPI = serde_json::from_str(include_str!("../spec.json"))?;
+
+        apply_descriptions(&mut app, &changed_descriptions(&compiled, &spec));
+    }
+
+    app._build_all();
+
+    Ok(app)
+}
+
+/// Trim a description the way clap trims the help of an argument.
+fn help_text(description: &str) -> String {
+    description.trim().trim_end_matches('.').to_string()
+}
+
+/// The descriptions of the parameters and properties that differ between two specs, keyed
+/// by the help text of the old one.
+fn changed_descriptions(old: &openapiv3::OpenAPI, new: &openapiv3::OpenAPI) -> HashMap<String, String> {
+    let mut changed = HashMap::new();
+    let mut add = |old: &Option<String>, new: &Option<String>| {
+        if let (Some(old), Some(new)) = (old, new) {
+            if old != new {
+                changed.insert(help_text(old), help_text(new));
+            }
+        }
+    };
+
+    for (path, old_item) in old.paths.paths.iter() {
+        let (old_item, new_item) = match (old_item.as_item(), new.paths.paths.get(path).and_then(|i| i.as_item())) {
+            (Some(old_item), Some(new_item)) => (old_item, new_item),
+            _ => continue,
+        };
+
+        for (method, old_op) in old_item.iter() {
+            let new_op = match new_item.iter().find(|(m, _)| *m == method) {
+                Some((_, new_op)) => new_op,
+                None => continue,
+            };
+
+            for old_param in old_op.parameters.iter().filter_map(|p| p.as_item()) {
+                let old_data = old_param.parameter_data_ref();
+                if let Some(new_param) = new_op
+                    .parameters
+                    .iter()
+                    .filter_map(|p| p.as_item())
+                    .find(|p| p.parameter_data_ref().name == old_data.name)
+                {
+                    add(&old_data.description, &new_param.parameter_data_ref().description);
+                }
+            }
+        }
+    }
+
+    // Only the object schemas have properties, the arguments of the request bodies.
+    if let (Some(old_components), Some(new_components)) = (&old.components, &new.components) {
+        for (name, old_schema) in old_components.schemas.iter() {
+            let (old_schema, new_schema) = match (
+                old_schema.as_item(),
+                new_components.schemas.get(name).and_then(|s| s.as_item()),
+            ) {
+                (Some(old_schema), Some(new_schema)) => (old_schema, new_schema),
+                _ => continue,
+            };
+
+            if let (
+                openapiv3::SchemaKind::Type(openapiv3::Type::Object(old_object)),
+                openapiv3::SchemaKind::Type(openapiv3::Type::Object(new_object)),
+            ) = (&old_schema.schema_kind, &new_schema.schema_kind)
+            {
+                for (property, old_property) in old_object.properties.iter() {
+                    if let (Some(old_property), Some(new_property)) = (
+                        old_property.as_item(),
+                        new_object.properties.get(property).and_then(|p| p.as_item()),
+                    ) {
+                        add(
+                            &old_property.schema_data.description,
+                            &new_property.schema_data.description,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    changed
+}
+
+/// Replace the help of the arguments that has a changed description, see
+/// `changed_descriptions`.
+fn apply_descriptions(cmd: &mut Command<'static>, changed: &HashMap<String, String>) {
+    let ids = cmd
+        .get_arguments()
+        .filter(|arg| arg.get_help().map(|help| changed.contains_key(&help_text(help))) == Some(true))
+        .map(|arg| arg.get_id())
+        .collect::<Vec<_>>();
+    for id in ids {
+        *cmd = std::mem::take(cmd).mut_arg(id, |arg| {
+            let help = changed[&help_text(arg.get_help().unwrap_or_default())].clone();
+            // The command tree lives until the docs are written, like the compiled in help.
+            arg.help(Box::leak(help.into_boxed_str()))
+        });
+    }
+
+    for subcmd in cmd.get_subcommands_mut() {
+        apply_descriptions(subcmd, changed);
+    }
+}
+
 /// Arg to CLI command for the JSON doc
 #[derive(Serialize, Debug, PartialEq, Eq)]
 pub struct JsonArg {
@@ -112,26 +233,226 @@ impl CmdGenerateJson {
                 .collect(),
             subcommands: cmd
                 .get_subcommands()
+                .filter(|subcmd| !subcmd.is_hide_set())
                 .filter_map(|subcmd| self.generate(ctx, subcmd).ok())
                 .collect(),
         })
     }
 }
 
+/// The values an argument accepts, if clap knows them. They are set either on the
+/// argument or on its value parser.
+fn possible_values(arg: &clap::Arg) -> Vec<String> {
+    if !arg.is_takes_value_set() {
+        return vec![];
+    }
+
+    let values: Vec<clap::PossibleValue> = match arg.get_possible_values() {
+        Some(values) => values.to_vec(),
+        None => arg
+            .get_value_parser()
+            .possible_values()
+            .map(|values| values.collect())
+            .unwrap_or_default(),
+    };
+
+    values
+        .iter()
+        .filter(|v| !v.is_hide_set())
+        .map(|v| v.get_name().to_string())
+        .collect()
+}
+
+/// The type of the values of an argument: `bool` and `count` for flags, `enum` if there
+/// is a list of values, `path` and `url` from the value hint or parser, and `string` for
+/// anything else, which the commands parse themselves.
+fn value_type(arg: &clap::Arg) -> &'static str {
+    if !arg.is_takes_value_set() {
+        if arg.is_multiple_occurrences_set() {
+            return "count";
+        }
+
+        return "bool";
+    }
+
+    if !possible_values(arg).is_empty() {
+        return "enum";
+    }
+
+    match arg.get_value_hint() {
+        clap::ValueHint::AnyPath | clap::ValueHint::FilePath | clap::ValueHint::DirPath => return "path",
+        clap::ValueHint::ExecutablePath => return "path",
+        clap::ValueHint::Url => return "url",
+        _ => {}
+    }
+
+    let type_id = arg.get_value_parser().type_id();
+    if type_id == clap::builder::ValueParser::bool().type_id() {
+        "bool"
+    } else if type_id == clap::builder::ValueParser::path_buf().type_id() {
+        "path"
+    } else {
+        "string"
+    }
+}
+
+/// Arg to CLI command for the JSON schema. The fields are sorted, so they serialize in a
+/// stable order.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct SchemaArg {
+    default_values: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    env: Option<String>,
+    global: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    help: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    long: Option<String>,
+    multiple: bool,
+    name: String,
+    positional: bool,
+    possible_values: Vec<String>,
+    required: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    short: Option<String>,
+    takes_value: bool,
+    value_names: Vec<String>,
+    value_type: String,
+}
+
+/// CLI command for the JSON schema. The fields are sorted, so they serialize in a stable
+/// order.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct SchemaCommand {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    about: Option<String>,
+    aliases: Vec<String>,
+    args: Vec<SchemaArg>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    long_about: Option<String>,
+    name: String,
+    subcommands: Vec<SchemaCommand>,
+}
+
+/// Generate a JSON schema of every command, argument, and value choice.
+///
+/// This is meant for tooling, like editor integrations. The output is stable across
+/// runs, so it can be diffed.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdGenerateSchema {
+    /// The file to write the schema to. Defaults to standard output.
+    #[clap(long, default_value = "")]
+    pub output: String,
+
+    /// An OpenAPI spec to take the help of the arguments from, instead of the one oxide
+    /// was built with.
+    #[clap(long, default_value = "")]
+    pub spec_file: String,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdGenerateSchema {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let app = app_from_spec_file(&self.spec_file)?;
+
+        self.write(ctx, &app)
+    }
+}
+
+impl CmdGenerateSchema {
+    fn write(&self, ctx: &mut crate::context::Context, app: &Command) -> Result<()> {
+        let schema = serde_json::to_string_pretty(&self.generate(app))?;
+
+        if self.output.is_empty() || self.output == "-" {
+            writeln!(ctx.io.out, "{}", schema)?;
+        } else {
+            let mut file = std::fs::File::create(&self.output)
+                .with_context(|| format!("failed to create file {}", self.output))?;
+            writeln!(file, "{}", schema)?;
+        }
+
+        Ok(())
+    }
+
+    fn generate(&self, cmd: &Command) -> SchemaCommand {
+        SchemaCommand {
+            about: cmd.get_about().map(String::from),
+            aliases: cmd.get_all_aliases().map(String::from).collect(),
+            args: cmd
+                .get_arguments()
+                .filter(|arg| !arg.is_hide_set())
+                .map(|arg| SchemaArg {
+                    default_values: arg
+                        .get_default_values()
+                        .iter()
+                        .map(|v| v.to_string_lossy().to_string())
+                        .collect(),
+                    env: arg.get_env().map(|(name, _)| name.to_string_lossy().to_string()),
+                    global: arg.is_global_set(),
+                    help: arg.get_help().map(String::from),
+                    long: arg.get_long().map(String::from),
+                    multiple: arg.is_multiple_occurrences_set() || arg.is_multiple_values_set(),
+                    name: arg.get_id().to_string(),
+                    positional: arg.is_positional(),
+                    possible_values: possible_values(arg),
+                    required: arg.is_required_set(),
+                    short: arg.get_short().map(|char| char.to_string()),
+                    takes_value: arg.is_takes_value_set(),
+                    value_names: arg
+                        .get_value_names()
+                        .unwrap_or_default()
+                        .iter()
+                        .map(|v| v.to_string())
+                        .collect(),
+                    value_type: value_type(arg).to_string(),
+                })
+                .collect(),
+            long_about: cmd.get_long_about().map(String::from),
+            name: cmd.get_name().to_string(),
+            subcommands: cmd
+                .get_subcommands()
+                .filter(|subcmd| !subcmd.is_hide_set())
+                .map(|subcmd| self.generate(subcmd))
+                .collect(),
+        }
+    }
+}
+
 /// Generate markdown documentation.
+///
+/// By default one file is generated per command. With `--output`, the full
+/// command reference is written to a single file instead, with an anchored
+/// heading per command.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdGenerateMarkdown {
     /// Path directory where you want to output the generated files.
-    #[clap(short = 'D', long, default_value = "")]
+    #[clap(short = 'D', long, default_value = "", conflicts_with = "output")]
     pub dir: String,
+
+    /// The file to write the single markdown reference to (use "-" for standard output).
+    #[clap(long, default_value = "")]
+    pub output: String,
+
+    /// The heading level of each command in the `--output` reference, from 1 to 5.
+    #[clap(long, default_value = "1")]
+    pub base_level: usize,
+
+    /// An OpenAPI spec to take the help of the arguments from, instead of the one oxide
+    /// was built with.
+    #[clap(long, default_value = "")]
+    pub spec_file: String,
 }
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdGenerateMarkdown {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
-        let mut app: Command = crate::Opts::command();
-        app._build_all();
+        let app = app_from_spec_file(&self.spec_file)?;
+
+        if !self.output.is_empty() {
+            return self.generate_reference(ctx, &app);
+        }
 
         // Make sure the output directory exists.
         if !self.dir.is_empty() {
@@ -145,6 +466,24 @@ impl crate::cmd::Command for CmdGenerateMarkdown {
 }
 
 impl CmdGenerateMarkdown {
+    fn generate_reference(&self, ctx: &mut crate::context::Context, app: &Command) -> Result<()> {
+        if !(1..=5).contains(&self.base_level) {
+            anyhow::bail!("--base-level must be between 1 and 5");
+        }
+
+        let markdown = crate::docs_markdown::app_to_markdown_reference(app, self.base_level)?;
+
+        if self.output == "-" {
+            writeln!(ctx.io.out, "{}", markdown)?;
+        } else {
+            let mut file = std::fs::File::create(&self.output)
+                .with_context(|| format!("failed to create file {}", self.output))?;
+            writeln!(file, "{}", markdown)?;
+        }
+
+        Ok(())
+    }
+
     fn generate(&self, ctx: &mut crate::context::Context, app: &Command, parent: &str) -> Result<()> {
         let mut p = parent.to_string();
         if !p.is_empty() {
@@ -183,7 +522,7 @@ layout: manual
         }
 
         // Iterate over all the subcommands and generate the documentation.
-        for subcmd in app.get_subcommands() {
+        for subcmd in app.get_subcommands().filter(|s| !s.is_hide_set()) {
             self.generate(ctx, subcmd, &p)?;
         }
 
@@ -192,19 +531,27 @@ layout: manual
 }
 
 /// Generate manual pages.
+///
+/// One page is written per command and subcommand, for example
+/// `oxide-instance-create.1`. The page of the root command, `oxide.1`, also
+/// lists the environment variables oxide reads.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdGenerateManPages {
     /// Path directory where you want to output the generated files.
-    #[clap(short = 'D', long, default_value = "")]
+    #[clap(short = 'D', long, visible_alias = "output-dir", default_value = "")]
     pub dir: String,
+
+    /// An OpenAPI spec to take the help of the arguments from, instead of the one oxide
+    /// was built with.
+    #[clap(long, default_value = "")]
+    pub spec_file: String,
 }
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdGenerateManPages {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
-        let mut app: Command = crate::Opts::command();
-        app._build_all();
+        let app = app_from_spec_file(&self.spec_file)?;
 
         // Make sure the output directory exists.
         if !self.dir.is_empty() {
@@ -246,7 +593,7 @@ impl CmdGenerateManPages {
         }
 
         // Iterate over all the subcommands and generate the documentation.
-        for subcmd in app.get_subcommands() {
+        for subcmd in app.get_subcommands().filter(|s| !s.is_hide_set()) {
             // Make it recursive.
             self.generate(ctx, subcmd, &p, root)?;
         }
@@ -317,9 +664,21 @@ mod test {
             config: &mut c,
             io,
             debug: false,
+            quiet: false,
+            profile: None,
+            api_clients: Default::default(),
+            timeout: None,
+            headers: Default::default(),
+            request_id_sent: Default::default(),
+            endpoint: None,
         };
 
-        let cmd = crate::cmd_generate::CmdGenerateMarkdown { dir: "".to_string() };
+        let cmd = crate::cmd_generate::CmdGenerateMarkdown {
+            dir: "".to_string(),
+            output: "".to_string(),
+            base_level: 1,
+            spec_file: "".to_string(),
+        };
 
         cmd.run(&mut ctx).await.unwrap();
 
@@ -342,9 +701,21 @@ mod test {
             config: &mut c,
             io,
             debug: false,
+            quiet: false,
+            profile: None,
+            api_clients: Default::default(),
+            timeout: None,
+            headers: Default::default(),
+            request_id_sent: Default::default(),
+            endpoint: None,
         };
 
-        let cmd = crate::cmd_generate::CmdGenerateMarkdown { dir: "".to_string() };
+        let cmd = crate::cmd_generate::CmdGenerateMarkdown {
+            dir: "".to_string(),
+            output: "".to_string(),
+            base_level: 1,
+            spec_file: "".to_string(),
+        };
 
         let app = crate::cmd_generate::test_app();
 
@@ -509,6 +880,191 @@ sub subcommand
         assert_eq!(stderr, "");
     }
 
+    #[test]
+    fn test_generate_schema() {
+        let mut config = crate::config::new_blank_config().unwrap();
+        let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+
+        let (io, stdout_path, stderr_path) = crate::iostreams::IoStreams::test();
+        let mut ctx = crate::context::Context {
+            config: &mut c,
+            io,
+            debug: false,
+            quiet: false,
+            profile: None,
+            api_clients: Default::default(),
+            timeout: None,
+            headers: Default::default(),
+            request_id_sent: Default::default(),
+            endpoint: None,
+        };
+
+        let cmd = crate::cmd_generate::CmdGenerateSchema {
+            output: "".to_string(),
+            spec_file: "".to_string(),
+        };
+
+        let mut app = crate::cmd_generate::test_app().subcommand(
+            clap::Command::new("log")
+                .arg(
+                    clap::arg!(--format <FORMAT> "How to print commits")
+                        .required(false)
+                        .value_parser(["short", "full"])
+                        .default_value("short"),
+                )
+                .arg(
+                    clap::arg!(--color <WHEN> "When to use colors")
+                        .required(false)
+                        .possible_values(["auto", "never"]),
+                )
+                .arg(
+                    clap::arg!(--output <FILE> "Where to write the log")
+                        .required(false)
+                        .value_hint(clap::ValueHint::FilePath),
+                )
+                .arg(clap::arg!(-v --verbose ... "Print more")),
+        );
+        app._build_all();
+
+        cmd.write(&mut ctx, &app).unwrap();
+        cmd.write(&mut ctx, &app).unwrap();
+
+        let stdout = std::fs::read_to_string(stdout_path).unwrap();
+        let (first, second) = stdout.split_at(stdout.len() / 2);
+        assert_eq!(first, second, "the schema should be the same across runs");
+
+        let schema: serde_json::Value = serde_json::from_str(first).unwrap();
+        let find = |items: &serde_json::Value, name: &str| -> serde_json::Value {
+            items
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|item| item["name"] == name)
+                .cloned()
+                .unwrap_or_else(|| panic!("{} not found", name))
+        };
+        assert_eq!(schema["name"], "git");
+
+        let clone = find(&schema["subcommands"], "clone");
+        let remote = find(&clone["args"], "REMOTE");
+        assert_eq!(remote["positional"], true);
+        assert_eq!(remote["required"], true);
+        assert_eq!(remote["value_type"], "string");
+
+        let log = find(&schema["subcommands"], "log");
+        let format = find(&log["args"], "format");
+        assert_eq!(format["long"], "format");
+        assert_eq!(format["possible_values"], serde_json::json!(["short", "full"]));
+        assert_eq!(format["default_values"], serde_json::json!(["short"]));
+        assert_eq!(format["required"], false);
+        assert_eq!(format["value_type"], "enum");
+
+        let color = find(&log["args"], "color");
+        assert_eq!(color["possible_values"], serde_json::json!(["auto", "never"]));
+        assert_eq!(color["value_type"], "enum");
+
+        assert_eq!(find(&log["args"], "output")["value_type"], "path");
+        assert_eq!(find(&log["args"], "verbose")["value_type"], "count");
+        assert_eq!(find(&log["args"], "help")["value_type"], "bool");
+
+        // Keys are sorted.
+        let keys = first
+            .lines()
+            .take_while(|l| !l.contains("\"args\""))
+            .filter_map(|l| l.trim().strip_prefix('"'))
+            .map(|l| l.split('"').next().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec!["about", "aliases"]);
+
+        assert_eq!(std::fs::read_to_string(stderr_path).unwrap(), "");
+    }
+
+    #[test]
+    fn test_spec_file_descriptions() {
+        let spec = |description: &str| -> openapiv3::OpenAPI {
+            serde_json::from_value(serde_json::json!({
+                "openapi": "3.0.3",
+                "info": {"title": "test", "version": "0.0.1"},
+                "paths": {},
+                "components": {
+                    "schemas": {
+                        "RepoCreate": {
+                            "type": "object",
+                            "properties": {
+                                "remote": {"type": "string", "description": description},
+                            },
+                        },
+                    },
+                },
+            }))
+            .unwrap()
+        };
+
+        let changed = super::changed_descriptions(&spec("The remote to clone."), &spec("The remote to copy."));
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed["The remote to clone"], "The remote to copy");
+        assert!(super::changed_descriptions(&spec("Same."), &spec("Same.")).is_empty());
+
+        let mut app = crate::cmd_generate::test_app();
+        super::apply_descriptions(&mut app, &changed);
+        let clone = app.find_subcommand("clone").unwrap();
+        let remote = clone.get_arguments().find(|arg| arg.get_id() == "REMOTE").unwrap();
+        assert_eq!(remote.get_help(), Some("The remote to copy"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("spec.json");
+        std::fs::write(&path, "{\"not\": \"a spec\"}").unwrap();
+        let err = super::app_from_spec_file(path.to_str().unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), format!("{} is not an OpenAPI spec", path.display()));
+    }
+
+    #[test]
+    fn test_generate_markdown_reference() {
+        let mut config = crate::config::new_blank_config().unwrap();
+        let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+
+        let (io, stdout_path, stderr_path) = crate::iostreams::IoStreams::test();
+        let mut ctx = crate::context::Context {
+            config: &mut c,
+            io,
+            debug: false,
+            quiet: false,
+            profile: None,
+            api_clients: Default::default(),
+            timeout: None,
+            headers: Default::default(),
+            request_id_sent: Default::default(),
+            endpoint: None,
+        };
+
+        let mut cmd = crate::cmd_generate::CmdGenerateMarkdown {
+            dir: "".to_string(),
+            output: "-".to_string(),
+            base_level: 2,
+            spec_file: "".to_string(),
+        };
+
+        let app = crate::cmd_generate::test_app();
+
+        cmd.generate_reference(&mut ctx, &app).unwrap();
+
+        let stdout = std::fs::read_to_string(&stdout_path).unwrap();
+        assert!(stdout.contains("<a id=\"git-add-new-foo\"></a>"), "{}", stdout);
+        assert!(stdout.contains("## git add new foo"), "{}", stdout);
+        assert!(stdout.contains("### Options"), "{}", stdout);
+        assert!(stdout.contains("[git clone](#git-clone)"), "{}", stdout);
+        assert!(stdout.contains("`--help`"), "{}", stdout);
+        assert!(!stdout.contains("#### "), "{}", stdout);
+
+        cmd.base_level = 6;
+        assert_eq!(
+            cmd.generate_reference(&mut ctx, &app).unwrap_err().to_string(),
+            "--base-level must be between 1 and 5"
+        );
+
+        assert_eq!(std::fs::read_to_string(stderr_path).unwrap(), "");
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn test_generate_man_pages() {
         let mut config = crate::config::new_blank_config().unwrap();
@@ -519,9 +1075,19 @@ sub subcommand
             config: &mut c,
             io,
             debug: true,
+            quiet: false,
+            profile: None,
+            api_clients: Default::default(),
+            timeout: None,
+            headers: Default::default(),
+            request_id_sent: Default::default(),
+            endpoint: None,
         };
 
-        let cmd = crate::cmd_generate::CmdGenerateManPages { dir: "".to_string() };
+        let cmd = crate::cmd_generate::CmdGenerateManPages {
+            dir: "".to_string(),
+            spec_file: "".to_string(),
+        };
 
         cmd.run(&mut ctx).await.unwrap();
 
@@ -533,6 +1099,43 @@ sub subcommand
         assert_eq!(stderr, "");
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_generate_man_pages_dir() {
+        let mut config = crate::config::new_blank_config().unwrap();
+        let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+
+        let (io, _, stderr_path) = crate::iostreams::IoStreams::test();
+        let mut ctx = crate::context::Context {
+            config: &mut c,
+            io,
+            debug: false,
+            quiet: false,
+            profile: None,
+            api_clients: Default::default(),
+            timeout: None,
+            headers: Default::default(),
+            request_id_sent: Default::default(),
+            endpoint: None,
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let cmd = crate::cmd_generate::CmdGenerateManPages {
+            dir: dir.path().to_str().unwrap().to_string(),
+            spec_file: "".to_string(),
+        };
+
+        cmd.run(&mut ctx).await.unwrap();
+
+        let page = std::fs::read_to_string(dir.path().join("oxide-instance-create.1")).unwrap();
+        assert!(page.contains("oxide\\-instance\\-create"), "{}", page);
+
+        let root = std::fs::read_to_string(dir.path().join("oxide.1")).unwrap();
+        assert!(root.contains(".SH \"ENVIRONMENT\""), "{}", root);
+        assert!(root.contains("OXIDE_TOKEN"), "{}", root);
+
+        assert_eq!(std::fs::read_to_string(stderr_path).unwrap(), "");
+    }
+
     #[test]
     fn test_generate_man_pages_sub_subcommands() {
         let mut config = crate::config::new_blank_config().unwrap();
@@ -543,9 +1146,19 @@ sub subcommand
             config: &mut c,
             io,
             debug: true,
+            quiet: false,
+            profile: None,
+            api_clients: Default::default(),
+            timeout: None,
+            headers: Default::default(),
+            request_id_sent: Default::default(),
+            endpoint: None,
         };
 
-        let cmd = crate::cmd_generate::CmdGenerateManPages { dir: "".to_string() };
+        let cmd = crate::cmd_generate::CmdGenerateManPages {
+            dir: "".to_string(),
+            spec_file: "".to_string(),
+        };
 
         // Define our app.
         let app = crate::cmd_generate::test_app();
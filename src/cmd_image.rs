@@ -1,8 +1,17 @@
-use anyhow::Result;
+use std::io::Write;
+
+use anyhow::{anyhow, Result};
 use clap::Parser;
 use cli_macro::crud_gen;
 
-/// Create, list, view, and delete images.
+/// Create, list, view, upload, and delete images.
+///
+/// Images belong to a project, like disks and instances: `oxide image list` and the
+/// other commands here act on the images of the project passed with `--organization`
+/// and `--project`, and only that project can use them.
+///
+/// Global images can be used by every project. They are listed, viewed, and deleted
+/// with `oxide image global`, and created with `oxide image upload`.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdImage {
@@ -15,7 +24,8 @@ pub struct CmdImage {
 }]
 #[derive(Parser, Debug, Clone)]
 enum SubCommand {
-    Global(crate::cmd_image_global::CmdImageGlobal),
+    Global(CmdImageGlobal),
+    Upload(CmdImageUpload),
 }
 
 #[async_trait::async_trait]
@@ -27,6 +37,409 @@ impl crate::cmd::Command for CmdImage {
             SubCommand::List(cmd) => cmd.run(ctx).await,
             SubCommand::View(cmd) => cmd.run(ctx).await,
             SubCommand::Global(cmd) => cmd.run(ctx).await,
+            SubCommand::Upload(cmd) => cmd.run(ctx).await,
+        }
+    }
+}
+
+/// Upload a global image from a URL or a local file.
+///
+/// The image is created as a global image, so it can be used by anyone as
+/// the base for their instances.
+///
+///     # upload an image hosted somewhere else
+///     $ oxide image upload ubuntu-22.04 --from https://example.com/ubuntu.raw --os ubuntu --version 22.04
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdImageUpload {
+    /// The name of the image to create.
+    #[clap(name = "image", required = true)]
+    pub image: String,
+
+    /// The URL or local file to read the image from.
+    #[clap(long, required = true)]
+    pub from: String,
+
+    /// The name of the OS distribution, for example 'ubuntu'.
+    #[clap(long, required = true)]
+    pub os: String,
+
+    /// The version of the OS distribution, for example '22.04'.
+    #[clap(long, required = true)]
+    pub version: String,
+
+    /// The block size of the image in bytes: 512, 2048, or 4096.
+    #[clap(long, short, default_value = "512")]
+    pub block_size: oxide_api::types::BlockSize,
+
+    /// The description for the image.
+    #[clap(long = "description", short = 'D', default_value_t)]
+    pub description: String,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdImageUpload {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let url = match url::Url::parse(&self.from) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => url,
+            _ => {
+                if !std::path::Path::new(&self.from).is_file() {
+                    return Err(anyhow!("--from `{}` is neither an http(s) URL nor a file", self.from));
+                }
+
+                // TODO: stream the file once the API has an image import endpoint, for now
+                // images can only be created from a URL.
+                return Err(anyhow!(
+                    "uploading a local file is not supported yet, host `{}` and pass its URL with --from",
+                    self.from
+                ));
+            }
+        };
+
+        let client = ctx.api_client("")?;
+
+        let body = oxide_api::types::GlobalImageCreate {
+            name: self.image.to_string(),
+            description: self.description.to_string(),
+            block_size: self.block_size,
+            distribution: oxide_api::types::Distribution {
+                name: self.os.to_string(),
+                version: self.version.to_string(),
+            },
+            source: oxide_api::types::ImageSource::Url(url.to_string()),
+        };
+
+        let handle = ctx
+            .io
+            .start_process_indicator_with_label(&format!(" Uploading image {}", self.image));
+        ctx.io.progress_event(
+            "uploading",
+            serde_json::json!({ "image": self.image, "url": url.to_string() }),
+        )?;
+
+        let result = ctx
+            .timed("POST", "/images", client.images_global().images_post(&body))
+            .await;
+
+        if let Some(handle) = handle {
+            handle.done();
+        }
+
+        let image = result?;
+        let id = &image.id;
+        ctx.io
+            .progress_event("done", serde_json::json!({ "image": self.image, "id": id }))?;
+
+        if ctx.quiet {
+            writeln!(ctx.io.out, "{}", id)?;
+        } else {
+            let cs = ctx.io.color_scheme();
+            writeln!(
+                ctx.io.out,
+                "{} Uploaded image {} with id {}",
+                cs.success_icon(),
+                self.image,
+                id
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// List, view, and delete global images.
+///
+/// Global images can be used by every project, unlike the images of `oxide image`,
+/// which belong to a single project. Create them with `oxide image upload`.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdImageGlobal {
+    #[clap(subcommand)]
+    subcmd: GlobalSubCommand,
+}
+
+// The global images are not generated with `crud_gen` like the project ones, their
+// endpoints are not scoped to a project and the calls for them in `images_global` are
+// named after the operations, so they are written by hand.
+#[derive(Parser, Debug, Clone)]
+enum GlobalSubCommand {
+    Delete(CmdImageGlobalDelete),
+    List(CmdImageGlobalList),
+    #[clap(alias = "get")]
+    View(CmdImageGlobalView),
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdImageGlobal {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        match &self.subcmd {
+            GlobalSubCommand::Delete(cmd) => cmd.run(ctx).await,
+            GlobalSubCommand::List(cmd) => cmd.run(ctx).await,
+            GlobalSubCommand::View(cmd) => cmd.run(ctx).await,
         }
     }
 }
+
+/// Returns the name of a global image passed by name or ID, the global image endpoints
+/// only take names.
+async fn global_image_name(
+    ctx: &crate::context::Context<'_>,
+    client: &oxide_api::Client,
+    image: &str,
+) -> Result<String> {
+    if uuid::Uuid::parse_str(image).is_err() {
+        return Ok(image.to_string());
+    }
+
+    let image = ctx
+        .timed(
+            "GET",
+            "/by-id/global-images/{id}",
+            client.images_global().image_global_view(image),
+        )
+        .await?;
+
+    Ok(image.name.to_string())
+}
+
+/// List global images.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdImageGlobalList {
+    /// The order in which to sort the results.
+    #[clap(long = "sort-by", short = 's', default_value_t)]
+    pub sort_by: oxide_api::types::NameSortMode,
+
+    /// Maximum number of items to list.
+    #[clap(long, short, default_value = "30")]
+    pub limit: u32,
+
+    /// Make additional HTTP requests to fetch all pages.
+    #[clap(long)]
+    pub paginate: bool,
+
+    /// Start at the page with this token, from the `next_page` of an earlier listing. With
+    /// `--format json`, the page is printed as the API returns it, with the token of the
+    /// page after it in `next_page`. Pass an empty token for the first page.
+    #[clap(long, conflicts_with = "paginate")]
+    pub page_token: Option<String>,
+
+    /// Display output in json, jsonl, yaml, table, csv, tsv, or template format.
+    #[clap(long, short)]
+    pub format: Option<crate::types::FormatOutput>,
+
+    /// The template to render each item with, for example '{{.name}} {{.id}}'.
+    #[clap(long)]
+    pub template: Option<String>,
+
+    /// The columns to display, for example 'name,id'. Defaults to all of them.
+    #[clap(long, use_value_delimiter = true)]
+    pub columns: Vec<String>,
+
+    /// Do not print the header row of table, csv, or tsv output.
+    #[clap(long)]
+    pub no_header: bool,
+
+    /// Sort the output by this column, for example 'time_created'. Any column works, the
+    /// items are sorted after they are fetched.
+    #[clap(long, default_value = "")]
+    pub sort: String,
+
+    /// Reverse the order of the output.
+    #[clap(long)]
+    pub reverse: bool,
+
+    /// Only list the items with a field equal to a value, as 'key=value', or containing
+    /// it, as 'key~value'. Can be passed more than once, items have to match all of them.
+    #[clap(long)]
+    pub filter: Vec<String>,
+
+    /// Write the output to this file instead of standard output.
+    #[clap(long, default_value = "")]
+    pub output_file: String,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdImageGlobalList {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        if self.limit < 1 {
+            return Err(anyhow!("--limit must be greater than 0"));
+        }
+
+        let client = ctx.api_client("")?;
+        let format = ctx.format(&self.format, &self.template)?;
+
+        // The pages are fetched the same way as for the generated list commands.
+        if self.paginate
+            && self.output_file.is_empty()
+            && self.sort.is_empty()
+            && !self.reverse
+            && self.filter.is_empty()
+            && format == crate::types::FormatOutput::Jsonl
+        {
+            return crate::cmd_api::write_pages_jsonl(ctx, &client, "/images", self.limit, &self.sort_by).await;
+        }
+
+        if let Some(page_token) = &self.page_token {
+            if format == crate::types::FormatOutput::Json {
+                let page =
+                    crate::cmd_api::get_page(ctx, &client, "/images", self.limit, &self.sort_by, page_token).await?;
+                let items = crate::iostreams::filter_by_fields(page.items, &self.filter)?;
+                let items = crate::iostreams::sort_by_column(items, &self.sort, self.reverse)?;
+                let page = crate::cmd_api::PaginatableResponse {
+                    items: crate::iostreams::select_fields(items, &self.columns)?,
+                    next_page: page.next_page,
+                };
+                return ctx.io.with_output_file(&self.output_file, |io| {
+                    io.write_output_json(&serde_json::to_value(&page)?)
+                });
+            }
+        }
+
+        let images = if self.paginate {
+            ctx.timed(
+                "GET",
+                "/images",
+                client.images_global().images_get_all(self.sort_by.clone()),
+            )
+            .await?
+        } else {
+            ctx.timed(
+                "GET",
+                "/images",
+                client.images_global().images_get(
+                    self.limit,
+                    self.page_token.as_deref().unwrap_or_default(),
+                    self.sort_by.clone(),
+                ),
+            )
+            .await?
+        };
+
+        let images = crate::iostreams::filter_by_fields(images, &self.filter)?;
+        let images = crate::iostreams::sort_by_column(images, &self.sort, self.reverse)?;
+        ctx.io.with_output_file(&self.output_file, |io| {
+            io.write_output_for_vec_with_columns(&format, &self.columns, self.no_header, &images)
+        })?;
+        Ok(())
+    }
+}
+
+/// View a global image.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdImageGlobalView {
+    /// The global image to view. Can be an ID or name.
+    #[clap(name = "image", required = true)]
+    pub image: String,
+
+    /// Display output in json, jsonl, yaml, table, csv, tsv, or template format.
+    #[clap(long, short)]
+    pub format: Option<crate::types::FormatOutput>,
+
+    /// The template to render each item with, for example '{{.name}} {{.id}}'.
+    #[clap(long)]
+    pub template: Option<String>,
+
+    /// The columns to display, for example 'name,id'. Defaults to all of them.
+    #[clap(long, use_value_delimiter = true)]
+    pub columns: Vec<String>,
+
+    /// Do not print the header row of table, csv, or tsv output.
+    #[clap(long)]
+    pub no_header: bool,
+    /// Write the output to this file instead of standard output.
+    #[clap(long, default_value = "")]
+    pub output_file: String,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdImageGlobalView {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let client = ctx.api_client("")?;
+
+        let image = if uuid::Uuid::parse_str(&self.image).is_ok() {
+            ctx.timed(
+                "GET",
+                "/by-id/global-images/{id}",
+                client.images_global().image_global_view(&self.image),
+            )
+            .await?
+        } else {
+            ctx.timed(
+                "GET",
+                "/images/{image_name}",
+                client.images_global().images_get_image(&self.image),
+            )
+            .await?
+        };
+
+        let format = ctx.format(&self.format, &self.template)?;
+        ctx.io.with_output_file(&self.output_file, |io| {
+            io.write_output_with_columns(&format, &self.columns, self.no_header, &image)
+        })?;
+        Ok(())
+    }
+}
+
+/// Delete a global image.
+///
+/// Every project can use a global image, make sure none of them needs it anymore.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdImageGlobalDelete {
+    /// The global image to delete. Can be an ID or name.
+    #[clap(name = "image", required = true)]
+    pub image: String,
+
+    /// Confirm deletion without prompting.
+    #[clap(long, short = 'y', visible_alias = "yes", env = "OXIDE_ASSUME_YES")]
+    pub confirm: bool,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdImageGlobalDelete {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        if !ctx.io.can_prompt() && !self.confirm {
+            return Err(anyhow!("--confirm required when not running interactively"));
+        }
+
+        if !self.confirm {
+            if let Err(err) = dialoguer::Input::<String>::new()
+                .with_prompt(format!("Type {} to confirm deletion:", self.image))
+                .validate_with(|input: &String| -> Result<(), &str> {
+                    if input.trim() == self.image {
+                        Ok(())
+                    } else {
+                        Err("mismatched confirmation")
+                    }
+                })
+                .interact_text()
+            {
+                return Err(anyhow!("prompt failed: {}", err));
+            }
+        }
+
+        let client = ctx.api_client("")?;
+        let image = global_image_name(ctx, &client, &self.image).await?;
+
+        ctx.timed(
+            "DELETE",
+            "/images/{image_name}",
+            client.images_global().images_delete_image(&image),
+        )
+        .await?;
+
+        if !ctx.quiet {
+            let cs = ctx.io.color_scheme();
+            writeln!(
+                ctx.io.out,
+                "{} Deleted global image {}",
+                cs.success_icon_with_color(ansi_term::Color::Red),
+                self.image
+            )?;
+        }
+
+        Ok(())
+    }
+}
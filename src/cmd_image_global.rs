@@ -1,29 +0,0 @@
-use anyhow::Result;
-use clap::Parser;
-use cli_macro::crud_gen;
-
-/// Create, list, view, and delete global images.
-#[derive(Parser, Debug, Clone)]
-#[clap(verbatim_doc_comment)]
-pub struct CmdImageGlobal {
-    #[clap(subcommand)]
-    subcmd: SubCommand,
-}
-
-#[crud_gen {
-    tag = "images:global",
-}]
-#[derive(Parser, Debug, Clone)]
-enum SubCommand {}
-
-#[async_trait::async_trait]
-impl crate::cmd::Command for CmdImageGlobal {
-    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
-        match &self.subcmd {
-            SubCommand::Create(cmd) => cmd.run(ctx).await,
-            SubCommand::Delete(cmd) => cmd.run(ctx).await,
-            SubCommand::List(cmd) => cmd.run(ctx).await,
-            SubCommand::View(cmd) => cmd.run(ctx).await,
-        }
-    }
-}
@@ -6,7 +6,7 @@ use cli_macro::crud_gen;
 
 /// Create, list, edit, view, and delete instances.
 ///
-/// Additionally, start, stop, and reboot instances.
+/// Additionally, start, stop, reboot, and migrate instances, and list their external IPs.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdInstance {
@@ -21,22 +21,28 @@ pub struct CmdInstance {
 enum SubCommand {
     Disks(CmdInstanceDisks),
     Edit(CmdInstanceEdit),
+    ExternalIps(crate::cmd_instance_external_ip::CmdInstanceExternalIps),
     Ssh(CmdInstanceSsh),
     Start(CmdInstanceStart),
     Stop(CmdInstanceStop),
     Reboot(CmdInstanceReboot),
+    Migrate(CmdInstanceMigrate),
     Serial(CmdInstanceSerial),
+    Console(CmdInstanceConsole),
 }
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdInstance {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
         match &self.subcmd {
+            SubCommand::Console(cmd) => cmd.run(ctx).await,
             SubCommand::Create(cmd) => cmd.run(ctx).await,
             SubCommand::Delete(cmd) => cmd.run(ctx).await,
             SubCommand::Disks(cmd) => cmd.run(ctx).await,
             SubCommand::Edit(cmd) => cmd.run(ctx).await,
+            SubCommand::ExternalIps(cmd) => cmd.run(ctx).await,
             SubCommand::List(cmd) => cmd.run(ctx).await,
+            SubCommand::Migrate(cmd) => cmd.run(ctx).await,
             SubCommand::Serial(cmd) => cmd.run(ctx).await,
             SubCommand::Ssh(cmd) => cmd.run(ctx).await,
             SubCommand::Start(cmd) => cmd.run(ctx).await,
@@ -47,6 +53,68 @@ impl crate::cmd::Command for CmdInstance {
     }
 }
 
+/// The block size of the disks created with `instance create --create-disk`, the same as
+/// the default of `image upload`.
+const DEFAULT_BLOCK_SIZE: oxide_api::types::BlockSize = 512;
+
+/// Parse a disk passed to `instance create --disk`, the name of an existing disk.
+pub fn parse_disk_attach(s: &str) -> Result<oxide_api::types::InstanceDiskAttachment> {
+    Ok(oxide_api::types::InstanceDiskAttachment::Attach {
+        name: parse_disk_name(s, s)?,
+    })
+}
+
+/// Parse a disk passed to `instance create --create-disk` as `name:size`, for example
+/// `data:10` or `data:512MiB`. Sizes without a unit are in GiB. The disk is blank.
+pub fn parse_disk_create(s: &str) -> Result<oxide_api::types::InstanceDiskAttachment> {
+    let (name, size) = s
+        .split_once(':')
+        .ok_or_else(|| anyhow!("invalid disk `{}`: expected `name:size`, for example `data:10`", s))?;
+
+    let name = parse_disk_name(s, name)?;
+
+    let size = size.trim();
+    let size = if !size.is_empty() && size.chars().all(|c| c.is_ascii_digit()) {
+        format!("{}GiB", size)
+    } else {
+        size.to_string()
+    };
+    let size = crate::types::parse_byte_count(&size).map_err(|err| anyhow!("invalid disk `{}`: {}", s, err))?;
+    if size == 0 {
+        return Err(anyhow!("invalid disk `{}`: the size must be greater than 0", s));
+    }
+
+    Ok(oxide_api::types::InstanceDiskAttachment::Create {
+        description: String::new(),
+        disk_source: oxide_api::types::DiskSource::Blank {
+            block_size: DEFAULT_BLOCK_SIZE,
+        },
+        name,
+        size,
+    })
+}
+
+/// Check a disk name the way the API does: lowercase letters, digits, and dashes, starting
+/// with a letter and not ending with a dash, at most 63 characters.
+fn parse_disk_name(s: &str, name: &str) -> Result<String> {
+    let valid = name.len() <= 63
+        && name.starts_with(|c: char| c.is_ascii_lowercase())
+        && !name.ends_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+
+    if !valid {
+        return Err(anyhow!(
+            "invalid disk `{}`: `{}` is not a valid name, names have lowercase letters, digits, and dashes, and start with a letter",
+            s,
+            name
+        ));
+    }
+
+    Ok(name.to_string())
+}
+
 /// List the disks attached to an instance.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
@@ -56,34 +124,90 @@ pub struct CmdInstanceDisks {
     pub instance: String,
 
     /// The project that holds the instance.
-    #[clap(long, short, required = true)]
+    #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
     pub project: String,
 
     /// The organization to view the project.
-    #[clap(long, short, required = true, env = "OXIDE_ORG")]
+    #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
     pub organization: String,
 
+    /// Maximum number of items to list.
+    #[clap(long, short, default_value = "30")]
+    pub limit: u32,
+
+    /// Make additional HTTP requests to fetch all pages.
+    #[clap(long)]
+    pub paginate: bool,
+
+    /// Start at the page with this token, from the `next_page` of an earlier listing. With
+    /// `--format json`, the page is printed as the API returns it, with the token of the
+    /// page after it in `next_page`. Pass an empty token for the first page.
+    #[clap(long, conflicts_with = "paginate")]
+    pub page_token: Option<String>,
+
     #[doc = r" Output format."]
     #[clap(long, short)]
     pub format: Option<crate::types::FormatOutput>,
+
+    #[doc = r" The template to render each item with, for example '{{.name}} {{.id}}'."]
+    #[clap(long)]
+    pub template: Option<String>,
 }
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdInstanceDisks {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        if self.limit < 1 {
+            return Err(anyhow!("--limit must be greater than 0"));
+        }
+
+        let (organization, project) = resolve_project(ctx, &self.organization, &self.project).await?;
         let client = ctx.api_client("")?;
+        let format = ctx.format(&self.format, &self.template)?;
+        let sort_by = oxide_api::types::NameSortMode::NameAscending;
+
+        // The pages are fetched the same way as for the generated list commands.
+        let endpoint = format!(
+            "/organizations/{}/projects/{}/instances/{}/disks",
+            organization, project, self.instance
+        );
+        if self.paginate && format == crate::types::FormatOutput::Jsonl {
+            return crate::cmd_api::write_pages_jsonl(ctx, &client, &endpoint, self.limit, &sort_by).await;
+        }
 
-        let results = client
-            .instances()
-            .disks_get_all(
-                &self.instance,
-                &self.organization,
-                &self.project,
-                oxide_api::types::NameSortMode::NameAscending,
+        if let Some(page_token) = &self.page_token {
+            if format == crate::types::FormatOutput::Json {
+                let page = crate::cmd_api::get_page(ctx, &client, &endpoint, self.limit, &sort_by, page_token).await?;
+                return ctx.io.write_output_json(&serde_json::to_value(&page)?);
+            }
+        }
+
+        let path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/disks";
+        let results = if self.paginate {
+            ctx.timed(
+                "GET",
+                path,
+                client
+                    .instances()
+                    .disks_get_all(&self.instance, &organization, &project, sort_by),
             )
-            .await?;
+            .await?
+        } else {
+            ctx.timed(
+                "GET",
+                path,
+                client.instances().disks_get(
+                    self.limit,
+                    &self.instance,
+                    &organization,
+                    self.page_token.as_deref().unwrap_or_default(),
+                    &project,
+                    sort_by,
+                ),
+            )
+            .await?
+        };
 
-        let format = ctx.format(&self.format)?;
         ctx.io.write_output_for_vec(&format, &results)?;
         Ok(())
     }
@@ -97,6 +221,8 @@ pub struct CmdInstanceEdit {}
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdInstanceEdit {
     async fn run(&self, _ctx: &mut crate::context::Context) -> Result<()> {
+        // TODO: add `instance resize` for the CPUs and memory once the API has an endpoint
+        // to update an instance, there is none in spec.json yet.
         println!("Not implemented yet in omicron.");
         Ok(())
     }
@@ -111,46 +237,62 @@ pub struct CmdInstanceStart {
     instance: String,
 
     /// The project that holds the instance.
-    #[clap(long, short, required = true)]
+    #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
     pub project: String,
 
     /// The organization that holds the project.
-    #[clap(long, short, required = true, env = "OXIDE_ORG")]
+    #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
     pub organization: String,
+
+    /// How long to wait for the instance to reach its new state, in seconds.
+    #[clap(long, default_value = "300")]
+    pub wait_timeout: u64,
 }
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdInstanceStart {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let (organization, project) = resolve_project(ctx, &self.organization, &self.project).await?;
         let client = ctx.api_client("")?;
 
-        let full_name = format!("{}/{}", self.organization, self.project);
+        let full_name = format!("{}/{}", organization, project);
 
         // Name the future to start the instance.
         let instances = client.instances();
-        let start_instance = instances.start(&self.instance, &self.organization, &self.project);
+        let start_instance = ctx.timed(
+            "POST",
+            "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/start",
+            instances.start(&self.instance, &organization, &project),
+        );
 
         // And another to wait for the instance to be started.
         let instance_state = InstanceDetails {
             host: "".to_string(),
             instance: self.instance.to_string(),
-            organization: self.organization.to_string(),
-            project: self.project.to_string(),
+            organization: organization.to_string(),
+            project: project.to_string(),
         };
-        let state_change = instance_state.wait_for_state(ctx, oxide_api::types::InstanceState::Running);
+        let state_change = instance_state.wait_for_state(
+            ctx,
+            oxide_api::types::InstanceState::Running,
+            std::time::Duration::from_secs(self.wait_timeout),
+            None,
+        );
 
         // Concurrently send the start request and wait for the instance to be started,
         // bail out if either fails.
         tokio::try_join!(start_instance, state_change)?;
 
-        let cs = ctx.io.color_scheme();
-        writeln!(
-            ctx.io.out,
-            "{} Started instance {} in {}",
-            cs.success_icon(),
-            self.instance,
-            full_name
-        )?;
+        if !ctx.quiet {
+            let cs = ctx.io.color_scheme();
+            writeln!(
+                ctx.io.out,
+                "{} Started instance {} in {}",
+                cs.success_icon(),
+                self.instance,
+                full_name
+            )?;
+        }
 
         Ok(())
     }
@@ -165,16 +307,20 @@ pub struct CmdInstanceStop {
     instance: String,
 
     /// The project that holds the instance.
-    #[clap(long, short, required = true)]
+    #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
     pub project: String,
 
     /// The organization that holds the project.
-    #[clap(long, short, required = true, env = "OXIDE_ORG")]
+    #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
     pub organization: String,
 
     /// Confirm stop without prompting.
-    #[clap(long)]
+    #[clap(long, short = 'y', visible_alias = "yes", env = "OXIDE_ASSUME_YES")]
     pub confirm: bool,
+
+    /// How long to wait for the instance to reach its new state, in seconds.
+    #[clap(long, default_value = "300")]
+    pub wait_timeout: u64,
 }
 
 #[async_trait::async_trait]
@@ -184,9 +330,10 @@ impl crate::cmd::Command for CmdInstanceStop {
             return Err(anyhow!("--confirm required when not running interactively"));
         }
 
+        let (organization, project) = resolve_project(ctx, &self.organization, &self.project).await?;
         let client = ctx.api_client("")?;
 
-        let full_name = format!("{}/{}", self.organization, self.project);
+        let full_name = format!("{}/{}", organization, project);
 
         // Confirm stop.
         if !self.confirm {
@@ -206,31 +353,40 @@ impl crate::cmd::Command for CmdInstanceStop {
         }
 
         // Stop the instance.
-        client
-            .instances()
-            .stop(&self.instance, &self.organization, &self.project)
-            .await?;
+        ctx.timed(
+            "POST",
+            "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/stop",
+            client.instances().stop(&self.instance, &organization, &project),
+        )
+        .await?;
 
         // Wait for the instance to be stopped.
         let instance_state = InstanceDetails {
             host: "".to_string(),
             instance: self.instance.to_string(),
-            organization: self.organization.to_string(),
-            project: self.project.to_string(),
+            organization: organization.to_string(),
+            project: project.to_string(),
         };
 
         instance_state
-            .wait_for_state(ctx, oxide_api::types::InstanceState::Stopped)
+            .wait_for_state(
+                ctx,
+                oxide_api::types::InstanceState::Stopped,
+                std::time::Duration::from_secs(self.wait_timeout),
+                None,
+            )
             .await?;
 
-        let cs = ctx.io.color_scheme();
-        writeln!(
-            ctx.io.out,
-            "{} Stopped instance {} in {}",
-            cs.failure_icon_with_color(ansi_term::Color::Green),
-            self.instance,
-            full_name
-        )?;
+        if !ctx.quiet {
+            let cs = ctx.io.color_scheme();
+            writeln!(
+                ctx.io.out,
+                "{} Stopped instance {} in {}",
+                cs.failure_icon_with_color(ansi_term::Color::Green),
+                self.instance,
+                full_name
+            )?;
+        }
 
         Ok(())
     }
@@ -245,16 +401,20 @@ pub struct CmdInstanceReboot {
     instance: String,
 
     /// The project that holds the instance.
-    #[clap(long, short, required = true)]
+    #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
     pub project: String,
 
     /// The organization that holds the project.
-    #[clap(long, short, required = true, env = "OXIDE_ORG")]
+    #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
     pub organization: String,
 
     /// Confirm reboot without prompting.
-    #[clap(long)]
+    #[clap(long, short = 'y', visible_alias = "yes", env = "OXIDE_ASSUME_YES")]
     pub confirm: bool,
+
+    /// How long to wait for the instance to reach its new state, in seconds.
+    #[clap(long, default_value = "300")]
+    pub wait_timeout: u64,
 }
 
 #[async_trait::async_trait]
@@ -264,9 +424,10 @@ impl crate::cmd::Command for CmdInstanceReboot {
             return Err(anyhow!("--confirm required when not running interactively"));
         }
 
+        let (organization, project) = resolve_project(ctx, &self.organization, &self.project).await?;
         let client = ctx.api_client("")?;
 
-        let full_name = format!("{}/{}", self.organization, self.project);
+        let full_name = format!("{}/{}", organization, project);
 
         // Confirm reboot.
         if !self.confirm {
@@ -286,36 +447,209 @@ impl crate::cmd::Command for CmdInstanceReboot {
         }
 
         // Reboot the instance.
-        client
-            .instances()
-            .reboot(&self.instance, &self.organization, &self.project)
-            .await?;
+        ctx.timed(
+            "POST",
+            "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/reboot",
+            client.instances().reboot(&self.instance, &organization, &project),
+        )
+        .await?;
 
         // Wait for the instance to be started.
         let instance_state = InstanceDetails {
             host: "".to_string(),
             instance: self.instance.to_string(),
-            organization: self.organization.to_string(),
-            project: self.project.to_string(),
+            organization: organization.to_string(),
+            project: project.to_string(),
         };
 
         instance_state
-            .wait_for_state(ctx, oxide_api::types::InstanceState::Running)
+            .wait_for_state(
+                ctx,
+                oxide_api::types::InstanceState::Running,
+                std::time::Duration::from_secs(self.wait_timeout),
+                None,
+            )
+            .await?;
+
+        if !ctx.quiet {
+            let cs = ctx.io.color_scheme();
+            writeln!(
+                ctx.io.out,
+                "{} Rebooted instance {} in {}",
+                cs.success_icon(),
+                self.instance,
+                full_name
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Migrate a running instance to another sled.
+///
+/// The instance keeps running while it moves, and the command waits for it to be
+/// `running` again on the new sled.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdInstanceMigrate {
+    /// The instance to migrate.
+    #[clap(name = "instance", required = true)]
+    instance: String,
+
+    /// The project that holds the instance.
+    #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
+    pub project: String,
+
+    /// The organization that holds the project.
+    #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
+    pub organization: String,
+
+    /// The ID of the sled to migrate the instance to.
+    #[clap(long = "to-sled", required = true, parse(try_from_str = uuid::Uuid::parse_str))]
+    pub to_sled: uuid::Uuid,
+
+    /// Confirm migration without prompting.
+    #[clap(long, short = 'y', visible_alias = "yes", env = "OXIDE_ASSUME_YES")]
+    pub confirm: bool,
+
+    /// How long to wait for the instance to reach its new state, in seconds.
+    #[clap(long, default_value = "300")]
+    pub wait_timeout: u64,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdInstanceMigrate {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        if !ctx.io.can_prompt() && !self.confirm {
+            return Err(anyhow!("--confirm required when not running interactively"));
+        }
+
+        let (organization, project) = resolve_project(ctx, &self.organization, &self.project).await?;
+        let client = ctx.api_client("")?;
+
+        let full_name = format!("{}/{}", organization, project);
+        let sled = self.to_sled.to_string();
+
+        // Make sure the sled exists before we touch the instance.
+        ctx.timed("GET", "/hardware/sleds/{sled_id}", client.sleds().get(&sled))
             .await?;
 
-        let cs = ctx.io.color_scheme();
-        writeln!(
-            ctx.io.out,
-            "{} Rebooted instance {} in {}",
-            cs.success_icon(),
-            self.instance,
-            full_name
+        // No endpoint says which sled an instance runs on, so we can't check that the
+        // instance isn't already on this one. If it is, the API rejects the migration
+        // and we say so below.
+
+        // Confirm migration.
+        if !self.confirm {
+            if let Err(err) = dialoguer::Input::<String>::new()
+                .with_prompt(format!("Type {} to confirm migration to sled {}:", self.instance, sled))
+                .validate_with(|input: &String| -> Result<(), &str> {
+                    if input.trim() == self.instance {
+                        Ok(())
+                    } else {
+                        Err("mismatched confirmation")
+                    }
+                })
+                .interact_text()
+            {
+                return Err(anyhow!("prompt failed: {}", err));
+            }
+        }
+
+        // Migrate the instance.
+        let migrate = ctx
+            .timed(
+                "POST",
+                "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/migrate",
+                client.instances().migrate(
+                    &self.instance,
+                    &organization,
+                    &project,
+                    &oxide_api::types::InstanceMigrate {
+                        dst_sled_id: sled.to_string(),
+                    },
+                ),
+            )
+            .await;
+        if let Err(err) = migrate {
+            return match err.downcast_ref::<oxide_api::types::Error>() {
+                Some(oxide_api::types::Error::InvalidRequest { message }) => {
+                    Err(oxide_api::types::Error::InvalidRequest {
+                        message: format!(
+                            "cannot migrate instance {} to sled {}, it may already be running there: {}",
+                            self.instance, sled, message
+                        ),
+                    }
+                    .into())
+                }
+                _ => Err(err),
+            };
+        }
+        ctx.io.progress_event(
+            "migrating",
+            serde_json::json!({ "instance": self.instance, "sled": sled }),
         )?;
 
+        // Wait for the instance to be running on the new sled.
+        let instance_state = InstanceDetails {
+            host: "".to_string(),
+            instance: self.instance.to_string(),
+            organization: organization.to_string(),
+            project: project.to_string(),
+        };
+
+        let progress = |state: &oxide_api::types::InstanceState| {
+            format!(" Migrating instance {} to sled {} [{}]", self.instance, sled, state)
+        };
+        instance_state
+            .wait_for_state(
+                ctx,
+                oxide_api::types::InstanceState::Running,
+                std::time::Duration::from_secs(self.wait_timeout),
+                Some(&progress),
+            )
+            .await?;
+
+        if !ctx.quiet {
+            let cs = ctx.io.color_scheme();
+            writeln!(
+                ctx.io.out,
+                "{} Migrated instance {} in {} to sled {}",
+                cs.success_icon(),
+                self.instance,
+                full_name,
+                sled
+            )?;
+        }
+
         Ok(())
     }
 }
 
+/// Resolve the organization and project of an instance command from the flags,
+/// environment, or defaults in the config. The API paths take names, so IDs are looked up.
+async fn resolve_project(
+    ctx: &crate::context::Context<'_>,
+    organization: &str,
+    project: &str,
+) -> Result<(String, String)> {
+    let organization = ctx.resolve_organization(organization)?;
+    if organization.is_empty() {
+        return Err(anyhow!("-o|--organization required"));
+    }
+
+    let project = ctx.resolve_project(project)?;
+    if project.is_empty() {
+        return Err(anyhow!("-p|--project required"));
+    }
+
+    let client = ctx.api_client("")?;
+    let organization = ctx.organization_name(&client, &organization).await?;
+    let project = ctx.project_name(&client, &project).await?;
+
+    Ok((organization, project))
+}
+
 #[derive(Debug, Clone, PartialEq)]
 struct InstanceDetails {
     host: String,
@@ -325,10 +659,32 @@ struct InstanceDetails {
 }
 
 impl InstanceDetails {
+    async fn run_state(&self, ctx: &mut crate::context::Context<'_>) -> Result<oxide_api::types::InstanceState> {
+        let client = ctx.api_client(&self.host)?;
+        let instance = ctx
+            .timed(
+                "GET",
+                "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}",
+                client
+                    .instances()
+                    .get(&self.instance, &self.organization, &self.project),
+            )
+            .await?;
+
+        Ok(instance.run_state)
+    }
+
+    /// Poll the instance until it is in `status`, giving up after `timeout`.
+    ///
+    /// Each time the instance changes state, `progress` is called with the new state
+    /// to get the label for the progress bar. Without it the label says which state
+    /// we are waiting for.
     async fn wait_for_state(
         &self,
         ctx: &mut crate::context::Context<'_>,
         status: oxide_api::types::InstanceState,
+        timeout: std::time::Duration,
+        progress: Option<&(dyn Fn(&oxide_api::types::InstanceState) -> String + Sync)>,
     ) -> Result<()> {
         // Start the progress bar.
         let handle = ctx
@@ -340,24 +696,63 @@ impl InstanceDetails {
 
         let client = ctx.api_client(&self.host)?;
 
-        // TODO: we should probably time out here eventually with an error.
+        let start = std::time::Instant::now();
         let mut last_state = None;
         loop {
-            let instance = client
-                .instances()
-                .get(&self.instance, &self.organization, &self.project)
+            let instance = ctx
+                .timed(
+                    "GET",
+                    "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}",
+                    client
+                        .instances()
+                        .get(&self.instance, &self.organization, &self.project),
+                )
                 .await?;
 
             if status == instance.run_state {
                 break;
             }
 
+            // A failed instance is not going anywhere, so don't wait for the timeout.
+            if instance.run_state == oxide_api::types::InstanceState::Failed {
+                if let Some(handle) = handle {
+                    handle.stop();
+                }
+
+                return Err(anyhow!(
+                    "instance {} failed while waiting for it to be `{}`",
+                    self.instance,
+                    status
+                ));
+            }
+
+            if start.elapsed() > timeout {
+                if let Some(handle) = handle {
+                    handle.stop();
+                }
+
+                return Err(anyhow!(
+                    "timed out after {}s waiting for instance {} to be `{}`, it is `{}`",
+                    timeout.as_secs(),
+                    self.instance,
+                    status,
+                    instance.run_state
+                ));
+            }
+
             if last_state.as_ref() != Some(&instance.run_state) {
+                ctx.io.progress_event(
+                    "waiting",
+                    serde_json::json!({ "instance": self.instance, "state": instance.run_state, "want": status }),
+                )?;
                 if let Some(handle) = &handle {
-                    handle.text(format!(
-                        " Waiting for instance status to be `{}` [{}]",
-                        status, instance.run_state
-                    ));
+                    handle.text(match progress {
+                        Some(progress) => progress(&instance.run_state),
+                        None => format!(
+                            " Waiting for instance status to be `{}` [{}]",
+                            status, instance.run_state
+                        ),
+                    });
                 }
                 last_state = Some(instance.run_state);
             }
@@ -371,6 +766,10 @@ impl InstanceDetails {
             handle.text(format!("Instance status now `{}`", status));
             handle.done();
         }
+        ctx.io.progress_event(
+            "done",
+            serde_json::json!({ "instance": self.instance, "state": status }),
+        )?;
 
         Ok(())
     }
@@ -379,7 +778,8 @@ impl InstanceDetails {
 /// SSH into an instance.
 ///
 /// This command is a thin wrapper around the **ssh(1)** command that takes care of
-/// authentication and the translation of the instance name into an IP address.
+/// the translation of the instance name into an IP address. The instance's first
+/// external IP is used, or the IP of its first network interface if it has none.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment, trailing_var_arg = true)]
 pub struct CmdInstanceSsh {
@@ -393,11 +793,11 @@ pub struct CmdInstanceSsh {
     pub args: Vec<String>,
 
     /// The project that holds the instance.
-    #[clap(long, short, required = true)]
+    #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
     pub project: String,
 
     /// The organization that holds the project.
-    #[clap(long, short, required = true, env = "OXIDE_ORG")]
+    #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
     pub organization: String,
 
     /// The ssh user. This defaults to `$USER` on the host the command is run on.
@@ -414,57 +814,88 @@ pub struct CmdInstanceSsh {
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdInstanceSsh {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let (organization, project) = resolve_project(ctx, &self.organization, &self.project).await?;
         let client = ctx.api_client("")?;
 
-        // Generate a key to use for ssh-ing into the instance.
-        // We default to ed25519 here, since its a nice thing.
-        writeln!(ctx.io.out, "Generating a temporary ssh key...")?;
-        /* let key = thrussh_keys::key::KeyPair::generate_ed25519().unwrap();
-                let pubkey = key.clone_public_key();
-
-                writeln!(
-                    ctx.io.out,
-                    "Temporary public key has fingerprint `{}`",
-                    pubkey.fingerprint()
-                )?;
-
-                writeln!(
-                    ctx.io.out,
-                    "Temporary bytes are `ssh-ed25519 {}`",
-                    pubkey.public_key_base64()
-                )?;
-
-                println!("ARGS: {:?}", self.args);
-
-                // TODO: Add our pubkey to our Oxide user's authorized_keys.
-                writeln!(ctx.io.out, "Adding temporary ssh key to your user account...")?;
-        */
-        // TODO: We need to get the instance IP address.
-        let _instance = client
-            .instances()
-            .get(&self.instance, &self.organization, &self.project)
-            .await?;
+        // Make sure the instance exists before we go looking for its addresses.
+        ctx.timed(
+            "GET",
+            "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}",
+            client.instances().get(&self.instance, &organization, &project),
+        )
+        .await?;
+
+        let ip = self.instance_ip(ctx, &client, &organization, &project).await?;
+
+        let mut ssh_flags = Vec::new();
+        for flag in &self.ssh_flags {
+            match shlex::split(flag) {
+                Some(flag) => ssh_flags.extend(flag),
+                None => return Err(anyhow!("invalid ssh flag: {}", flag)),
+            }
+        }
 
-        // Wrap the ssh command in a shell.
-        std::process::Command::new("ssh")
-            //.arg(host)
+        let status = std::process::Command::new("ssh")
+            .arg("-l")
+            .arg(&self.user)
+            .args(&ssh_flags)
+            .arg(&ip)
             .args(&self.args)
             .stdout(std::process::Stdio::inherit())
             .stdin(std::process::Stdio::inherit())
             .stderr(std::process::Stdio::inherit())
-            .output()?;
+            .status()?;
 
-        // TODO: When we are done, we need to remove our key from our Oxide user's authorized keys.
-        // This makes it act as a temporary key.
-        writeln!(
-            ctx.io.out,
-            "Cleaning up the temporary ssh key from your user account..."
-        )?;
+        if !status.success() {
+            return Err(anyhow!("ssh exited with {}", status));
+        }
 
         Ok(())
     }
 }
 
+impl CmdInstanceSsh {
+    /// Get the address to connect to: the instance's first external IP, or the IP of its
+    /// first network interface if it has no external IPs.
+    async fn instance_ip(
+        &self,
+        ctx: &crate::context::Context<'_>,
+        client: &oxide_api::Client,
+        organization: &str,
+        project: &str,
+    ) -> Result<String> {
+        let base = format!(
+            "/organizations/{}/projects/{}/instances/{}",
+            organization, project, self.instance
+        );
+
+        for path in ["external-ips", "network-interfaces"] {
+            let endpoint = format!("{}/{}", base, path);
+            let req = client.request_raw(http::Method::GET, &endpoint, None).await?;
+            let resp = ctx.timed_raw("GET", &endpoint, req).await?;
+
+            if !resp.status().is_success() {
+                return Err(anyhow!(
+                    "listing the {} of instance {} failed: {}",
+                    path,
+                    self.instance,
+                    resp.status()
+                ));
+            }
+
+            let page: serde_json::Value = resp.json().await?;
+            if let Some(ip) = page["items"][0]["ip"].as_str() {
+                return Ok(ip.to_string());
+            }
+        }
+
+        Err(anyhow!(
+            "instance {} has no external IP address, see `oxide instance external-ips --help`",
+            self.instance
+        ))
+    }
+}
+
 /// Read the buffered data from an instance's serial console.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment, trailing_var_arg = true)]
@@ -474,11 +905,11 @@ pub struct CmdInstanceSerial {
     pub instance: String,
 
     /// The project that holds the instance.
-    #[clap(long, short, required = true)]
+    #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
     pub project: String,
 
     /// The organization that holds the project.
-    #[clap(long, short, required = true, env = "OXIDE_ORG")]
+    #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
     pub organization: String,
 
     /// The maximum length of bytes to retrieve.
@@ -495,20 +926,63 @@ pub struct CmdInstanceSerial {
     pub continuous: bool,
 
     /// Whether to connect interactively (read/write) to the running instance's serial console.
-    /// (NOTE: ignores --byte-offset, --max-bytes, and --continuous)
+    /// (NOTE: ignores --byte-offset, --max-bytes, --continuous, and --output-file)
     #[cfg(unix)]
     #[clap(long, short)]
     pub interactive: bool,
+
+    /// Append the output to this file instead of writing it to standard output. With
+    /// `--continuous`, the file is written to as the output comes in, so it can be followed
+    /// with `tail -f`.
+    #[clap(long, default_value = "")]
+    pub output_file: String,
 }
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdInstanceSerial {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let (organization, project) = resolve_project(ctx, &self.organization, &self.project).await?;
+
+        // The console is read in a few places, which all take the names from the command.
+        let cmd = CmdInstanceSerial {
+            organization,
+            project,
+            ..self.clone()
+        };
+
         #[cfg(unix)]
-        if self.interactive {
-            return self.websock_stream_tty(ctx).await;
+        if cmd.interactive {
+            return cmd.websock_stream_tty(ctx).await;
         }
 
+        // Keep what the file has already, so the output of several runs can be collected.
+        let stdout = if self.output_file.is_empty() {
+            None
+        } else {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.output_file)
+                .map_err(|err| anyhow!("opening {} failed: {}", self.output_file, err))?;
+            Some(std::mem::replace(&mut ctx.io.out, Box::new(file)))
+        };
+
+        let result = cmd.read_output(ctx).await;
+
+        match stdout {
+            Some(stdout) => ctx.io.out = stdout,
+            // Reset any attributes the console output left set, even when reading it failed.
+            None => writeln!(ctx.io.out, "\x1b[0m")?,
+        }
+
+        result
+    }
+}
+
+impl CmdInstanceSerial {
+    /// Write the buffered output of the serial console, and with `--continuous`, the output
+    /// that comes after it.
+    async fn read_output(&self, ctx: &mut crate::context::Context<'_>) -> Result<()> {
         let client = ctx.api_client("")?;
 
         let mut from_start = None;
@@ -521,21 +995,33 @@ impl crate::cmd::Command for CmdInstanceSerial {
             None => most_recent = Some(16384),
         }
 
+        // On unix, continuous mode follows the console over a websocket once the buffered
+        // output has been written.
         let mut cont = true;
         while cont {
-            let output = client
-                .instances()
-                .serial_get(
-                    from_start,
-                    &self.instance,
-                    max_bytes,
-                    most_recent,
-                    &self.organization,
-                    &self.project,
+            let output = ctx
+                .timed(
+                    "GET",
+                    "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/serial-console",
+                    client.instances().serial_get(
+                        from_start,
+                        &self.instance,
+                        max_bytes,
+                        most_recent,
+                        &self.organization,
+                        &self.project,
+                    ),
                 )
                 .await?;
 
-            std::io::stdout().write_all(&output.data)?;
+            // Flush every time, so output going to a file shows up as it comes in.
+            ctx.io.out.write_all(&output.data)?;
+            ctx.io.out.flush()?;
+
+            #[cfg(unix)]
+            if self.continuous {
+                return self.websock_stream_continuous(ctx).await;
+            }
 
             cont = self.continuous;
             most_recent = None;
@@ -546,12 +1032,166 @@ impl crate::cmd::Command for CmdInstanceSerial {
             }
         }
 
-        println!("\x1b[0m");
+        Ok(())
+    }
+}
+
+/// Follow an instance's serial console, like `tail -f`.
+///
+/// The most recent output is printed first, then new output as it comes in. When the
+/// connection drops, it is retried with a backoff and picks up at the same byte offset,
+/// so no output is lost or printed twice. The command exits once the instance is
+/// stopped, or on Ctrl-C.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdInstanceConsole {
+    /// The instance whose serial console we wish to follow. Can be an ID or name.
+    #[clap(name = "instance", required = true)]
+    pub instance: String,
+
+    /// The project that holds the instance.
+    #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
+    pub project: String,
+
+    /// The organization that holds the project.
+    #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
+    pub organization: String,
+
+    /// The offset since boot (or if negative, the current end of the buffered data) from which to
+    /// start following the output.
+    #[clap(long, short, default_value = "-16384", allow_hyphen_values = true)]
+    pub byte_offset: i64,
+
+    /// The number of times in a row to retry after losing the connection before giving up.
+    #[clap(long, default_value = "10")]
+    pub max_retries: u32,
+}
+
+/// The longest we wait before reconnecting to a console.
+const CONSOLE_MAX_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(30);
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdInstanceConsole {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let (organization, project) = resolve_project(ctx, &self.organization, &self.project).await?;
+
+        let details = InstanceDetails {
+            host: "".to_string(),
+            instance: self.instance.to_string(),
+            organization: organization.to_string(),
+            project: project.to_string(),
+        };
+
+        let state = details.run_state(ctx).await?;
+        if !ctx.quiet {
+            let cs = ctx.io.color_scheme();
+            let full_name = format!("{}/{}", organization, project);
+            let header = format!("Console of instance {} in {}", self.instance, full_name);
+            writeln!(ctx.io.out, "{} {}", cs.bold(&header), cs.gray(&format!("[{}]", state)))?;
+        }
+
+        let client = ctx.api_client("")?;
+
+        let mut from_start = None;
+        let mut most_recent = None;
+        if self.byte_offset >= 0 {
+            from_start = Some(self.byte_offset as u64);
+        } else {
+            most_recent = Some(self.byte_offset.unsigned_abs());
+        }
+
+        let mut failures = 0;
+        loop {
+            let result = ctx
+                .timed(
+                    "GET",
+                    "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/serial-console",
+                    client.instances().serial_get(
+                        from_start,
+                        &self.instance,
+                        None,
+                        most_recent,
+                        &organization,
+                        &project,
+                    ),
+                )
+                .await;
+
+            match result {
+                Ok(output) => {
+                    failures = 0;
+                    ctx.io.out.write_all(&output.data)?;
+                    ctx.io.out.flush()?;
+
+                    most_recent = None;
+                    from_start = Some(output.last_byte_offset);
+
+                    if !output.data.is_empty() {
+                        continue;
+                    }
+                }
+                Err(err) => {
+                    if !is_transient_console_error(&err) || failures >= self.max_retries {
+                        // Reset any attributes the console output left set.
+                        writeln!(ctx.io.out, "\x1b[0m")?;
+                        return Err(err);
+                    }
+
+                    // The console might have gone away because the instance stopped.
+                    if details.run_state(ctx).await.ok() != Some(oxide_api::types::InstanceState::Stopped) {
+                        let delay = console_retry_delay(failures);
+                        failures += 1;
+
+                        writeln!(
+                            ctx.io.err_out,
+                            "{} Lost the console: {}, reconnecting in {}s",
+                            ctx.io.color_scheme().warning_icon(),
+                            err,
+                            delay.as_secs()
+                        )?;
+                        tokio::time::sleep(delay).await;
+                        continue;
+                    }
+                }
+            }
+
+            // No new output, so this is a good time to check if the instance stopped.
+            if details.run_state(ctx).await? == oxide_api::types::InstanceState::Stopped {
+                break;
+            }
+
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        }
+
+        writeln!(ctx.io.out, "\x1b[0m")?;
+
+        if !ctx.quiet {
+            let cs = ctx.io.color_scheme();
+            writeln!(ctx.io.out, "{} Instance {} stopped", cs.success_icon(), self.instance)?;
+        }
 
         Ok(())
     }
 }
 
+/// Whether reconnecting to a console might help. Errors from the API other than
+/// the service being unavailable won't go away by trying again.
+fn is_transient_console_error(err: &anyhow::Error) -> bool {
+    match err.downcast_ref::<oxide_api::types::Error>() {
+        Some(oxide_api::types::Error::ServiceUnavailable { .. })
+        | Some(oxide_api::types::Error::InternalError { .. }) => true,
+        Some(_) => false,
+        None => true,
+    }
+}
+
+/// How long to wait before reconnecting to a console after `failures` failed attempts in
+/// a row. The delay doubles every time, up to `CONSOLE_MAX_RETRY_DELAY`.
+fn console_retry_delay(failures: u32) -> std::time::Duration {
+    let secs = 1u64.checked_shl(failures).unwrap_or(u64::MAX);
+    std::cmp::min(std::time::Duration::from_secs(secs), CONSOLE_MAX_RETRY_DELAY)
+}
+
 #[cfg(test)]
 mod test {
     use pretty_assertions::assert_eq;
@@ -566,6 +1206,59 @@ mod test {
         want_err: String,
     }
 
+    #[test]
+    fn test_console_retry_delay() {
+        assert_eq!(super::console_retry_delay(0).as_secs(), 1);
+        assert_eq!(super::console_retry_delay(3).as_secs(), 8);
+        assert_eq!(super::console_retry_delay(5).as_secs(), 30);
+        assert_eq!(super::console_retry_delay(100).as_secs(), 30);
+
+        assert!(super::is_transient_console_error(&anyhow::anyhow!("connection reset")));
+        assert!(super::is_transient_console_error(&anyhow::Error::new(
+            oxide_api::types::Error::ServiceUnavailable {
+                internal_message: "".to_string()
+            }
+        )));
+        assert!(!super::is_transient_console_error(&anyhow::Error::new(
+            oxide_api::types::Error::ObjectNotFound {
+                message: "not found".to_string()
+            }
+        )));
+    }
+
+    #[test]
+    fn test_parse_disk() {
+        assert!(matches!(
+            super::parse_disk_attach("boot").unwrap(),
+            oxide_api::types::InstanceDiskAttachment::Attach { name } if name == "boot"
+        ));
+        assert!(super::parse_disk_attach("Boot").is_err());
+        assert!(super::parse_disk_attach("boot-").is_err());
+
+        match super::parse_disk_create("data:10").unwrap() {
+            oxide_api::types::InstanceDiskAttachment::Create { name, size, .. } => {
+                assert_eq!(name, "data");
+                assert_eq!(size, 10 * 1024 * 1024 * 1024);
+            }
+            other => panic!("unexpected disk: {:?}", other),
+        }
+        match super::parse_disk_create("data:512MiB").unwrap() {
+            oxide_api::types::InstanceDiskAttachment::Create { size, .. } => assert_eq!(size, 512 * 1024 * 1024),
+            other => panic!("unexpected disk: {:?}", other),
+        }
+
+        assert_eq!(
+            super::parse_disk_create("data").unwrap_err().to_string(),
+            "invalid disk `data`: expected `name:size`, for example `data:10`"
+        );
+        assert_eq!(
+            super::parse_disk_create("data:0").unwrap_err().to_string(),
+            "invalid disk `data:0`: the size must be greater than 0"
+        );
+        assert!(super::parse_disk_create("data:lots").is_err());
+        assert!(super::parse_disk_create("1data:10").is_err());
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
     async fn test_cmd_instance() {
         let tests: Vec<TestItem> = vec![
@@ -581,9 +1274,11 @@ mod test {
                     hostname: "holla".to_string(),
                     network_interfaces: Default::default(),
                     disks: Default::default(),
+                    create_disks: Default::default(),
                     user_data: "some data".to_string(),
                     external_ips: Vec::from(["mypool".to_string()]),
                     start: true,
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -602,9 +1297,11 @@ mod test {
                     hostname: "holla".to_string(),
                     network_interfaces: Default::default(),
                     disks: Default::default(),
+                    create_disks: Default::default(),
                     user_data: "some data".to_string(),
                     external_ips: Vec::from(["mypool".to_string()]),
                     start: true,
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -623,9 +1320,11 @@ mod test {
                     hostname: "holla".to_string(),
                     network_interfaces: Default::default(),
                     disks: Default::default(),
+                    create_disks: Default::default(),
                     user_data: "some data".to_string(),
                     external_ips: Vec::from(["mypool".to_string()]),
                     start: true,
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -644,9 +1343,11 @@ mod test {
                     hostname: "".to_string(),
                     network_interfaces: Default::default(),
                     disks: Default::default(),
+                    create_disks: Default::default(),
                     user_data: "some data".to_string(),
                     external_ips: Vec::from(["mypool".to_string()]),
                     start: true,
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -665,9 +1366,11 @@ mod test {
                     hostname: "sup".to_string(),
                     network_interfaces: Default::default(),
                     disks: Default::default(),
+                    create_disks: Default::default(),
                     user_data: "some data".to_string(),
                     external_ips: Vec::from(["mypool".to_string()]),
                     start: true,
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -686,9 +1389,11 @@ mod test {
                     hostname: "sup".to_string(),
                     network_interfaces: Default::default(),
                     disks: Default::default(),
+                    create_disks: Default::default(),
                     user_data: "some data".to_string(),
                     external_ips: Vec::from(["mypool".to_string()]),
                     start: true,
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -701,6 +1406,8 @@ mod test {
                     instance: "things".to_string(),
                     organization: "".to_string(),
                     project: "".to_string(),
+                    all: false,
+                    filter: "".to_string(),
                     confirm: false,
                 }),
 
@@ -708,6 +1415,47 @@ mod test {
                 want_out: "".to_string(),
                 want_err: "--confirm required when not running interactively".to_string(),
             },
+            TestItem {
+                name: "migrate no --confirm non-interactive".to_string(),
+                cmd: crate::cmd_instance::SubCommand::Migrate(crate::cmd_instance::CmdInstanceMigrate {
+                    instance: "things".to_string(),
+                    organization: "foo".to_string(),
+                    project: "bar".to_string(),
+                    to_sled: uuid::Uuid::nil(),
+                    confirm: false,
+                    wait_timeout: 300,
+                }),
+
+                stdin: "".to_string(),
+                want_out: "".to_string(),
+                want_err: "--confirm required when not running interactively".to_string(),
+            },
+            TestItem {
+                name: "start no organization".to_string(),
+                cmd: crate::cmd_instance::SubCommand::Start(crate::cmd_instance::CmdInstanceStart {
+                    instance: "things".to_string(),
+                    organization: "".to_string(),
+                    project: "bar".to_string(),
+                    wait_timeout: 300,
+                }),
+
+                stdin: "".to_string(),
+                want_out: "".to_string(),
+                want_err: "-o|--organization required".to_string(),
+            },
+            TestItem {
+                name: "start no project".to_string(),
+                cmd: crate::cmd_instance::SubCommand::Start(crate::cmd_instance::CmdInstanceStart {
+                    instance: "things".to_string(),
+                    organization: "foo".to_string(),
+                    project: "".to_string(),
+                    wait_timeout: 300,
+                }),
+
+                stdin: "".to_string(),
+                want_out: "".to_string(),
+                want_err: "-p|--project required".to_string(),
+            },
             TestItem {
                 name: "list zero limit".to_string(),
                 cmd: crate::cmd_instance::SubCommand::List(crate::cmd_instance::CmdInstanceList {
@@ -715,7 +1463,15 @@ mod test {
                     organization: "".to_string(),
                     project: "".to_string(),
                     paginate: false,
+                    page_token: None,
                     format: None,
+                    template: None,
+                    columns: vec![],
+                    no_header: false,
+                    sort: "".to_string(),
+                    reverse: false,
+                    filter: vec![],
+                    output_file: "".to_string(),
                     sort_by: Default::default(),
                 }),
 
@@ -741,6 +1497,13 @@ mod test {
                 config: &mut c,
                 io,
                 debug: false,
+                quiet: false,
+                profile: None,
+                api_clients: Default::default(),
+                timeout: None,
+                headers: Default::default(),
+                request_id_sent: Default::default(),
+                endpoint: None,
             };
 
             let cmd_instance = crate::cmd_instance::CmdInstance { subcmd: t.cmd };
@@ -0,0 +1,96 @@
+use anyhow::{anyhow, Result};
+use clap::Parser;
+
+/// List the external IPs of an instance.
+///
+/// External IPs are allocated when the instance is created, with
+/// `oxide instance create --external-ips`.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdInstanceExternalIps {
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+// TODO: add `attach --pool` and `detach <ip>` once the API has endpoints to allocate and
+// release the external IPs of an existing instance, spec.json only has the list.
+#[derive(Parser, Debug, Clone)]
+enum SubCommand {
+    List(CmdInstanceExternalIpsList),
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdInstanceExternalIps {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        match &self.subcmd {
+            SubCommand::List(cmd) => cmd.run(ctx).await,
+        }
+    }
+}
+
+/// List the external IPs of an instance, with their kind, ephemeral or floating.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdInstanceExternalIpsList {
+    /// The instance to list the external IPs of.
+    #[clap(name = "instance", required = true)]
+    pub instance: String,
+
+    /// The project that holds the instance.
+    #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
+    pub project: String,
+
+    /// The organization that holds the project.
+    #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
+    pub organization: String,
+
+    /// Output format.
+    #[clap(long, short)]
+    pub format: Option<crate::types::FormatOutput>,
+
+    /// The template to render each item with, for example '{{.ip}} {{.kind}}'.
+    #[clap(long)]
+    pub template: Option<String>,
+
+    /// The columns to display, for example 'ip'. Defaults to all of them.
+    #[clap(long, use_value_delimiter = true)]
+    pub columns: Vec<String>,
+
+    /// Do not print the header row of table, csv, or tsv output.
+    #[clap(long)]
+    pub no_header: bool,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdInstanceExternalIpsList {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let organization = ctx.resolve_organization(&self.organization)?;
+        if organization.is_empty() {
+            return Err(anyhow!("-o|--organization required"));
+        }
+
+        let project = ctx.resolve_project(&self.project)?;
+        if project.is_empty() {
+            return Err(anyhow!("-p|--project required"));
+        }
+
+        let format = ctx.format(&self.format, &self.template)?;
+
+        let client = ctx.api_client("")?;
+
+        // The API does not say which pool an IP came from, so there is no pool column.
+        let result = ctx
+            .timed(
+                "GET",
+                "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/external-ips",
+                client
+                    .instances()
+                    .external_ip_list(&self.instance, &organization, &project),
+            )
+            .await?;
+
+        ctx.io
+            .write_output_for_vec_with_columns(&format, &self.columns, self.no_header, &result.items)?;
+        Ok(())
+    }
+}
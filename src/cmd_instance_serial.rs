@@ -2,7 +2,6 @@ use std::{mem::swap, os::unix::io::AsRawFd, time::Duration};
 
 use anyhow::Result;
 use futures::{SinkExt, StreamExt};
-use http::HeaderMap;
 use reqwest::ClientBuilder;
 use tokio_tungstenite::{
     tungstenite::protocol::{Message, Role},
@@ -14,7 +13,8 @@ mod nexus_client {
 }
 
 impl super::cmd_instance::CmdInstanceSerial {
-    pub(crate) async fn websock_stream_tty(&self, ctx: &mut crate::context::Context<'_>) -> Result<()> {
+    /// Open a websocket to the instance's serial console.
+    async fn connect(&self, ctx: &mut crate::context::Context<'_>) -> Result<WebSocketStream<reqwest::Upgraded>> {
         // shenanigans to get the info we need to construct a progenitor-client
         let reqw = ctx
             .api_client("")?
@@ -23,11 +23,18 @@ impl super::cmd_instance::CmdInstanceSerial {
             .build()?;
 
         let base = reqw.url().as_str();
-        let mut headers = HeaderMap::new();
+        // This client is ours, so unlike the typed calls it can send the `--header`s and
+        // the request ID.
+        let mut headers = ctx.headers.clone();
         headers.insert(
             http::header::AUTHORIZATION,
             reqw.headers().get(http::header::AUTHORIZATION).unwrap().to_owned(),
         );
+        headers.insert(
+            crate::context::REQUEST_ID_HEADER,
+            http::HeaderValue::from_static(crate::context::request_id()),
+        );
+        ctx.request_id_sent.store(true, std::sync::atomic::Ordering::Relaxed);
 
         let reqw_client = ClientBuilder::new()
             .connect_timeout(Duration::new(60, 0))
@@ -37,17 +44,61 @@ impl super::cmd_instance::CmdInstanceSerial {
 
         let nexus_client = nexus_client::Client::new_with_client(base, reqw_client);
 
-        let upgraded = nexus_client
-            .instance_serial_console_stream()
-            .organization_name(self.organization.to_owned())
-            .project_name(self.project.to_owned())
-            .instance_name(self.instance.to_owned())
-            .send()
-            .await
-            .map_err(|e| anyhow::anyhow!("{}", e))?
+        // Only opening the websocket is timed, the stream itself lasts as long as it needs.
+        let upgraded = ctx
+            .timed(
+                "GET",
+                "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/serial-console/stream",
+                async {
+                    nexus_client
+                        .instance_serial_console_stream()
+                        .organization_name(self.organization.to_owned())
+                        .project_name(self.project.to_owned())
+                        .instance_name(self.instance.to_owned())
+                        .send()
+                        .await
+                        .map_err(|e| anyhow::anyhow!("{}", e))
+                },
+            )
+            .await?
             .into_inner();
 
-        let mut ws = WebSocketStream::from_raw_socket(upgraded, Role::Client, None).await;
+        Ok(WebSocketStream::from_raw_socket(upgraded, Role::Client, None).await)
+    }
+
+    /// Stream the instance's serial console output to `ctx.io.out` as it comes in, until the
+    /// console is closed or we get a Ctrl-C.
+    pub(crate) async fn websock_stream_continuous(&self, ctx: &mut crate::context::Context<'_>) -> Result<()> {
+        let mut ws = self.connect(ctx).await?;
+
+        let ctrl_c = tokio::signal::ctrl_c();
+        tokio::pin!(ctrl_c);
+
+        loop {
+            tokio::select! {
+                _ = &mut ctrl_c => break,
+                msg = ws.next() => {
+                    match msg {
+                        Some(Ok(Message::Binary(output))) => {
+                            ctx.io.out.write_all(&output)?;
+                            ctx.io.out.flush()?;
+                        }
+                        Some(Ok(Message::Close(..))) | None => break,
+                        Some(Err(err)) => return Err(err.into()),
+                        _ => continue,
+                    }
+                }
+            }
+        }
+
+        // Let the server know we are going away, it is fine if it already did.
+        ws.close(None).await.ok();
+
+        Ok(())
+    }
+
+    pub(crate) async fn websock_stream_tty(&self, ctx: &mut crate::context::Context<'_>) -> Result<()> {
+        let mut ws = self.connect(ctx).await?;
 
         let mut stdin: Box<dyn std::io::Read + Send + Sync> = Box::new(std::io::empty());
         let mut stdout: Box<dyn std::io::Write + Send + Sync> = Box::new(std::io::sink());
@@ -275,6 +326,7 @@ mod test {
             byte_offset: None,
             continuous: false,
             interactive: true,
+            output_file: "".to_string(),
         };
         let mut config = crate::config::new_blank_config().unwrap();
         let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
@@ -284,6 +336,13 @@ mod test {
             config: &mut c,
             io,
             debug: false,
+            quiet: false,
+            profile: None,
+            api_clients: Default::default(),
+            timeout: None,
+            headers: Default::default(),
+            request_id_sent: Default::default(),
+            endpoint: None,
         };
         cmd.run(&mut ctx).await.unwrap();
 
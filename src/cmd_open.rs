@@ -1,15 +1,32 @@
+use std::io::Write;
+
 use anyhow::Result;
 use clap::Parser;
 use parse_display::{Display, FromStr};
 
-/// Shortcut to open the Oxide documentation or Console in your browser.
+/// Shortcut to open the Oxide documentation, Console, or issue tracker in your browser.
 ///
 /// If no arguments are given, the default is to open the Oxide documentation.
+/// The Console is the one of the host of the active profile, see `--profile`.
+///
+/// With `--print-url`, the URL is printed instead, which is handy when there is no
+/// browser, for example over ssh.
+///
+///     # Open the Console of the staging profile.
+///     $ oxide open console --profile staging
+///
+///     # Print the URL of the issue tracker.
+///     $ oxide open issues --print-url
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdOpen {
+    /// What to open: docs, api-ref, cli-ref, console, issues, or repo.
     #[clap(name = "shortcut", default_value_t)]
     shortcut: OpenShortcut,
+
+    /// Print the URL instead of opening it in a browser.
+    #[clap(long)]
+    print_url: bool,
 }
 
 /// The type of shortcut to open.
@@ -24,6 +41,10 @@ pub enum OpenShortcut {
     CliRef,
     /// Open the Oxide Console in your browser.
     Console,
+    /// Open the issue tracker of the CLI in your browser.
+    Issues,
+    /// Open the repository of the CLI in your browser.
+    Repo,
 }
 
 impl Default for OpenShortcut {
@@ -33,35 +54,30 @@ impl Default for OpenShortcut {
 }
 
 impl OpenShortcut {
-    fn get_url(&self) -> String {
-        match self {
+    fn get_url(&self, ctx: &crate::context::Context) -> Result<String> {
+        Ok(match self {
             OpenShortcut::Docs => "https://docs.oxide.computer".to_string(),
             OpenShortcut::ApiRef => "https://docs.oxide.computer/api".to_string(),
             OpenShortcut::CliRef => "https://docs.oxide.computer/cli".to_string(),
-            OpenShortcut::Console => "".to_string(),
-        }
+            // The Console is served by the host of the active profile.
+            OpenShortcut::Console => ctx.console_url()?,
+            OpenShortcut::Issues => "https://github.com/oxidecomputer/cli/issues".to_string(),
+            OpenShortcut::Repo => "https://github.com/oxidecomputer/cli".to_string(),
+        })
     }
 }
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdOpen {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
-        if self.shortcut != OpenShortcut::Console {
-            return ctx.browser("", &self.shortcut.get_url());
-        }
-
-        // If they want to open the console, we need to get their default host.
-        let mut host = ctx.config.default_host()?;
+        let url = self.shortcut.get_url(ctx)?;
 
-        if !host.starts_with("http") {
-            // Default to https://
-            host = format!("https://{}", host);
+        if self.print_url {
+            writeln!(ctx.io.out, "{}", url)?;
+            return Ok(());
         }
 
-        // TODO: check this works once we have a proper console.
-        ctx.browser("", &host)?;
-
-        Ok(())
+        ctx.browser("", &url)
     }
 }
 
@@ -69,3 +85,51 @@ impl crate::cmd::Command for CmdOpen {
 pub fn changelog_url(version: &str) -> String {
     format!("https://github.com/oxidecomputer/cli/releases/tag/v{}", version)
 }
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use crate::cmd::Command;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_cmd_open_print_url() {
+        let tests = vec![
+            (super::OpenShortcut::Docs, "https://docs.oxide.computer\n"),
+            (super::OpenShortcut::Console, "https://rack2.oxide.computer\n"),
+            (
+                super::OpenShortcut::Issues,
+                "https://github.com/oxidecomputer/cli/issues\n",
+            ),
+        ];
+
+        for (shortcut, want) in tests {
+            let mut config = crate::config::new_blank_config().unwrap();
+            config.set("staging", "host", "rack2.oxide.computer").unwrap();
+            config.set("staging", "token", "MY_TOKEN").unwrap();
+            let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+
+            let (io, stdout_path, _) = crate::iostreams::IoStreams::test();
+            let mut ctx = crate::context::Context {
+                config: &mut c,
+                io,
+                debug: false,
+                quiet: false,
+                profile: Some("staging".to_string()),
+                api_clients: Default::default(),
+                timeout: None,
+                headers: Default::default(),
+                request_id_sent: Default::default(),
+                endpoint: None,
+            };
+
+            let cmd = super::CmdOpen {
+                shortcut,
+                print_url: true,
+            };
+            cmd.run(&mut ctx).await.unwrap();
+
+            assert_eq!(std::fs::read_to_string(stdout_path).unwrap(), want);
+        }
+    }
+}
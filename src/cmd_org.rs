@@ -1,10 +1,12 @@
 use std::io::Write;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use clap::Parser;
 use cli_macro::crud_gen;
 
 /// Create, list, edit, view, and delete organizations.
+///
+/// Additionally, switch the default organization.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdOrganization {
@@ -16,21 +18,125 @@ pub struct CmdOrganization {
     tag = "organizations",
 }]
 #[derive(Parser, Debug, Clone)]
-enum SubCommand {}
+enum SubCommand {
+    Switch(CmdOrganizationSwitch),
+    Current(CmdOrganizationCurrent),
+}
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdOrganization {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
         match &self.subcmd {
             SubCommand::Create(cmd) => cmd.run(ctx).await,
+            SubCommand::Current(cmd) => cmd.run(ctx).await,
             SubCommand::Delete(cmd) => cmd.run(ctx).await,
             SubCommand::Edit(cmd) => cmd.run(ctx).await,
             SubCommand::List(cmd) => cmd.run(ctx).await,
+            SubCommand::Switch(cmd) => cmd.run(ctx).await,
             SubCommand::View(cmd) => cmd.run(ctx).await,
         }
     }
 }
 
+/// Set the default organization.
+///
+/// The organization is saved as `default_org` for the active profile, so commands
+/// that need an organization no longer need `-o` for it. The default project is
+/// cleared when the organization changes, since it belonged to the old one.
+///
+///     $ oxide org switch maze-war
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdOrganizationSwitch {
+    /// The organization to switch to.
+    #[clap(name = "organization", default_value = "")]
+    pub organization: String,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdOrganizationSwitch {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let mut organization = self.organization.to_string();
+        if organization.is_empty() && !ctx.io.can_prompt() {
+            return Err(anyhow!("[organization] required in non-interactive mode"));
+        }
+
+        let client = ctx.api_client("")?;
+
+        let mut orgs: Vec<String> = Vec::new();
+        let resp = ctx
+            .timed(
+                "GET",
+                "/organizations",
+                client
+                    .organizations()
+                    .get_all(oxide_api::types::NameOrIdSortMode::NameAscending),
+            )
+            .await?;
+        for org in resp {
+            orgs.push(org.name.to_string());
+        }
+
+        // If they didn't specify an organization, prompt for it.
+        if organization.is_empty() {
+            match dialoguer::Select::new()
+                .with_prompt("Select organization:")
+                .items(&orgs)
+                .interact()
+            {
+                Ok(index) => organization = orgs[index].to_string(),
+                Err(err) => {
+                    return Err(anyhow!("prompt failed: {}", err));
+                }
+            }
+        }
+
+        if !orgs.contains(&organization) {
+            return Err(anyhow!("organization `{}` does not exist", organization));
+        }
+
+        let profile = ctx.active_profile()?;
+        if ctx.config.get(&profile, "default_org").unwrap_or_default() != organization {
+            ctx.config.set(&profile, "default_project", "")?;
+        }
+        ctx.config.set(&profile, "default_org", &organization)?;
+        ctx.config.write()?;
+
+        let cs = ctx.io.color_scheme();
+        writeln!(
+            ctx.io.out,
+            "{} Switched to organization {}",
+            cs.success_icon(),
+            cs.bold(&organization)
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Print the default organization.
+///
+/// This is the organization used when `-o` and `OXIDE_ORG` are not set.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdOrganizationCurrent {}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdOrganizationCurrent {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let organization = ctx.resolve_organization("")?;
+        if organization.is_empty() {
+            return Err(anyhow!(
+                "no default organization is set, set one with `oxide org switch <name>`"
+            ));
+        }
+
+        writeln!(ctx.io.out, "{}", organization)?;
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use pretty_assertions::assert_eq;
@@ -96,6 +202,7 @@ mod test {
                 cmd: crate::cmd_org::SubCommand::Create(crate::cmd_org::CmdOrganizationCreate {
                     organization: "".to_string(),
                     description: "hi hi".to_string(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -107,6 +214,7 @@ mod test {
                 cmd: crate::cmd_org::SubCommand::Create(crate::cmd_org::CmdOrganizationCreate {
                     organization: "".to_string(),
                     description: "".to_string(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -117,6 +225,8 @@ mod test {
                 name: "delete no --confirm non-interactive".to_string(),
                 cmd: crate::cmd_org::SubCommand::Delete(crate::cmd_org::CmdOrganizationDelete {
                     organization: "things".to_string(),
+                    all: false,
+                    filter: "".to_string(),
                     confirm: false,
                 }),
 
@@ -124,13 +234,83 @@ mod test {
                 want_out: "".to_string(),
                 want_err: "--confirm required when not running interactively".to_string(),
             },
+            TestItem {
+                name: "delete --filter without --all".to_string(),
+                cmd: crate::cmd_org::SubCommand::Delete(crate::cmd_org::CmdOrganizationDelete {
+                    organization: "".to_string(),
+                    all: false,
+                    filter: "name~things-".to_string(),
+                    confirm: true,
+                }),
+
+                stdin: "".to_string(),
+                want_out: "".to_string(),
+                want_err: "--filter requires --all".to_string(),
+            },
+            TestItem {
+                name: "delete --all with a name".to_string(),
+                cmd: crate::cmd_org::SubCommand::Delete(crate::cmd_org::CmdOrganizationDelete {
+                    organization: "things".to_string(),
+                    all: true,
+                    filter: "".to_string(),
+                    confirm: true,
+                }),
+
+                stdin: "".to_string(),
+                want_out: "".to_string(),
+                want_err: "[organization] cannot be used with --all".to_string(),
+            },
+            TestItem {
+                name: "view no name non-interactive".to_string(),
+                cmd: crate::cmd_org::SubCommand::View(crate::cmd_org::CmdOrganizationView {
+                    organization: "".to_string(),
+                    web: false,
+                    format: None,
+                    template: None,
+                    columns: vec![],
+                    no_header: false,
+                    output_file: "".to_string(),
+                }),
+
+                stdin: "".to_string(),
+                want_out: "".to_string(),
+                want_err: "[organization] required in non-interactive mode".to_string(),
+            },
+            TestItem {
+                name: "switch no name non-interactive".to_string(),
+                cmd: crate::cmd_org::SubCommand::Switch(crate::cmd_org::CmdOrganizationSwitch {
+                    organization: "".to_string(),
+                }),
+
+                stdin: "".to_string(),
+                want_out: "".to_string(),
+                want_err: "[organization] required in non-interactive mode".to_string(),
+            },
+            TestItem {
+                name: "switch does not exist".to_string(),
+                cmd: crate::cmd_org::SubCommand::Switch(crate::cmd_org::CmdOrganizationSwitch {
+                    organization: "does-not-exist".to_string(),
+                }),
+
+                stdin: "".to_string(),
+                want_out: "".to_string(),
+                want_err: "organization `does-not-exist` does not exist".to_string(),
+            },
             TestItem {
                 name: "list zero limit".to_string(),
                 cmd: crate::cmd_org::SubCommand::List(crate::cmd_org::CmdOrganizationList {
                     sort_by: Default::default(),
                     limit: 0,
                     paginate: false,
+                    page_token: None,
                     format: None,
+                    template: None,
+                    columns: vec![],
+                    no_header: false,
+                    sort: "".to_string(),
+                    reverse: false,
+                    filter: vec![],
+                    output_file: "".to_string(),
                 }),
 
                 stdin: "".to_string(),
@@ -143,7 +323,15 @@ mod test {
                     sort_by: Default::default(),
                     limit: 30,
                     paginate: true,
+                    page_token: None,
                     format: Some(crate::types::FormatOutput::Json),
+                    template: None,
+                    columns: vec![],
+                    no_header: false,
+                    sort: "".to_string(),
+                    reverse: false,
+                    filter: vec![],
+                    output_file: "".to_string(),
                 }),
 
                 stdin: "".to_string(),
@@ -168,6 +356,13 @@ mod test {
                 config: &mut c,
                 io,
                 debug: false,
+                quiet: false,
+                profile: None,
+                api_clients: Default::default(),
+                timeout: None,
+                headers: Default::default(),
+                request_id_sent: Default::default(),
+                endpoint: None,
             };
 
             let cmd_org = crate::cmd_org::CmdOrganization { subcmd: t.cmd };
@@ -1,10 +1,12 @@
 use std::io::Write;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use clap::Parser;
 use cli_macro::crud_gen;
 
 /// Create, list, edit, view, and delete projects.
+///
+/// Additionally, switch the default project.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdProject {
@@ -16,21 +18,141 @@ pub struct CmdProject {
     tag = "projects",
 }]
 #[derive(Parser, Debug, Clone)]
-enum SubCommand {}
+enum SubCommand {
+    Switch(CmdProjectSwitch),
+    Current(CmdProjectCurrent),
+}
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdProject {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
         match &self.subcmd {
             SubCommand::Create(cmd) => cmd.run(ctx).await,
+            SubCommand::Current(cmd) => cmd.run(ctx).await,
             SubCommand::Delete(cmd) => cmd.run(ctx).await,
             SubCommand::Edit(cmd) => cmd.run(ctx).await,
             SubCommand::List(cmd) => cmd.run(ctx).await,
+            SubCommand::Switch(cmd) => cmd.run(ctx).await,
             SubCommand::View(cmd) => cmd.run(ctx).await,
         }
     }
 }
 
+/// Set the default project.
+///
+/// The project is looked up in the default organization, or the one passed with
+/// `-o`, and saved as `default_project` for the active profile. Passing `-o` also
+/// makes that organization the default, so the two always go together.
+///
+///     $ oxide project switch development
+///     $ oxide project switch development -o maze-war
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdProjectSwitch {
+    /// The project to switch to.
+    #[clap(name = "project", default_value = "")]
+    pub project: String,
+
+    /// The organization that holds the project.
+    #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
+    pub organization: String,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdProjectSwitch {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let mut project = self.project.to_string();
+        if project.is_empty() && !ctx.io.can_prompt() {
+            return Err(anyhow!("[project] required in non-interactive mode"));
+        }
+
+        let organization = ctx.resolve_organization(&self.organization)?;
+        if organization.is_empty() {
+            return Err(anyhow!(
+                "-o|--organization required, or set a default with `oxide org switch <name>`"
+            ));
+        }
+
+        let client = ctx.api_client("")?;
+
+        let mut projects: Vec<String> = Vec::new();
+        let resp = ctx
+            .timed(
+                "GET",
+                "/organizations/{organization_name}/projects",
+                client
+                    .projects()
+                    .get_all(&organization, oxide_api::types::NameOrIdSortMode::NameAscending),
+            )
+            .await?;
+        for project in resp {
+            projects.push(project.name.to_string());
+        }
+
+        // If they didn't specify a project, prompt for it.
+        if project.is_empty() {
+            match dialoguer::Select::new()
+                .with_prompt("Select project:")
+                .items(&projects)
+                .interact()
+            {
+                Ok(index) => project = projects[index].to_string(),
+                Err(err) => {
+                    return Err(anyhow!("prompt failed: {}", err));
+                }
+            }
+        }
+
+        if !projects.contains(&project) {
+            return Err(anyhow!(
+                "project `{}` does not exist in organization `{}`",
+                project,
+                organization
+            ));
+        }
+
+        let profile = ctx.active_profile()?;
+        ctx.config.set(&profile, "default_org", &organization)?;
+        ctx.config.set(&profile, "default_project", &project)?;
+        ctx.config.write()?;
+
+        let cs = ctx.io.color_scheme();
+        writeln!(
+            ctx.io.out,
+            "{} Switched to project {}",
+            cs.success_icon(),
+            cs.bold(&format!("{}/{}", organization, project))
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Print the default organization and project.
+///
+/// They are printed as `organization/project`, and are the ones used when `-o`,
+/// `-p`, `OXIDE_ORG`, and `OXIDE_PROJECT` are not set.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdProjectCurrent {}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdProjectCurrent {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let organization = ctx.resolve_organization("")?;
+        let project = ctx.resolve_project("")?;
+        if organization.is_empty() || project.is_empty() {
+            return Err(anyhow!(
+                "no default project is set, set one with `oxide project switch <name>`"
+            ));
+        }
+
+        writeln!(ctx.io.out, "{}/{}", organization, project)?;
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use pretty_assertions::assert_eq;
@@ -54,6 +176,7 @@ mod test {
                     project: "".to_string(),
                     organization: "".to_string(),
                     description: "hello".to_string(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -66,6 +189,7 @@ mod test {
                     project: "things".to_string(),
                     organization: "".to_string(),
                     description: "foo".to_string(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -78,6 +202,7 @@ mod test {
                     project: "things".to_string(),
                     organization: "foo".to_string(),
                     description: "".to_string(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -89,6 +214,8 @@ mod test {
                 cmd: crate::cmd_project::SubCommand::Delete(crate::cmd_project::CmdProjectDelete {
                     project: "things".to_string(),
                     organization: "".to_string(),
+                    all: false,
+                    filter: "".to_string(),
                     confirm: false,
                 }),
 
@@ -96,6 +223,28 @@ mod test {
                 want_out: "".to_string(),
                 want_err: "--confirm required when not running interactively".to_string(),
             },
+            TestItem {
+                name: "switch no name non-interactive".to_string(),
+                cmd: crate::cmd_project::SubCommand::Switch(crate::cmd_project::CmdProjectSwitch {
+                    project: "".to_string(),
+                    organization: "foo".to_string(),
+                }),
+
+                stdin: "".to_string(),
+                want_out: "".to_string(),
+                want_err: "[project] required in non-interactive mode".to_string(),
+            },
+            TestItem {
+                name: "switch no organization".to_string(),
+                cmd: crate::cmd_project::SubCommand::Switch(crate::cmd_project::CmdProjectSwitch {
+                    project: "things".to_string(),
+                    organization: "".to_string(),
+                }),
+
+                stdin: "".to_string(),
+                want_out: "".to_string(),
+                want_err: "-o|--organization required, or set a default with `oxide org switch <name>`".to_string(),
+            },
             TestItem {
                 name: "list zero limit".to_string(),
                 cmd: crate::cmd_project::SubCommand::List(crate::cmd_project::CmdProjectList {
@@ -103,7 +252,15 @@ mod test {
                     limit: 0,
                     organization: "".to_string(),
                     paginate: false,
+                    page_token: None,
                     format: None,
+                    template: None,
+                    columns: vec![],
+                    no_header: false,
+                    sort: "".to_string(),
+                    reverse: false,
+                    filter: vec![],
+                    output_file: "".to_string(),
                 }),
 
                 stdin: "".to_string(),
@@ -128,6 +285,13 @@ mod test {
                 config: &mut c,
                 io,
                 debug: false,
+                quiet: false,
+                profile: None,
+                api_clients: Default::default(),
+                timeout: None,
+                headers: Default::default(),
+                request_id_sent: Default::default(),
+                endpoint: None,
             };
 
             let cmd_project = crate::cmd_project::CmdProject { subcmd: t.cmd };
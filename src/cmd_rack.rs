@@ -10,6 +10,9 @@ pub struct CmdRack {
     subcmd: SubCommand,
 }
 
+// TODO: add a `--wide` to `rack list` with the sled count and CPU/memory utilization of each
+// rack once the API has them. In spec.json a `Sled` does not say which rack it is in, and
+// there is no endpoint for utilization yet.
 #[crud_gen {
     tag = "racks",
 }]
@@ -1,8 +1,16 @@
-use anyhow::Result;
+use std::io::Write;
+
+use anyhow::{anyhow, Result};
 use clap::Parser;
 use cli_macro::crud_gen;
 
+/// How many times a policy is read, changed, and written back before giving up because
+/// someone else keeps changing it at the same time.
+const POLICY_UPDATE_ATTEMPTS: usize = 3;
+
 /// Manage built-in roles.
+///
+/// Additionally, assign and revoke the roles of users on organizations and projects.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdRole {
@@ -14,14 +22,486 @@ pub struct CmdRole {
     tag = "roles",
 }]
 #[derive(Parser, Debug, Clone)]
-enum SubCommand {}
+enum SubCommand {
+    Assign(CmdRoleAssign),
+    Revoke(CmdRoleRevoke),
+}
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdRole {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
         match &self.subcmd {
+            SubCommand::Assign(cmd) => cmd.run(ctx).await,
             SubCommand::List(cmd) => cmd.run(ctx).await,
+            SubCommand::Revoke(cmd) => cmd.run(ctx).await,
             SubCommand::View(cmd) => cmd.run(ctx).await,
         }
     }
 }
+
+/// Assign a role to a user on an organization or project.
+///
+/// The role is assigned on the organization, unless a project is passed with
+/// `--project`. The role assignments of the organization or project are printed
+/// afterward.
+///
+/// The whole policy of the organization or project is read and written back, and
+/// the API cannot make the write fail if the policy changed in between. So the
+/// policy is read once more afterward, and this fails if it is not what was written,
+/// since a role assigned or revoked by someone else at the same time may be lost.
+///
+///     # Make a user a viewer of an organization.
+///     $ oxide role assign viewer --user 4c7d1e5a-... --organization my-org
+///
+///     # Make a user an admin of a project.
+///     $ oxide role assign admin --user 4c7d1e5a-... --organization my-org --project my-project
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdRoleAssign {
+    /// The role to assign: admin, collaborator, or viewer.
+    #[clap(name = "role", required = true)]
+    pub role: String,
+
+    /// The ID of the user to assign the role to.
+    #[clap(long, short, required = true, parse(try_from_str = uuid::Uuid::parse_str))]
+    pub user: uuid::Uuid,
+
+    /// The organization to assign the role on.
+    #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
+    pub organization: String,
+
+    /// The project to assign the role on, instead of the organization.
+    #[clap(long, short, default_value = "")]
+    pub project: String,
+
+    /// Output format.
+    #[clap(long, short)]
+    pub format: Option<crate::types::FormatOutput>,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdRoleAssign {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let scope = PolicyScope::resolve(ctx, &self.organization, &self.project)?;
+        let change = RoleChange {
+            role: parse_role(&self.role, &scope)?,
+            user: self.user.to_string(),
+            assign: true,
+        };
+
+        update_policy(ctx, &scope, &change, &self.format).await
+    }
+}
+
+/// Revoke a role from a user on an organization or project.
+///
+/// The role is revoked on the organization, unless a project is passed with
+/// `--project`. Roles assigned on the organization still apply to its projects.
+/// The role assignments of the organization or project are printed afterward.
+///
+/// Like `oxide role assign`, this fails if the policy is not what was written when
+/// it is read back, since a change made by someone else at the same time may be lost.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdRoleRevoke {
+    /// The role to revoke: admin, collaborator, or viewer.
+    #[clap(name = "role", required = true)]
+    pub role: String,
+
+    /// The ID of the user to revoke the role from.
+    #[clap(long, short, required = true, parse(try_from_str = uuid::Uuid::parse_str))]
+    pub user: uuid::Uuid,
+
+    /// The organization to revoke the role on.
+    #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
+    pub organization: String,
+
+    /// The project to revoke the role on, instead of the organization.
+    #[clap(long, short, default_value = "")]
+    pub project: String,
+
+    /// Output format.
+    #[clap(long, short)]
+    pub format: Option<crate::types::FormatOutput>,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdRoleRevoke {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let scope = PolicyScope::resolve(ctx, &self.organization, &self.project)?;
+        let change = RoleChange {
+            role: parse_role(&self.role, &scope)?,
+            user: self.user.to_string(),
+            assign: false,
+        };
+
+        update_policy(ctx, &scope, &change, &self.format).await
+    }
+}
+
+/// The resource whose policy holds the role assignments.
+#[derive(Debug, Clone)]
+enum PolicyScope {
+    Organization(String),
+    Project(String, String),
+}
+
+impl std::fmt::Display for PolicyScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PolicyScope::Organization(organization) => write!(f, "organization {}", organization),
+            PolicyScope::Project(organization, project) => write!(f, "project {}/{}", organization, project),
+        }
+    }
+}
+
+impl PolicyScope {
+    /// The project is only taken from `--project`, not from `OXIDE_PROJECT` or the
+    /// config, since it decides which policy is changed.
+    fn resolve(ctx: &crate::context::Context, organization: &str, project: &str) -> Result<Self> {
+        let organization = ctx.resolve_organization(organization)?;
+        if organization.is_empty() {
+            return Err(anyhow!("-o|--organization required"));
+        }
+
+        if project.is_empty() {
+            Ok(PolicyScope::Organization(organization))
+        } else {
+            Ok(PolicyScope::Project(organization, project.to_string()))
+        }
+    }
+
+    /// The prefix of the built-in roles for this scope, as shown by `oxide role list`.
+    fn role_prefix(&self) -> &'static str {
+        match self {
+            PolicyScope::Organization(_) => "organization",
+            PolicyScope::Project(..) => "project",
+        }
+    }
+
+    /// Get the policy as JSON, so the role assignments can be changed the same way
+    /// for every scope.
+    async fn get(&self, ctx: &crate::context::Context, client: &oxide_api::Client) -> Result<serde_json::Value> {
+        Ok(match self {
+            PolicyScope::Organization(organization) => serde_json::to_value(
+                ctx.timed(
+                    "GET",
+                    "/organizations/{organization_name}/policy",
+                    client.organizations().get_policy(organization),
+                )
+                .await?,
+            )?,
+            PolicyScope::Project(organization, project) => serde_json::to_value(
+                ctx.timed(
+                    "GET",
+                    "/organizations/{organization_name}/projects/{project_name}/policy",
+                    client.projects().get_policy(organization, project),
+                )
+                .await?,
+            )?,
+        })
+    }
+
+    async fn put(
+        &self,
+        ctx: &crate::context::Context,
+        client: &oxide_api::Client,
+        policy: &serde_json::Value,
+    ) -> Result<()> {
+        match self {
+            PolicyScope::Organization(organization) => {
+                let policy: oxide_api::types::OrganizationRolePolicy = serde_json::from_value(policy.clone())?;
+                ctx.timed(
+                    "PUT",
+                    "/organizations/{organization_name}/policy",
+                    client.organizations().put_policy(organization, &policy),
+                )
+                .await?;
+            }
+            PolicyScope::Project(organization, project) => {
+                let policy: oxide_api::types::ProjectRolePolicy = serde_json::from_value(policy.clone())?;
+                ctx.timed(
+                    "PUT",
+                    "/organizations/{organization_name}/projects/{project_name}/policy",
+                    client.projects().put_policy(organization, project, &policy),
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_assignments(
+        &self,
+        ctx: &mut crate::context::Context,
+        format: &crate::types::FormatOutput,
+        policy: serde_json::Value,
+    ) -> Result<()> {
+        match self {
+            PolicyScope::Organization(_) => {
+                let policy: oxide_api::types::OrganizationRolePolicy = serde_json::from_value(policy)?;
+                ctx.io.write_output_for_vec(format, &policy.role_assignments)
+            }
+            PolicyScope::Project(..) => {
+                let policy: oxide_api::types::ProjectRolePolicy = serde_json::from_value(policy)?;
+                ctx.io.write_output_for_vec(format, &policy.role_assignments)
+            }
+        }
+    }
+}
+
+/// A role to assign to or revoke from a user.
+#[derive(Debug, Clone)]
+struct RoleChange {
+    role: String,
+    user: String,
+    assign: bool,
+}
+
+impl RoleChange {
+    fn matches(&self, assignment: &serde_json::Value) -> bool {
+        assignment["role_name"] == self.role.as_str()
+            && assignment["identity_id"]
+                .as_str()
+                .map(|id| id.eq_ignore_ascii_case(&self.user))
+                .unwrap_or(false)
+    }
+
+    /// Whether the policy already looks the way this change wants it to.
+    fn is_applied(&self, policy: &serde_json::Value) -> bool {
+        let found = policy["role_assignments"]
+            .as_array()
+            .map(|assignments| assignments.iter().any(|a| self.matches(a)))
+            .unwrap_or(false);
+
+        found == self.assign
+    }
+
+    /// Change the role assignments of the policy. Returns false if there was nothing to
+    /// change.
+    fn apply(&self, policy: &mut serde_json::Value) -> Result<bool> {
+        if self.is_applied(policy) {
+            return Ok(false);
+        }
+
+        let assignments = policy["role_assignments"]
+            .as_array_mut()
+            .ok_or_else(|| anyhow!("the policy has no role assignments"))?;
+
+        if self.assign {
+            assignments.push(serde_json::json!({
+                "identity_id": self.user,
+                "identity_type": "silo_user",
+                "role_name": self.role,
+            }));
+        } else {
+            assignments.retain(|a| !self.matches(a));
+        }
+
+        Ok(true)
+    }
+}
+
+/// The role assignments of a policy, in an order that does not depend on the order the
+/// API returns them in, to compare two policies.
+fn sorted_assignments(policy: &serde_json::Value) -> Vec<(String, String, String)> {
+    let mut assignments: Vec<(String, String, String)> = policy["role_assignments"]
+        .as_array()
+        .map(|assignments| {
+            assignments
+                .iter()
+                .map(|a| {
+                    (
+                        a["identity_id"].as_str().unwrap_or_default().to_lowercase(),
+                        a["identity_type"].as_str().unwrap_or_default().to_string(),
+                        a["role_name"].as_str().unwrap_or_default().to_string(),
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    assignments.sort();
+
+    assignments
+}
+
+/// Parse the role passed to `role assign` and `role revoke`. Both the plain name, like
+/// `admin`, and the name shown by `oxide role list`, like `project.admin`, are accepted.
+fn parse_role(role: &str, scope: &PolicyScope) -> Result<String> {
+    let name = match role.split_once('.') {
+        Some((prefix, name)) if prefix == scope.role_prefix() => name,
+        Some(_) => return Err(anyhow!("role `{}` cannot be used on {}", role, scope)),
+        None => role,
+    };
+
+    match name {
+        "admin" | "collaborator" | "viewer" => Ok(name.to_string()),
+        _ => Err(anyhow!(
+            "invalid role `{}`: expected admin, collaborator, or viewer",
+            role
+        )),
+    }
+}
+
+/// Read the policy, change its role assignments, and write it back. A policy is always
+/// written as a whole, so if someone else changes it at the same time one of the writes
+/// is lost. To catch that, the policy is read again after writing it. The whole change
+/// is retried if it did not stick, and this fails if it did but the policy is not what
+/// was written, since then the write may have undone a change made by someone else.
+async fn update_policy(
+    ctx: &mut crate::context::Context,
+    scope: &PolicyScope,
+    change: &RoleChange,
+    format: &Option<crate::types::FormatOutput>,
+) -> Result<()> {
+    let format = ctx.format(format, &None)?;
+    let client = ctx.api_client("")?;
+    let cs = ctx.io.color_scheme();
+
+    for _ in 0..POLICY_UPDATE_ATTEMPTS {
+        let mut policy = scope.get(ctx, &client).await?;
+
+        if !change.apply(&mut policy)? {
+            if !ctx.quiet {
+                writeln!(
+                    ctx.io.err_out,
+                    "{} User {} {} role {} on {}, nothing to do",
+                    cs.warning_icon(),
+                    change.user,
+                    if change.assign { "already has" } else { "does not have" },
+                    change.role,
+                    scope
+                )?;
+            }
+
+            return scope.write_assignments(ctx, &format, policy);
+        }
+
+        scope.put(ctx, &client, &policy).await?;
+
+        let written = policy;
+        let policy = scope.get(ctx, &client).await?;
+        if !change.is_applied(&policy) {
+            continue;
+        }
+
+        if sorted_assignments(&policy) != sorted_assignments(&written) {
+            return Err(anyhow!(
+                "the policy of {} was changed by someone else at the same time, check its role assignments before \
+                 trying again",
+                scope
+            ));
+        }
+
+        if !ctx.quiet {
+            writeln!(
+                ctx.io.err_out,
+                "{} {} role {} {} user {} on {}",
+                cs.success_icon(),
+                if change.assign { "Assigned" } else { "Revoked" },
+                change.role,
+                if change.assign { "to" } else { "from" },
+                change.user,
+                scope
+            )?;
+        }
+
+        return scope.write_assignments(ctx, &format, policy);
+    }
+
+    Err(anyhow!(
+        "the policy of {} kept changing while updating it, try again",
+        scope
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_role() {
+        let organization = PolicyScope::Organization("foo".to_string());
+        let project = PolicyScope::Project("foo".to_string(), "bar".to_string());
+
+        assert_eq!(parse_role("admin", &organization).unwrap(), "admin");
+        assert_eq!(parse_role("organization.viewer", &organization).unwrap(), "viewer");
+        assert_eq!(parse_role("project.collaborator", &project).unwrap(), "collaborator");
+
+        assert_eq!(
+            parse_role("project.admin", &organization).unwrap_err().to_string(),
+            "role `project.admin` cannot be used on organization foo"
+        );
+        assert_eq!(
+            parse_role("owner", &project).unwrap_err().to_string(),
+            "invalid role `owner`: expected admin, collaborator, or viewer"
+        );
+    }
+
+    #[test]
+    fn test_role_change() {
+        let user = "4c7d1e5a-9d3b-4a0e-8a57-6d2c3f1b0e91";
+        let mut policy = serde_json::json!({
+            "role_assignments": [
+                {"identity_id": "0b6c1e2d-1f7a-4c3e-9e0d-2a4b5c6d7e8f", "identity_type": "silo_user", "role_name": "admin"},
+            ]
+        });
+
+        let assign = RoleChange {
+            role: "viewer".to_string(),
+            user: user.to_string(),
+            assign: true,
+        };
+        assert!(assign.apply(&mut policy).unwrap());
+        assert!(assign.is_applied(&policy));
+        assert_eq!(policy["role_assignments"].as_array().unwrap().len(), 2);
+
+        // Assigning it again does nothing.
+        assert!(!assign.apply(&mut policy).unwrap());
+        assert_eq!(policy["role_assignments"].as_array().unwrap().len(), 2);
+
+        let revoke = RoleChange {
+            role: "viewer".to_string(),
+            user: user.to_uppercase(),
+            assign: false,
+        };
+        assert!(revoke.apply(&mut policy).unwrap());
+        assert!(revoke.is_applied(&policy));
+        assert_eq!(
+            policy["role_assignments"],
+            serde_json::json!([
+                {"identity_id": "0b6c1e2d-1f7a-4c3e-9e0d-2a4b5c6d7e8f", "identity_type": "silo_user", "role_name": "admin"},
+            ])
+        );
+    }
+
+    #[test]
+    fn test_sorted_assignments() {
+        let written = serde_json::json!({
+            "role_assignments": [
+                {"identity_id": "4c7d1e5a-9d3b-4a0e-8a57-6d2c3f1b0e91", "identity_type": "silo_user", "role_name": "viewer"},
+                {"identity_id": "0b6c1e2d-1f7a-4c3e-9e0d-2a4b5c6d7e8f", "identity_type": "silo_user", "role_name": "admin"},
+            ]
+        });
+
+        // The same assignments, in another order and case.
+        let read = serde_json::json!({
+            "role_assignments": [
+                {"identity_id": "0b6c1e2d-1f7a-4c3e-9e0d-2a4b5c6d7e8f", "identity_type": "silo_user", "role_name": "admin"},
+                {"identity_id": "4C7D1E5A-9D3B-4A0E-8A57-6D2C3F1B0E91", "identity_type": "silo_user", "role_name": "viewer"},
+            ]
+        });
+        assert_eq!(sorted_assignments(&read), sorted_assignments(&written));
+
+        // Someone else revoked a role in between.
+        let read = serde_json::json!({
+            "role_assignments": [
+                {"identity_id": "4c7d1e5a-9d3b-4a0e-8a57-6d2c3f1b0e91", "identity_type": "silo_user", "role_name": "viewer"},
+            ]
+        });
+        assert_ne!(sorted_assignments(&read), sorted_assignments(&written));
+    }
+}
@@ -1,6 +1,6 @@
-use std::io::Write;
+use std::{io::Write, str::FromStr};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use clap::Parser;
 use cli_macro::crud_gen;
 
@@ -31,6 +31,75 @@ impl crate::cmd::Command for CmdRoute {
     }
 }
 
+/// The kinds of route destinations, as passed to `--destination`.
+const DESTINATION_KINDS: &[&str] = &["ip", "ip_net", "vpc", "subnet"];
+
+/// The kinds of route targets, as passed to `--target`.
+const TARGET_KINDS: &[&str] = &["ip", "vpc", "subnet", "instance", "internet_gateway"];
+
+/// Parse a route destination passed as `kind=value`, for example `ip=10.0.2.1` or
+/// `ip_net=10.0.2.0/24`.
+pub fn parse_route_destination(s: &str) -> Result<oxide_api::types::RouteDestination> {
+    let (kind, value) = split_kind_value("destination", s, DESTINATION_KINDS)?;
+
+    Ok(match kind {
+        "ip" => oxide_api::types::RouteDestination::Ip(parse_ip("destination", s, value)?),
+        "ip_net" => oxide_api::types::RouteDestination::IpNet(
+            oxide_api::types::IpNet::from_str(value)
+                .map_err(|_| anyhow!("invalid destination `{}`: `{}` is not an IP network", s, value))?,
+        ),
+        "vpc" => oxide_api::types::RouteDestination::Vpc(value.to_string()),
+        _ => oxide_api::types::RouteDestination::Subnet(value.to_string()),
+    })
+}
+
+/// Parse a route target passed as `kind=value`, for example `ip=10.0.2.1` or
+/// `internet_gateway=outbound`. `inetgw` is short for `internet_gateway`.
+pub fn parse_route_target(s: &str) -> Result<oxide_api::types::RouteTarget> {
+    let s = match s.strip_prefix("inetgw=") {
+        Some(value) => format!("internet_gateway={}", value),
+        None => s.to_string(),
+    };
+    let (kind, value) = split_kind_value("target", &s, TARGET_KINDS)?;
+
+    Ok(match kind {
+        "ip" => oxide_api::types::RouteTarget::Ip(parse_ip("target", &s, value)?),
+        "vpc" => oxide_api::types::RouteTarget::Vpc(value.to_string()),
+        "subnet" => oxide_api::types::RouteTarget::Subnet(value.to_string()),
+        "instance" => oxide_api::types::RouteTarget::Instance(value.to_string()),
+        _ => oxide_api::types::RouteTarget::InternetGateway(value.to_string()),
+    })
+}
+
+/// Split `kind=value`, making sure the kind is one of the given kinds and the value is not
+/// empty.
+fn split_kind_value<'a>(what: &str, s: &'a str, kinds: &[&str]) -> Result<(&'a str, &'a str)> {
+    let (kind, value) = match s.split_once('=') {
+        Some((kind, value)) if kinds.contains(&kind) => (kind, value),
+        _ => {
+            return Err(anyhow!(
+                "invalid {} `{}`: expected `kind=value`, where kind is one of: {}",
+                what,
+                s,
+                kinds.join(", ")
+            ))
+        }
+    };
+
+    if value.is_empty() {
+        return Err(anyhow!("invalid {} `{}`: the {} value is empty", what, s, kind));
+    }
+
+    Ok((kind, value))
+}
+
+fn parse_ip(what: &str, s: &str, value: &str) -> Result<String> {
+    match value.parse::<std::net::IpAddr>() {
+        Ok(ip) => Ok(ip.to_string()),
+        Err(_) => Err(anyhow!("invalid {} `{}`: `{}` is not an IP address", what, s, value)),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use pretty_assertions::assert_eq;
@@ -62,6 +131,7 @@ mod test {
                     router: "".to_string(),
                     target: target.clone(),
                     destination: destination.clone(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -79,6 +149,7 @@ mod test {
                     router: "".to_string(),
                     target: target.clone(),
                     destination: destination.clone(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -96,6 +167,7 @@ mod test {
                     router: "".to_string(),
                     target: target.clone(),
                     destination: destination.clone(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -113,6 +185,7 @@ mod test {
                     router: "".to_string(),
                     target: target.clone(),
                     destination: destination.clone(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -130,6 +203,7 @@ mod test {
                     router: "testing".to_string(),
                     target: target.clone(),
                     destination: destination.clone(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -147,6 +221,7 @@ mod test {
                     router: "".to_string(),
                     target: target.clone(),
                     destination: destination.clone(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -164,6 +239,7 @@ mod test {
                     router: "testing".to_string(),
                     target: Default::default(),
                     destination: destination.clone(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -181,6 +257,7 @@ mod test {
                     router: "testing".to_string(),
                     target: target.clone(),
                     destination: Default::default(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -195,6 +272,8 @@ mod test {
                     project: "".to_string(),
                     vpc: "things".to_string(),
                     router: "blah".to_string(),
+                    all: false,
+                    filter: "".to_string(),
                     confirm: false,
                 }),
 
@@ -212,7 +291,15 @@ mod test {
                     project: "".to_string(),
                     router: "blah".to_string(),
                     paginate: false,
+                    page_token: None,
                     format: None,
+                    template: None,
+                    columns: vec![],
+                    no_header: false,
+                    sort: "".to_string(),
+                    reverse: false,
+                    filter: vec![],
+                    output_file: "".to_string(),
                 }),
 
                 stdin: "".to_string(),
@@ -237,6 +324,13 @@ mod test {
                 config: &mut c,
                 io,
                 debug: false,
+                quiet: false,
+                profile: None,
+                api_clients: Default::default(),
+                timeout: None,
+                headers: Default::default(),
+                request_id_sent: Default::default(),
+                endpoint: None,
             };
 
             let cmd_route = crate::cmd_route::CmdRoute { subcmd: t.cmd };
@@ -261,4 +355,66 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_parse_route_destination() {
+        assert!(matches!(
+            crate::cmd_route::parse_route_destination("ip=10.0.2.1").unwrap(),
+            oxide_api::types::RouteDestination::Ip(value) if value == "10.0.2.1"
+        ));
+        assert!(matches!(
+            crate::cmd_route::parse_route_destination("vpc=default").unwrap(),
+            oxide_api::types::RouteDestination::Vpc(value) if value == "default"
+        ));
+        assert!(crate::cmd_route::parse_route_destination("ip_net=10.0.2.0/24").is_ok());
+
+        assert_eq!(
+            crate::cmd_route::parse_route_destination("ip_net=10.0.2.1/64")
+                .unwrap_err()
+                .to_string(),
+            "invalid destination `ip_net=10.0.2.1/64`: `10.0.2.1/64` is not an IP network"
+        );
+        assert_eq!(
+            crate::cmd_route::parse_route_destination("ip=10.0.2")
+                .unwrap_err()
+                .to_string(),
+            "invalid destination `ip=10.0.2`: `10.0.2` is not an IP address"
+        );
+        assert_eq!(
+            crate::cmd_route::parse_route_destination("instance=foo")
+                .unwrap_err()
+                .to_string(),
+            "invalid destination `instance=foo`: expected `kind=value`, where kind is one of: ip, ip_net, vpc, subnet"
+        );
+        assert_eq!(
+            crate::cmd_route::parse_route_destination("subnet=")
+                .unwrap_err()
+                .to_string(),
+            "invalid destination `subnet=`: the subnet value is empty"
+        );
+    }
+
+    #[test]
+    fn test_parse_route_target() {
+        assert!(matches!(
+            crate::cmd_route::parse_route_target("ip=fd00::1").unwrap(),
+            oxide_api::types::RouteTarget::Ip(value) if value == "fd00::1"
+        ));
+        assert!(matches!(
+            crate::cmd_route::parse_route_target("inetgw=outbound").unwrap(),
+            oxide_api::types::RouteTarget::InternetGateway(value) if value == "outbound"
+        ));
+        assert!(matches!(
+            crate::cmd_route::parse_route_target("internet_gateway=outbound").unwrap(),
+            oxide_api::types::RouteTarget::InternetGateway(value) if value == "outbound"
+        ));
+
+        assert_eq!(
+            crate::cmd_route::parse_route_target("10.0.2.1")
+                .unwrap_err()
+                .to_string(),
+            "invalid target `10.0.2.1`: expected `kind=value`, where kind is one of: ip, vpc, subnet, instance, \
+             internet_gateway"
+        );
+    }
 }
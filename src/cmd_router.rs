@@ -56,6 +56,7 @@ mod test {
                     project: "bar".to_string(),
                     description: "".to_string(),
                     vpc: "".to_string(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -70,6 +71,7 @@ mod test {
                     project: "".to_string(),
                     description: "blah blah".to_string(),
                     vpc: "foo bar".to_string(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -84,6 +86,7 @@ mod test {
                     project: "".to_string(),
                     description: "blah blah".to_string(),
                     vpc: "blah".to_string(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -98,6 +101,7 @@ mod test {
                     project: "".to_string(),
                     description: "blah blah".to_string(),
                     vpc: "blah".to_string(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -112,6 +116,7 @@ mod test {
                     project: "bar".to_string(),
                     description: "blah blah".to_string(),
                     vpc: "".to_string(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -125,6 +130,8 @@ mod test {
                     organization: "".to_string(),
                     project: "".to_string(),
                     vpc: "things".to_string(),
+                    all: false,
+                    filter: "".to_string(),
                     confirm: false,
                 }),
 
@@ -141,7 +148,15 @@ mod test {
                     vpc: "things".to_string(),
                     project: "".to_string(),
                     paginate: false,
+                    page_token: None,
                     format: None,
+                    template: None,
+                    columns: vec![],
+                    no_header: false,
+                    sort: "".to_string(),
+                    reverse: false,
+                    filter: vec![],
+                    output_file: "".to_string(),
                 }),
 
                 stdin: "".to_string(),
@@ -166,6 +181,13 @@ mod test {
                 config: &mut c,
                 io,
                 debug: false,
+                quiet: false,
+                profile: None,
+                api_clients: Default::default(),
+                timeout: None,
+                headers: Default::default(),
+                request_id_sent: Default::default(),
+                endpoint: None,
             };
 
             let cmd_router = crate::cmd_router::CmdRouter { subcmd: t.cmd };
@@ -10,6 +10,9 @@ pub struct CmdSled {
     subcmd: SubCommand,
 }
 
+// TODO: add `sled instances <sled-id>` once the API can tell which instances run on a sled.
+// In spec.json there is no endpoint for it and an `Instance` does not have a sled id to
+// filter the instance list by.
 #[crud_gen {
     tag = "sleds",
 }]
@@ -1,8 +1,10 @@
-use anyhow::Result;
+use std::io::Write;
+
+use anyhow::{anyhow, Result};
 use clap::Parser;
 use cli_macro::crud_gen;
 
-/// Create, list, view, and delete snapshots.
+/// Create, list, view, restore, and delete snapshots.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdSnapshot {
@@ -14,7 +16,10 @@ pub struct CmdSnapshot {
     tag = "snapshots",
 }]
 #[derive(Parser, Debug, Clone)]
-enum SubCommand {}
+enum SubCommand {
+    Create(CmdSnapshotCreate),
+    Restore(CmdSnapshotRestore),
+}
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdSnapshot {
@@ -23,7 +28,253 @@ impl crate::cmd::Command for CmdSnapshot {
             SubCommand::Create(cmd) => cmd.run(ctx).await,
             SubCommand::Delete(cmd) => cmd.run(ctx).await,
             SubCommand::List(cmd) => cmd.run(ctx).await,
+            SubCommand::Restore(cmd) => cmd.run(ctx).await,
             SubCommand::View(cmd) => cmd.run(ctx).await,
         }
     }
 }
+
+/// Create a new snapshot.
+///
+/// With `--format` or `--template`, the new snapshot is printed in that format
+/// instead of a success message.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdSnapshotCreate {
+    /// The name of the snapshot to create.
+    #[clap(name = "snapshot", required = true)]
+    pub snapshot: String,
+
+    /// The name of the disk to be snapshotted.
+    #[clap(long, required = true)]
+    pub disk: String,
+
+    /// The description for the snapshot.
+    #[clap(long = "description", short = 'D', default_value_t)]
+    pub description: String,
+
+    /// The project that holds the snapshot.
+    #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
+    pub project: String,
+
+    /// The organization that holds the project.
+    #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
+    pub organization: String,
+
+    /// Set a field of the request body, like `--set a.b.c=value`. The value is parsed as
+    /// JSON, or taken as a string if it is not valid JSON. Can be passed more than once.
+    #[clap(long)]
+    pub set: Vec<String>,
+
+    /// Display output in json, jsonl, yaml, table, csv, tsv, or template format.
+    #[clap(long, short)]
+    pub format: Option<crate::types::FormatOutput>,
+
+    /// The template to render each item with, for example '{{.name}} {{.id}}'.
+    #[clap(long)]
+    pub template: Option<String>,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdSnapshotCreate {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let organization = ctx.resolve_organization(&self.organization)?;
+        if organization.is_empty() {
+            return Err(anyhow!("-o|--organization required"));
+        }
+
+        let project = ctx.resolve_project(&self.project)?;
+        if project.is_empty() {
+            return Err(anyhow!("-p|--project required"));
+        }
+
+        let client = ctx.api_client("")?;
+
+        let snapshot = ctx
+            .timed(
+                "POST",
+                "/organizations/{organization_name}/projects/{project_name}/snapshots",
+                client.snapshots().post(
+                    &organization,
+                    &project,
+                    &crate::types::apply_overrides(
+                        oxide_api::types::SnapshotCreate {
+                            description: self.description.to_string(),
+                            disk: self.disk.to_string(),
+                            name: self.snapshot.to_string(),
+                        },
+                        &self.set,
+                    )?,
+                ),
+            )
+            .await?;
+
+        if self.format.is_some() || self.template.is_some() {
+            let format = ctx.format(&self.format, &self.template)?;
+            return ctx.io.write_output(&format, &snapshot);
+        }
+
+        if !ctx.quiet {
+            let cs = ctx.io.color_scheme();
+            writeln!(
+                ctx.io.out,
+                "{} Created snapshot {} of disk {} in {}/{}",
+                cs.success_icon(),
+                self.snapshot,
+                self.disk,
+                organization,
+                project
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Restore a snapshot into a new disk.
+///
+/// The disk is created in the same project as the snapshot, with the size of
+/// the snapshot.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdSnapshotRestore {
+    /// The name of the snapshot to restore.
+    #[clap(name = "snapshot", required = true)]
+    pub snapshot: String,
+
+    /// The name of the disk to create.
+    #[clap(long, required = true)]
+    pub new_disk: String,
+
+    /// The description for the disk.
+    #[clap(long = "description", short = 'D', default_value_t)]
+    pub description: String,
+
+    /// The project that holds the snapshot.
+    #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
+    pub project: String,
+
+    /// The organization that holds the project.
+    #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
+    pub organization: String,
+
+    /// How long to wait for the disk to be created, in seconds.
+    #[clap(long, default_value = "300")]
+    pub wait_timeout: u64,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdSnapshotRestore {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let organization = ctx.resolve_organization(&self.organization)?;
+        if organization.is_empty() {
+            return Err(anyhow!("-o|--organization required"));
+        }
+
+        let project = ctx.resolve_project(&self.project)?;
+        if project.is_empty() {
+            return Err(anyhow!("-p|--project required"));
+        }
+
+        let client = ctx.api_client("")?;
+
+        let snapshot = ctx
+            .timed(
+                "GET",
+                "/organizations/{organization_name}/projects/{project_name}/snapshots/{snapshot_name}",
+                client.snapshots().get(&organization, &project, &self.snapshot),
+            )
+            .await?;
+
+        ctx.timed(
+            "POST",
+            "/organizations/{organization_name}/projects/{project_name}/disks",
+            client.disks().post(
+                &organization,
+                &project,
+                &oxide_api::types::DiskCreate {
+                    description: self.description.to_string(),
+                    disk_source: oxide_api::types::DiskSource::Snapshot {
+                        snapshot_id: snapshot.id.to_string(),
+                    },
+                    name: self.new_disk.to_string(),
+                    size: snapshot.size,
+                },
+            ),
+        )
+        .await?;
+
+        // Wait for the disk to be created.
+        let handle = ctx
+            .io
+            .start_process_indicator_with_label(" Waiting for the disk to be created");
+        ctx.io.progress_event(
+            "waiting",
+            serde_json::json!({ "disk": self.new_disk, "state": "creating" }),
+        )?;
+
+        let timeout = std::time::Duration::from_secs(self.wait_timeout);
+        let start = std::time::Instant::now();
+        loop {
+            let disk = ctx
+                .timed(
+                    "GET",
+                    "/organizations/{organization_name}/projects/{project_name}/disks/{disk_name}",
+                    client.disks().get(&self.new_disk, &organization, &project),
+                )
+                .await?;
+
+            match disk.state {
+                oxide_api::types::DiskState::Creating => {}
+                oxide_api::types::DiskState::Faulted => {
+                    if let Some(handle) = handle {
+                        handle.stop();
+                    }
+
+                    return Err(anyhow!(
+                        "disk {} faulted while restoring snapshot {}",
+                        self.new_disk,
+                        self.snapshot
+                    ));
+                }
+                _ => break,
+            }
+
+            if start.elapsed() > timeout {
+                if let Some(handle) = handle {
+                    handle.stop();
+                }
+
+                return Err(anyhow!(
+                    "timed out after {}s waiting for disk {} to be created",
+                    timeout.as_secs(),
+                    self.new_disk
+                ));
+            }
+
+            // Back off a bit.
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+
+        if let Some(handle) = handle {
+            handle.done();
+        }
+        ctx.io
+            .progress_event("done", serde_json::json!({ "disk": self.new_disk }))?;
+
+        if !ctx.quiet {
+            let cs = ctx.io.color_scheme();
+            writeln!(
+                ctx.io.out,
+                "{} Restored snapshot {} to disk {} in {}/{}",
+                cs.success_icon(),
+                self.snapshot,
+                self.new_disk,
+                organization,
+                project
+            )?;
+        }
+
+        Ok(())
+    }
+}
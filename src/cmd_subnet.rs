@@ -1,6 +1,6 @@
-use std::io::Write;
+use std::{io::Write, str::FromStr};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use clap::Parser;
 use cli_macro::crud_gen;
 
@@ -31,6 +31,40 @@ impl crate::cmd::Command for CmdSubnet {
     }
 }
 
+/// Parse an IPv4 block like `10.0.0.0/24`. The address must be the network address of the
+/// block, with none of the host bits set.
+pub fn parse_ipv4_block(s: &str) -> Result<oxide_api::types::Ipv4Net> {
+    let block = ipnetwork::Ipv4Network::from_str(s).map_err(|e| anyhow!("invalid IPv4 block `{}`: {}", s, e))?;
+
+    if block.ip() != block.network() {
+        return Err(anyhow!(
+            "invalid IPv4 block `{}`: host bits are set, did you mean `{}/{}`?",
+            s,
+            block.network(),
+            block.prefix()
+        ));
+    }
+
+    Ok(oxide_api::types::Ipv4Net(block))
+}
+
+/// Parse an IPv6 block like `fd00:1122:3344:0100::/64`. The address must be the network
+/// address of the block, with none of the host bits set.
+pub fn parse_ipv6_block(s: &str) -> Result<oxide_api::types::Ipv6Net> {
+    let block = ipnetwork::Ipv6Network::from_str(s).map_err(|e| anyhow!("invalid IPv6 block `{}`: {}", s, e))?;
+
+    if block.ip() != block.network() {
+        return Err(anyhow!(
+            "invalid IPv6 block `{}`: host bits are set, did you mean `{}/{}`?",
+            s,
+            block.network(),
+            block.prefix()
+        ));
+    }
+
+    Ok(oxide_api::types::Ipv6Net(block))
+}
+
 #[cfg(test)]
 mod test {
     use pretty_assertions::assert_eq;
@@ -61,6 +95,7 @@ mod test {
                     vpc: "".to_string(),
                     ipv4_block: Some(ipv4_block),
                     ipv6_block: Default::default(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -77,6 +112,7 @@ mod test {
                     vpc: "foo bar".to_string(),
                     ipv4_block: Some(ipv4_block),
                     ipv6_block: Default::default(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -93,6 +129,7 @@ mod test {
                     vpc: "blah".to_string(),
                     ipv4_block: Some(ipv4_block),
                     ipv6_block: Default::default(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -109,6 +146,7 @@ mod test {
                     vpc: "blah".to_string(),
                     ipv4_block: Some(ipv4_block),
                     ipv6_block: Default::default(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -125,6 +163,7 @@ mod test {
                     vpc: "".to_string(),
                     ipv4_block: Some(ipv4_block),
                     ipv6_block: Default::default(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -138,6 +177,8 @@ mod test {
                     organization: "".to_string(),
                     project: "".to_string(),
                     vpc: "things".to_string(),
+                    all: false,
+                    filter: "".to_string(),
                     confirm: false,
                 }),
 
@@ -154,7 +195,15 @@ mod test {
                     vpc: "things".to_string(),
                     project: "".to_string(),
                     paginate: false,
+                    page_token: None,
                     format: None,
+                    template: None,
+                    columns: vec![],
+                    no_header: false,
+                    sort: "".to_string(),
+                    reverse: false,
+                    filter: vec![],
+                    output_file: "".to_string(),
                 }),
 
                 stdin: "".to_string(),
@@ -179,6 +228,13 @@ mod test {
                 config: &mut c,
                 io,
                 debug: false,
+                quiet: false,
+                profile: None,
+                api_clients: Default::default(),
+                timeout: None,
+                headers: Default::default(),
+                request_id_sent: Default::default(),
+                endpoint: None,
             };
 
             let cmd_subnet = crate::cmd_subnet::CmdSubnet { subcmd: t.cmd };
@@ -203,4 +259,30 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_parse_ip_blocks() {
+        let block = crate::cmd_subnet::parse_ipv4_block("172.30.0.0/22").unwrap();
+        assert_eq!(block.0.to_string(), "172.30.0.0/22");
+
+        assert_eq!(
+            crate::cmd_subnet::parse_ipv4_block("10.0.0.2/24")
+                .unwrap_err()
+                .to_string(),
+            "invalid IPv4 block `10.0.0.2/24`: host bits are set, did you mean `10.0.0.0/24`?"
+        );
+        assert!(crate::cmd_subnet::parse_ipv4_block("10.0.0.0/33").is_err());
+        assert!(crate::cmd_subnet::parse_ipv4_block("fd00::/64").is_err());
+
+        let block = crate::cmd_subnet::parse_ipv6_block("fd00:1122:3344:100::/64").unwrap();
+        assert_eq!(block.0.to_string(), "fd00:1122:3344:100::/64");
+
+        assert_eq!(
+            crate::cmd_subnet::parse_ipv6_block("fd00::1/64")
+                .unwrap_err()
+                .to_string(),
+            "invalid IPv6 block `fd00::1/64`: host bits are set, did you mean `fd00::/64`?"
+        );
+        assert!(crate::cmd_subnet::parse_ipv6_block("10.0.0.0/24").is_err());
+    }
 }
@@ -3,52 +3,107 @@ use clap::Parser;
 
 /// Update the current running binary to the latest version.
 ///
-/// This function will return an error if the current binary is under Homebrew or if
-/// the running version is already the latest version.
+/// The binary for the current OS and architecture is downloaded from the GitHub
+/// release, its SHA256 checksum is verified, and it atomically replaces the running
+/// binary.
+///
+/// This function will return an error if the running version is already the
+/// requested version. Binaries installed with Homebrew are skipped, since Homebrew
+/// manages those.
+///
+/// With `--check`, only check if a new version is available, without installing it.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
-pub struct CmdUpdate {}
+pub struct CmdUpdate {
+    /// The version to update to, for example 'v0.1.0'. Defaults to the latest version.
+    #[clap(long)]
+    pub version: Option<String>,
+
+    /// Only check if a new version is available, without installing it.
+    #[clap(long, conflicts_with = "version")]
+    pub check: bool,
+}
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdUpdate {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let current_version = clap::crate_version!();
+
+        if self.check {
+            let interval = crate::update::check_interval(ctx.config);
+            let cs = ctx.io.color_scheme();
+            match crate::update::check_for_update(current_version, true, interval).await? {
+                Some(latest_release) => writeln!(
+                    ctx.io.out,
+                    "A new release of oxide is available: {} → {}\n{}",
+                    cs.cyan(current_version),
+                    cs.purple(&latest_release.version),
+                    latest_release.url
+                )?,
+                None => writeln!(
+                    ctx.io.out,
+                    "{} You are running the latest version ({}) of `oxide`.",
+                    cs.success_icon(),
+                    current_version
+                )?,
+            }
+
+            return Ok(());
+        }
+
         if crate::update::is_under_homebrew()? {
-            anyhow::bail!("You are running under Homebrew. Please run `brew update && brew upgrade oxide` instead.");
+            let cs = ctx.io.color_scheme();
+            writeln!(
+                ctx.io.err_out,
+                "{} You are running under Homebrew. Please run `brew update && brew upgrade oxide` instead.",
+                cs.warning_icon()
+            )?;
+            return Ok(());
         }
 
-        // Get the latest release.
-        let latest_release = crate::update::get_latest_release_info().await?;
-        let current_version = clap::crate_version!();
+        let release = match &self.version {
+            Some(version) => {
+                // Pinning a version can also be a downgrade, so we only skip the version we run.
+                let release = crate::update::get_release_info(Some(version)).await?;
+                if release.version.trim_start_matches('v') == current_version.trim_start_matches('v') {
+                    anyhow::bail!("You are already running version {} of `oxide`.", release.version);
+                }
 
-        if !crate::update::version_greater_then(&latest_release.version, current_version)? {
-            anyhow::bail!(
-                "You are already running the latest version ({}) of `oxide`.",
-                current_version
-            );
-        }
+                release
+            }
+            None => {
+                let latest_release = crate::update::get_latest_release_info().await?;
+                if !crate::update::version_greater_then(&latest_release.version, current_version)? {
+                    anyhow::bail!(
+                        "You are already running the latest version ({}) of `oxide`.",
+                        current_version
+                    );
+                }
+
+                latest_release
+            }
+        };
 
         let current_binary_path = std::env::current_exe()?;
+        let current_binary_dir = current_binary_path
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("failed to get the directory of {}", current_binary_path.display()))?;
 
         let cs = ctx.io.color_scheme();
 
         writeln!(
             ctx.io.out,
             "Updating from v{} to {}...",
-            current_version, latest_release.version
+            current_version, release.version
         )?;
 
-        // Download the latest release.
-        let temp_latest_binary_path = crate::update::download_binary_to_temp_file(&latest_release.version).await?;
+        // Download the release next to the current binary.
+        let new_binary = crate::update::download_binary_to_temp_file(&release, current_binary_dir).await?;
 
         // Rename the file to that of the current running exe.
-        std::fs::rename(temp_latest_binary_path, current_binary_path)?;
+        crate::update::replace_binary(new_binary, &current_binary_path)?;
 
-        writeln!(
-            ctx.io.out,
-            "{} Updated to v{}!",
-            cs.success_icon(),
-            latest_release.version
-        )?;
+        writeln!(ctx.io.out, "{} Updated to {}!", cs.success_icon(), release.version)?;
 
         Ok(())
     }
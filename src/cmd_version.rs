@@ -2,12 +2,45 @@ use anyhow::Result;
 use clap::Parser;
 
 /// Prints the version of the program.
+///
+/// Pass `--format json` for the version, git commit, build date, target, and rustc
+/// version in a form that is easy for scripts to read.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdVersion {
     #[doc = "Open the version in the browser."]
     #[clap(short, long)]
     pub web: bool,
+
+    /// Output format. Without it, the version is printed for people.
+    #[clap(long, short)]
+    pub format: Option<crate::types::FormatOutput>,
+}
+
+/// What `oxide version --format` prints.
+#[derive(serde::Serialize, tabled::Tabled, Debug, Clone)]
+pub struct VersionInfo {
+    pub version: String,
+    /// The git commit the binary was built from, empty if it was not built from git.
+    pub commit: String,
+    /// When the binary was built, in RFC 3339.
+    pub build_date: String,
+    pub target: String,
+    pub rustc: String,
+}
+
+impl VersionInfo {
+    pub fn current() -> Self {
+        VersionInfo {
+            version: clap::crate_version!().to_string(),
+            commit: git_rev::try_revision_string!().unwrap_or_default().to_string(),
+            build_date: chrono::DateTime::parse_from_rfc2822(crate::built_info::BUILT_TIME_UTC)
+                .map(|date| date.to_rfc3339())
+                .unwrap_or_else(|_| crate::built_info::BUILT_TIME_UTC.to_string()),
+            target: crate::built_info::TARGET.to_string(),
+            rustc: crate::built_info::RUSTC_VERSION.to_string(),
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -17,13 +50,17 @@ impl crate::cmd::Command for CmdVersion {
         let git_hash = git_rev::try_revision_string!();
         let url = changelog_url(version);
 
-        if let Some(gh) = git_hash {
-            writeln!(ctx.io.out, "oxide {} ({})", version, gh)?;
+        if let Some(format) = &self.format {
+            ctx.io.write_output(format, &VersionInfo::current())?;
         } else {
-            writeln!(ctx.io.out, "oxide {}", version)?;
-        }
+            if let Some(gh) = git_hash {
+                writeln!(ctx.io.out, "oxide {} ({})", version, gh)?;
+            } else {
+                writeln!(ctx.io.out, "oxide {}", version)?;
+            }
 
-        writeln!(ctx.io.out, "{}", url)?;
+            writeln!(ctx.io.out, "{}", url)?;
+        }
 
         if self.web {
             ctx.browser("", &url)?;
@@ -5,6 +5,8 @@ use clap::Parser;
 use cli_macro::crud_gen;
 
 /// Create, list, edit, view, and delete VPCs.
+///
+/// Additionally, list, view, and edit the firewall rules of a VPC.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdVpc {
@@ -16,7 +18,9 @@ pub struct CmdVpc {
     tag = "vpcs",
 }]
 #[derive(Parser, Debug, Clone)]
-enum SubCommand {}
+enum SubCommand {
+    FirewallRules(crate::cmd_vpc_firewall::CmdVpcFirewallRules),
+}
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdVpc {
@@ -25,6 +29,7 @@ impl crate::cmd::Command for CmdVpc {
             SubCommand::Create(cmd) => cmd.run(ctx).await,
             SubCommand::Delete(cmd) => cmd.run(ctx).await,
             SubCommand::Edit(cmd) => cmd.run(ctx).await,
+            SubCommand::FirewallRules(cmd) => cmd.run(ctx).await,
             SubCommand::List(cmd) => cmd.run(ctx).await,
             SubCommand::View(cmd) => cmd.run(ctx).await,
         }
@@ -57,6 +62,7 @@ mod test {
                     description: "".to_string(),
                     dns_name: "".to_string(),
                     ipv6_prefix: Default::default(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -72,6 +78,7 @@ mod test {
                     description: "blah blah".to_string(),
                     dns_name: "foo bar".to_string(),
                     ipv6_prefix: Default::default(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -87,6 +94,7 @@ mod test {
                     description: "blah blah".to_string(),
                     dns_name: "blah".to_string(),
                     ipv6_prefix: Default::default(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -102,6 +110,7 @@ mod test {
                     description: "blah blah".to_string(),
                     dns_name: "blah".to_string(),
                     ipv6_prefix: Default::default(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -117,6 +126,7 @@ mod test {
                     description: "blah blah".to_string(),
                     dns_name: "".to_string(),
                     ipv6_prefix: Default::default(),
+                    set: Default::default(),
                 }),
 
                 stdin: "".to_string(),
@@ -129,6 +139,8 @@ mod test {
                     vpc: "things".to_string(),
                     organization: "".to_string(),
                     project: "".to_string(),
+                    all: false,
+                    filter: "".to_string(),
                     confirm: false,
                 }),
 
@@ -144,7 +156,15 @@ mod test {
                     organization: "".to_string(),
                     project: "".to_string(),
                     paginate: false,
+                    page_token: None,
                     format: None,
+                    template: None,
+                    columns: vec![],
+                    no_header: false,
+                    sort: "".to_string(),
+                    reverse: false,
+                    filter: vec![],
+                    output_file: "".to_string(),
                 }),
 
                 stdin: "".to_string(),
@@ -169,6 +189,13 @@ mod test {
                 config: &mut c,
                 io,
                 debug: false,
+                quiet: false,
+                profile: None,
+                api_clients: Default::default(),
+                timeout: None,
+                headers: Default::default(),
+                request_id_sent: Default::default(),
+                endpoint: None,
             };
 
             let cmd_vpc = crate::cmd_vpc::CmdVpc { subcmd: t.cmd };
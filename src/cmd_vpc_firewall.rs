@@ -0,0 +1,335 @@
+use std::{io::Write, path::PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use clap::Parser;
+
+/// The columns shown by `vpc firewall-rules list` in a table, unless `--columns` is passed.
+const DEFAULT_LIST_COLUMNS: &[&str] = &["name", "action", "direction", "priority"];
+
+/// List, view, and edit the firewall rules of a VPC.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdVpcFirewallRules {
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Parser, Debug, Clone)]
+enum SubCommand {
+    List(CmdVpcFirewallRulesList),
+    View(CmdVpcFirewallRulesView),
+    Edit(CmdVpcFirewallRulesEdit),
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdVpcFirewallRules {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        match &self.subcmd {
+            SubCommand::Edit(cmd) => cmd.run(ctx).await,
+            SubCommand::List(cmd) => cmd.run(ctx).await,
+            SubCommand::View(cmd) => cmd.run(ctx).await,
+        }
+    }
+}
+
+/// List the firewall rules of a VPC.
+///
+/// The API returns all of the rules of a VPC at once, so unlike other lists
+/// this has no `--limit`, `--paginate`, or `--page-token`.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdVpcFirewallRulesList {
+    /// The VPC that holds the firewall rules.
+    #[clap(long, short = 'V', required = true)]
+    pub vpc: String,
+
+    /// The project that holds the VPC.
+    #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
+    pub project: String,
+
+    /// The organization that holds the project.
+    #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
+    pub organization: String,
+
+    /// Output format.
+    #[clap(long, short)]
+    pub format: Option<crate::types::FormatOutput>,
+
+    /// The template to render each item with, for example '{{.name}} {{.id}}'.
+    #[clap(long)]
+    pub template: Option<String>,
+
+    /// The columns to display, for example 'name,id'. Tables default to name, action,
+    /// direction, and priority, other formats to all of them.
+    #[clap(long, use_value_delimiter = true)]
+    pub columns: Vec<String>,
+
+    /// Do not print the header row of table, csv, or tsv output.
+    #[clap(long)]
+    pub no_header: bool,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdVpcFirewallRulesList {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let (organization, project) = resolve_project(ctx, &self.organization, &self.project)?;
+        let format = ctx.format(&self.format, &self.template)?;
+
+        let client = ctx.api_client("")?;
+
+        let result = ctx
+            .timed(
+                "GET",
+                "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/firewall/rules",
+                client.vpcs().firewall_rules_get(&organization, &project, &self.vpc),
+            )
+            .await?;
+
+        let columns = if self.columns.is_empty() && format == crate::types::FormatOutput::Table {
+            DEFAULT_LIST_COLUMNS.iter().map(|c| c.to_string()).collect()
+        } else {
+            self.columns.clone()
+        };
+
+        ctx.io
+            .write_output_for_vec_with_columns(&format, &columns, self.no_header, &result.rules)?;
+        Ok(())
+    }
+}
+
+/// View a firewall rule of a VPC.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdVpcFirewallRulesView {
+    /// The name of the firewall rule to view.
+    #[clap(name = "rule", required = true)]
+    pub rule: String,
+
+    /// The VPC that holds the firewall rule.
+    #[clap(long, short = 'V', required = true)]
+    pub vpc: String,
+
+    /// The project that holds the VPC.
+    #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
+    pub project: String,
+
+    /// The organization that holds the project.
+    #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
+    pub organization: String,
+
+    /// Output format.
+    #[clap(long, short)]
+    pub format: Option<crate::types::FormatOutput>,
+
+    /// The template to render the rule with, for example '{{.name}} {{.action}}'.
+    #[clap(long)]
+    pub template: Option<String>,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdVpcFirewallRulesView {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let (organization, project) = resolve_project(ctx, &self.organization, &self.project)?;
+        let format = ctx.format(&self.format, &self.template)?;
+
+        let client = ctx.api_client("")?;
+
+        // There is no endpoint for a single rule, so we look for it in the full set.
+        let result = ctx
+            .timed(
+                "GET",
+                "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/firewall/rules",
+                client.vpcs().firewall_rules_get(&organization, &project, &self.vpc),
+            )
+            .await?;
+
+        let rule = result
+            .rules
+            .iter()
+            .find(|r| r.name.to_string() == self.rule)
+            .ok_or_else(|| {
+                anyhow!(
+                    "firewall rule `{}` does not exist in VPC `{}` in {}/{}",
+                    self.rule,
+                    self.vpc,
+                    organization,
+                    project
+                )
+            })?;
+
+        ctx.io.write_output(&format, rule)?;
+        Ok(())
+    }
+}
+
+/// Replace the firewall rules of a VPC with the rules in a file.
+///
+/// The file holds JSON, either an object like `{"rules": [...]}` or a plain
+/// array of rules, like the output of `oxide vpc firewall-rules list --format json`.
+/// The rules in the file replace all of the current rules of the VPC, so any
+/// rule missing from the file is removed.
+///
+///     # Add a rule to the current ones.
+///     $ oxide vpc firewall-rules list --vpc my-vpc --format json > rules.json
+///     $ $EDITOR rules.json
+///     $ oxide vpc firewall-rules edit --vpc my-vpc --from-file rules.json
+///
+///     # Read the rules from stdin.
+///     $ cat rules.json | oxide vpc firewall-rules edit --vpc my-vpc --from-file -
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdVpcFirewallRulesEdit {
+    /// The VPC that holds the firewall rules.
+    #[clap(long, short = 'V', required = true)]
+    pub vpc: String,
+
+    /// The project that holds the VPC.
+    #[clap(long, short, default_value = "", env = "OXIDE_PROJECT")]
+    pub project: String,
+
+    /// The organization that holds the project.
+    #[clap(long, short, default_value = "", env = "OXIDE_ORG")]
+    pub organization: String,
+
+    /// The JSON file to read the rules from, or `-` for stdin.
+    #[clap(long, required = true)]
+    pub from_file: PathBuf,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdVpcFirewallRulesEdit {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let (organization, project) = resolve_project(ctx, &self.organization, &self.project)?;
+
+        let contents = if self.from_file.to_str() == Some("-") {
+            let mut contents = String::new();
+            std::io::Read::read_to_string(&mut ctx.io.stdin, &mut contents)?;
+            contents
+        } else {
+            std::fs::read_to_string(&self.from_file)
+                .with_context(|| format!("failed to read {}", self.from_file.display()))?
+        };
+
+        // Validate the rules before we talk to the API.
+        let rules = parse_firewall_rules(&contents)?;
+
+        let client = ctx.api_client("")?;
+
+        let result = ctx
+            .timed(
+                "PUT",
+                "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/firewall/rules",
+                client
+                    .vpcs()
+                    .firewall_rules_put(&organization, &project, &self.vpc, &rules),
+            )
+            .await?;
+
+        if !ctx.quiet {
+            let cs = ctx.io.color_scheme();
+            writeln!(
+                ctx.io.out,
+                "{} Updated the firewall rules of VPC {} in {}/{}, it now has {} rules",
+                cs.success_icon(),
+                self.vpc,
+                organization,
+                project,
+                result.rules.len()
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolve the organization and project of a firewall rules command from the flags,
+/// environment, or defaults in the config.
+fn resolve_project(ctx: &crate::context::Context, organization: &str, project: &str) -> Result<(String, String)> {
+    let organization = ctx.resolve_organization(organization)?;
+    if organization.is_empty() {
+        return Err(anyhow!("-o|--organization required"));
+    }
+
+    let project = ctx.resolve_project(project)?;
+    if project.is_empty() {
+        return Err(anyhow!("-p|--project required"));
+    }
+
+    Ok((organization, project))
+}
+
+/// Parse the firewall rules passed to `vpc firewall-rules edit`. Both an object with a
+/// `rules` array and a plain array of rules are accepted. Fields that only exist on
+/// existing rules, like `id` and `time_created`, are ignored.
+pub fn parse_firewall_rules(contents: &str) -> Result<oxide_api::types::VpcFirewallRuleUpdateParams> {
+    let value: serde_json::Value =
+        serde_json::from_str(contents).map_err(|err| anyhow!("invalid firewall rules: {}", err))?;
+
+    let value = if value.is_array() {
+        serde_json::json!({ "rules": value })
+    } else {
+        value
+    };
+
+    serde_json::from_value(value).map_err(|err| anyhow!("invalid firewall rules: {}", err))
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use crate::cmd::Command;
+
+    #[test]
+    fn test_parse_firewall_rules() {
+        assert!(super::parse_firewall_rules("[]").unwrap().rules.is_empty());
+        assert!(super::parse_firewall_rules(r#"{"rules": []}"#)
+            .unwrap()
+            .rules
+            .is_empty());
+
+        assert!(super::parse_firewall_rules("not json")
+            .unwrap_err()
+            .to_string()
+            .starts_with("invalid firewall rules: "));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_cmd_vpc_firewall_rules_edit_invalid_rules() {
+        let mut config = crate::config::new_blank_config().unwrap();
+        let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+
+        let (mut io, stdout_path, stderr_path) = crate::iostreams::IoStreams::test();
+        io.stdin = Box::new(std::io::Cursor::new("{\"rules\": 1}"));
+        io.set_color_enabled(false);
+        io.set_never_prompt(true);
+        let mut ctx = crate::context::Context {
+            config: &mut c,
+            io,
+            debug: false,
+            quiet: false,
+            profile: None,
+            api_clients: Default::default(),
+            timeout: None,
+            headers: Default::default(),
+            request_id_sent: Default::default(),
+            endpoint: None,
+        };
+
+        let cmd = crate::cmd_vpc_firewall::CmdVpcFirewallRulesEdit {
+            vpc: "things".to_string(),
+            project: "bar".to_string(),
+            organization: "foo".to_string(),
+            from_file: "-".into(),
+        };
+
+        let err = cmd.run(&mut ctx).await.unwrap_err();
+        assert!(
+            err.to_string().starts_with("invalid firewall rules: "),
+            "unexpected error: {}",
+            err
+        );
+        assert_eq!(std::fs::read_to_string(stdout_path).unwrap(), "");
+        assert_eq!(std::fs::read_to_string(stderr_path).unwrap(), "");
+    }
+}
@@ -0,0 +1,44 @@
+use anyhow::Result;
+use clap::Parser;
+
+/// Shows who you are logged in as, and to which host and profile.
+///
+/// This is the same as `oxide api session/me`, printed for people. Pass
+/// `--format json` for the session as the API returns it.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdWhoami {
+    /// Output format. Without it, the user, host, and profile are printed for people.
+    #[clap(long, short)]
+    pub format: Option<crate::types::FormatOutput>,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdWhoami {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let profile = ctx.active_profile()?;
+        let host = ctx.config.profile_host(&profile)?;
+
+        let client = ctx.api_client(&profile)?;
+        let session = ctx.timed("GET", "/session/me", client.hidden().session_me()).await?;
+
+        if let Some(format) = &self.format {
+            ctx.io.write_output(format, &session)?;
+            return Ok(());
+        }
+
+        let cs = ctx.io.color_scheme();
+
+        // The display name is not required to be set, fall back to the ID.
+        let user = if session.display_name.is_empty() {
+            session.id.to_string()
+        } else {
+            format!("{} ({})", session.display_name, session.id)
+        };
+
+        writeln!(ctx.io.out, "Logged in to {} as {}", host, cs.bold(&user))?;
+        writeln!(ctx.io.out, "Profile: {}", profile)?;
+
+        Ok(())
+    }
+}
@@ -8,6 +8,49 @@ pub fn env_color_forced() -> bool {
     !get_env_var("CLICOLOR_FORCE").is_empty() && get_env_var("CLICOLOR_FORCE") != "0"
 }
 
+/// When to print ANSI colors, as passed with `--color`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Use colors when the output is a terminal and the environment does not disable
+    /// them.
+    Auto,
+    /// Always use colors, even when the output is piped.
+    Always,
+    /// Never use colors.
+    Never,
+}
+
+impl Default for ColorMode {
+    fn default() -> ColorMode {
+        ColorMode::Auto
+    }
+}
+
+impl std::fmt::Display for ColorMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ColorMode::Auto => "auto",
+            ColorMode::Always => "always",
+            ColorMode::Never => "never",
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+impl std::str::FromStr for ColorMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "auto" => Ok(ColorMode::Auto),
+            "always" => Ok(ColorMode::Always),
+            "never" => Ok(ColorMode::Never),
+            _ => Err(anyhow::anyhow!("invalid color mode: {}", s)),
+        }
+    }
+}
+
 pub fn is_true_color_supported() -> bool {
     let term = get_env_var("TERM");
     let color_term = get_env_var("COLORTERM");
@@ -21,6 +21,10 @@ pub trait Config {
     /// Get the default host with the source.
     fn default_host_with_source(&self) -> Result<(String, String)>;
 
+    /// Get the host a profile points to. Profiles created without `--profile` are
+    /// keyed by their hostname, so the profile name is returned as is.
+    fn profile_host(&self, profile: &str) -> Result<String>;
+
     /// Get the aliases.
     fn aliases(&mut self) -> Result<crate::config_alias::AliasConfig>;
     /// Save the aliases to our config.
@@ -84,6 +88,13 @@ pub fn config_options() -> Vec<ConfigOption> {
             default_value: crate::types::FormatOutput::default().to_string(),
             allowed_values: crate::types::FormatOutput::variants(),
         },
+        ConfigOption {
+            key: "update_check_interval".to_string(),
+            description: "how often to check for a new release of oxide".to_string(),
+            comment: "How often oxide should check for a new release, for example 12h or 7d.".to_string(),
+            default_value: crate::update::DEFAULT_CHECK_INTERVAL.to_string(),
+            allowed_values: vec![],
+        },
         ConfigOption {
             key: "client_id".to_string(),
             description: "a unique identifier for this client".to_string(),
@@ -111,6 +122,11 @@ pub enum InvalidValueError {
 }
 
 pub fn validate_value(key: &str, value: &str) -> Result<()> {
+    if key == "update_check_interval" {
+        crate::types::parse_duration(value)?;
+        return Ok(());
+    }
+
     let mut valid_values: Vec<String> = vec![];
 
     // Set the valid values for the key.
@@ -232,7 +248,7 @@ prompt = "enabled"
 browser = ""
 
 # What formatting Oxide should use when printing text.
-# Supported values: table, json, yaml
+# Supported values: table, json, jsonl, yaml, csv, tsv
 format = "table""#;
         assert!(doc_config.contains(expected));
 
@@ -305,6 +321,42 @@ default = true"#;
         assert_eq!(c.hosts_to_string().unwrap(), expected);
     }
 
+    #[test]
+    fn test_parse_config_profiles() {
+        let mut c = crate::config::new_from_string(
+            r#"[hosts]
+
+[hosts."thing.com"]
+user = "jess"
+token = "MY_TOKEN"
+
+[hosts.staging]
+host = "rack2.thing.com"
+user = "new_user"
+token = "STAGING_TOKEN""#,
+        )
+        .unwrap();
+
+        // Existing hosts are their own profile.
+        assert_eq!(c.profile_host("thing.com").unwrap(), "thing.com");
+        assert_eq!(c.get("thing.com", "token").unwrap(), "MY_TOKEN");
+
+        // Named profiles can be found by name or by their host.
+        assert_eq!(c.profile_host("staging").unwrap(), "rack2.thing.com");
+        assert_eq!(c.get("staging", "token").unwrap(), "STAGING_TOKEN");
+        assert_eq!(c.get("rack2.thing.com", "token").unwrap(), "STAGING_TOKEN");
+
+        // Hosts we know nothing about resolve to themselves.
+        assert_eq!(c.profile_host("other.com").unwrap(), "other.com");
+
+        c.set("rack2.thing.com", "user", "jess").unwrap();
+        assert_eq!(c.get("staging", "user").unwrap(), "jess");
+        assert_eq!(c.hosts().unwrap(), vec!["thing.com".to_string(), "staging".to_string()]);
+
+        c.unset_host("rack2.thing.com").unwrap();
+        assert_eq!(c.hosts().unwrap(), vec!["thing.com".to_string()]);
+    }
+
     #[test]
     fn test_validate_key() {
         let result = validate_key("invalid").unwrap_err();
@@ -466,6 +518,125 @@ default = true"#;
                 want_is_shell: false,
                 want_err: "".to_string(),
             },
+            TestItem {
+                name: "numbered placeholder".to_string(),
+                args: vec![
+                    "oxide".to_string(),
+                    "pv".to_string(),
+                    "maze-war".to_string(),
+                    "development".to_string(),
+                ],
+                want_expanded: vec![
+                    "oxide".to_string(),
+                    "project".to_string(),
+                    "view".to_string(),
+                    "--organization".to_string(),
+                    "maze-war".to_string(),
+                    "development".to_string(),
+                ],
+                want_is_shell: false,
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "numbered placeholder keeps spaces".to_string(),
+                args: vec!["oxide".to_string(), "pv".to_string(), "maze war".to_string()],
+                want_expanded: vec![
+                    "oxide".to_string(),
+                    "project".to_string(),
+                    "view".to_string(),
+                    "--organization".to_string(),
+                    "maze war".to_string(),
+                ],
+                want_is_shell: false,
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "catch-all placeholder".to_string(),
+                args: vec![
+                    "oxide".to_string(),
+                    "il".to_string(),
+                    "-o".to_string(),
+                    "maze-war".to_string(),
+                ],
+                want_expanded: vec![
+                    "oxide".to_string(),
+                    "instance".to_string(),
+                    "list".to_string(),
+                    "-o".to_string(),
+                    "maze-war".to_string(),
+                    "--format".to_string(),
+                    "json".to_string(),
+                ],
+                want_is_shell: false,
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "catch-all placeholder after numbered placeholder".to_string(),
+                args: vec![
+                    "oxide".to_string(),
+                    "iv".to_string(),
+                    "db".to_string(),
+                    "-o".to_string(),
+                    "maze-war".to_string(),
+                ],
+                want_expanded: vec![
+                    "oxide".to_string(),
+                    "instance".to_string(),
+                    "view".to_string(),
+                    "db".to_string(),
+                    "-o".to_string(),
+                    "maze-war".to_string(),
+                    "--format".to_string(),
+                    "json".to_string(),
+                ],
+                want_is_shell: false,
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "catch-all placeholder without arguments".to_string(),
+                args: vec!["oxide".to_string(), "il".to_string()],
+                want_expanded: vec![
+                    "oxide".to_string(),
+                    "instance".to_string(),
+                    "list".to_string(),
+                    "--format".to_string(),
+                    "json".to_string(),
+                ],
+                want_is_shell: false,
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "shell alias passes arguments through".to_string(),
+                args: vec!["oxide".to_string(), "sh".to_string(), "foo".to_string()],
+                want_expanded: vec![
+                    "sh".to_string(),
+                    "-c".to_string(),
+                    "oxide config get $1".to_string(),
+                    "--".to_string(),
+                    "foo".to_string(),
+                ],
+                want_is_shell: true,
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "alias expanding to another alias".to_string(),
+                args: vec!["oxide".to_string(), "csh".to_string()],
+                want_expanded: vec![
+                    "oxide".to_string(),
+                    "config".to_string(),
+                    "set".to_string(),
+                    "host".to_string(),
+                ],
+                want_is_shell: false,
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "alias cycle".to_string(),
+                args: vec!["oxide".to_string(), "a".to_string()],
+                want_expanded: vec![],
+                want_is_shell: false,
+                want_err: "alias cycle detected: a -> b -> a".to_string(),
+            },
         ];
 
         let mut config = crate::config::new_blank_config().unwrap();
@@ -476,6 +647,13 @@ default = true"#;
         aliases.add("cs", "config set").unwrap();
         aliases.add("ca", "config set $1 $2").unwrap();
         aliases.add("ci", "config set $1 $1").unwrap();
+        aliases.add("pv", "project view --organization $1").unwrap();
+        aliases.add("il", "instance list $* --format json").unwrap();
+        aliases.add("iv", "instance view $1 $@ --format json").unwrap();
+        aliases.add("sh", "!oxide config get $1").unwrap();
+        aliases.add("csh", "cs host").unwrap();
+        aliases.add("a", "b list").unwrap();
+        aliases.add("b", "a").unwrap();
 
         for t in tests {
             let result = c.expand_alias(t.args);
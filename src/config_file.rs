@@ -9,105 +9,67 @@ use anyhow::{anyhow, Context, Result};
 const OXIDE_CONFIG_DIR: &str = "OXIDE_CONFIG_DIR";
 const XDG_CONFIG_HOME: &str = "XDG_CONFIG_HOME";
 const XDG_STATE_HOME: &str = "XDG_STATE_HOME";
-#[allow(dead_code)]
 const XDG_DATA_HOME: &str = "XDG_DATA_HOME";
-const APP_DATA: &str = "CommandData";
-const LOCAL_APP_DATA: &str = "LocalCommandData";
-
-// Config path precedence
-// 1. OXIDE_CONFIG_DIR
-// 2. XDG_CONFIG_HOME
-// 3. CommandData (windows only)
-// 4. HOME
-pub fn config_dir() -> Result<String> {
-    let path: PathBuf;
 
+/// The kinds of directories oxide keeps its files in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dir {
+    Config,
+    State,
+    #[allow(dead_code)]
+    Data,
+}
+
+/// Resolve one of the directories oxide keeps its files in. Every file oxide reads or
+/// writes, the config, the hosts, and the update check state, goes through here so they
+/// all agree on where things live.
+///
+/// Path precedence:
+/// 1. OXIDE_CONFIG_DIR (not for data)
+/// 2. XDG_CONFIG_HOME, XDG_STATE_HOME, or XDG_DATA_HOME
+/// 3. The roaming or local app data directory (windows only)
+/// 4. HOME, using the XDG defaults
+fn resolve_dir(dir: Dir) -> Result<PathBuf> {
     let oxide_config_dir = get_env_var(OXIDE_CONFIG_DIR);
-    let xdg_config_home = get_env_var(XDG_CONFIG_HOME);
-    let app_data = get_env_var(APP_DATA);
-
-    if !oxide_config_dir.is_empty() {
-        path = Path::new(&oxide_config_dir).to_path_buf();
-    } else if !xdg_config_home.is_empty() {
-        path = Path::new(&xdg_config_home).join("oxide");
-    } else if !app_data.is_empty() && std::env::consts::OS == "windows" {
-        path = Path::new(&app_data).join("Oxide CLI");
-    } else {
-        match dirs::home_dir() {
-            Some(home) => {
-                path = home.join(".config").join("oxide");
-            }
-            None => {
-                return Err(anyhow!("could not find home directory"));
-            }
-        }
+    if !oxide_config_dir.is_empty() && dir != Dir::Data {
+        return Ok(Path::new(&oxide_config_dir).to_path_buf());
     }
 
-    // Convert the path into a string slice
-    match path.to_str() {
-        None => Err(anyhow!("path is not a valid UTF-8 sequence")),
-        Some(s) => Ok(s.to_string()),
-    }
-}
-
-// State path precedence
-// 2. XDG_STATE_HOME
-// 3. LocalCommandData (windows only)
-// 4. HOME
-pub fn state_dir() -> Result<String> {
-    let path: PathBuf;
+    let (xdg_var, home_default) = match dir {
+        Dir::Config => (XDG_CONFIG_HOME, [".config"].as_slice()),
+        Dir::State => (XDG_STATE_HOME, [".local", "state"].as_slice()),
+        Dir::Data => (XDG_DATA_HOME, [".local", "share"].as_slice()),
+    };
 
-    let xdg_state_home = get_env_var(XDG_STATE_HOME);
-    let local_app_data = get_env_var(LOCAL_APP_DATA);
+    let xdg_dir = get_env_var(xdg_var);
+    if !xdg_dir.is_empty() {
+        return Ok(Path::new(&xdg_dir).join("oxide"));
+    }
 
-    if !xdg_state_home.is_empty() {
-        path = Path::new(&xdg_state_home).join("oxide");
-    } else if !local_app_data.is_empty() && std::env::consts::OS == "windows" {
-        path = Path::new(&local_app_data).join("Oxide CLI");
-    } else {
-        match dirs::home_dir() {
-            Some(home) => {
-                path = home.join(".local").join("state").join("oxide");
-            }
-            None => {
-                return Err(anyhow!("could not find home directory"));
-            }
+    if cfg!(windows) {
+        let app_data = match dir {
+            Dir::Config => dirs::config_dir(),
+            Dir::State | Dir::Data => dirs::data_local_dir(),
+        };
+        if let Some(app_data) = app_data {
+            return Ok(app_data.join("Oxide CLI"));
         }
     }
 
-    // Convert the path into a string slice
-    match path.to_str() {
-        None => Err(anyhow!("path is not a valid UTF-8 sequence")),
-        Some(s) => Ok(s.to_string()),
+    // macOS uses the XDG defaults too, rather than ~/Library, so the paths are the same
+    // as on linux.
+    match dirs::home_dir() {
+        Some(home) => Ok(home_default.iter().fold(home, |path, p| path.join(p)).join("oxide")),
+        None => Err(anyhow!("could not find home directory")),
     }
 }
 
-// Data path precedence
-// 2. XDG_DATA_HOME
-// 3. LocalCommandData (windows only)
-// 4. HOME
-#[allow(dead_code)]
-pub fn data_dir() -> Result<String> {
-    let path: PathBuf;
-
-    let xdg_data_home = get_env_var(XDG_DATA_HOME);
-    let local_app_data = get_env_var(LOCAL_APP_DATA);
-
-    if !xdg_data_home.is_empty() {
-        path = Path::new(&xdg_data_home).join("oxide");
-    } else if !local_app_data.is_empty() && std::env::consts::OS == "windows" {
-        path = Path::new(&local_app_data).join("Oxide CLI");
-    } else {
-        match dirs::home_dir() {
-            Some(home) => {
-                path = home.join(".local").join("share").join("oxide");
-            }
-            None => {
-                return Err(anyhow!("could not find home directory"));
-            }
-        }
-    }
+/// Resolve a file in one of oxide's directories, as a string.
+fn resolve_file(dir: Dir, filename: &str) -> Result<String> {
+    path_to_string(resolve_dir(dir)?.join(filename))
+}
 
+fn path_to_string(path: PathBuf) -> Result<String> {
     // Convert the path into a string slice
     match path.to_str() {
         None => Err(anyhow!("path is not a valid UTF-8 sequence")),
@@ -115,41 +77,45 @@ pub fn data_dir() -> Result<String> {
     }
 }
 
-pub fn config_file() -> Result<String> {
-    let config_dir = config_dir()?;
-    let path = Path::new(&config_dir).join("config.toml");
+pub fn config_dir() -> Result<String> {
+    path_to_string(resolve_dir(Dir::Config)?)
+}
 
-    // Convert the path into a string slice
-    match path.to_str() {
-        None => Err(anyhow!("path is not a valid UTF-8 sequence")),
-        Some(s) => Ok(s.to_string()),
-    }
+#[allow(dead_code)]
+pub fn state_dir() -> Result<String> {
+    path_to_string(resolve_dir(Dir::State)?)
 }
 
-pub fn hosts_file() -> Result<String> {
-    let config_dir = config_dir()?;
-    let path = Path::new(&config_dir).join("hosts.toml");
+#[allow(dead_code)]
+pub fn data_dir() -> Result<String> {
+    path_to_string(resolve_dir(Dir::Data)?)
+}
 
-    // Convert the path into a string slice
-    match path.to_str() {
-        None => Err(anyhow!("path is not a valid UTF-8 sequence")),
-        Some(s) => Ok(s.to_string()),
-    }
+pub fn config_file() -> Result<String> {
+    resolve_file(Dir::Config, "config.toml")
+}
+
+pub fn hosts_file() -> Result<String> {
+    resolve_file(Dir::Config, "hosts.toml")
 }
 
 pub fn state_file() -> Result<String> {
-    let state_dir = state_dir()?;
-    let path = Path::new(&state_dir).join("state.toml");
+    resolve_file(Dir::State, "state.toml")
+}
 
-    // Convert the path into a string slice
-    match path.to_str() {
-        None => Err(anyhow!("path is not a valid UTF-8 sequence")),
-        Some(s) => Ok(s.to_string()),
-    }
+/// The files oxide keeps only to avoid redoing work, like the state of the update
+/// check. They are safe to remove, see `oxide config clear-cache`.
+pub fn cache_files() -> Result<Vec<String>> {
+    Ok(vec![state_file()?])
 }
 
 pub fn parse_default_config() -> Result<impl crate::config::Config> {
-    let config_file_path = config_file()?;
+    // Without a directory for the config, like on a CI box without a home directory, start
+    // blank. OXIDE_HOST and OXIDE_TOKEN are then all it takes, see `config_from_env`.
+    let config_file_path = match config_file() {
+        Ok(config_file_path) => config_file_path,
+        Err(_) => return Ok(crate::config::new_config(crate::config::new_blank_root()?)),
+    };
 
     // If the config file does not exist, create it.
     let path = Path::new(&config_file_path);
@@ -159,7 +125,7 @@ pub fn parse_default_config() -> Result<impl crate::config::Config> {
     } else {
         // Get the default config from the file.
         let contents = read_config_file(&config_file_path)?;
-        contents.parse::<toml_edit::Document>()?
+        parse(&contents)?
     };
 
     // Parse the hosts file.
@@ -167,7 +133,7 @@ pub fn parse_default_config() -> Result<impl crate::config::Config> {
     let path = Path::new(&hosts_file_path);
     if path.exists() {
         let contents = read_config_file(&hosts_file_path)?;
-        let doc = contents.parse::<toml_edit::Document>()?;
+        let doc = parse(&contents)?;
         let hosts = doc.as_table().clone();
         root.insert("hosts", toml_edit::Item::Table(hosts));
     }
@@ -175,6 +141,30 @@ pub fn parse_default_config() -> Result<impl crate::config::Config> {
     Ok(crate::config::new_config(root))
 }
 
+/// Parse the contents of a config file, making sure the tables oxide relies on have
+/// the right shape.
+pub fn parse(contents: &str) -> Result<toml_edit::Document> {
+    let doc = contents.parse::<toml_edit::Document>()?;
+
+    for key in ["aliases", "hosts"] {
+        if let Some(item) = doc.get(key) {
+            if !item.is_table_like() {
+                return Err(anyhow!("{} is not a table", key));
+            }
+        }
+    }
+
+    if let Some(hosts) = doc.get("hosts").and_then(|h| h.as_table_like()) {
+        for (host, item) in hosts.iter() {
+            if !item.is_table_like() {
+                return Err(anyhow!("hosts.{} is not a table", host));
+            }
+        }
+    }
+
+    Ok(doc)
+}
+
 fn read_config_file(filename: &str) -> Result<String> {
     fs::read_to_string(filename).with_context(|| format!("failed to read from {}", filename))
 }
@@ -184,9 +174,33 @@ pub fn write_config_file(filename: &str, data: &str) -> Result<()> {
     let parent = path.parent().unwrap();
     fs::create_dir_all(parent).with_context(|| format!("failed to create directory {}", parent.display()))?;
 
-    let mut file = fs::File::create(filename)?;
+    // Write to a temporary file next to the config file and rename it into place, so we
+    // never leave a partially written config file behind. The temporary file is removed
+    // if anything fails before that.
+    let mut file = tempfile::Builder::new()
+        .prefix(".oxide")
+        .tempfile_in(parent)
+        .with_context(|| format!("failed to write to {}", filename))?;
+
+    // The hosts file holds tokens, so only the user gets to read what we write.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.as_file()
+            .set_permissions(fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("failed to write to {}", filename))?;
+    }
+
     file.write_all(data.as_bytes())
-        .with_context(|| format!("failed to write to {}", filename))
+        .with_context(|| format!("failed to write to {}", filename))?;
+    file.as_file()
+        .sync_all()
+        .with_context(|| format!("failed to write to {}", filename))?;
+
+    file.persist(filename)
+        .with_context(|| format!("failed to write to {}", filename))?;
+
+    Ok(())
 }
 
 #[allow(dead_code)]
@@ -200,3 +214,104 @@ pub fn get_env_var(key: &str) -> String {
         Err(_) => "".to_string(),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use test_context::{test_context, TestContext};
+
+    use super::*;
+    use crate::config::Config;
+
+    struct TContext {
+        orig_env: Vec<(&'static str, Result<String, std::env::VarError>)>,
+    }
+
+    impl TestContext for TContext {
+        fn setup() -> TContext {
+            TContext {
+                orig_env: [OXIDE_CONFIG_DIR, XDG_CONFIG_HOME, XDG_STATE_HOME, XDG_DATA_HOME]
+                    .iter()
+                    .map(|key| (*key, std::env::var(key)))
+                    .collect(),
+            }
+        }
+
+        fn teardown(self) {
+            for (key, val) in self.orig_env {
+                if let Ok(ref val) = val {
+                    std::env::set_var(key, val);
+                } else {
+                    std::env::remove_var(key);
+                }
+            }
+        }
+    }
+
+    #[test_context(TContext)]
+    #[test]
+    #[serial_test::serial]
+    fn test_oxide_config_dir(_ctx: &mut TContext) {
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var(OXIDE_CONFIG_DIR, dir.path());
+        std::env::set_var(XDG_CONFIG_HOME, "/tmp/xdg-config");
+        std::env::set_var(XDG_STATE_HOME, "/tmp/xdg-state");
+        std::env::set_var(XDG_DATA_HOME, "/tmp/xdg-data");
+
+        // OXIDE_CONFIG_DIR wins over the XDG directories for everything but data.
+        let join = |f: &str| dir.path().join(f).to_str().unwrap().to_string();
+        assert_eq!(config_dir().unwrap(), dir.path().to_str().unwrap());
+        assert_eq!(config_file().unwrap(), join("config.toml"));
+        assert_eq!(hosts_file().unwrap(), join("hosts.toml"));
+        assert_eq!(state_file().unwrap(), join("state.toml"));
+        assert_eq!(cache_files().unwrap(), vec![join("state.toml")]);
+        assert_eq!(data_dir().unwrap(), "/tmp/xdg-data/oxide");
+
+        // The config and the hosts are read from there.
+        std::fs::write(join("config.toml"), "editor = \"vim\"\n").unwrap();
+        std::fs::write(join("hosts.toml"), "[\"oxide.computer\"]\ntoken = \"MY_TOKEN\"\n").unwrap();
+        let config = parse_default_config().unwrap();
+        assert_eq!(config.get("", "editor").unwrap(), "vim");
+        assert_eq!(config.get("oxide.computer", "token").unwrap(), "MY_TOKEN");
+    }
+
+    #[test_context(TContext)]
+    #[test]
+    #[serial_test::serial]
+    fn test_xdg_dirs(_ctx: &mut TContext) {
+        std::env::remove_var(OXIDE_CONFIG_DIR);
+        std::env::set_var(XDG_CONFIG_HOME, "/tmp/xdg-config");
+        std::env::set_var(XDG_STATE_HOME, "/tmp/xdg-state");
+
+        assert_eq!(config_file().unwrap(), "/tmp/xdg-config/oxide/config.toml");
+        assert_eq!(hosts_file().unwrap(), "/tmp/xdg-config/oxide/hosts.toml");
+        assert_eq!(state_file().unwrap(), "/tmp/xdg-state/oxide/state.toml");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_config_file_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let filename = dir.path().join("hosts.toml");
+        let filename = filename.to_str().unwrap();
+        let mode = |filename: &str| fs::metadata(filename).unwrap().permissions().mode() & 0o777;
+
+        // A new file is only readable by the user.
+        write_config_file(filename, "[\"oxide.computer\"]\ntoken = \"MY_TOKEN\"\n").unwrap();
+        assert_eq!(mode(filename), 0o600);
+
+        // And a file locked down by the user stays that way.
+        fs::set_permissions(filename, fs::Permissions::from_mode(0o600)).unwrap();
+        write_config_file(filename, "[\"oxide.computer\"]\ntoken = \"OTHER_TOKEN\"\n").unwrap();
+        assert_eq!(mode(filename), 0o600);
+        assert_eq!(
+            fs::read_to_string(filename).unwrap(),
+            "[\"oxide.computer\"]\ntoken = \"OTHER_TOKEN\"\n"
+        );
+
+        // No temporary files are left behind.
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
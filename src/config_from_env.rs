@@ -9,6 +9,11 @@ use crate::config_file::get_env_var;
 const OXIDE_HOST: &str = "OXIDE_HOST";
 const OXIDE_TOKEN: &str = "OXIDE_TOKEN";
 
+/// A config that takes values from the environment before the config it wraps.
+///
+/// OXIDE_HOST and OXIDE_TOKEN are enough to talk to the API, so wrapping a blank config,
+/// which is what `parse_default_config` returns when there are no config files, works
+/// without ever running `oxide auth login`.
 pub struct EnvConfig<'a> {
     pub config: &'a mut (dyn crate::config::Config + 'a),
 }
@@ -78,6 +83,10 @@ impl crate::config::Config for EnvConfig<'_> {
         }
     }
 
+    fn profile_host(&self, profile: &str) -> Result<String> {
+        self.config.profile_host(profile)
+    }
+
     fn aliases(&mut self) -> Result<crate::config_alias::AliasConfig> {
         self.config.aliases()
     }
@@ -114,3 +123,65 @@ impl crate::config::Config for EnvConfig<'_> {
         self.config.hosts_to_string()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use test_context::{test_context, TestContext};
+
+    use crate::config::Config;
+
+    struct TContext {
+        orig_env: Vec<(&'static str, Result<String, std::env::VarError>)>,
+    }
+
+    impl TestContext for TContext {
+        fn setup() -> TContext {
+            TContext {
+                orig_env: ["OXIDE_CONFIG_DIR", super::OXIDE_HOST, super::OXIDE_TOKEN]
+                    .iter()
+                    .map(|key| (*key, std::env::var(key)))
+                    .collect(),
+            }
+        }
+
+        fn teardown(self) {
+            for (key, val) in self.orig_env {
+                if let Ok(ref val) = val {
+                    std::env::set_var(key, val);
+                } else {
+                    std::env::remove_var(key);
+                }
+            }
+        }
+    }
+
+    #[test_context(TContext)]
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_env_only_config(_ctx: &mut TContext) {
+        // A config directory with nothing in it, like on a fresh CI box.
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("OXIDE_CONFIG_DIR", dir.path().join("missing"));
+        std::env::set_var(super::OXIDE_HOST, "api.oxide.computer");
+        std::env::set_var(super::OXIDE_TOKEN, "MY_TOKEN");
+
+        let host = crate::cmd_auth::parse_host("api.oxide.computer").unwrap().to_string();
+
+        let mut c = crate::config_file::parse_default_config().unwrap();
+        let mut config = super::EnvConfig::inherit_env(&mut c);
+        assert_eq!(
+            config.default_host_with_source().unwrap(),
+            (host.to_string(), "OXIDE_HOST".to_string())
+        );
+        assert_eq!(config.get(&host, "token").unwrap(), "MY_TOKEN");
+        assert_eq!(config.get("", "prompt").unwrap(), "enabled");
+
+        let ctx = crate::context::Context::new(&mut config);
+        assert_eq!(ctx.active_profile().unwrap(), host);
+        assert!(ctx.api_client("").is_ok());
+
+        // Nothing was written.
+        assert!(!dir.path().join("missing").exists());
+    }
+}
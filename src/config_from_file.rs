@@ -74,8 +74,16 @@ impl FileConfig {
     fn get_host_config(&self, hostname: &str) -> Result<HostConfig> {
         let host_configs = self.get_host_entries()?;
 
-        for host_config in host_configs {
+        for host_config in &host_configs {
             if host_config.host == *hostname {
+                return Ok(host_config.clone());
+            }
+        }
+
+        // Named profiles store the host they point to, so we can also find a profile
+        // by its host.
+        for host_config in host_configs {
+            if matches!(host_config.map.get_string_value("host"), Ok(host) if host == *hostname) {
                 return Ok(host_config);
             }
         }
@@ -97,6 +105,107 @@ impl FileConfig {
 
         Ok(host_config)
     }
+
+    /// Expand the alias in the arguments, if there is one, without expanding the result
+    /// again.
+    fn expand_alias_once(&mut self, args: Vec<String>) -> Result<(Vec<String>, bool)> {
+        let mut is_shell = false;
+
+        if args.len() < 2 {
+            // The command is lacking a subcommand.
+            return Ok((Vec::new(), is_shell));
+        }
+
+        let mut expanded = args.clone();
+
+        // Save the first argument and remove it at the same time.
+        // The first argument is the command name, we want to add it back at the end.
+        let first = expanded.remove(0);
+
+        // Get our aliases.
+        let aliases = crate::config::Config::aliases(self)?;
+
+        // Expand the alias.
+        let (expansion, ok) = aliases.get(expanded.first().unwrap());
+        if !ok {
+            // Return the original args.
+            return Ok((args, is_shell));
+        }
+
+        // Get the additional arguments.
+        let mut additional_args = args;
+
+        additional_args.remove(0); // Remove the first argument.
+        additional_args.remove(0); // Remove the second argument.
+
+        if expansion.starts_with('!') {
+            is_shell = true;
+
+            expanded = vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                expansion.trim_start_matches('!').to_string(),
+            ];
+
+            if !additional_args.is_empty() {
+                // Add the additional arguments.
+                expanded.push("--".to_string());
+                expanded.append(&mut additional_args);
+            }
+
+            return Ok((expanded, is_shell));
+        }
+
+        // Positional placeholders are $1 through $9, $* and $@ are replaced by all the
+        // arguments after the highest positional placeholder.
+        let placeholder = regex::Regex::new(r"\$([1-9]|\*|@)")?;
+
+        let mut highest = 0;
+        let mut has_catch_all = false;
+        for cap in placeholder.captures_iter(&expansion) {
+            match cap[1].parse::<usize>() {
+                Ok(n) => highest = highest.max(n),
+                Err(_) => has_catch_all = true,
+            }
+        }
+
+        let trailing_args = additional_args.get(highest..).unwrap_or_default().to_vec();
+
+        // Quote the arguments we substitute so they stay a single argument when we split
+        // the expansion.
+        let mut missing = false;
+        let expansion = placeholder.replace_all(&expansion, |cap: &regex::Captures| match cap[1].parse::<usize>() {
+            Ok(n) => match additional_args.get(n - 1) {
+                Some(arg) => shlex::quote(arg).to_string(),
+                None => {
+                    missing = true;
+                    cap[0].to_string()
+                }
+            },
+            Err(_) => trailing_args
+                .iter()
+                .map(|arg| shlex::quote(arg).to_string())
+                .collect::<Vec<String>>()
+                .join(" "),
+        });
+
+        if missing {
+            return Err(anyhow!("not enough arguments for alias: {}", expansion));
+        }
+
+        let mut new_args = vec![first];
+        match shlex::split(&expansion) {
+            Some(mut args) => new_args.append(&mut args),
+            None => return Err(anyhow!("invalid expansion for alias: {}", expansion)),
+        }
+
+        // If the arguments were not all used, append them to the expanded command.
+        if !has_catch_all {
+            new_args.extend(trailing_args);
+        }
+
+        Ok((new_args, is_shell))
+    }
 }
 
 impl crate::config::Config for FileConfig {
@@ -106,14 +215,16 @@ impl crate::config::Config for FileConfig {
     }
 
     fn get_with_source(&self, hostname: &str, key: &str) -> Result<(String, String)> {
+        // The source is only shown to people, so a missing config directory is not an
+        // error here, the config might come from the environment alone.
         if hostname.is_empty() {
-            let default_source = crate::config_file::config_file()?;
+            let default_source = crate::config_file::config_file().unwrap_or_default();
             let value = self.map.get_string_value(key)?;
 
             return Ok((value, default_source));
         }
 
-        let hosts_source = crate::config_file::hosts_file()?;
+        let hosts_source = crate::config_file::hosts_file().unwrap_or_default();
 
         let host_config = self.get_host_config(hostname)?;
 
@@ -140,7 +251,7 @@ impl crate::config::Config for FileConfig {
         // Get our hosts table.
         let mut hosts_table = self.get_hosts_table()?;
 
-        hosts_table.insert(hostname, toml_edit::Item::Table(host_config.map.root.clone()));
+        hosts_table.insert(&host_config.host, toml_edit::Item::Table(host_config.map.root.clone()));
 
         // Reset the hosts.
         self.map.root.insert("hosts", toml_edit::Item::Table(hosts_table));
@@ -153,10 +264,16 @@ impl crate::config::Config for FileConfig {
             return Ok(());
         }
 
+        // The host might be referenced by the profile pointing to it.
+        let hostname = match self.get_host_config(hostname) {
+            Ok(host_config) => host_config.host,
+            Err(_) => hostname.to_string(),
+        };
+
         let mut hosts_table = self.get_hosts_table()?;
 
         // Remove the host from the table.
-        hosts_table.remove_entry(hostname);
+        hosts_table.remove_entry(&hostname);
 
         // Reset the hosts.
         self.map.root.insert("hosts", toml_edit::Item::Table(hosts_table));
@@ -189,7 +306,7 @@ impl crate::config::Config for FileConfig {
             return Err(anyhow!("No hosts found. Try logging in with `oxide auth login`."));
         }
 
-        let hosts_source = crate::config_file::hosts_file()?;
+        let hosts_source = crate::config_file::hosts_file().unwrap_or_default();
 
         // Get the first host.
         if hosts.len() == 1 {
@@ -210,6 +327,17 @@ impl crate::config::Config for FileConfig {
         ))
     }
 
+    fn profile_host(&self, profile: &str) -> Result<String> {
+        match self.get_host_config(profile) {
+            Ok(host_config) => match host_config.map.get_string_value("host") {
+                Ok(host) => Ok(host),
+                Err(_) => Ok(host_config.host),
+            },
+            // This is likely a host that was only given through the environment.
+            Err(_) => Ok(profile.to_string()),
+        }
+    }
+
     fn aliases(&mut self) -> Result<crate::config_alias::AliasConfig> {
         let aliases_table = self.get_aliases_table()?;
 
@@ -229,72 +357,35 @@ impl crate::config::Config for FileConfig {
     }
 
     fn expand_alias(&mut self, args: Vec<String>) -> Result<(Vec<String>, bool)> {
-        let mut is_shell = false;
-
-        if args.len() < 2 {
-            // The command is lacking a subcommand.
-            return Ok((Vec::new(), is_shell));
-        }
-
-        let mut expanded = args.clone();
-
-        // Save the first argument and remove it at the same time.
-        // The first argument is the command name, we want to add it back at the end.
-        let first = expanded.remove(0);
-
-        // Get our aliases.
-        let aliases = self.aliases()?;
-
-        // Expand the alias.
-        let (mut expansion, ok) = aliases.get(expanded.first().unwrap());
-        if !ok {
-            // Return the original args.
-            return Ok((args, is_shell));
-        }
-
-        // Get the additional arguments.
-        let mut additional_args = args;
-
-        additional_args.remove(0); // Remove the first argument.
-        additional_args.remove(0); // Remove the second argument.
-
-        if expansion.starts_with('!') {
-            is_shell = true;
-
-            expanded = vec![
-                "sh".to_string(),
-                "-c".to_string(),
-                expansion.trim_start_matches('!').to_string(),
-            ];
+        // An alias can expand to another alias, expand until we get to a command, keeping
+        // the chain of aliases to report cycles.
+        let mut args = args;
+        let mut chain: Vec<String> = Vec::new();
+        loop {
+            let name = match args.get(1) {
+                Some(name) => name.to_string(),
+                None => return self.expand_alias_once(args),
+            };
 
-            if !additional_args.is_empty() {
-                // Add the additional arguments.
-                expanded.push("--".to_string());
-                expanded.append(&mut additional_args);
+            // An alias that expands to the command it is named after, like `version` to
+            // `version --format json`, runs the built-in.
+            if chain.last() == Some(&name) || !self.aliases()?.get(&name).1 {
+                return Ok((args, false));
             }
 
-            return Ok((expanded, is_shell));
-        }
+            if chain.contains(&name) {
+                chain.push(name);
+                return Err(anyhow!("alias cycle detected: {}", chain.join(" -> ")));
+            }
 
-        let mut extra_args: Vec<String> = vec![];
-        for (i, a) in additional_args.iter().enumerate() {
-            if !expansion.contains('$') {
-                extra_args.push(a.clone());
-            } else {
-                expansion = expansion.replace(&format!("${}", i + 1), a);
+            let (expanded, is_shell) = self.expand_alias_once(args)?;
+            if is_shell {
+                return Ok((expanded, is_shell));
             }
-        }
 
-        let lingering = regex::Regex::new(r"\$\d")?;
-        if lingering.is_match(&expansion) {
-            return Err(anyhow!("not enough arguments for alias: {}", expansion));
+            chain.push(name);
+            args = expanded;
         }
-
-        let mut new_args = vec![first];
-        new_args.append(&mut shlex::split(&expansion).unwrap());
-        new_args.append(&mut extra_args);
-
-        Ok((new_args, is_shell))
     }
 
     fn check_writable(&self, _hostname: &str, _key: &str) -> Result<()> {
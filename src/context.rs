@@ -8,6 +8,94 @@ pub struct Context<'a> {
     pub config: &'a mut (dyn Config + Send + Sync + 'a),
     pub io: crate::iostreams::IoStreams,
     pub debug: bool,
+    pub quiet: bool,
+    pub profile: Option<String>,
+    /// The API clients made so far, keyed by base URL and token, so the commands that
+    /// make several calls reuse the same connection pool.
+    pub api_clients: std::sync::Mutex<std::collections::HashMap<(String, String), oxide_api::Client>>,
+    /// How long to wait for each API call, from `--timeout` or `OXIDE_TIMEOUT`. No timeout
+    /// if it is not set.
+    pub timeout: Option<std::time::Duration>,
+    /// The headers from `--header`, sent with the raw API requests and the serial console.
+    pub headers: reqwest::header::HeaderMap,
+    /// Whether a request that carried `request_id` was sent, so the ID is only printed with
+    /// an error when the server may have logged it.
+    pub request_id_sent: std::sync::atomic::AtomicBool,
+    /// The URL from `--endpoint`, which the API clients use instead of the host of the
+    /// profile.
+    pub endpoint: Option<String>,
+}
+
+/// The header `request_id` is sent in.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// The ID of this run of oxide. It is sent with the raw API requests and the serial
+/// console, and printed with errors if one of those was sent, see
+/// `Context::request_id_sent`, so a problem can be matched with the logs of the server
+/// when it is reported. It is the same for every request of a run.
+pub fn request_id() -> &'static str {
+    static REQUEST_ID: once_cell::sync::Lazy<String> = once_cell::sync::Lazy::new(|| uuid::Uuid::new_v4().to_string());
+    &REQUEST_ID
+}
+
+/// An API call that took longer than `--timeout`.
+#[derive(Debug)]
+pub struct TimeoutError(pub std::time::Duration);
+
+impl std::fmt::Display for TimeoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "request timed out after {}s", self.0.as_secs())
+    }
+}
+
+impl std::error::Error for TimeoutError {}
+
+/// How many times an edit is retried when it conflicts with another change to the
+/// resource, see `is_conflict`.
+pub const CONFLICT_RETRIES: u32 = 3;
+
+/// Returns whether an API call failed with a 409 Conflict. The typed calls of `oxide_api`
+/// drop the status of the errors the API describes, so only the ones it could not parse
+/// are recognized, an `ObjectAlreadyExists` for a name that is taken is not one of them.
+pub fn is_conflict(err: &anyhow::Error) -> bool {
+    err.to_string()
+        .starts_with(&format!("code: {},", reqwest::StatusCode::CONFLICT))
+}
+
+/// Await a future, failing with a `TimeoutError` if there is a timeout and it is not done
+/// in time.
+async fn with_timeout<T>(
+    timeout: Option<std::time::Duration>,
+    call: impl std::future::Future<Output = Result<T>>,
+) -> Result<T> {
+    match timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, call).await {
+            Ok(result) => result,
+            Err(_) => Err(TimeoutError(timeout).into()),
+        },
+        None => call.await,
+    }
+}
+
+/// Turn a configured host into a URL. Hosts without a scheme use https, except for
+/// localhost, which uses http.
+fn base_url(host: &str) -> String {
+    if host.starts_with("http://") || host.starts_with("https://") {
+        host.to_string()
+    } else if host.starts_with("localhost") {
+        format!("http://{}", host)
+    } else {
+        format!("https://{}", host)
+    }
+}
+
+/// Parse the URL passed with `--endpoint`. Like the hosts in the config, it is https
+/// unless it says otherwise, or is on localhost.
+pub fn parse_endpoint(s: &str) -> Result<String> {
+    let url = base_url(s.trim());
+    reqwest::Url::parse(&url).map_err(|err| anyhow!("invalid endpoint `{}`: {}", s, err))?;
+
+    Ok(url.trim_end_matches('/').to_string())
 }
 
 impl Context<'_> {
@@ -32,6 +120,13 @@ impl Context<'_> {
             config,
             io,
             debug: false,
+            quiet: false,
+            profile: None,
+            api_clients: Default::default(),
+            timeout: None,
+            headers: Default::default(),
+            request_id_sent: Default::default(),
+            endpoint: None,
         }
     }
 
@@ -39,29 +134,229 @@ impl Context<'_> {
     /// user.
     pub fn api_client(&self, hostname: &str) -> Result<oxide_api::Client> {
         // Use the host passed in if it's set.
-        // Otherwise, use the default host.
-        let host = if hostname.is_empty() {
-            self.config.default_host()?
-        } else {
+        // Otherwise, use the active profile or the default host.
+        let profile = if !hostname.is_empty() {
             hostname.to_string()
+        } else if self.endpoint.is_some() {
+            // There does not have to be a profile to use `--endpoint`, OXIDE_TOKEN works.
+            self.active_profile().unwrap_or_default()
+        } else {
+            self.active_profile()?
+        };
+
+        // Get the host the profile points to, unless `--endpoint` overrides it.
+        let baseurl = match &self.endpoint {
+            Some(endpoint) => endpoint.to_string(),
+            None => base_url(&self.config.profile_host(&profile)?),
+        };
+
+        // Get the token for that profile.
+        let token = match self.config.get(&profile, "token") {
+            Ok(token) => token,
+            // A mock server at `--endpoint` might not check it.
+            Err(_) if self.endpoint.is_some() => String::new(),
+            Err(err) => return Err(err),
         };
 
-        // Change the baseURL to the one we want.
-        let mut baseurl = host.to_string();
-        if !host.starts_with("http://") && !host.starts_with("https://") {
-            baseurl = format!("https://{}", host);
-            if host.starts_with("localhost") {
-                baseurl = format!("http://{}", host)
+        // Reuse the client if we already made one for this host and token. The token is
+        // part of the key since `auth login` changes it halfway through.
+        let mut clients = self
+            .api_clients
+            .lock()
+            .map_err(|_| anyhow!("the API client cache is poisoned"))?;
+        let client = clients
+            .entry((baseurl.to_string(), token.to_string()))
+            .or_insert_with(|| oxide_api::Client::new(&token, &baseurl));
+
+        Ok(client.clone())
+    }
+
+    /// Await an API call. It fails with a `TimeoutError` if it takes longer than
+    /// `--timeout`. With `-v` or `--debug`, its method, path, outcome, and how long it
+    /// took are logged. A `get_all` is a single call here, across all of its pages.
+    ///
+    /// `oxide_api` builds its own HTTP client and has no hook for its requests, so every
+    /// typed API call, generated or written by hand, has to go through here.
+    ///
+    /// The returned future does not borrow the context, so it can be awaited while the
+    /// context is borrowed elsewhere.
+    pub fn timed<'a, T, E, F>(
+        &self,
+        method: &'a str,
+        path: &'a str,
+        call: F,
+    ) -> impl std::future::Future<Output = Result<T>> + 'a
+    where
+        F: std::future::Future<Output = Result<T, E>> + 'a,
+        E: Into<anyhow::Error>,
+    {
+        let timeout = self.timeout;
+        async move {
+            let start = std::time::Instant::now();
+            let result = with_timeout(timeout, async { call.await.map_err(Into::into) }).await;
+
+            let elapsed = start.elapsed().as_millis();
+            match &result {
+                Ok(_) => log::info!("{} {} succeeded in {}ms", method, path, elapsed),
+                Err(err) => log::info!("{} {} failed in {}ms: {}", method, path, elapsed, err),
             }
+
+            result
+        }
+    }
+
+    /// Send a raw API request, like `timed`, but with the full URL of the request and the
+    /// HTTP status of the response in the log.
+    pub fn timed_raw<'a>(
+        &self,
+        method: &'a str,
+        path: &'a str,
+        req: reqwest::RequestBuilder,
+    ) -> impl std::future::Future<Output = Result<reqwest::Response>> + 'a {
+        let timeout = self.timeout;
+        // The requests of the typed calls are built by `oxide_api`, which has no way to add
+        // a header to them, so only the raw ones carry the ID and the `--header`s.
+        let req = req
+            .header(REQUEST_ID_HEADER, request_id())
+            .headers(self.headers.clone());
+        self.request_id_sent.store(true, std::sync::atomic::Ordering::Relaxed);
+        // A request with a streamed body can't be cloned, log the path for those.
+        let url = req
+            .try_clone()
+            .and_then(|req| req.build().ok())
+            .map(|req| req.url().to_string())
+            .unwrap_or_else(|| path.to_string());
+        async move {
+            let start = std::time::Instant::now();
+            let result = with_timeout(timeout, async { Ok(req.send().await?) }).await;
+
+            let elapsed = start.elapsed().as_millis();
+            match &result {
+                Ok(resp) => log::info!("{} {} returned {} in {}ms", method, url, resp.status(), elapsed),
+                Err(err) => log::info!("{} {} failed in {}ms: {}", method, url, elapsed, err),
+            }
+
+            result
+        }
+    }
+
+    /// Returns the profile in use, either the one passed with `--profile` or the
+    /// default host.
+    pub fn active_profile(&self) -> Result<String> {
+        if let Some(profile) = &self.profile {
+            self.check_profile(profile)?;
+            return Ok(profile.to_string());
+        }
+
+        self.config.default_host()
+    }
+
+    /// Make sure a profile passed with `--profile` or `OXIDE_PROFILE` exists, so a typo
+    /// fails with the profiles to pick from rather than an authentication error.
+    /// This is not done before dispatch, since `oxide auth login --profile` creates it.
+    fn check_profile(&self, profile: &str) -> Result<()> {
+        let profiles = self.config.hosts()?;
+        if profiles.iter().any(|p| p == profile) {
+            return Ok(());
+        }
+
+        // A host given only through OXIDE_HOST is not in the config.
+        if let Ok((host, source)) = self.config.default_host_with_source() {
+            if host == profile && source == "OXIDE_HOST" {
+                return Ok(());
+            }
+        }
+
+        if profiles.is_empty() {
+            return Err(anyhow!(
+                "profile `{}` not found, no profiles are configured, create one with `oxide auth login --profile {}`",
+                profile,
+                profile
+            ));
+        }
+
+        Err(anyhow!(
+            "profile `{}` not found, options are: {}",
+            profile,
+            profiles.join(", ")
+        ))
+    }
+
+    /// Resolve the organization to use for a command.
+    ///
+    /// Organization precedence:
+    /// 1. --organization
+    /// 2. OXIDE_ORG
+    /// 3. default_org for the active profile from config
+    ///
+    /// An empty string is returned if none of them are set, so the caller can prompt.
+    pub fn resolve_organization(&self, organization: &str) -> Result<String> {
+        self.resolve_default("organization", organization, "default_org")
+    }
+
+    /// Resolve the project to use for a command.
+    ///
+    /// Project precedence:
+    /// 1. --project
+    /// 2. OXIDE_PROJECT
+    /// 3. default_project for the active profile from config
+    ///
+    /// An empty string is returned if none of them are set, so the caller can prompt.
+    pub fn resolve_project(&self, project: &str) -> Result<String> {
+        self.resolve_default("project", project, "default_project")
+    }
+
+    /// Returns the name of an organization passed as an ID or name. The API paths take
+    /// names, so an ID is looked up, while a name is returned as is without an API call.
+    pub async fn organization_name(&self, client: &oxide_api::Client, organization: &str) -> Result<String> {
+        if uuid::Uuid::parse_str(organization).is_err() {
+            return Ok(organization.to_string());
+        }
+
+        let org = self
+            .timed(
+                "GET",
+                "/by-id/organizations/{id}",
+                client.organizations().view_by_id(organization),
+            )
+            .await?;
+
+        Ok(org.name.to_string())
+    }
+
+    /// Returns the name of a project passed as an ID or name, like `organization_name`.
+    pub async fn project_name(&self, client: &oxide_api::Client, project: &str) -> Result<String> {
+        if uuid::Uuid::parse_str(project).is_err() {
+            return Ok(project.to_string());
         }
 
-        // Get the token for that host.
-        let token = self.config.get(&host, "token")?;
+        let project = self
+            .timed("GET", "/by-id/projects/{id}", client.projects().view_by_id(project))
+            .await?;
 
-        // Create the client.
-        let client = oxide_api::Client::new(&token, &baseurl);
+        Ok(project.name.to_string())
+    }
 
-        Ok(client)
+    fn resolve_default(&self, name: &str, value: &str, key: &str) -> Result<String> {
+        // The flag and the environment variable are already merged by clap.
+        if !value.is_empty() {
+            log::debug!("using {} `{}` from flag or environment", name, value);
+            return Ok(value.to_string());
+        }
+
+        // We might not be logged in yet, in which case there is no default.
+        let default = match self.active_profile() {
+            Ok(profile) => self.config.get(&profile, key).unwrap_or_default(),
+            Err(_) => String::new(),
+        };
+
+        if default.is_empty() {
+            log::debug!("no {} given and no {} set in config", name, key);
+        } else {
+            log::debug!("using {} `{}` from {} in config", name, default, key);
+        }
+
+        Ok(default)
     }
 
     /// This function opens a browser that is based on the configured
@@ -101,14 +396,30 @@ impl Context<'_> {
         Ok(())
     }
 
+    /// Returns the URL of the Console for the active profile. The Console is served from
+    /// the same host as the API.
+    pub fn console_url(&self) -> Result<String> {
+        let host = self.config.profile_host(&self.active_profile()?)?;
+        Ok(base_url(&host).trim_end_matches('/').to_string())
+    }
+
     /// Return the configured output format or override the default with the value passed in,
-    /// if it is some.
-    pub fn format(&self, format: &Option<FormatOutput>) -> Result<FormatOutput> {
-        if let Some(format) = format {
-            Ok(format.clone())
+    /// if it is some. Passing a template implies the `template` format.
+    pub fn format(&self, format: &Option<FormatOutput>, template: &Option<String>) -> Result<FormatOutput> {
+        let format = if let Some(format) = format {
+            format.clone()
+        } else if template.is_some() {
+            FormatOutput::Template(String::new())
         } else {
             let value = self.config.get("", "format")?;
-            Ok(FormatOutput::from_str(&value).unwrap_or_default())
+            FormatOutput::from_str(&value).unwrap_or_default()
+        };
+
+        match (format, template) {
+            (FormatOutput::Template(_), Some(template)) => Ok(FormatOutput::Template(template.to_string())),
+            (FormatOutput::Template(_), None) => Err(anyhow!("--template is required with --format template")),
+            (_, Some(_)) => Err(anyhow!("--template can only be used with --format template")),
+            (format, None) => Ok(format),
         }
     }
 }
@@ -204,4 +515,203 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_resolve_defaults() {
+        let mut c = crate::config::new_blank_config().unwrap();
+        c.set("oxide.computer", "token", "MY_TOKEN").unwrap();
+        c.set("oxide.computer", "default_org", "maze-war").unwrap();
+        c.set("staging", "host", "rack2.oxide.computer").unwrap();
+        c.set("staging", "default_project", "development").unwrap();
+
+        let mut ctx = Context::new(&mut c);
+        ctx.profile = Some("oxide.computer".to_string());
+
+        // Explicit values win over the config defaults.
+        assert_eq!(ctx.resolve_organization("other").unwrap(), "other");
+        assert_eq!(ctx.resolve_organization("").unwrap(), "maze-war");
+        assert_eq!(ctx.resolve_project("").unwrap(), "");
+
+        ctx.profile = Some("staging".to_string());
+        assert_eq!(ctx.resolve_organization("").unwrap(), "");
+        assert_eq!(ctx.resolve_project("").unwrap(), "development");
+    }
+
+    #[test]
+    fn test_api_client_cache() {
+        let mut c = crate::config::new_blank_config().unwrap();
+        c.set("oxide.computer", "token", "MY_TOKEN").unwrap();
+        c.set("staging", "host", "rack2.oxide.computer").unwrap();
+        c.set("staging", "token", "MY_OTHER_TOKEN").unwrap();
+
+        let ctx = Context::new(&mut c);
+        ctx.api_client("oxide.computer").unwrap();
+        ctx.api_client("oxide.computer").unwrap();
+        assert_eq!(ctx.api_clients.lock().unwrap().len(), 1);
+
+        // Another host gets its own client.
+        ctx.api_client("staging").unwrap();
+        assert_eq!(ctx.api_clients.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_request_id() {
+        // The same for the whole run.
+        assert_eq!(request_id(), request_id());
+        assert!(uuid::Uuid::parse_str(request_id()).is_ok());
+    }
+
+    #[test]
+    fn test_parse_endpoint() {
+        assert_eq!(parse_endpoint("localhost:12220").unwrap(), "http://localhost:12220");
+        assert_eq!(parse_endpoint("rack.example.com").unwrap(), "https://rack.example.com");
+        assert_eq!(
+            parse_endpoint("http://preview.example.com/").unwrap(),
+            "http://preview.example.com"
+        );
+        assert!(parse_endpoint("http://").is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_api_client_endpoint() {
+        let mut c = crate::config::new_blank_config().unwrap();
+        let mut ctx = Context::new(&mut c);
+
+        // Without a profile there is no host to talk to, unless `--endpoint` is passed.
+        assert!(ctx.api_client("").is_err());
+
+        ctx.endpoint = Some("http://localhost:12220".to_string());
+        assert!(ctx.api_client("").is_ok());
+    }
+
+    #[test]
+    fn test_console_url() {
+        let mut c = crate::config::new_blank_config().unwrap();
+        c.set("oxide.computer", "token", "MY_TOKEN").unwrap();
+        c.set("local", "host", "localhost:12220").unwrap();
+        c.set("local", "token", "MY_OTHER_TOKEN").unwrap();
+
+        let mut ctx = Context::new(&mut c);
+        ctx.profile = Some("oxide.computer".to_string());
+        assert_eq!(ctx.console_url().unwrap(), "https://oxide.computer");
+
+        ctx.profile = Some("local".to_string());
+        assert_eq!(ctx.console_url().unwrap(), "http://localhost:12220");
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_timed() {
+        let mut c = crate::config::new_blank_config().unwrap();
+        let mut ctx = Context::new(&mut c);
+
+        // The outcome of the call is passed through as is.
+        let ok = ctx
+            .timed("GET", "/organizations", async { Ok::<_, anyhow::Error>(1) })
+            .await;
+        assert_eq!(ok.unwrap(), 1);
+
+        let err = ctx
+            .timed("GET", "/organizations", async { Err::<u32, _>(anyhow!("nope")) })
+            .await;
+        assert_eq!(err.unwrap_err().to_string(), "nope");
+
+        // A call that takes longer than the timeout fails.
+        ctx.timeout = Some(std::time::Duration::from_secs(1));
+        let slow = async {
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            Ok::<_, anyhow::Error>(1)
+        };
+        let err = ctx.timed("GET", "/organizations", slow).await.unwrap_err();
+        assert!(err.downcast_ref::<TimeoutError>().is_some());
+        assert_eq!(err.to_string(), "request timed out after 1s");
+    }
+
+    #[test]
+    fn test_is_conflict() {
+        // The errors `oxide_api` makes of the responses it could not parse.
+        assert!(is_conflict(&anyhow!("code: 409 Conflict, empty response")));
+        assert!(is_conflict(&anyhow!("code: 409 Conflict, error: \"try again\"")));
+        assert!(!is_conflict(&anyhow!("code: 400 Bad Request, empty response")));
+
+        // A name that is taken is not going to be free when the edit is retried.
+        assert!(!is_conflict(
+            &oxide_api::types::Error::ObjectAlreadyExists {
+                message: "already exists: vpc \"default\"".to_string(),
+            }
+            .into()
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_name_or_id() {
+        let mut c = crate::config::new_blank_config().unwrap();
+        c.set("localhost:1", "token", "MY_TOKEN").unwrap();
+
+        let ctx = Context::new(&mut c);
+        let client = ctx.api_client("localhost:1").unwrap();
+
+        // Names are used as is, there is nothing listening to look them up.
+        assert_eq!(ctx.organization_name(&client, "maze-war").await.unwrap(), "maze-war");
+        assert_eq!(ctx.project_name(&client, "development").await.unwrap(), "development");
+        assert_eq!(ctx.project_name(&client, "").await.unwrap(), "");
+
+        // IDs are looked up, which fails here.
+        assert!(ctx
+            .organization_name(&client, "2e0b8b4a-7fb5-4b4e-9f0a-4d3b5f6f2c1a")
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_active_profile() {
+        let mut c = crate::config::new_blank_config().unwrap();
+        c.set("oxide.computer", "token", "MY_TOKEN").unwrap();
+        c.set("staging", "host", "rack2.oxide.computer").unwrap();
+        c.set("staging", "token", "MY_OTHER_TOKEN").unwrap();
+
+        let mut ctx = Context::new(&mut c);
+        ctx.profile = Some("staging".to_string());
+        assert_eq!(ctx.active_profile().unwrap(), "staging");
+
+        ctx.profile = Some("prod".to_string());
+        let err = ctx.active_profile().unwrap_err().to_string();
+        assert!(err.starts_with("profile `prod` not found, options are: "), "{}", err);
+        assert!(err.contains("oxide.computer") && err.contains("staging"), "{}", err);
+
+        // Without a profile that exists, there are no defaults to resolve.
+        assert_eq!(ctx.resolve_organization("").unwrap(), "");
+    }
+
+    #[test]
+    fn test_format() {
+        let mut c = crate::config::new_blank_config().unwrap();
+        c.set("", "format", "json").unwrap();
+        let ctx = Context::new(&mut c);
+
+        let template = Some("{{.name}}".to_string());
+        assert_eq!(ctx.format(&None, &None).unwrap(), FormatOutput::Json);
+        assert_eq!(
+            ctx.format(&Some(FormatOutput::Yaml), &None).unwrap(),
+            FormatOutput::Yaml
+        );
+        assert_eq!(
+            ctx.format(&None, &template).unwrap(),
+            FormatOutput::Template("{{.name}}".to_string())
+        );
+        assert_eq!(
+            ctx.format(&Some(FormatOutput::Template(String::new())), &template)
+                .unwrap(),
+            FormatOutput::Template("{{.name}}".to_string())
+        );
+        assert_eq!(
+            ctx.format(&Some(FormatOutput::Template(String::new())), &None)
+                .unwrap_err()
+                .to_string(),
+            "--template is required with --format template"
+        );
+        assert_eq!(
+            ctx.format(&Some(FormatOutput::Csv), &template).unwrap_err().to_string(),
+            "--template can only be used with --format template"
+        );
+    }
 }
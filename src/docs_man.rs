@@ -53,6 +53,11 @@ impl Man {
             )
         }
 
+        let env = environment(app);
+        if !env.is_empty() {
+            page = page.section("Environment", &env);
+        }
+
         if app.get_after_long_help().is_some() || app.get_after_help().is_some() {
             page = page.section("Extra", &after_help(app))
         }
@@ -119,14 +124,55 @@ fn about(app: &clap::Command, title: &str) -> String {
 
 fn description(app: &clap::Command) -> Vec<String> {
     match app.get_long_about().or_else(|| app.get_about()) {
-        Some(about) => about
-            .lines()
+        Some(about) => split_environment(about)
+            .0
+            .iter()
+            .flat_map(|p| p.lines())
             .filter_map(|l| (!l.trim().is_empty()).then(|| paragraph(l.trim())))
             .collect(),
         None => Vec::new(),
     }
 }
 
+/// The environment variables documented in the long about, like the ones of the root
+/// command, get their own section.
+fn environment(app: &clap::Command) -> Vec<String> {
+    match app.get_long_about() {
+        Some(about) => split_environment(about)
+            .1
+            .iter()
+            .map(|(name, help)| list(&[bold(name)], &[help.as_str(), "\n"]))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Split a long about into the paragraphs of the description and the environment
+/// variables it documents. Environment variables are paragraphs of the form
+/// `NAME, OTHER_NAME (note): help`.
+fn split_environment(about: &str) -> (Vec<String>, Vec<(String, String)>) {
+    let re = regex::Regex::new(r"^([A-Z][A-Z0-9_]*(?:, [A-Z][A-Z0-9_]*)*(?: \([^)]*\))?): ").unwrap();
+
+    let mut description = Vec::new();
+    let mut env = Vec::new();
+    for p in about.split("\n\n") {
+        let p = p.trim();
+        match re.captures(p) {
+            Some(caps) => {
+                let help = p[caps[0].len()..]
+                    .lines()
+                    .map(|l| l.trim())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                env.push((caps[1].to_string(), help));
+            }
+            None => description.push(p.to_string()),
+        }
+    }
+
+    (description, env)
+}
+
 fn synopsis(app: &clap::Command, title: &str) -> String {
     let mut res = String::new();
 
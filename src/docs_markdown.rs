@@ -35,6 +35,51 @@ impl MarkdownDocument<'_> {
         self.0.push(pulldown_cmark::Event::End(link));
         self.0.push(pulldown_cmark::Event::End(pulldown_cmark::Tag::Item));
     }
+
+    fn anchor(&mut self, id: String) {
+        self.0
+            .push(pulldown_cmark::Event::Html(format!("<a id=\"{}\"></a>\n\n", id).into()));
+    }
+
+    fn code_block(&mut self, text: String) {
+        let block = pulldown_cmark::Tag::CodeBlock(pulldown_cmark::CodeBlockKind::Fenced("".into()));
+
+        self.0.push(pulldown_cmark::Event::Start(block.clone()));
+        self.0.push(pulldown_cmark::Event::Text(text.into()));
+        self.0.push(pulldown_cmark::Event::End(block));
+    }
+
+    fn table(&mut self, head: Vec<String>, rows: Vec<Vec<pulldown_cmark::Event<'static>>>) {
+        let alignment = vec![pulldown_cmark::Alignment::None; head.len()];
+
+        self.0.push(pulldown_cmark::Event::Start(pulldown_cmark::Tag::Table(
+            alignment.clone(),
+        )));
+
+        self.0
+            .push(pulldown_cmark::Event::Start(pulldown_cmark::Tag::TableHead));
+        for cell in head {
+            self.0
+                .push(pulldown_cmark::Event::Start(pulldown_cmark::Tag::TableCell));
+            self.0.push(pulldown_cmark::Event::Text(cell.into()));
+            self.0.push(pulldown_cmark::Event::End(pulldown_cmark::Tag::TableCell));
+        }
+        self.0.push(pulldown_cmark::Event::End(pulldown_cmark::Tag::TableHead));
+
+        for row in rows {
+            self.0.push(pulldown_cmark::Event::Start(pulldown_cmark::Tag::TableRow));
+            for cell in row {
+                self.0
+                    .push(pulldown_cmark::Event::Start(pulldown_cmark::Tag::TableCell));
+                self.0.push(cell);
+                self.0.push(pulldown_cmark::Event::End(pulldown_cmark::Tag::TableCell));
+            }
+            self.0.push(pulldown_cmark::Event::End(pulldown_cmark::Tag::TableRow));
+        }
+
+        self.0
+            .push(pulldown_cmark::Event::End(pulldown_cmark::Tag::Table(alignment)));
+    }
 }
 
 fn do_markdown(doc: &mut MarkdownDocument, app: &Command, title: &str) {
@@ -51,7 +96,7 @@ fn do_markdown(doc: &mut MarkdownDocument, app: &Command, title: &str) {
         doc.0
             .push(pulldown_cmark::Event::Start(pulldown_cmark::Tag::List(None)));
 
-        for cmd in app.get_subcommands() {
+        for cmd in app.get_subcommands().filter(|s| !s.is_hide_set()) {
             doc.link_in_list(
                 format!("{} {}", title, cmd.get_name()),
                 format!("./{}_{}", title.replace(' ', "_"), cmd.get_name()),
@@ -158,3 +203,106 @@ pub fn app_to_markdown(app: &Command, title: &str) -> Result<String> {
 
     Ok(result)
 }
+
+/// The anchor of a command in the reference, for example `oxide-instance-create`.
+fn reference_anchor(title: &str) -> String {
+    title.replace(' ', "-")
+}
+
+fn do_markdown_reference(
+    doc: &mut MarkdownDocument,
+    app: &Command,
+    title: &str,
+    level: pulldown_cmark::HeadingLevel,
+    section_level: pulldown_cmark::HeadingLevel,
+) {
+    doc.anchor(reference_anchor(title));
+    doc.header(title.to_string(), level);
+
+    if let Some(about) = app.get_about() {
+        doc.paragraph(about.to_string());
+    }
+
+    // The long about is everything after the first line of the doc comment. Indented
+    // paragraphs are the examples, those become code blocks.
+    if let Some(about) = app.get_long_about() {
+        let about = about
+            .trim_start_matches(app.get_about().unwrap_or_default())
+            .trim_start_matches('.');
+
+        for p in about.split("\n\n").filter(|p| !p.trim().is_empty()) {
+            let p = p.trim_matches('\n');
+            if p.lines().all(|l| l.starts_with("    ")) {
+                let code = p.lines().map(|l| &l[4..]).collect::<Vec<_>>().join("\n");
+                doc.code_block(format!("{}\n", code));
+            } else {
+                doc.paragraph(p.trim().to_string());
+            }
+        }
+    }
+
+    let args = app
+        .get_arguments()
+        .filter(|arg| !arg.is_hide_set() && (arg.get_short().is_some() || arg.get_long().is_some()))
+        .collect::<Vec<&clap::Arg>>();
+    if !args.is_empty() {
+        doc.header("Options".to_string(), section_level);
+
+        let rows = args
+            .iter()
+            .map(|arg| {
+                let flag = match (arg.get_short(), arg.get_long()) {
+                    (Some(short), Some(long)) => format!("-{}/--{}", short, long),
+                    (Some(short), None) => format!("-{}", short),
+                    (None, Some(long)) => format!("--{}", long),
+                    (None, None) => unreachable!(),
+                };
+
+                vec![
+                    pulldown_cmark::Event::Code(flag.into()),
+                    pulldown_cmark::Event::Text(arg.get_help().unwrap_or_default().to_string().into()),
+                ]
+            })
+            .collect();
+
+        doc.table(vec!["Flag".to_string(), "Description".to_string()], rows);
+    }
+
+    let subcommands = app.get_subcommands().filter(|s| !s.is_hide_set()).collect::<Vec<_>>();
+    if !subcommands.is_empty() {
+        doc.header("Subcommands".to_string(), section_level);
+
+        doc.0
+            .push(pulldown_cmark::Event::Start(pulldown_cmark::Tag::List(None)));
+
+        for cmd in &subcommands {
+            let sub = format!("{} {}", title, cmd.get_name());
+            doc.link_in_list(sub.to_string(), format!("#{}", reference_anchor(&sub)));
+        }
+
+        doc.0.push(pulldown_cmark::Event::End(pulldown_cmark::Tag::List(None)));
+    }
+
+    for cmd in subcommands {
+        do_markdown_reference(doc, cmd, &format!("{} {}", title, cmd.get_name()), level, section_level);
+    }
+}
+
+/// Convert a clap Command and all of its subcommands to a single markdown document, with
+/// an anchored heading per command. Commands get headings of `base_level`, and the
+/// sections of a command the level below that.
+pub fn app_to_markdown_reference(app: &Command, base_level: usize) -> Result<String> {
+    let level = pulldown_cmark::HeadingLevel::try_from(base_level)
+        .map_err(|_| anyhow::anyhow!("invalid heading level {}", base_level))?;
+    let section_level = pulldown_cmark::HeadingLevel::try_from(base_level + 1)
+        .map_err(|_| anyhow::anyhow!("invalid heading level {}", base_level + 1))?;
+
+    let mut document = MarkdownDocument(Vec::new());
+
+    do_markdown_reference(&mut document, app, app.get_name(), level, section_level);
+
+    let mut result = String::new();
+    cmark(document.0.iter(), &mut result)?;
+
+    Ok(result)
+}
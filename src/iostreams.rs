@@ -2,6 +2,7 @@ use anyhow::{anyhow, Result};
 use terminal_size::{terminal_size, Height, Width};
 
 const DEFAULT_WIDTH: i32 = 80;
+const DEFAULT_PAGER: &str = "less -FRX";
 
 pub struct IoStreams {
     pub stdin: Box<dyn std::io::Read + Send + Sync>,
@@ -14,6 +15,7 @@ pub struct IoStreams {
     terminal_theme: String,
 
     progress_indicator_enabled: bool,
+    progress_format: crate::types::ProgressFormat,
 
     stdin_tty_override: bool,
     stdin_is_tty: bool,
@@ -27,6 +29,8 @@ pub struct IoStreams {
 
     never_prompt: bool,
 
+    pager: String,
+
     pub tmp_file_override: Option<std::fs::File>,
 }
 
@@ -81,6 +85,16 @@ impl IoStreams {
         self.color_enabled = color_enabled;
     }
 
+    /// Apply `--color`. With `auto` the detection from the environment, the terminal,
+    /// and `OXIDE_FORCE_TTY` is kept.
+    pub fn set_color_mode(&mut self, mode: crate::colors::ColorMode) {
+        match mode {
+            crate::colors::ColorMode::Auto => {}
+            crate::colors::ColorMode::Always => self.color_enabled = true,
+            crate::colors::ColorMode::Never => self.color_enabled = false,
+        }
+    }
+
     #[allow(dead_code)]
     pub fn set_stdin_tty(&mut self, is_tty: bool) {
         self.stdin_tty_override = true;
@@ -150,15 +164,146 @@ impl IoStreams {
         self.never_prompt = never_prompt;
     }
 
+    /// Set the command long output is piped through. An empty command or `cat` disables
+    /// the pager.
+    pub fn set_pager(&mut self, pager: &str) {
+        self.pager = pager.to_string();
+    }
+
+    /// Whether output that does not fit on the screen should go through the pager. We
+    /// only page when we know the height of the terminal, so output that is redirected
+    /// with `OXIDE_FORCE_TTY` set is written as is.
+    fn pager_height(&self) -> Option<usize> {
+        if self.pager.is_empty() || self.pager == "cat" || !self.is_stdout_tty() {
+            return None;
+        }
+
+        match (self.tty_size)() {
+            Ok((_, h)) if h > 0 => Some(h as usize),
+            _ => None,
+        }
+    }
+
+    /// Run the write and send what it wrote through the pager if it is taller than the
+    /// terminal.
+    fn with_pager(&mut self, write: impl FnOnce(&mut Self) -> Result<()>) -> Result<()> {
+        let height = match self.pager_height() {
+            Some(height) => height,
+            None => return write(self),
+        };
+
+        // Render the output first, so we know if it fits on the screen.
+        let buffer = OutputBuffer::default();
+        let out = std::mem::replace(&mut self.out, Box::new(buffer.clone()));
+        let result = write(self);
+        self.out = out;
+        result?;
+
+        let output = buffer.0.lock().unwrap().clone();
+        self.write_paged(&output, height)
+    }
+
+    /// Write the output through the pager if it is taller than the terminal, otherwise
+    /// write it directly.
+    fn write_paged(&mut self, output: &[u8], height: usize) -> Result<()> {
+        let lines = output.iter().filter(|b| **b == b'\n').count();
+        if lines < height {
+            self.out.write_all(output)?;
+            return Ok(());
+        }
+
+        let args = match shlex::split(&self.pager) {
+            Some(args) if !args.is_empty() => args,
+            _ => return Err(anyhow!("invalid pager command: {}", self.pager)),
+        };
+
+        let mut child = std::process::Command::new(&args[0])
+            .args(&args[1..])
+            .stdin(std::process::Stdio::piped())
+            .spawn()
+            .map_err(|err| anyhow!("failed to start pager `{}`: {}", self.pager, err))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            // The pager goes away without reading everything when the user quits early.
+            if let Err(err) = std::io::Write::write_all(&mut stdin, output) {
+                if err.kind() != std::io::ErrorKind::BrokenPipe {
+                    return Err(err.into());
+                }
+            }
+        }
+
+        child.wait()?;
+
+        Ok(())
+    }
+
+    /// Run the write with its output going to the file at `path` instead of standard output.
+    /// The output is written to a temporary file next to it first and renamed into place,
+    /// so a failed write never leaves a partial file behind. An empty path writes to
+    /// standard output as usual.
+    pub fn with_output_file(&mut self, path: &str, write: impl FnOnce(&mut Self) -> Result<()>) -> Result<()> {
+        if path.is_empty() {
+            return write(self);
+        }
+
+        // A file is not a terminal, so render the output like stdout was redirected, without
+        // colors or a pager.
+        let buffer = OutputBuffer::default();
+        let out = std::mem::replace(&mut self.out, Box::new(buffer.clone()));
+        let pager = std::mem::take(&mut self.pager);
+        let color_enabled = std::mem::replace(&mut self.color_enabled, false);
+        let (tty_override, is_tty) = (self.stdout_tty_override, self.stdout_is_tty);
+        self.set_stdout_tty(false);
+
+        let result = write(self);
+
+        self.out = out;
+        self.pager = pager;
+        self.color_enabled = color_enabled;
+        self.stdout_tty_override = tty_override;
+        self.stdout_is_tty = is_tty;
+        result?;
+
+        let path = std::path::Path::new(path);
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => std::path::Path::new("."),
+        };
+
+        let mut file = tempfile::NamedTempFile::new_in(dir)
+            .map_err(|err| anyhow!("creating a temporary file in {} failed: {}", dir.display(), err))?;
+        std::io::Write::write_all(&mut file, &buffer.0.lock().unwrap())?;
+        file.persist(path)
+            .map_err(|err| anyhow!("writing {} failed: {}", path.display(), err.error))?;
+
+        Ok(())
+    }
+
     #[allow(dead_code)]
     /// This returns a handle to a spinner. To stop the spinner, call `.stop()` on it.
     pub fn start_process_indicator(&mut self) -> Option<terminal_spinners::SpinnerHandle> {
         self.start_process_indicator_with_label("")
     }
 
+    /// Report progress as JSON lines on stderr instead of with a spinner.
+    pub fn set_progress_format(&mut self, format: crate::types::ProgressFormat) {
+        self.progress_format = format;
+    }
+
+    /// Turn the spinner off when the output is in a format for programs, since the spinner
+    /// would end up in the middle of it.
+    pub fn suppress_progress_indicator_for(&mut self, format: Option<&crate::types::FormatOutput>) {
+        if matches!(format, Some(format) if *format != crate::types::FormatOutput::Table) {
+            self.progress_indicator_enabled = false;
+        }
+    }
+
     /// This returns a handle to a spinner. To stop the spinner, call `.stop()` on it.
+    ///
+    /// There is no spinner when stdout or stderr is not a terminal, or when progress is
+    /// reported as JSON, see `progress_event`.
     pub fn start_process_indicator_with_label(&mut self, label: &str) -> Option<terminal_spinners::SpinnerHandle> {
-        if !self.progress_indicator_enabled {
+        if !self.progress_indicator_enabled || self.progress_format == crate::types::ProgressFormat::Json {
             return None;
         }
 
@@ -169,6 +314,24 @@ impl IoStreams {
         Some(pi.start())
     }
 
+    /// With `--progress json`, write an event of a long operation to stderr as a line of
+    /// JSON, like `{"event":"waiting","state":"starting"}`. The fields are merged into the
+    /// object. Otherwise this does nothing, people get the spinner.
+    pub fn progress_event(&mut self, event: &str, fields: serde_json::Value) -> Result<()> {
+        if self.progress_format != crate::types::ProgressFormat::Json {
+            return Ok(());
+        }
+
+        let mut line = serde_json::Map::new();
+        line.insert("event".to_string(), serde_json::Value::String(event.to_string()));
+        if let serde_json::Value::Object(fields) = fields {
+            line.extend(fields);
+        }
+
+        writeln!(self.err_out, "{}", serde_json::Value::Object(line))?;
+        Ok(())
+    }
+
     #[allow(dead_code)]
     pub fn terminal_width(&self) -> i32 {
         if self.terminal_width_override > 0 {
@@ -210,11 +373,25 @@ impl IoStreams {
         &mut self,
         format: &crate::types::FormatOutput,
         value: impl IntoIterator<Item = T> + serde::Serialize,
+    ) -> Result<()> {
+        self.with_pager(|io| io.write_output_for_vec_unpaged(format, value))
+    }
+
+    fn write_output_for_vec_unpaged<T: serde::Serialize + tabled::Tabled>(
+        &mut self,
+        format: &crate::types::FormatOutput,
+        value: impl IntoIterator<Item = T> + serde::Serialize,
     ) -> Result<()> {
         match format {
             crate::types::FormatOutput::Json => self.write_output_json(&serde_json::to_value(value)?),
+            crate::types::FormatOutput::Jsonl => self.write_output_jsonl(&serde_json::to_value(value)?),
             crate::types::FormatOutput::Table => self.write_output_table_for_vec(value),
             crate::types::FormatOutput::Yaml => self.write_output_yaml(&value),
+            crate::types::FormatOutput::Csv => self.write_output_csv_for_vec(&serde_json::to_value(value)?, b','),
+            crate::types::FormatOutput::Tsv => self.write_output_csv_for_vec(&serde_json::to_value(value)?, b'\t'),
+            crate::types::FormatOutput::Template(template) => {
+                self.write_output_template_for_vec(template, &serde_json::to_value(value)?)
+            }
         }
     }
 
@@ -225,15 +402,95 @@ impl IoStreams {
     ) -> Result<()> {
         match format {
             crate::types::FormatOutput::Json => self.write_output_json(&serde_json::to_value(value)?),
+            crate::types::FormatOutput::Jsonl => self.write_output_jsonl(&serde_json::to_value(value)?),
             crate::types::FormatOutput::Table => self.write_output_table(value),
             crate::types::FormatOutput::Yaml => self.write_output_yaml(value),
+            crate::types::FormatOutput::Csv => self.write_output_csv(&serde_json::to_value(value)?, b','),
+            crate::types::FormatOutput::Tsv => self.write_output_csv(&serde_json::to_value(value)?, b'\t'),
+            crate::types::FormatOutput::Template(template) => {
+                self.write_output_template(template, &serde_json::to_value(value)?)
+            }
+        }
+    }
+
+    /// Write a list of items like `write_output_for_vec`, but only with the given columns, in
+    /// the order they were given. An empty list of columns means all of them. With
+    /// `no_header`, table, csv, and tsv output is written without its header row.
+    pub fn write_output_for_vec_with_columns<T: serde::Serialize + tabled::Tabled>(
+        &mut self,
+        format: &crate::types::FormatOutput,
+        columns: &[String],
+        no_header: bool,
+        value: impl IntoIterator<Item = T> + serde::Serialize,
+    ) -> Result<()> {
+        if columns.is_empty() && !(no_header && has_header(format)) {
+            return self.write_output_for_vec(format, value);
         }
+
+        let (columns, rows) = select_columns(&serde_json::to_value(value)?, columns)?;
+        self.with_pager(|io| match format {
+            crate::types::FormatOutput::Json => {
+                io.write_output_json(&serde_json::Value::Array(select_objects(&columns, &rows)))
+            }
+            crate::types::FormatOutput::Jsonl => {
+                io.write_output_jsonl(&serde_json::Value::Array(select_objects(&columns, &rows)))
+            }
+            crate::types::FormatOutput::Table => io.write_table(&columns, &rows, !no_header),
+            crate::types::FormatOutput::Yaml => io.write_output_yaml(&select_objects(&columns, &rows)),
+            crate::types::FormatOutput::Csv => io.write_records(&columns, &rows, !no_header, b','),
+            crate::types::FormatOutput::Tsv => io.write_records(&columns, &rows, !no_header, b'\t'),
+            crate::types::FormatOutput::Template(_) => Err(anyhow!("--columns cannot be used with --format template")),
+        })
     }
 
+    /// Write a single item like `write_output`, but only with the given columns, in the order
+    /// they were given. An empty list of columns means all of them.
+    pub fn write_output_with_columns<T: serde::Serialize + tabled::Tabled>(
+        &mut self,
+        format: &crate::types::FormatOutput,
+        columns: &[String],
+        no_header: bool,
+        value: &T,
+    ) -> Result<()> {
+        if columns.is_empty() && !(no_header && has_header(format)) {
+            return self.write_output(format, value);
+        }
+
+        let (columns, rows) = select_columns(&serde_json::to_value(value)?, columns)?;
+        let row = rows.into_iter().next().unwrap_or_default();
+        let object = select_objects(&columns, &[row.clone()]).remove(0);
+
+        // A single item is written as key/value pairs.
+        let fields: Vec<Vec<serde_json::Value>> = columns
+            .iter()
+            .zip(row)
+            .map(|(column, value)| vec![serde_json::Value::String(column.to_string()), value])
+            .collect();
+        let key_value = vec!["key".to_string(), "value".to_string()];
+
+        match format {
+            crate::types::FormatOutput::Json => self.write_output_json(&object),
+            crate::types::FormatOutput::Jsonl => self.write_output_jsonl(&object),
+            // Like the full table, the key/value table never has a header.
+            crate::types::FormatOutput::Table => self.write_table(&key_value, &fields, false),
+            crate::types::FormatOutput::Yaml => self.write_output_yaml(&object),
+            crate::types::FormatOutput::Csv => self.write_records(&key_value, &fields, !no_header, b','),
+            crate::types::FormatOutput::Tsv => self.write_records(&key_value, &fields, !no_header, b'\t'),
+            crate::types::FormatOutput::Template(_) => Err(anyhow!("--columns cannot be used with --format template")),
+        }
+    }
+
+    /// Write pretty-printed JSON, with colors when they are enabled. That follows the
+    /// terminal and `--color`, so piped output and `--output-file` stay plain JSON.
     pub fn write_output_json(&mut self, json: &serde_json::Value) -> Result<()> {
         if self.color_enabled() {
-            // Print the response body.
-            writeln!(self.out, "{}", colored_json::to_colored_json_auto(json)?)?;
+            // Print the response body. Whether to use colors was decided already, so
+            // don't check for a terminal again.
+            writeln!(
+                self.out,
+                "{}",
+                colored_json::to_colored_json(json, colored_json::ColorMode::On)?
+            )?;
         } else {
             // Print the response body.
             writeln!(self.out, "{}", serde_json::to_string_pretty(json)?)?;
@@ -242,6 +499,23 @@ impl IoStreams {
         Ok(())
     }
 
+    /// Write a list of items as one compact JSON object per line, or a single item on its own
+    /// line. Each line is flushed right away, so the output can be read as it comes.
+    pub fn write_output_jsonl(&mut self, json: &serde_json::Value) -> Result<()> {
+        match json {
+            serde_json::Value::Array(items) => {
+                for item in items {
+                    writeln!(self.out, "{}", serde_json::to_string(item)?)?;
+                }
+            }
+            _ => writeln!(self.out, "{}", serde_json::to_string(json)?)?,
+        }
+
+        self.out.flush()?;
+
+        Ok(())
+    }
+
     pub fn write_output_yaml<Y: serde::Serialize>(&mut self, yaml: &Y) -> Result<()> {
         // Print the response body.
         writeln!(self.out, "{}", serde_yaml::to_string(yaml)?)?;
@@ -249,30 +523,138 @@ impl IoStreams {
         Ok(())
     }
 
-    pub fn write_output_table_for_vec<T: tabled::Tabled>(&mut self, value: impl IntoIterator<Item = T>) -> Result<()> {
-        let table = tabled::Table::new(value).with(tabled::Style::psql()).to_string();
+    /// Write a list of items as delimited records, with a header row. The top-level fields
+    /// of the items become the columns, nested values are written as JSON.
+    pub fn write_output_csv_for_vec(&mut self, value: &serde_json::Value, delimiter: u8) -> Result<()> {
+        let (columns, rows) = select_columns(value, &[])?;
+        self.write_records(&columns, &rows, true, delimiter)
+    }
 
-        writeln!(self.out, "{}", table)?;
+    /// Write a single item as delimited key/value records, with a header row.
+    pub fn write_output_csv(&mut self, value: &serde_json::Value, delimiter: u8) -> Result<()> {
+        let mut wtr = csv::WriterBuilder::new().delimiter(delimiter).from_writer(vec![]);
+        wtr.write_record(&["key", "value"])?;
+
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, value) in map {
+                    wtr.write_record(&[key.to_string(), csv_field(value)])?;
+                }
+            }
+            _ => wtr.write_record(&["value".to_string(), csv_field(value)])?,
+        }
+
+        self.out.write_all(&wtr.into_inner()?)?;
 
         Ok(())
     }
 
-    pub fn write_output_table<T: tabled::Tabled>(&mut self, value: &T) -> Result<()> {
-        let table = tabled::Table::new(vec![value])
-            .with(tabled::Rotate::Left)
-            .with(
-                tabled::Modify::new(tabled::Full)
-                    .with(tabled::Alignment::left())
-                    .with(tabled::Alignment::top()),
-            )
-            .with(tabled::Style::psql().header_off())
-            .to_string();
+    /// Write rows as delimited records, optionally with a header row.
+    fn write_records(
+        &mut self,
+        columns: &[String],
+        rows: &[Vec<serde_json::Value>],
+        header: bool,
+        delimiter: u8,
+    ) -> Result<()> {
+        let mut wtr = csv::WriterBuilder::new().delimiter(delimiter).from_writer(vec![]);
+        if header && !columns.is_empty() {
+            wtr.write_record(columns)?;
+        }
+
+        for row in rows {
+            wtr.write_record(row.iter().map(csv_field))?;
+        }
+
+        self.out.write_all(&wtr.into_inner()?)?;
+
+        Ok(())
+    }
+
+    /// Write rows as a psql style table, optionally with a header row.
+    fn write_table(&mut self, columns: &[String], rows: &[Vec<serde_json::Value>], header: bool) -> Result<()> {
+        let mut builder = tabled::builder::Builder::default();
+        if header {
+            builder = builder.set_header(columns);
+        }
+        for row in rows {
+            builder = builder.add_row(row.iter().map(table_field));
+        }
+
+        let table = builder.build().with(
+            tabled::Modify::new(tabled::Full)
+                .with(tabled::Alignment::left())
+                .with(tabled::Alignment::top()),
+        );
+        let table = if header {
+            table.with(tabled::Style::psql())
+        } else {
+            table.with(tabled::Style::psql().header_off())
+        };
 
         writeln!(self.out, "{}", table)?;
 
         Ok(())
     }
 
+    /// Write each item of a list rendered through the template, one per line.
+    pub fn write_output_template_for_vec(&mut self, template: &str, value: &serde_json::Value) -> Result<()> {
+        let items = match value {
+            serde_json::Value::Array(items) => items.as_slice(),
+            _ => std::slice::from_ref(value),
+        };
+
+        // Render everything first, so a bad template does not leave partial output behind.
+        let registry = template_registry(template)?;
+        let mut output = String::new();
+        for item in items {
+            output.push_str(&registry.render(TEMPLATE_NAME, item)?);
+            output.push('\n');
+        }
+
+        self.out.write_all(output.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Write a single item rendered through the template.
+    pub fn write_output_template(&mut self, template: &str, value: &serde_json::Value) -> Result<()> {
+        let registry = template_registry(template)?;
+        let output = registry.render(TEMPLATE_NAME, value)?;
+
+        writeln!(self.out, "{}", output)?;
+
+        Ok(())
+    }
+
+    /// Write a list of items as a table with a column per top-level field. Every field goes
+    /// through `table_field`, so columns line up no matter the type of the field.
+    pub fn write_output_table_for_vec<T: serde::Serialize + tabled::Tabled>(
+        &mut self,
+        value: impl IntoIterator<Item = T>,
+    ) -> Result<()> {
+        let value = serde_json::to_value(value.into_iter().collect::<Vec<T>>())?;
+        let (columns, rows) = select_columns(&value, &table_columns::<T>(&value)?)?;
+
+        self.write_table(&columns, &rows, true)
+    }
+
+    /// Write a single item as a table of key/value pairs, formatted like
+    /// `write_output_table_for_vec`.
+    pub fn write_output_table<T: serde::Serialize + tabled::Tabled>(&mut self, value: &T) -> Result<()> {
+        let value = serde_json::to_value(value)?;
+        let (columns, rows) = select_columns(&value, &table_columns::<T>(&value)?)?;
+        let row = rows.into_iter().next().unwrap_or_default();
+
+        let fields: Vec<Vec<serde_json::Value>> = columns
+            .into_iter()
+            .zip(row)
+            .map(|(column, value)| vec![serde_json::Value::String(column), value])
+            .collect();
+
+        self.write_table(&["key".to_string(), "value".to_string()], &fields, false)
+    }
+
     pub fn system() -> Self {
         let stdout_is_tty = atty::is(atty::Stream::Stdout);
         let stderr_is_tty = atty::is(atty::Stream::Stderr);
@@ -296,6 +678,10 @@ impl IoStreams {
             let enabled = colored_json::enable_ansi_support();
         }
 
+        let pager = std::env::var("OXIDE_PAGER")
+            .or_else(|_| std::env::var("PAGER"))
+            .unwrap_or_else(|_| DEFAULT_PAGER.to_string());
+
         let mut io = IoStreams {
             stdin: Box::new(std::io::stdin()),
             out: Box::new(std::io::stdout()),
@@ -307,6 +693,7 @@ impl IoStreams {
             terminal_theme: "".to_string(),
 
             progress_indicator_enabled: false,
+            progress_format: crate::types::ProgressFormat::Human,
 
             stdin_tty_override: false,
             stdin_is_tty: atty::is(atty::Stream::Stdin),
@@ -320,6 +707,7 @@ impl IoStreams {
             tty_size,
 
             never_prompt: false,
+            pager,
             tmp_file_override: None,
         };
 
@@ -354,6 +742,294 @@ impl IoStreams {
     }
 }
 
+/// A writer that keeps everything in memory, so we can measure output before paging it.
+#[derive(Clone, Default)]
+struct OutputBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl std::io::Write for OutputBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Whether the format writes a header row that `--no-header` can turn off.
+fn has_header(format: &crate::types::FormatOutput) -> bool {
+    matches!(
+        format,
+        crate::types::FormatOutput::Table | crate::types::FormatOutput::Csv | crate::types::FormatOutput::Tsv
+    )
+}
+
+/// Split a list of items into columns and rows. With no columns given, every top-level field
+/// is a column, in the order they are first seen. Items that are not objects go into a
+/// single `value` column.
+fn select_columns(value: &serde_json::Value, columns: &[String]) -> Result<(Vec<String>, Vec<Vec<serde_json::Value>>)> {
+    let items = match value {
+        serde_json::Value::Array(items) => items.as_slice(),
+        _ => std::slice::from_ref(value),
+    };
+
+    // Get the columns in the order we first see them.
+    let mut available: Vec<String> = Vec::new();
+    for item in items {
+        match item {
+            serde_json::Value::Object(map) => {
+                for key in map.keys() {
+                    if !available.contains(key) {
+                        available.push(key.to_string());
+                    }
+                }
+            }
+            _ => {
+                if !available.iter().any(|c| c == "value") {
+                    available.push("value".to_string());
+                }
+            }
+        }
+    }
+
+    let columns = if columns.is_empty() {
+        available
+    } else {
+        // We can only tell which columns exist if there are items.
+        if !items.is_empty() {
+            if let Some(column) = columns.iter().find(|c| !available.contains(c)) {
+                return Err(anyhow!(
+                    "unknown column `{}`, available columns are: {}",
+                    column,
+                    available.join(", ")
+                ));
+            }
+        }
+
+        columns.to_vec()
+    };
+
+    let rows = items
+        .iter()
+        .map(|item| {
+            columns
+                .iter()
+                .map(|column| match item {
+                    serde_json::Value::Object(map) => map.get(column).cloned().unwrap_or_default(),
+                    _ => item.clone(),
+                })
+                .collect()
+        })
+        .collect();
+
+    Ok((columns, rows))
+}
+
+/// Keep only the given top-level fields of a list of items, like `--columns` does for the
+/// other formats. An empty list of columns keeps all of them.
+pub fn select_fields(items: Vec<serde_json::Value>, columns: &[String]) -> Result<Vec<serde_json::Value>> {
+    if columns.is_empty() {
+        return Ok(items);
+    }
+
+    let (columns, rows) = select_columns(&serde_json::Value::Array(items), columns)?;
+    Ok(select_objects(&columns, &rows))
+}
+
+/// Keep the items of a list that match all the filters. A filter is `key=value` for the items
+/// with the top-level field `key` equal to `value`, or `key~value` for the ones with the field
+/// containing it. Fields are compared as they are written in csv output.
+pub fn filter_by_fields<T: serde::Serialize>(items: Vec<T>, filters: &[String]) -> Result<Vec<T>> {
+    if filters.is_empty() {
+        return Ok(items);
+    }
+
+    let filters = filters.iter().map(|f| Filter::parse(f)).collect::<Result<Vec<_>>>()?;
+    let keys: Vec<String> = filters.iter().map(|f| f.key.to_string()).collect();
+    let (_, rows) = select_columns(&serde_json::to_value(&items)?, &keys)?;
+
+    Ok(rows
+        .into_iter()
+        .zip(items)
+        .filter(|(row, _)| filters.iter().zip(row).all(|(filter, value)| filter.matches(value)))
+        .map(|(_, item)| item)
+        .collect())
+}
+
+/// A filter passed to `filter_by_fields`.
+struct Filter<'a> {
+    key: &'a str,
+    value: &'a str,
+    contains: bool,
+}
+
+impl<'a> Filter<'a> {
+    fn parse(filter: &'a str) -> Result<Self> {
+        match filter.find(|c| c == '=' || c == '~') {
+            Some(i) if i > 0 => Ok(Filter {
+                key: &filter[..i],
+                value: &filter[i + 1..],
+                contains: filter[i..].starts_with('~'),
+            }),
+            _ => Err(anyhow!("invalid filter `{}`, expected key=value or key~value", filter)),
+        }
+    }
+
+    fn matches(&self, value: &serde_json::Value) -> bool {
+        let value = csv_field(value);
+        if self.contains {
+            value.contains(self.value)
+        } else {
+            value == self.value
+        }
+    }
+}
+
+/// Sort a list of items by one of their top-level fields, as they are serialized, and reverse
+/// the result if asked to. Numbers compare as numbers, as do the runs of digits in strings,
+/// so `disk2` comes before `disk10`. With no column, the items keep their order.
+pub fn sort_by_column<T: serde::Serialize>(items: Vec<T>, column: &str, reverse: bool) -> Result<Vec<T>> {
+    let mut items = if column.is_empty() {
+        items
+    } else {
+        let (_, rows) = select_columns(&serde_json::to_value(&items)?, &[column.to_string()])?;
+        let mut keyed: Vec<(serde_json::Value, T)> = rows.into_iter().map(|mut row| row.remove(0)).zip(items).collect();
+        keyed.sort_by(|(a, _), (b, _)| compare_fields(a, b));
+        keyed.into_iter().map(|(_, item)| item).collect()
+    };
+
+    if reverse {
+        items.reverse();
+    }
+
+    Ok(items)
+}
+
+/// Compare two fields for sorting. Numbers compare as numbers, anything else by how it is
+/// written in a table, with the digits in it compared as numbers.
+fn compare_fields(a: &serde_json::Value, b: &serde_json::Value) -> std::cmp::Ordering {
+    if let (Some(a), Some(b)) = (a.as_f64(), b.as_f64()) {
+        return a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal);
+    }
+
+    natural_cmp(&table_field(a), &table_field(b))
+}
+
+/// Compare two strings, with runs of digits compared by their value rather than character by
+/// character.
+fn natural_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return std::cmp::Ordering::Equal,
+            (None, Some(_)) => return std::cmp::Ordering::Less,
+            (Some(_), None) => return std::cmp::Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x = take_digits(&mut a);
+                let y = take_digits(&mut b);
+                let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                // Without leading zeros, the longer number is the bigger one.
+                let ordering = x.len().cmp(&y.len()).then_with(|| x.cmp(y));
+                if ordering != std::cmp::Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+        digits.push(c);
+    }
+    digits
+}
+
+/// The columns of a table of items of type `T`. JSON objects do not keep the order of their
+/// fields, so the columns are put in the order of the fields of the type, with any the type
+/// does not know about after them. Without items, the columns are those of the type.
+fn table_columns<T: tabled::Tabled>(value: &serde_json::Value) -> Result<Vec<String>> {
+    let (available, _) = select_columns(value, &[])?;
+    if available.is_empty() {
+        return Ok(T::headers());
+    }
+
+    let mut columns: Vec<String> = T::headers().into_iter().filter(|h| available.contains(h)).collect();
+    for column in available {
+        if !columns.contains(&column) {
+            columns.push(column);
+        }
+    }
+
+    Ok(columns)
+}
+
+/// Turn rows back into objects with only the selected columns.
+fn select_objects(columns: &[String], rows: &[Vec<serde_json::Value>]) -> Vec<serde_json::Value> {
+    rows.iter()
+        .map(|row| {
+            serde_json::Value::Object(
+                columns
+                    .iter()
+                    .cloned()
+                    .zip(row.iter().cloned())
+                    .collect::<serde_json::Map<String, serde_json::Value>>(),
+            )
+        })
+        .collect()
+}
+
+/// Format a JSON value as a single delimited field.
+fn csv_field(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => "".to_string(),
+        serde_json::Value::String(s) => s.to_string(),
+        // Numbers and booleans are written as is, nested values are stringified as JSON.
+        _ => value.to_string(),
+    }
+}
+
+/// Format a JSON value as a table cell. Like `csv_field`, but booleans are written as
+/// `yes` or `no`.
+fn table_field(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Bool(true) => "yes".to_string(),
+        serde_json::Value::Bool(false) => "no".to_string(),
+        _ => csv_field(value),
+    }
+}
+
+const TEMPLATE_NAME: &str = "output";
+
+/// Compile an output template. Go style field references like `{{.name}}` and `{{.}}`
+/// are accepted and rewritten to their handlebars equivalents.
+fn template_registry(template: &str) -> Result<handlebars::Handlebars<'static>> {
+    let dot = regex::Regex::new(r"\{\{(\s*)\.(\s*)\}\}")?;
+    let field = regex::Regex::new(r"\{\{(\s*)\.([A-Za-z_])")?;
+    let template = dot.replace_all(template, "{{${1}this${2}}}");
+    let template = field.replace_all(&template, "{{${1}${2}");
+
+    let mut registry = handlebars::Handlebars::new();
+    // We are not writing HTML.
+    registry.register_escape_fn(handlebars::no_escape);
+    registry
+        .register_template_string(TEMPLATE_NAME, template.as_ref())
+        .map_err(|err| anyhow!("invalid template: {}", err))?;
+
+    Ok(registry)
+}
+
 #[cfg(test)]
 fn test_tty_size() -> Result<(i32, i32)> {
     Err(anyhow::anyhow!("tty_size not implemented in tests"))
@@ -442,4 +1118,433 @@ mod test {
             assert_eq!(width, t.want_width, "test {}", t.name);
         }
     }
+
+    #[test]
+    fn test_set_color_mode() {
+        let (mut io, _, _) = IoStreams::test();
+        io.set_color_enabled(false);
+
+        io.set_color_mode(crate::colors::ColorMode::Auto);
+        assert!(!io.color_enabled());
+
+        io.set_color_mode(crate::colors::ColorMode::Always);
+        assert!(io.color_enabled());
+
+        io.set_color_mode(crate::colors::ColorMode::Auto);
+        assert!(io.color_enabled());
+
+        io.set_color_mode(crate::colors::ColorMode::Never);
+        assert!(!io.color_enabled());
+
+        // `--color always` also wins over OXIDE_FORCE_TTY, which follows the environment.
+        io.force_terminal("72");
+        io.set_color_mode(crate::colors::ColorMode::Always);
+        assert!(io.color_enabled());
+        assert!(io.is_stdout_tty());
+    }
+
+    #[test]
+    fn test_write_output_json_color() {
+        let json = serde_json::json!({"name": "db", "ncpus": 2});
+
+        let (mut io, stdout_path, _) = IoStreams::test();
+        io.set_color_enabled(true);
+        io.write_output_json(&json).unwrap();
+        let stdout = std::fs::read_to_string(&stdout_path).unwrap();
+        assert!(stdout.contains('\u{1b}'), "expected colors in {:?}", stdout);
+
+        let (mut io, stdout_path, _) = IoStreams::test();
+        io.set_color_enabled(false);
+        io.write_output_json(&json).unwrap();
+        let stdout = std::fs::read_to_string(&stdout_path).unwrap();
+        assert!(!stdout.contains('\u{1b}'));
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&stdout).unwrap(), json);
+    }
+
+    #[test]
+    fn test_write_output_csv() {
+        let items = serde_json::json!([
+            {"id": "1", "name": "db, primary", "ncpus": 2, "tags": {"env": "prod"}},
+            {"id": "2", "name": "web", "ncpus": 4, "tags": null},
+        ]);
+
+        let (mut io, stdout_path, _) = IoStreams::test();
+        io.write_output_csv_for_vec(&items, b',').unwrap();
+        io.write_output_csv_for_vec(&items, b'\t').unwrap();
+        io.write_output_csv(&items[1], b',').unwrap();
+
+        let stdout = std::fs::read_to_string(stdout_path).unwrap();
+        assert_eq!(
+            stdout,
+            r#"id,name,ncpus,tags
+1,"db, primary",2,"{""env"":""prod""}"
+2,web,4,
+id	name	ncpus	tags
+1	db, primary	2	"{""env"":""prod""}"
+2	web	4	
+key,value
+id,2
+name,web
+ncpus,4
+tags,
+"#
+        );
+    }
+
+    #[test]
+    fn test_write_output_jsonl() {
+        let items = serde_json::json!([
+            {"id": "1", "name": "db"},
+            {"id": "2", "name": "web"},
+        ]);
+
+        let (mut io, stdout_path, _) = IoStreams::test();
+        io.write_output_jsonl(&items).unwrap();
+        io.write_output_jsonl(&items[1]).unwrap();
+
+        let stdout = std::fs::read_to_string(stdout_path).unwrap();
+        assert_eq!(
+            stdout,
+            r#"{"id":"1","name":"db"}
+{"id":"2","name":"web"}
+{"id":"2","name":"web"}
+"#
+        );
+    }
+
+    #[test]
+    fn test_progress_event() {
+        let (mut io, _, stderr_path) = IoStreams::test();
+        io.progress_event("waiting", serde_json::json!({"state": "starting"}))
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&stderr_path).unwrap(), "");
+
+        io.set_progress_format(crate::types::ProgressFormat::Json);
+        assert!(io.start_process_indicator_with_label(" Waiting").is_none());
+        io.progress_event("waiting", serde_json::json!({"state": "starting"}))
+            .unwrap();
+        io.progress_event("done", serde_json::json!({})).unwrap();
+
+        let stderr = std::fs::read_to_string(&stderr_path).unwrap();
+        assert_eq!(
+            stderr,
+            r#"{"event":"waiting","state":"starting"}
+{"event":"done"}
+"#
+        );
+    }
+
+    #[test]
+    fn test_write_output_template() {
+        let items = serde_json::json!([
+            {"id": "1", "name": "db <primary>", "ncpus": 2},
+            {"id": "2", "name": "web", "ncpus": 4},
+        ]);
+
+        let (mut io, stdout_path, _) = IoStreams::test();
+        io.write_output_template_for_vec("{{.name}} {{ .id }}", &items).unwrap();
+        io.write_output_template("{{name}}: {{.ncpus}}", &items[1]).unwrap();
+        io.write_output_template("{{.}}", &items[0]["id"]).unwrap();
+
+        let err = io.write_output_template_for_vec("{{.name", &items).unwrap_err();
+        assert!(err.to_string().starts_with("invalid template"), "got {}", err);
+
+        let stdout = std::fs::read_to_string(stdout_path).unwrap();
+        assert_eq!(
+            stdout,
+            r#"db <primary> 1
+web 2
+web: 4
+1
+"#
+        );
+    }
+
+    #[derive(serde::Serialize, tabled::Tabled)]
+    struct TestRow {
+        id: String,
+        name: String,
+        ncpus: u16,
+    }
+
+    #[test]
+    fn test_write_output_with_columns() {
+        let items = vec![
+            TestRow {
+                id: "1".to_string(),
+                name: "db".to_string(),
+                ncpus: 2,
+            },
+            TestRow {
+                id: "2".to_string(),
+                name: "web".to_string(),
+                ncpus: 4,
+            },
+        ];
+        let columns = vec!["name".to_string(), "id".to_string()];
+        let csv = crate::types::FormatOutput::Csv;
+
+        let (mut io, stdout_path, _) = IoStreams::test();
+        io.write_output_for_vec_with_columns(&csv, &columns, false, &items)
+            .unwrap();
+        io.write_output_for_vec_with_columns(&crate::types::FormatOutput::Tsv, &columns, true, &items)
+            .unwrap();
+        io.write_output_for_vec_with_columns(&csv, &[], true, &items).unwrap();
+        io.write_output_with_columns(&csv, &columns, false, &items[1]).unwrap();
+
+        let err = io
+            .write_output_for_vec_with_columns(&csv, &["memory".to_string()], false, &items)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown column `memory`, available columns are: id, name, ncpus"
+        );
+
+        let stdout = std::fs::read_to_string(stdout_path).unwrap();
+        assert_eq!(
+            stdout,
+            r#"name,id
+db,1
+web,2
+db	1
+web	2
+1,db,2
+2,web,4
+key,value
+name,web
+id,2
+"#
+        );
+    }
+
+    #[derive(serde::Serialize)]
+    struct TestInstance {
+        name: String,
+        running: bool,
+        hostname: Option<String>,
+        disk: serde_json::Value,
+    }
+
+    // Optional and nested fields are not `Display`, so this cannot be derived.
+    impl tabled::Tabled for TestInstance {
+        fn fields(&self) -> Vec<String> {
+            vec![
+                self.name.to_string(),
+                self.running.to_string(),
+                self.hostname.clone().unwrap_or_default(),
+                self.disk.to_string(),
+            ]
+        }
+
+        fn headers() -> Vec<String> {
+            vec![
+                "name".to_string(),
+                "running".to_string(),
+                "hostname".to_string(),
+                "disk".to_string(),
+            ]
+        }
+    }
+
+    /// Split a table into its cells, leaving out the line under the header.
+    fn table_cells(table: &str) -> Vec<Vec<String>> {
+        table
+            .lines()
+            .filter(|line| !line.contains("-+-"))
+            .map(|line| line.split('|').map(|cell| cell.trim().to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_write_output_table() {
+        let items = vec![
+            TestInstance {
+                name: "db".to_string(),
+                running: true,
+                hostname: None,
+                disk: serde_json::json!({"size": 1}),
+            },
+            TestInstance {
+                name: "webserver".to_string(),
+                running: false,
+                hostname: Some("web".to_string()),
+                disk: serde_json::Value::Null,
+            },
+        ];
+
+        let (mut io, stdout_path, _) = IoStreams::test();
+        io.write_output_table_for_vec(&items).unwrap();
+        let stdout = std::fs::read_to_string(&stdout_path).unwrap();
+        assert_eq!(
+            table_cells(&stdout),
+            vec![
+                vec!["name", "running", "hostname", "disk"],
+                vec!["db", "yes", "", r#"{"size":1}"#],
+                vec!["webserver", "no", "web", ""],
+            ]
+        );
+        // Every line has its separators in the same place.
+        let lines: Vec<&str> = stdout.lines().filter(|line| line.contains('|')).collect();
+        assert!(lines.iter().all(|line| line.find('|') == lines[0].find('|')));
+
+        let (mut io, stdout_path, _) = IoStreams::test();
+        io.write_output_table(&items[0]).unwrap();
+        let stdout = std::fs::read_to_string(&stdout_path).unwrap();
+        assert_eq!(
+            table_cells(&stdout),
+            vec![
+                vec!["name", "db"],
+                vec!["running", "yes"],
+                vec!["hostname", ""],
+                vec!["disk", r#"{"size":1}"#],
+            ]
+        );
+
+        let (mut io, stdout_path, _) = IoStreams::test();
+        io.write_output_table_for_vec(Vec::<TestInstance>::new()).unwrap();
+        let stdout = std::fs::read_to_string(&stdout_path).unwrap();
+        assert_eq!(table_cells(&stdout), vec![vec!["name", "running", "hostname", "disk"]]);
+    }
+
+    #[test]
+    fn test_filter_by_fields() {
+        let items = vec![
+            serde_json::json!({"name": "db", "run_state": "running", "ncpus": 2, "hostname": null}),
+            serde_json::json!({"name": "web", "run_state": "stopped", "ncpus": 2, "hostname": "web"}),
+            serde_json::json!({"name": "webdb", "run_state": "running", "ncpus": 4, "hostname": "webdb"}),
+        ];
+        let names = |items: Vec<serde_json::Value>| -> Vec<String> {
+            items
+                .iter()
+                .map(|item| item["name"].as_str().unwrap().to_string())
+                .collect()
+        };
+        let filter = |filters: &[&str]| {
+            filter_by_fields(
+                items.clone(),
+                &filters.iter().map(|f| f.to_string()).collect::<Vec<_>>(),
+            )
+        };
+
+        assert_eq!(names(filter(&["run_state=running"]).unwrap()), vec!["db", "webdb"]);
+        assert_eq!(names(filter(&["name~web", "ncpus=2"]).unwrap()), vec!["web"]);
+        assert_eq!(names(filter(&["hostname="]).unwrap()), vec!["db"]);
+        assert_eq!(names(filter(&[]).unwrap()), vec!["db", "web", "webdb"]);
+
+        let err = filter(&["memory=1"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown column `memory`, available columns are: hostname, name, ncpus, run_state"
+        );
+        let err = filter(&["running"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid filter `running`, expected key=value or key~value"
+        );
+    }
+
+    #[test]
+    fn test_sort_by_column() {
+        let items = vec![
+            serde_json::json!({"name": "disk10", "size": 2, "state": "attached"}),
+            serde_json::json!({"name": "disk2", "size": 10, "state": null}),
+            serde_json::json!({"name": "disk1", "size": 2.5, "state": "detached"}),
+        ];
+        let names = |items: Vec<serde_json::Value>| -> Vec<String> {
+            items
+                .iter()
+                .map(|item| item["name"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        assert_eq!(
+            names(sort_by_column(items.clone(), "name", false).unwrap()),
+            vec!["disk1", "disk2", "disk10"]
+        );
+        assert_eq!(
+            names(sort_by_column(items.clone(), "size", true).unwrap()),
+            vec!["disk2", "disk1", "disk10"]
+        );
+        assert_eq!(
+            names(sort_by_column(items.clone(), "state", false).unwrap()),
+            vec!["disk2", "disk10", "disk1"]
+        );
+        assert_eq!(
+            names(sort_by_column(items.clone(), "", true).unwrap()),
+            vec!["disk1", "disk2", "disk10"]
+        );
+
+        let err = sort_by_column(items, "memory", false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown column `memory`, available columns are: name, size, state"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_output_pager() {
+        let items = vec![
+            TestRow {
+                id: "1".to_string(),
+                name: "db".to_string(),
+                ncpus: 2,
+            },
+            TestRow {
+                id: "2".to_string(),
+                name: "web".to_string(),
+                ncpus: 4,
+            },
+        ];
+        let csv = crate::types::FormatOutput::Csv;
+        let paged = tempfile::NamedTempFile::new().unwrap();
+
+        let (mut io, stdout_path, _) = IoStreams::test();
+        io.set_stdout_tty(true);
+        io.tty_size = || Ok((72, 3));
+        io.set_pager(&format!("sh -c 'cat > {}'", paged.path().display()));
+
+        // Only output taller than the terminal goes through the pager.
+        io.write_output_for_vec(&csv, &items).unwrap();
+        io.write_output_for_vec(&csv, &items[..1]).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(paged.path()).unwrap(),
+            "id,name,ncpus\n1,db,2\n2,web,4\n"
+        );
+        assert_eq!(std::fs::read_to_string(stdout_path).unwrap(), "id,name,ncpus\n1,db,2\n");
+    }
+
+    #[test]
+    fn test_write_output_file() {
+        let items = vec![TestRow {
+            id: "1".to_string(),
+            name: "db".to_string(),
+            ncpus: 2,
+        }];
+        let csv = crate::types::FormatOutput::Csv;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.csv");
+        let path = path.to_str().unwrap();
+
+        let (mut io, stdout_path, _) = IoStreams::test();
+        io.with_output_file(path, |io| io.write_output_for_vec(&csv, &items))
+            .unwrap();
+
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "id,name,ncpus\n1,db,2\n");
+        assert_eq!(std::fs::read_to_string(&stdout_path).unwrap(), "");
+
+        // A failed write leaves the existing file alone.
+        let err = io
+            .with_output_file(path, |io| {
+                io.write_output_for_vec_with_columns(&csv, &["nope".to_string()], false, &items)
+            })
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown column `nope`, available columns are: id, name, ncpus"
+        );
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "id,name,ncpus\n1,db,2\n");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
 }
@@ -21,10 +21,10 @@ pub mod cmd_disk;
 pub mod cmd_generate;
 /// The image command.
 pub mod cmd_image;
-/// The image global subcommand.
-pub mod cmd_image_global;
 /// The instance command.
 pub mod cmd_instance;
+/// The instance external-ips subcommand.
+pub mod cmd_instance_external_ip;
 #[cfg(unix)]
 /// Support for interactive instance serial access
 pub mod cmd_instance_serial;
@@ -56,6 +56,10 @@ pub mod cmd_update;
 pub mod cmd_version;
 /// The vpc command.
 pub mod cmd_vpc;
+/// The vpc firewall-rules subcommand.
+pub mod cmd_vpc_firewall;
+/// The whoami command.
+pub mod cmd_whoami;
 
 // Use of a mod or pub mod is not actually necessary.
 mod built_info {
@@ -63,6 +67,7 @@ mod built_info {
     include!(concat!(env!("OUT_DIR"), "/built.rs"));
 }
 
+mod bulk;
 mod colors;
 mod config;
 mod config_alias;
@@ -75,6 +80,7 @@ mod docs_man;
 mod docs_markdown;
 mod iostreams;
 mod prompt_ext;
+mod redact;
 mod types;
 
 #[cfg(test)]
@@ -93,38 +99,68 @@ use slog::Drain;
 /// Environment variables that can be used with oxide. Additionally to those
 /// listed below, some flags have a corresponding environment variable. For example,
 /// most of the time, the `--organization,-o` flag is mapped to the `OXIDE_ORG` environment
-/// variable.
+/// variable and the `--project,-p` flag to `OXIDE_PROJECT`. When neither the flag nor the
+/// environment variable is set, the `default_org` and `default_project` settings of the
+/// host are used, see `oxide config set --help`.
 ///
 /// OXIDE_TOKEN: an authentication token for Oxide API requests. Setting this
 /// avoids being prompted to authenticate and takes precedence over previously
 /// stored credentials.
 ///
 /// OXIDE_HOST: specify the Oxide hostname for commands that would otherwise assume
-/// the "api.oxide.computer" host.
+/// the "api.oxide.computer" host. Together with OXIDE_TOKEN, this is all oxide needs, in
+/// CI for example, without a config file or `oxide auth login`. `--endpoint` wins over
+/// it, to point a single command at another server, like a mock.
+///
+/// OXIDE_PROFILE: the name of the profile to use for authentication, as created with
+/// `oxide auth login --profile`. This is the same as passing `--profile`.
+///
+/// OXIDE_TIMEOUT: how long to wait for each API call before giving up, in seconds or
+/// with a unit like "5m". This is the same as passing `--timeout`.
+///
+/// OXIDE_ASSUME_YES: set to any value to skip the confirmation prompt of commands that
+/// delete, stop, or reboot something. This is the same as passing `--confirm` or `--yes`.
 ///
 /// OXIDE_BROWSER, BROWSER (in order of precedence): the web browser to use for opening
 /// links.
 ///
-/// DEBUG: set to any value to enable verbose output to standard error.
+/// OXIDE_PAGER, PAGER (in order of precedence): a terminal paging program to send
+/// long list output to. Defaults to "less -FRX". Set to "cat" or pass `--no-pager` to
+/// disable paging.
+///
+/// DEBUG: set to any value to enable debug output to standard error, the same as
+/// `--debug`. Pass `-v` instead for only the API requests and how long they took.
 ///
 /// NO_COLOR: set to any value to avoid printing ANSI escape sequences for color output.
 ///
 /// CLICOLOR: set to "0" to disable printing ANSI colors in output.
 ///
 /// CLICOLOR_FORCE: set to a value other than "0" to keep ANSI colors in output
-/// even when the output is piped.
+/// even when the output is piped. Passing `--color` or `--no-color` takes precedence
+/// over NO_COLOR, CLICOLOR, and CLICOLOR_FORCE.
 ///
 /// OXIDE_FORCE_TTY: set to any value to force terminal-style output even when the
 /// output is redirected. When the value is a number, it is interpreted as the number of
 /// columns available in the viewport. When the value is a percentage, it will be applied
-/// against the number of columns available in the current viewport.
+/// against the number of columns available in the current viewport. Output that is
+/// redirected is never sent to the pager.
 ///
 /// OXIDE_NO_UPDATE_NOTIFIER: set to any value to disable update notifications. By
 /// default, oxide checks for new releases once every 24 hours and displays an upgrade
-/// notice on standard error if a newer version was found.
+/// notice on standard error if a newer version was found. Set `update_check_interval`
+/// with `oxide config set` to check more or less often. It also turns off the warning
+/// printed when the server runs a different version of the API than oxide was built for.
 ///
-/// OXIDE_CONFIG_DIR: the directory where oxide will store configuration files.
-/// Default: "$XDG_CONFIG_HOME/oxide" or "$HOME/.config/oxide".
+/// OXIDE_CONFIG_DIR: the directory where oxide will store configuration files, and the
+/// state of the update check. Default: "$XDG_CONFIG_HOME/oxide" or "$HOME/.config/oxide",
+/// on Windows "%APPDATA%\Oxide CLI".
+///
+/// Exit codes
+///
+/// oxide exits with 0 on success and 1 on most errors. Some errors from the API have
+/// their own exit code: 3 when you are not authorized to perform the action (Forbidden),
+/// 4 when you are not authenticated (Unauthenticated), 22 for an invalid request
+/// (InvalidRequest), and 44 when the object was not found (ObjectNotFound).
 ///
 /// Authentication
 ///
@@ -141,6 +177,73 @@ struct Opts {
     #[clap(short, long, global = true, env)]
     debug: bool,
 
+    /// Print the API requests and how long they took, `-vv` for debug info too
+    #[clap(short, long, global = true, parse(from_occurrences))]
+    verbose: u64,
+
+    /// Do not print success messages
+    #[clap(short, long, global = true)]
+    quiet: bool,
+
+    /// Do not send long output to a pager
+    #[clap(long, global = true)]
+    no_pager: bool,
+
+    /// The profile to use for authentication
+    #[clap(short = 'P', long, global = true, env = "OXIDE_PROFILE")]
+    profile: Option<String>,
+
+    /// Send the API requests to this URL instead of the host of the profile, for this run only
+    // Not named `endpoint`, which is the path `oxide api` takes.
+    #[clap(long = "endpoint", global = true, value_name = "URL", parse(try_from_str = crate::context::parse_endpoint))]
+    endpoint_url: Option<String>,
+
+    /// How long to wait for each API call, like 30 or 5m, there is no timeout by default
+    #[clap(long, global = true, env = "OXIDE_TIMEOUT", parse(try_from_str = crate::types::parse_duration))]
+    timeout: Option<std::time::Duration>,
+
+    /// Add a HTTP request header in `key:value` format to the raw API requests, like those of `oxide api`
+    ///
+    /// The requests of the other commands are sent by a client that has no way to add headers,
+    /// except for the serial console of `oxide instance serial`, which gets them too.
+    ///
+    /// There is no `-H` for this flag, since `auth` and `config` use `-H` for `--host`. `oxide api`
+    /// has its own `-H,--header`, which takes the place of this flag there: after `api`, `-H` and
+    /// `--header` are the same, and if headers are given both before and after `api`, only the
+    /// ones after it are sent.
+    // clap does not add a global flag to a subcommand that has one with the same name, which
+    // is what lets `api` keep its `-H`. The values given to the subcommand are copied up to
+    // `Opts`, so both see the same headers.
+    #[clap(long, global = true)]
+    header: Vec<String>,
+
+    /// When to use colors: auto, always, or never
+    #[clap(long, global = true, default_value = "auto", possible_values = &["auto", "always", "never"])]
+    color: crate::colors::ColorMode,
+
+    /// Do not use colors, the same as `--color never`
+    #[clap(long, global = true)]
+    no_color: bool,
+
+    /// How to print errors: human or json
+    #[clap(long, global = true, default_value = "human", possible_values = &["human", "json"])]
+    error_format: crate::types::ErrorFormat,
+
+    /// How to report progress of long operations: human or json
+    #[clap(long, global = true, default_value = "human", possible_values = &["human", "json"])]
+    progress: crate::types::ProgressFormat,
+
+    /// Never prompt for input, even in a terminal, missing flags are errors instead
+    #[clap(long, global = true)]
+    no_input: bool,
+
+    /// Do not expand aliases, to run a built-in command that an alias shadows
+    // This is read from the raw arguments in `run_with`, since it decides how they are
+    // parsed.
+    #[allow(dead_code)]
+    #[clap(long, global = true)]
+    no_alias: bool,
+
     #[clap(subcommand)]
     subcmd: SubCommand,
 }
@@ -152,6 +255,8 @@ enum SubCommand {
     Api(cmd_api::CmdApi),
     Auth(cmd_auth::CmdAuth),
     Completion(cmd_completion::CmdCompletion),
+    #[clap(name = "__complete", hide = true)]
+    Complete(cmd_completion::CmdComplete),
     Config(cmd_config::CmdConfig),
     #[clap(alias = "disks")]
     Disk(cmd_disk::CmdDisk),
@@ -186,24 +291,34 @@ enum SubCommand {
     Version(cmd_version::CmdVersion),
     #[clap(alias = "vpcs")]
     Vpc(cmd_vpc::CmdVpc),
+    Whoami(cmd_whoami::CmdWhoami),
 }
 
 #[tokio::main]
 async fn main() -> Result<(), ()> {
     let build_version = clap::crate_version!();
-    // Check for updates to the cli.
-    // We don't await here since we don't want to block the main thread.
-    // We'll check again before we exit.
-    let update = crate::update::check_for_update(build_version, false);
 
     // Let's get our configuration.
     let mut c = crate::config_file::parse_default_config().unwrap();
     let mut config = crate::config_from_env::EnvConfig::inherit_env(&mut c);
     let mut ctx = crate::context::Context::new(&mut config);
 
+    // Check for updates to the cli.
+    // We don't await here since we don't want to block the main thread.
+    // We'll check again before we exit.
+    let check_interval = crate::update::check_interval(ctx.config);
+    let update = crate::update::check_for_update(build_version, false, check_interval);
+
     // Let's grab all our args.
     let args: Vec<String> = std::env::args().collect();
-    let result = do_main(args, &mut ctx).await;
+
+    // Setup our logger. This is mainly for debug purposes.
+    // And getting debug logs from other libraries we consume, like even Oxide.
+    if let Some(level) = log_level(&args) {
+        init_logger(&ctx, level);
+    }
+
+    let result = run_with(args, &mut ctx).await;
 
     // If we have an update, let's print it.
     handle_update(&mut ctx, update.await.unwrap_or_default(), build_version).unwrap();
@@ -216,7 +331,13 @@ async fn main() -> Result<(), ()> {
     std::process::exit(result.unwrap_or(0));
 }
 
-async fn do_main(mut args: Vec<String>, ctx: &mut crate::context::Context<'_>) -> Result<i32> {
+/// Run oxide with the given arguments, the first one being the program name, and return
+/// the exit code.
+///
+/// Everything comes from the context: this does not read the config file, nor set up
+/// logging, that is up to the caller. `main` does both before calling this, tests and
+/// other tools embedding oxide can build the context however they need.
+pub async fn run_with(mut args: Vec<String>, ctx: &mut crate::context::Context<'_>) -> Result<i32> {
     let original_args = args.clone();
 
     // Remove the first argument, which is the program name, and can change depending on how
@@ -225,8 +346,24 @@ async fn do_main(mut args: Vec<String>, ctx: &mut crate::context::Context<'_>) -
 
     let args_str = shlex::join(args.iter().map(|s| s.as_str()).collect::<Vec<&str>>());
 
-    // Check if the user is passing in an alias.
-    if !crate::cmd_alias::valid_command(&args_str) {
+    let no_alias = args
+        .iter()
+        .take_while(|arg| *arg != "--")
+        .any(|arg| arg == "--no-alias");
+    let is_builtin = crate::cmd_alias::valid_command(&args_str);
+    let is_alias = !no_alias && !args.is_empty() && ctx.config.aliases()?.get(&args[0]).1;
+
+    // Check if the user is passing in an alias. Aliases take precedence over the
+    // built-in command they are named after, unless `--no-alias` is passed.
+    if !no_alias && (!is_builtin || is_alias) {
+        if is_builtin {
+            writeln!(
+                ctx.io.err_out,
+                "warning: alias `{}` shadows the built-in command, pass `--no-alias` to run the built-in",
+                args[0]
+            )?;
+        }
+
         // Let's validate if it is an alias.
         // It is okay to check the error here because we will not error out if the
         // alias does not exist. We will just return the expanded args.
@@ -242,20 +379,19 @@ async fn do_main(mut args: Vec<String>, ctx: &mut crate::context::Context<'_>) -
                 .stderr(std::process::Stdio::piped())
                 .spawn()?;
 
-            let ecode = external_cmd.wait()?;
-
-            // Pipe the output to the terminal.
-            if let Some(stdout_rd) = external_cmd.stdout.as_mut() {
-                let mut stdout = Vec::new();
-                stdout_rd.read_to_end(&mut stdout)?;
-                ctx.io.out.write_all(&stdout)?;
+            // Copy the output as it comes, so long running aliases show their progress.
+            let chunks = read_chunks(external_cmd.stdout.take(), external_cmd.stderr.take());
+            for (is_stderr, chunk) in chunks {
+                let w = if is_stderr {
+                    &mut ctx.io.err_out
+                } else {
+                    &mut ctx.io.out
+                };
+                w.write_all(&chunk)?;
+                w.flush()?;
             }
 
-            if let Some(mut stderr_rd) = external_cmd.stderr {
-                let mut stderr = Vec::new();
-                stderr_rd.read_to_end(&mut stderr)?;
-                ctx.io.err_out.write_all(&stderr)?;
-            }
+            let ecode = external_cmd.wait()?;
 
             return Ok(ecode.code().unwrap_or(0));
         }
@@ -270,55 +406,225 @@ async fn do_main(mut args: Vec<String>, ctx: &mut crate::context::Context<'_>) -
     // Parse the command line arguments.
     let opts: Opts = Opts::parse_from(args);
 
-    // Set our debug flag.
-    ctx.debug = opts.debug;
+    // Set our debug flag, `-vv` is the same as `--debug`.
+    ctx.debug = opts.debug || opts.verbose > 1;
 
-    // Setup our logger. This is mainly for debug purposes.
-    // And getting debug logs from other libraries we consume, like even Oxide.
-    if ctx.debug {
-        let decorator = slog_term::TermDecorator::new().build();
-        let drain = slog_term::FullFormat::new(decorator).build().fuse();
-        let drain = slog_async::Async::new(drain).build().fuse();
+    // Set our quiet flag.
+    ctx.quiet = opts.quiet;
 
-        let logger = slog::Logger::root(drain, slog::o!());
+    // Override the color detection if asked to.
+    if opts.no_color {
+        ctx.io.set_color_mode(crate::colors::ColorMode::Never);
+    } else {
+        ctx.io.set_color_mode(opts.color);
+    }
+
+    // Disable the pager if asked to.
+    if opts.no_pager {
+        ctx.io.set_pager("");
+    }
 
-        let scope_guard = slog_scope::set_global_logger(logger);
-        scope_guard.cancel_reset();
+    // Report progress for scripts if asked to.
+    ctx.io.set_progress_format(opts.progress);
 
-        slog_stdlog::init_with_level(log::Level::Debug).unwrap();
+    // Never prompt if asked to, so missing flags fail like they would in a script.
+    if opts.no_input {
+        ctx.io.set_never_prompt(true);
     }
 
+    // Set the profile to use for the api client.
+    ctx.profile = opts.profile;
+
+    // And how long to wait for the API.
+    ctx.timeout = opts.timeout;
+
+    // And where to send the requests, over the profile and OXIDE_HOST.
+    ctx.endpoint = opts.endpoint_url;
+
+    // Check the headers before anything is sent.
+    ctx.headers = crate::cmd_api::parse_headers(&opts.header)?;
+
+    // So the requests in the debug logs can be matched with the logs of the server.
+    if ctx.debug {
+        log::debug!("request ID {}", crate::context::request_id());
+    }
+
+    let error_format = opts.error_format;
+
     match opts.subcmd {
-        SubCommand::Alias(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::Api(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::Auth(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::Completion(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::Config(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::Disk(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::Generate(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::Image(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::Instance(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::Open(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::Org(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::Project(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::Rack(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::Role(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::Route(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::Router(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::Sled(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::Snapshot(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::SshKey(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::Subnet(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::Update(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::Version(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::Vpc(cmd) => run_cmd(&cmd, ctx).await,
+        SubCommand::Alias(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Api(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Auth(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Completion(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Complete(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Config(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Disk(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Generate(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Image(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Instance(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Open(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Org(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Project(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Rack(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Role(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Route(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Router(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Sled(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Snapshot(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::SshKey(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Subnet(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Update(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Version(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Vpc(cmd) => run_cmd(&cmd, ctx, error_format).await,
+        SubCommand::Whoami(cmd) => run_cmd(&cmd, ctx, error_format).await,
+    }
+}
+
+/// How much to log, from `--debug` or `DEBUG` and the number of `-v`s. The logger has
+/// to be set up before `run_with` parses the arguments, so this looks at them as they
+/// are: everything goes with `--debug` or `-vv`, and only the API requests oxide makes
+/// with `-v`. No logger at all without any of them.
+fn log_level(args: &[String]) -> Option<log::Level> {
+    if std::env::var_os("DEBUG").is_some() {
+        return Some(log::Level::Debug);
+    }
+
+    let args: Vec<&String> = args.iter().skip(1).take_while(|arg| *arg != "--").collect();
+    if args.iter().any(|arg| *arg == "--debug" || *arg == "-d") {
+        return Some(log::Level::Debug);
+    }
+
+    let verbose: usize = args
+        .iter()
+        .map(|arg| match arg.strip_prefix('-') {
+            Some("-verbose") => 1,
+            Some(flags) if !flags.is_empty() && flags.chars().all(|c| c == 'v') => flags.len(),
+            _ => 0,
+        })
+        .sum();
+
+    match verbose {
+        0 => None,
+        1 => Some(log::Level::Info),
+        _ => Some(log::Level::Debug),
     }
 }
 
-async fn run_cmd(cmd: &impl crate::cmd::Command, ctx: &mut context::Context<'_>) -> Result<i32> {
+/// Send the logs of oxide, and of the libraries it uses, to standard error, up to
+/// `level`. Tokens and `Authorization` headers are masked before anything is written.
+fn init_logger(ctx: &crate::context::Context, level: log::Level) {
+    let writer = redact::Writer::new(std::io::stderr(), redact::secrets(&*ctx.config));
+    let decorator = slog_term::PlainDecorator::new(writer);
+    let drain = slog_term::FullFormat::new(decorator).build().fuse();
+    let drain = slog_async::Async::new(drain).build().fuse();
+
+    let logger = slog::Logger::root(drain, slog::o!());
+
+    let scope_guard = slog_scope::set_global_logger(logger);
+    scope_guard.cancel_reset();
+
+    slog_stdlog::init_with_level(level).unwrap();
+}
+
+/// Read the stdout and stderr of a child process as the data arrives, each on its own
+/// thread so neither pipe fills up while we wait on the other. The chunks are tagged
+/// with whether they came from stderr, and the iterator ends once both pipes are closed.
+fn read_chunks(
+    stdout: Option<std::process::ChildStdout>,
+    stderr: Option<std::process::ChildStderr>,
+) -> std::sync::mpsc::Receiver<(bool, Vec<u8>)> {
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    fn forward<R: Read + Send + 'static>(mut r: R, is_stderr: bool, tx: std::sync::mpsc::Sender<(bool, Vec<u8>)>) {
+        std::thread::spawn(move || {
+            let mut buf = [0; 8192];
+            loop {
+                match r.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        if tx.send((is_stderr, buf[..n].to_vec())).is_err() {
+                            break;
+                        }
+                    }
+                    Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                    // Any other error ends the output the same way closing the pipe does.
+                    Err(_) => break,
+                }
+            }
+        });
+    }
+
+    if let Some(stdout) = stdout {
+        forward(stdout, false, tx.clone());
+    }
+    if let Some(stderr) = stderr {
+        forward(stderr, true, tx);
+    }
+
+    rx
+}
+
+async fn run_cmd(
+    cmd: &impl crate::cmd::Command,
+    ctx: &mut context::Context<'_>,
+    error_format: crate::types::ErrorFormat,
+) -> Result<i32> {
+    // Check the version of the API alongside the command, like the update check, and
+    // turned off with it. There is nothing to check before `auth login`.
+    let api_version = if crate::update::should_check_for_update() {
+        ctx.api_client("")
+            .ok()
+            .map(|client| tokio::spawn(crate::update::get_api_version(client)))
+    } else {
+        None
+    };
+
+    let result = cmd.run(ctx).await;
+    let code = report_result(ctx, result, error_format);
+
+    // The warning comes after what the command printed, and a failed check is not worth
+    // failing the command for.
+    handle_api_version(ctx, api_version).await.ok();
+
+    code
+}
+
+/// Print the error of a command, if it failed, and return its exit code.
+fn report_result(
+    ctx: &mut context::Context<'_>,
+    result: Result<()>,
+    error_format: crate::types::ErrorFormat,
+) -> Result<i32> {
     let cs = ctx.io.color_scheme();
 
-    if let Err(err) = cmd.run(ctx).await {
+    if let Err(err) = result {
+        // The typed API calls can't send the request ID, so the server only knows it if one
+        // of the other requests was sent.
+        let request_id = if ctx.request_id_sent.load(std::sync::atomic::Ordering::Relaxed) {
+            Some(crate::context::request_id())
+        } else {
+            None
+        };
+
+        if error_format == crate::types::ErrorFormat::Json {
+            writeln!(ctx.io.err_out, "{}", error_to_json(&err, request_id))?;
+            return Ok(exit_code(&err));
+        }
+
+        if let Some(timeout) = err.downcast_ref::<crate::context::TimeoutError>() {
+            writeln!(ctx.io.err_out, "{} {}", cs.failure_icon(), timeout)?;
+            if let Some(request_id) = request_id {
+                writeln!(ctx.io.err_out, "Request ID: {}", request_id)?;
+            }
+            return Ok(exit_code(&err));
+        }
+
+        // The items that failed were already printed with the result.
+        if let Some(bulk) = err.downcast_ref::<crate::bulk::BulkError>() {
+            writeln!(ctx.io.err_out, "{} {}", cs.failure_icon(), bulk)?;
+            return Ok(exit_code(&err));
+        }
+
         // If the error was from the API, let's handle it better for each type of error.
         // These are defined here: https://github.com/oxidecomputer/omicron/blob/main/common/src/api/external/error.rs#L28
         match err.downcast_ref::<oxide_api::types::Error>() {
@@ -384,12 +690,95 @@ async fn run_cmd(cmd: &impl crate::cmd::Command, ctx: &mut context::Context<'_>)
                 writeln!(ctx.io.err_out, "{}", err)?;
             }
         }
-        return Ok(1);
+
+        // Give them something to reference when they report a problem with the API.
+        if let Some(request_id) = request_id {
+            writeln!(ctx.io.err_out, "Request ID: {}", request_id)?;
+        }
+
+        return Ok(exit_code(&err));
     }
 
     Ok(0)
 }
 
+/// The exit code for a failed command, see "Exit codes" in the help of `oxide`.
+fn exit_code(err: &anyhow::Error) -> i32 {
+    match err.downcast_ref::<oxide_api::types::Error>() {
+        Some(oxide_api::types::Error::Forbidden) => 3,
+        Some(oxide_api::types::Error::Unauthenticated { .. }) => 4,
+        Some(oxide_api::types::Error::InvalidRequest { .. }) => 22,
+        Some(oxide_api::types::Error::ObjectNotFound { .. }) => 44,
+        _ => 1,
+    }
+}
+
+/// Serialize an error for `--error-format json`. API errors are named after their
+/// variant, anything else is a plain `Error`. The request ID of the run is included if
+/// it was sent.
+fn error_to_json(err: &anyhow::Error, request_id: Option<&str>) -> serde_json::Value {
+    let (error, message) = match err.downcast_ref::<oxide_api::types::Error>() {
+        Some(oxide_api::types::Error::ObjectNotFound { message }) => ("ObjectNotFound", message.to_string()),
+        Some(oxide_api::types::Error::ObjectAlreadyExists { message }) => ("ObjectAlreadyExists", message.to_string()),
+        Some(oxide_api::types::Error::InvalidRequest { message }) => ("InvalidRequest", message.to_string()),
+        Some(oxide_api::types::Error::Unauthenticated { internal_message }) => {
+            ("Unauthenticated", internal_message.to_string())
+        }
+        Some(oxide_api::types::Error::InvalidValue { message }) => ("InvalidValue", message.to_string()),
+        Some(oxide_api::types::Error::Forbidden) => {
+            ("Forbidden", "You are not authorized to perform this action".to_string())
+        }
+        Some(oxide_api::types::Error::InternalError { internal_message }) => {
+            ("InternalError", internal_message.to_string())
+        }
+        Some(oxide_api::types::Error::ServiceUnavailable { internal_message }) => {
+            ("ServiceUnavailable", internal_message.to_string())
+        }
+        Some(oxide_api::types::Error::MethodNotAllowed { internal_message }) => {
+            ("MethodNotAllowed", internal_message.to_string())
+        }
+        None => ("Error", err.to_string()),
+    };
+
+    let mut value = serde_json::json!({
+        "error": error,
+        "message": message,
+    });
+    if let Some(request_id) = request_id {
+        value["request_id"] = request_id.into();
+    }
+
+    value
+}
+
+/// Warn if the server runs a different version of the API than the one the commands
+/// were generated for, if the check started with the command is done by now. A check
+/// that is still running is stopped rather than waited for.
+async fn handle_api_version(
+    ctx: &mut crate::context::Context<'_>,
+    check: Option<tokio::task::JoinHandle<Option<String>>>,
+) -> Result<()> {
+    let server_version = match check {
+        Some(check) if check.is_finished() => check.await.unwrap_or_default(),
+        Some(check) => {
+            check.abort();
+            return Ok(());
+        }
+        None => return Ok(()),
+    };
+
+    if let Some(server_version) = crate::update::api_version_mismatch(server_version) {
+        writeln!(
+            ctx.io.err_out,
+            "warning: the server runs version {} of the API, but oxide was built for {}, run `oxide update` to get a matching version",
+            server_version,
+            crate::update::SPEC_VERSION
+        )?;
+    }
+
+    Ok(())
+}
+
 fn handle_update(
     ctx: &mut crate::context::Context,
     update: Option<crate::update::ReleaseInfo>,
@@ -98,18 +98,14 @@ impl PromptExt for oxide_api::types::Ipv4Net {
     fn prompt(base: &str) -> Result<Self> {
         let input = dialoguer::Input::<String>::new()
             .with_prompt(base)
-            .validate_with(|input: &String| -> Result<(), &str> {
-                let ipnet = oxide_api::types::Ipv4Net::from_str(input);
-
-                if ipnet.is_err() {
-                    Err("invalid IPv4 network")
-                } else {
-                    Ok(())
-                }
+            .validate_with(|input: &String| -> Result<(), String> {
+                crate::cmd_subnet::parse_ipv4_block(input)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
             })
             .interact_text()?;
 
-        oxide_api::types::Ipv4Net::from_str(&input).map_err(|e| anyhow::anyhow!("invalid ipv4net `{}`: {}", input, e))
+        crate::cmd_subnet::parse_ipv4_block(&input)
     }
 }
 
@@ -117,18 +113,14 @@ impl PromptExt for oxide_api::types::Ipv6Net {
     fn prompt(base: &str) -> Result<Self> {
         let input = dialoguer::Input::<String>::new()
             .with_prompt(base)
-            .validate_with(|input: &String| -> Result<(), &str> {
-                let ipnet = oxide_api::types::Ipv6Net::from_str(input);
-
-                if ipnet.is_err() {
-                    Err("invalid IPv6 network")
-                } else {
-                    Ok(())
-                }
+            .validate_with(|input: &String| -> Result<(), String> {
+                crate::cmd_subnet::parse_ipv6_block(input)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
             })
             .interact_text()?;
 
-        oxide_api::types::Ipv6Net::from_str(&input).map_err(|e| anyhow::anyhow!("invalid ipv6net `{}`: {}", input, e))
+        crate::cmd_subnet::parse_ipv6_block(&input)
     }
 }
 
@@ -195,6 +195,29 @@ async fn test_main(ctx: &mut MainContext) {
             want_code: 0,
             ..Default::default()
         },
+        TestItem {
+            name: "add a shell alias that writes to both streams".to_string(),
+            args: vec![
+                "oxide".to_string(),
+                "alias".to_string(),
+                "set".to_string(),
+                "-s".to_string(),
+                "loud".to_string(),
+                "echo out; echo err >&2; exit 3".to_string(),
+            ],
+            want_out: "- Adding alias for loud: !echo out; echo err >&2; exit 3\n✔ Added alias.".to_string(),
+            want_err: "".to_string(),
+            want_code: 0,
+            ..Default::default()
+        },
+        TestItem {
+            name: "call shell alias that writes to both streams".to_string(),
+            args: vec!["oxide".to_string(), "loud".to_string()],
+            want_out: "out\n".to_string(),
+            want_err: "err\n".to_string(),
+            want_code: 3,
+            ..Default::default()
+        },
         TestItem {
             name: "version".to_string(),
             args: vec!["oxide".to_string(), "version".to_string()],
@@ -203,6 +226,19 @@ async fn test_main(ctx: &mut MainContext) {
             want_code: 0,
             ..Default::default()
         },
+        TestItem {
+            name: "version json".to_string(),
+            args: vec![
+                "oxide".to_string(),
+                "version".to_string(),
+                "--format".to_string(),
+                "json".to_string(),
+            ],
+            want_out: format!(r#""version": "{}""#, version),
+            want_err: "".to_string(),
+            want_code: 0,
+            ..Default::default()
+        },
         TestItem {
             name: "login".to_string(),
             args: vec![
@@ -280,6 +316,20 @@ async fn test_main(ctx: &mut MainContext) {
             want_code: 0,
             ..Default::default()
         },
+        TestItem {
+            name: "api session/me with global header".to_string(),
+            args: vec![
+                "oxide".to_string(),
+                "--header".to_string(),
+                "Origin: https://example.com".to_string(),
+                "api".to_string(),
+                "session/me".to_string(),
+            ],
+            want_out: r#""display_name": "privileged""#.to_string(),
+            want_err: "".to_string(),
+            want_code: 0,
+            ..Default::default()
+        },
         TestItem {
             name: "api session/me with output headers".to_string(),
             args: vec![
@@ -294,6 +344,41 @@ async fn test_main(ctx: &mut MainContext) {
             want_code: 0,
             ..Default::default()
         },
+        TestItem {
+            name: "api session/me with output headers as json".to_string(),
+            args: vec![
+                "oxide".to_string(),
+                "api".to_string(),
+                "session/me".to_string(),
+                "--include-format".to_string(),
+                "json".to_string(),
+            ],
+            want_out: r#"{"headers":{"#.to_string(),
+            want_err: "".to_string(),
+            want_code: 0,
+            ..Default::default()
+        },
+        TestItem {
+            name: "whoami".to_string(),
+            args: vec!["oxide".to_string(), "whoami".to_string()],
+            want_out: "as privileged (001de000-05e4-4000-8000-000000004007)".to_string(),
+            want_err: "".to_string(),
+            want_code: 0,
+            ..Default::default()
+        },
+        TestItem {
+            name: "whoami as json".to_string(),
+            args: vec![
+                "oxide".to_string(),
+                "whoami".to_string(),
+                "--format".to_string(),
+                "json".to_string(),
+            ],
+            want_out: r#""display_name": "privileged""#.to_string(),
+            want_err: "".to_string(),
+            want_code: 0,
+            ..Default::default()
+        },
         TestItem {
             name: "api endpoint does not exist".to_string(),
             args: vec!["oxide".to_string(), "api".to_string(), "foo/bar".to_string()],
@@ -302,6 +387,14 @@ async fn test_main(ctx: &mut MainContext) {
             want_code: 1,
             ..Default::default()
         },
+        TestItem {
+            name: "api endpoint does not exist prints the request ID".to_string(),
+            args: vec!["oxide".to_string(), "api".to_string(), "foo/bar".to_string()],
+            want_out: "".to_string(),
+            want_err: "Request ID: ".to_string(),
+            want_code: 1,
+            ..Default::default()
+        },
         TestItem {
             name: "try to paginate over a post".to_string(),
             args: vec![
@@ -317,6 +410,34 @@ async fn test_main(ctx: &mut MainContext) {
             want_code: 1,
             ..Default::default()
         },
+        TestItem {
+            name: "malformed global header".to_string(),
+            args: vec![
+                "oxide".to_string(),
+                "--header".to_string(),
+                "x-feature".to_string(),
+                "org".to_string(),
+                "list".to_string(),
+            ],
+            want_out: "".to_string(),
+            want_err: "missing value in --header `x-feature`, expected key:value".to_string(),
+            want_code: 1,
+            ..Default::default()
+        },
+        TestItem {
+            name: "create org without input".to_string(),
+            args: vec![
+                "oxide".to_string(),
+                "--no-input".to_string(),
+                "org".to_string(),
+                "create".to_string(),
+                "maze-war".to_string(),
+            ],
+            want_out: "".to_string(),
+            want_err: "-D|--description required in non-interactive mode".to_string(),
+            want_code: 1,
+            ..Default::default()
+        },
         TestItem {
             name: "list racks".to_string(),
             args: vec!["oxide".to_string(), "racks".to_string(), "list".to_string()],
@@ -387,6 +508,69 @@ async fn test_main(ctx: &mut MainContext) {
             want_code: 0,
             ..Default::default()
         },
+        TestItem {
+            name: "list global images".to_string(),
+            args: vec![
+                "oxide".to_string(),
+                "image".to_string(),
+                "global".to_string(),
+                "list".to_string(),
+                "--format".to_string(),
+                "json".to_string(),
+            ],
+            want_out: "[".to_string(),
+            want_err: "".to_string(),
+            want_code: 0,
+            ..Default::default()
+        },
+        TestItem {
+            name: "list global images with a filter".to_string(),
+            args: vec![
+                "oxide".to_string(),
+                "image".to_string(),
+                "global".to_string(),
+                "list".to_string(),
+                "--filter".to_string(),
+                "name=no-such-image".to_string(),
+                "--sort".to_string(),
+                "name".to_string(),
+                "--format".to_string(),
+                "json".to_string(),
+            ],
+            want_out: "[]".to_string(),
+            want_err: "".to_string(),
+            want_code: 0,
+            ..Default::default()
+        },
+        TestItem {
+            name: "view a global image that does not exist".to_string(),
+            args: vec![
+                "oxide".to_string(),
+                "image".to_string(),
+                "global".to_string(),
+                "view".to_string(),
+                "no-such-image".to_string(),
+            ],
+            want_out: "".to_string(),
+            want_err: "Object not found".to_string(),
+            want_code: 44,
+            ..Default::default()
+        },
+        TestItem {
+            name: "delete a global image that does not exist".to_string(),
+            args: vec![
+                "oxide".to_string(),
+                "image".to_string(),
+                "global".to_string(),
+                "delete".to_string(),
+                "no-such-image".to_string(),
+                "--confirm".to_string(),
+            ],
+            want_out: "".to_string(),
+            want_err: "Object not found".to_string(),
+            want_code: 44,
+            ..Default::default()
+        },
         TestItem {
             name: "list orgs empty".to_string(),
             args: vec!["oxide".to_string(), "org".to_string(), "list".to_string()],
@@ -438,6 +622,21 @@ async fn test_main(ctx: &mut MainContext) {
             want_code: 1,
             ..Default::default()
         },
+        TestItem {
+            name: "view an org that does not exist --error-format json".to_string(),
+            args: vec![
+                "oxide".to_string(),
+                "org".to_string(),
+                "view".to_string(),
+                "no-such-org".to_string(),
+                "--error-format".to_string(),
+                "json".to_string(),
+            ],
+            want_out: "".to_string(),
+            want_err: r#"{"error":"ObjectNotFound","message":"#.to_string(),
+            want_code: 44,
+            ..Default::default()
+        },
         TestItem {
             name: "view an org --format json".to_string(),
             args: vec![
@@ -552,6 +751,53 @@ async fn test_main(ctx: &mut MainContext) {
             want_code: 0,
             ..Default::default()
         },
+        TestItem {
+            name: "create an org quietly".to_string(),
+            args: vec![
+                "oxide".to_string(),
+                "--quiet".to_string(),
+                "org".to_string(),
+                "create".to_string(),
+                "pong".to_string(),
+                "-D".to_string(),
+                "The Pong game organization".to_string(),
+            ],
+            want_out: "".to_string(),
+            want_err: "".to_string(),
+            want_code: 0,
+            ..Default::default()
+        },
+        TestItem {
+            name: "view an org as json quietly".to_string(),
+            args: vec![
+                "oxide".to_string(),
+                "--quiet".to_string(),
+                "org".to_string(),
+                "view".to_string(),
+                "pong".to_string(),
+                "--format".to_string(),
+                "json".to_string(),
+            ],
+            want_out: r#""name": "pong""#.to_string(),
+            want_err: "".to_string(),
+            want_code: 0,
+            ..Default::default()
+        },
+        TestItem {
+            name: "delete an org quietly".to_string(),
+            args: vec![
+                "oxide".to_string(),
+                "--quiet".to_string(),
+                "org".to_string(),
+                "delete".to_string(),
+                "pong".to_string(),
+                "--confirm".to_string(),
+            ],
+            want_out: "".to_string(),
+            want_err: "".to_string(),
+            want_code: 0,
+            ..Default::default()
+        },
         TestItem {
             name: "list projects empty".to_string(),
             args: vec![
@@ -1764,9 +2010,16 @@ async fn test_main(ctx: &mut MainContext) {
             config: &mut c,
             io,
             debug: false,
+            quiet: false,
+            profile: None,
+            api_clients: Default::default(),
+            timeout: None,
+            headers: Default::default(),
+            request_id_sent: Default::default(),
+            endpoint: None,
         };
 
-        let result = crate::do_main(t.args, &mut ctx).await;
+        let result = crate::run_with(t.args, &mut ctx).await;
 
         let stdout = std::fs::read_to_string(stdout_path).unwrap_or_default();
         let stderr = std::fs::read_to_string(stderr_path).unwrap_or_default();
@@ -1823,3 +2076,100 @@ async fn test_main(ctx: &mut MainContext) {
         }
     }
 }
+
+#[tokio::test]
+async fn test_alias_shadows_builtin() {
+    use crate::config::Config;
+
+    let mut config = crate::config::new_blank_config().unwrap();
+    let map = {
+        let mut aliases = config.aliases().unwrap();
+        aliases.map.set_string_value("version", "completion -s zsh").unwrap();
+        aliases.map
+    };
+    config.save_aliases(&map).unwrap();
+    let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+
+    let tests = vec![
+        TestItem {
+            name: "alias named version".to_string(),
+            args: vec!["oxide".to_string(), "version".to_string()],
+            want_out: "#compdef oxide".to_string(),
+            want_err: "warning: alias `version` shadows the built-in command, pass `--no-alias` to run the built-in"
+                .to_string(),
+            want_code: 0,
+            ..Default::default()
+        },
+        TestItem {
+            name: "version with --no-alias".to_string(),
+            args: vec!["oxide".to_string(), "--no-alias".to_string(), "version".to_string()],
+            want_out: format!("oxide {}", clap::crate_version!()),
+            want_err: "".to_string(),
+            want_code: 0,
+            ..Default::default()
+        },
+    ];
+
+    for t in tests {
+        let (mut io, stdout_path, stderr_path) = crate::iostreams::IoStreams::test();
+        io.set_stdout_tty(false);
+        io.set_color_enabled(false);
+        let mut ctx = crate::context::Context {
+            config: &mut c,
+            io,
+            debug: false,
+            quiet: false,
+            profile: None,
+            api_clients: Default::default(),
+            timeout: None,
+            headers: Default::default(),
+            request_id_sent: Default::default(),
+            endpoint: None,
+        };
+
+        let code = crate::run_with(t.args, &mut ctx).await.unwrap();
+
+        let stdout = std::fs::read_to_string(stdout_path).unwrap_or_default();
+        let stderr = std::fs::read_to_string(stderr_path).unwrap_or_default();
+
+        assert_eq!(code, t.want_code, "test {}", t.name);
+        assert!(stdout.contains(&t.want_out), "test {} -> stdout: {}", t.name, stdout);
+        assert_eq!(
+            stderr.is_empty(),
+            t.want_err.is_empty(),
+            "test {} -> stderr: {}",
+            t.name,
+            stderr
+        );
+        assert!(stderr.contains(&t.want_err), "test {} -> stderr: {}", t.name, stderr);
+    }
+}
+
+#[test]
+fn test_log_level() {
+    // `DEBUG` turns everything on, whatever the arguments.
+    if std::env::var_os("DEBUG").is_some() {
+        return;
+    }
+
+    let level = |args: &[&str]| crate::log_level(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>());
+
+    assert_eq!(level(&["oxide", "instance", "list"]), None);
+    assert_eq!(level(&["oxide", "-v", "instance", "list"]), Some(log::Level::Info));
+    assert_eq!(
+        level(&["oxide", "--verbose", "instance", "list"]),
+        Some(log::Level::Info)
+    );
+    assert_eq!(level(&["oxide", "-vv", "instance", "list"]), Some(log::Level::Debug));
+    assert_eq!(
+        level(&["oxide", "-v", "-v", "instance", "list"]),
+        Some(log::Level::Debug)
+    );
+    assert_eq!(
+        level(&["oxide", "instance", "list", "--debug"]),
+        Some(log::Level::Debug)
+    );
+    assert_eq!(level(&["oxide", "instance", "list", "-v"]), Some(log::Level::Info));
+    assert_eq!(level(&["oxide", "route", "list", "-V", "vpc1"]), None);
+    assert_eq!(level(&["oxide", "api", "--", "-d"]), None);
+}
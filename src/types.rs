@@ -1,11 +1,17 @@
-use parse_display::{Display, FromStr};
+use anyhow::{anyhow, Result};
 
-#[derive(Debug, Clone, PartialEq, Eq, FromStr, Display)]
-#[display(style = "kebab-case")]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum FormatOutput {
     Json,
+    /// One JSON object per line. Paginated lists are written page by page as they come in.
+    Jsonl,
     Yaml,
     Table,
+    Csv,
+    Tsv,
+    /// Render each item through a template, for example '{{.name}} {{.id}}'.
+    /// The template itself is passed with `--template`.
+    Template(String),
 }
 
 impl Default for FormatOutput {
@@ -14,8 +20,346 @@ impl Default for FormatOutput {
     }
 }
 
+impl std::fmt::Display for FormatOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            FormatOutput::Json => "json",
+            FormatOutput::Jsonl => "jsonl",
+            FormatOutput::Yaml => "yaml",
+            FormatOutput::Table => "table",
+            FormatOutput::Csv => "csv",
+            FormatOutput::Tsv => "tsv",
+            FormatOutput::Template(_) => "template",
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+impl std::str::FromStr for FormatOutput {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "json" => Ok(FormatOutput::Json),
+            "jsonl" => Ok(FormatOutput::Jsonl),
+            "yaml" => Ok(FormatOutput::Yaml),
+            "table" => Ok(FormatOutput::Table),
+            "csv" => Ok(FormatOutput::Csv),
+            "tsv" => Ok(FormatOutput::Tsv),
+            // The template text comes from `--template`.
+            "template" => Ok(FormatOutput::Template(String::new())),
+            _ => Err(anyhow!("invalid output format: {}", s)),
+        }
+    }
+}
+
 impl FormatOutput {
+    /// The formats that can be set as the default in the config. `template` is not one of
+    /// them since it needs a template passed with every command.
     pub fn variants() -> Vec<String> {
-        vec!["table".to_string(), "json".to_string(), "yaml".to_string()]
+        vec![
+            "table".to_string(),
+            "json".to_string(),
+            "jsonl".to_string(),
+            "yaml".to_string(),
+            "csv".to_string(),
+            "tsv".to_string(),
+        ]
+    }
+}
+
+/// How errors are printed to standard error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// A message for people, with an icon.
+    Human,
+    /// A JSON object like `{"error":"ObjectNotFound","message":"..."}` for scripts.
+    Json,
+}
+
+impl Default for ErrorFormat {
+    fn default() -> ErrorFormat {
+        ErrorFormat::Human
+    }
+}
+
+impl std::fmt::Display for ErrorFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ErrorFormat::Human => "human",
+            ErrorFormat::Json => "json",
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+impl std::str::FromStr for ErrorFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "human" => Ok(ErrorFormat::Human),
+            "json" => Ok(ErrorFormat::Json),
+            _ => Err(anyhow!("invalid error format: {}", s)),
+        }
+    }
+}
+
+/// How progress of long operations, like waiting for an instance to start, is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressFormat {
+    /// A spinner with a label, when writing to a terminal.
+    Human,
+    /// A JSON object per line on stderr, like `{"event":"waiting","state":"starting"}`,
+    /// for scripts.
+    Json,
+}
+
+impl Default for ProgressFormat {
+    fn default() -> ProgressFormat {
+        ProgressFormat::Human
+    }
+}
+
+impl std::fmt::Display for ProgressFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ProgressFormat::Human => "human",
+            ProgressFormat::Json => "json",
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+impl std::str::FromStr for ProgressFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "human" => Ok(ProgressFormat::Human),
+            "json" => Ok(ProgressFormat::Json),
+            _ => Err(anyhow!("invalid progress format: {}", s)),
+        }
+    }
+}
+
+/// Parse a byte count passed as a flag. Plain integers are bytes. Decimal (K, M, G, T)
+/// and binary (Ki, Mi, Gi, Ti) suffixes are accepted, optionally followed by a B, for
+/// example `512M` or `1GiB`.
+pub fn parse_byte_count(s: &str) -> Result<oxide_api::types::ByteCount> {
+    let input = s.trim();
+    let (number, unit) = input.split_at(input.find(|c: char| !c.is_ascii_digit()).unwrap_or(input.len()));
+
+    let number: u128 = number
+        .parse()
+        .map_err(|_| anyhow!("invalid byte count `{}`: expected a number of bytes", s))?;
+
+    let unit = unit.trim().to_uppercase();
+    let unit = unit.strip_suffix('B').unwrap_or(&unit);
+    let multiplier: u128 = match unit {
+        "" => 1,
+        "K" => 1000,
+        "M" => 1000u128.pow(2),
+        "G" => 1000u128.pow(3),
+        "T" => 1000u128.pow(4),
+        "KI" => 1 << 10,
+        "MI" => 1 << 20,
+        "GI" => 1 << 30,
+        "TI" => 1 << 40,
+        _ => {
+            return Err(anyhow!(
+                "invalid byte count `{}`: supported units are K, M, G, T, Ki, Mi, Gi, and Ti",
+                s
+            ))
+        }
+    };
+
+    let bytes = number
+        .checked_mul(multiplier)
+        .ok_or_else(|| anyhow!("invalid byte count `{}`: too large", s))?;
+
+    oxide_api::types::ByteCount::try_from(bytes).map_err(|_| anyhow!("invalid byte count `{}`: too large", s))
+}
+
+/// Parse a duration like `30m`, `12h`, or `7d`. Plain integers are seconds. The units are
+/// s, m, h, and d.
+pub fn parse_duration(s: &str) -> Result<std::time::Duration> {
+    let input = s.trim();
+    let (number, unit) = input.split_at(input.find(|c: char| !c.is_ascii_digit()).unwrap_or(input.len()));
+
+    let number: u64 = number
+        .parse()
+        .map_err(|_| anyhow!("invalid duration `{}`: expected a number followed by s, m, h, or d", s))?;
+
+    let multiplier: u64 = match unit.trim() {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(anyhow!("invalid duration `{}`: supported units are s, m, h, and d", s)),
+    };
+
+    let secs = number
+        .checked_mul(multiplier)
+        .ok_or_else(|| anyhow!("invalid duration `{}`: too large", s))?;
+
+    Ok(std::time::Duration::from_secs(secs))
+}
+
+/// Parse the `--filter` of a bulk delete. The only filter is `name~<string>`, which matches
+/// the names that contain the string. No filter matches everything.
+pub fn parse_name_filter(s: &str) -> Result<String> {
+    if s.is_empty() {
+        return Ok(String::new());
+    }
+
+    match s.strip_prefix("name~") {
+        Some(name) if !name.is_empty() => Ok(name.to_string()),
+        _ => Err(anyhow!("invalid filter `{}`: expected `name~<string>`", s)),
+    }
+}
+
+/// Apply `--set a.b.c=value` overrides to a request body before it is sent. The value is
+/// parsed as JSON, and taken as a string when it is not valid JSON. Objects along the path
+/// are created if they are missing; array elements are addressed by their index.
+pub fn apply_overrides<T>(body: T, overrides: &[String]) -> Result<T>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    if overrides.is_empty() {
+        return Ok(body);
+    }
+
+    let mut value = serde_json::to_value(body)?;
+    for o in overrides {
+        let (path, v) = o
+            .split_once('=')
+            .ok_or_else(|| anyhow!("invalid override `{}`: expected `key=value`", o))?;
+        if path.is_empty() {
+            return Err(anyhow!("invalid override `{}`: the key is empty", o));
+        }
+
+        let v = serde_json::from_str(v).unwrap_or_else(|_| serde_json::Value::String(v.to_string()));
+
+        let mut target = &mut value;
+        for key in path.split('.') {
+            if target.is_null() {
+                *target = serde_json::Value::Object(Default::default());
+            }
+
+            target = match target {
+                serde_json::Value::Array(items) => {
+                    let index: usize = key
+                        .parse()
+                        .map_err(|_| anyhow!("invalid override `{}`: `{}` is not an array index", o, key))?;
+                    items
+                        .get_mut(index)
+                        .ok_or_else(|| anyhow!("invalid override `{}`: index {} is out of bounds", o, index))?
+                }
+                serde_json::Value::Object(fields) => fields.entry(key).or_insert(serde_json::Value::Null),
+                _ => return Err(anyhow!("invalid override `{}`: `{}` is not an object", o, key)),
+            };
+        }
+        *target = v;
+    }
+
+    serde_json::from_value(value).map_err(|err| anyhow!("invalid overrides: {}", err))
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_byte_count() {
+        assert_eq!(parse_byte_count("1024").unwrap(), 1024);
+        assert_eq!(parse_byte_count("1GiB").unwrap(), 1073741824);
+        assert_eq!(parse_byte_count("1Gi").unwrap(), 1073741824);
+        assert_eq!(parse_byte_count("512M").unwrap(), 512000000);
+        assert_eq!(parse_byte_count("2 KiB").unwrap(), 2048);
+        assert_eq!(parse_byte_count("10kb").unwrap(), 10000);
+
+        assert_eq!(
+            parse_byte_count("1XB").unwrap_err().to_string(),
+            "invalid byte count `1XB`: supported units are K, M, G, T, Ki, Mi, Gi, and Ti"
+        );
+        assert!(parse_byte_count("GiB").is_err());
+        assert!(parse_byte_count("1.5G").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90").unwrap().as_secs(), 90);
+        assert_eq!(parse_duration("30m").unwrap().as_secs(), 30 * 60);
+        assert_eq!(parse_duration("24h").unwrap().as_secs(), 24 * 60 * 60);
+        assert_eq!(parse_duration("7d").unwrap().as_secs(), 7 * 24 * 60 * 60);
+        assert_eq!(parse_duration("0").unwrap().as_secs(), 0);
+
+        assert_eq!(
+            parse_duration("1w").unwrap_err().to_string(),
+            "invalid duration `1w`: supported units are s, m, h, and d"
+        );
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("1.5h").is_err());
+    }
+
+    #[test]
+    fn test_parse_name_filter() {
+        assert_eq!(parse_name_filter("name~prefix-").unwrap(), "prefix-");
+        assert_eq!(parse_name_filter("").unwrap(), "");
+
+        assert_eq!(
+            parse_name_filter("id~1234").unwrap_err().to_string(),
+            "invalid filter `id~1234`: expected `name~<string>`"
+        );
+        assert!(parse_name_filter("name~").is_err());
+    }
+
+    #[test]
+    fn test_apply_overrides() {
+        let body = serde_json::json!({
+            "name": "foo",
+            "size": 1024,
+            "tags": ["a", "b"],
+        });
+
+        assert_eq!(apply_overrides(body.clone(), &[]).unwrap(), body);
+
+        let overrides = vec![
+            "size=2048".to_string(),
+            "name=bar".to_string(),
+            "tags.1=\"c\"".to_string(),
+            "source.snapshot.id=1234".to_string(),
+            "labels={\"env\": \"prod\"}".to_string(),
+        ];
+        assert_eq!(
+            apply_overrides(body.clone(), &overrides).unwrap(),
+            serde_json::json!({
+                "name": "bar",
+                "size": 2048,
+                "tags": ["a", "c"],
+                "source": {"snapshot": {"id": 1234}},
+                "labels": {"env": "prod"},
+            })
+        );
+
+        assert_eq!(
+            apply_overrides(body.clone(), &["size".to_string()])
+                .unwrap_err()
+                .to_string(),
+            "invalid override `size`: expected `key=value`"
+        );
+        assert_eq!(
+            apply_overrides(body.clone(), &["name.first=a".to_string()])
+                .unwrap_err()
+                .to_string(),
+            "invalid override `name.first=a`: `first` is not an object"
+        );
+        assert!(apply_overrides(body, &["tags.5=a".to_string()]).is_err());
     }
 }
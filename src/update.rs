@@ -15,6 +15,16 @@ pub struct ReleaseInfo {
     pub version: String,
     pub url: String,
     pub published_at: chrono::DateTime<chrono::Utc>,
+    /// The files attached to the release. Older state files do not have them.
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+/// ReleaseAsset stores information about a file attached to a release.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
 }
 
 /// StateEntry stores information about a state.
@@ -24,11 +34,30 @@ pub struct StateEntry {
     pub latest_release: ReleaseInfo,
 }
 
+/// How often we check for updates unless `update_check_interval` is set.
+pub const DEFAULT_CHECK_INTERVAL: &str = "24h";
+
+/// Get how often to check for updates from the `update_check_interval` config key.
+/// A missing or invalid value falls back to the default, so the update check never
+/// gets in the way of a command.
+pub fn check_interval(config: &dyn crate::config::Config) -> std::time::Duration {
+    let interval = config.get("", "update_check_interval").unwrap_or_default();
+
+    crate::types::parse_duration(&interval)
+        .or_else(|_| crate::types::parse_duration(DEFAULT_CHECK_INTERVAL))
+        .unwrap_or_default()
+}
+
 /// Check for updates to the cli.
 ///
 /// Returns the latest version of the cli, or none if there is not a new
-/// update or we shouldn't update.
-pub async fn check_for_update(current_version: &str, force: bool) -> Result<Option<ReleaseInfo>> {
+/// update or we shouldn't update. Unless `force` is set, we check at most once
+/// per `interval`.
+pub async fn check_for_update(
+    current_version: &str,
+    force: bool,
+    interval: std::time::Duration,
+) -> Result<Option<ReleaseInfo>> {
     if !should_check_for_update() && !force {
         return Ok(None);
     }
@@ -41,9 +70,8 @@ pub async fn check_for_update(current_version: &str, force: bool) -> Result<Opti
 
         if !force {
             let duration_since_last_check = chrono::Utc::now() - state.checked_for_update_at;
-            // TODO: After we make a mjor release of v1 we should bump this to like 6/12 hours.
-            if duration_since_last_check < chrono::Duration::hours(1) {
-                // If we've checked for updates in the last 1 hour, don't check again.
+            if duration_since_last_check < chrono::Duration::from_std(interval)? {
+                // If we've checked for updates recently, don't check again.
                 return Ok(None);
             }
         }
@@ -62,9 +90,42 @@ pub async fn check_for_update(current_version: &str, force: bool) -> Result<Opti
     Ok(None)
 }
 
+/// The version of the API in the spec.json the commands were generated from.
+pub const SPEC_VERSION: &str = env!("OXIDE_SPEC_VERSION");
+
+/// The response header the server sends the version of its API in.
+pub const API_VERSION_HEADER: &str = "api-version";
+
+/// Get the version of the API the server runs, from the `api-version` header of a request
+/// to it. Returns none if the server does not send the header or the request fails, the
+/// check should never get in the way of a command.
+pub async fn get_api_version(client: oxide_api::Client) -> Option<String> {
+    let resp = client
+        .request_raw(http::Method::GET, "/session/me", None)
+        .await
+        .ok()?
+        .timeout(std::time::Duration::from_secs(5))
+        .send()
+        .await
+        .ok()?;
+
+    let version = resp.headers().get(API_VERSION_HEADER)?.to_str().ok()?;
+
+    Some(version.to_string())
+}
+
+/// Returns the version of the API the server runs if it is not the one the commands
+/// were generated for.
+pub fn api_version_mismatch(server_version: Option<String>) -> Option<String> {
+    server_version.filter(|v| !v.is_empty() && v.trim_start_matches('v') != SPEC_VERSION.trim_start_matches('v'))
+}
+
 /// If we should check for an update to the cli.
-fn should_check_for_update() -> bool {
-    !is_ci() && atty::is(atty::Stream::Stdout) && atty::is(atty::Stream::Stderr)
+pub fn should_check_for_update() -> bool {
+    get_env_var("OXIDE_NO_UPDATE_NOTIFIER").is_empty()
+        && !is_ci()
+        && atty::is(atty::Stream::Stdout)
+        && atty::is(atty::Stream::Stderr)
 }
 
 /// If we are running in a CI environment.
@@ -76,14 +137,25 @@ fn is_ci() -> bool {
 
 /// Get the information about the latest version of the cli.
 pub async fn get_latest_release_info() -> Result<ReleaseInfo> {
+    get_release_info(None).await
+}
+
+/// Get the information about a version of the cli, or the latest version if none is given.
+pub async fn get_release_info(version: Option<&str>) -> Result<ReleaseInfo> {
     // If the user has a GITHUB_TOKEN environment variable, use it to get the latest release.
     // This allows us to test this while the repo is still private.
     // We might want to remove this in the future.
     let github_token = crate::config_file::get_env_var("GITHUB_TOKEN");
 
-    let url = "https://api.github.com/repos/oxidecomputer/cli/releases/latest";
+    let url = match version {
+        Some(version) => format!(
+            "https://api.github.com/repos/oxidecomputer/cli/releases/tags/{}",
+            with_v_prefix(version)
+        ),
+        None => "https://api.github.com/repos/oxidecomputer/cli/releases/latest".to_string(),
+    };
 
-    let mut req = reqwest::Client::new().get(url);
+    let mut req = reqwest::Client::new().get(&url);
 
     // Set the user agent.
     req = req.header("User-Agent", format!("oxide/{}", clap::crate_version!()));
@@ -93,6 +165,12 @@ pub async fn get_latest_release_info() -> Result<ReleaseInfo> {
     }
 
     let resp = req.send().await?;
+    if let (Some(version), reqwest::StatusCode::NOT_FOUND) = (version, resp.status()) {
+        anyhow::bail!(
+            "There is no release of `oxide` with version {}.",
+            with_v_prefix(version)
+        );
+    }
     let text = resp.text().await?;
 
     let latest_release: ReleaseInfo = match serde_json::from_str(&text) {
@@ -171,65 +249,105 @@ pub fn is_under_homebrew() -> Result<bool> {
     }
 }
 
-/// Takes a version string and returns the URL to download the latest release.
-fn get_exe_download_url(version: &str) -> String {
-    // Make sure the version starts with a v.
-    let version = if !version.starts_with('v') {
+/// Makes sure a version string starts with a v, like the release tags do.
+fn with_v_prefix(version: &str) -> String {
+    if !version.starts_with('v') {
         format!("v{}", version)
     } else {
         version.to_string()
-    };
+    }
+}
 
+/// Takes a version string and returns the URL to download the latest release.
+fn get_exe_download_url(version: &str) -> String {
     format!(
         "https://dl.oxide.computer/releases/cli/{}/oxide-{}",
-        version,
+        with_v_prefix(version),
         crate::built_info::TARGET
     )
 }
 
-/// Takes a version string and downloads the latest binary to a temp file.
-/// This also checks the SHA256 hash of the file.
-pub async fn download_binary_to_temp_file(version: &str) -> Result<String> {
-    let temp_dir = std::env::temp_dir();
-    let temp_file = temp_dir.join("oxide");
+/// Returns the URL to download the binary for the current OS and architecture from a release.
+/// We prefer the file attached to the GitHub release and fall back to dl.oxide.computer for
+/// releases that do not have one.
+fn get_release_download_url(release: &ReleaseInfo) -> String {
+    let name = format!("oxide-{}", crate::built_info::TARGET);
 
-    let url = get_exe_download_url(version);
+    match release.assets.iter().find(|asset| asset.name == name) {
+        Some(asset) => asset.browser_download_url.to_string(),
+        None => get_exe_download_url(&release.version),
+    }
+}
+
+/// Downloads the binary of a release to a temp file in `dir`.
+/// This also checks the SHA256 hash of the file.
+///
+/// The temp file should be in the same directory as the binary it replaces, so it can be
+/// renamed into place atomically.
+pub async fn download_binary_to_temp_file(
+    release: &ReleaseInfo,
+    dir: &std::path::Path,
+) -> Result<tempfile::NamedTempFile> {
+    let url = get_release_download_url(release);
 
     // Get the contents of the binary.
-    let resp = reqwest::get(&url).await?;
-    let bin_body = resp.bytes().await?;
+    let bin_body = download(&url).await?;
 
     // Get the contents of the sha256sum.
-    let resp = reqwest::get(&format!("{}.sha256", url)).await?;
-    let sha256_body = resp.text().await?;
-    let sha256_parts = sha256_body.split(' ').collect::<Vec<&str>>();
-    let sha256_hash = sha256_parts[0];
+    let sha256_body = String::from_utf8(download(&format!("{}.sha256", url)).await?)?;
+    let sha256_hash = sha256_body.split_whitespace().next().unwrap_or_default();
 
     // Verify the sha256 hash of the binary.
-    let bin_hash = sha256_digest(bin_body.as_ref())?;
+    let bin_hash = sha256_digest(bin_body.as_slice())?;
     if bin_hash != sha256_hash {
         anyhow::bail!("SHA256 hash mismatch: local ({}) != remote ({})", bin_hash, sha256_hash);
     }
 
     // Write the body to the file.
-    let mut f = std::fs::OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .create(true)
-        .open(&temp_file)?;
+    let mut f = tempfile::Builder::new().prefix(".oxide").tempfile_in(dir)?;
     f.write_all(&bin_body)?;
     f.flush()?;
 
-    let temp_file_path = temp_file
-        .to_str()
-        .ok_or_else(|| anyhow::anyhow!("failed to convert temp file path to string"))?;
-
     // Set the file permissions to be correct, executable, write (so we can update).
-    // TODO: make this also work on windows.
     #[cfg(target_family = "unix")]
-    std::fs::set_permissions(&temp_file_path, std::fs::Permissions::from_mode(0o755))?;
+    std::fs::set_permissions(f.path(), std::fs::Permissions::from_mode(0o755))?;
+
+    Ok(f)
+}
+
+/// Downloads a file, failing on any response that is not a success.
+async fn download(url: &str) -> Result<Vec<u8>> {
+    let resp = reqwest::Client::new()
+        .get(url)
+        .header("User-Agent", format!("oxide/{}", clap::crate_version!()))
+        .send()
+        .await?;
+
+    if !resp.status().is_success() {
+        anyhow::bail!("Downloading {} failed: {}", url, resp.status());
+    }
+
+    Ok(resp.bytes().await?.to_vec())
+}
+
+/// Replaces the binary at `path` with the downloaded one.
+///
+/// On Windows the running binary can not be overwritten, but it can be renamed, so we move it
+/// out of the way first.
+pub fn replace_binary(new_binary: tempfile::NamedTempFile, path: &std::path::Path) -> Result<()> {
+    #[cfg(target_family = "windows")]
+    {
+        let old = path.with_extension("old.exe");
+        // A leftover from the previous update.
+        let _ = std::fs::remove_file(&old);
+        std::fs::rename(path, &old)?;
+    }
 
-    Ok(temp_file_path.to_string())
+    new_binary
+        .persist(path)
+        .map_err(|err| anyhow!("Failed to replace {}: {}", path.display(), err.error))?;
+
+    Ok(())
 }
 
 /// Calculates the SHA256 hash of a reader.
@@ -265,11 +383,24 @@ mod test {
             return;
         }
 
-        let file = super::download_binary_to_temp_file("v0.1.0").await.unwrap();
+        let release = super::get_release_info(Some("v0.1.0")).await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let file = super::download_binary_to_temp_file(&release, dir.path()).await.unwrap();
+
+        assert_eq!(file.path().parent().unwrap(), dir.path());
+    }
+
+    #[test]
+    fn test_api_version_mismatch() {
+        let spec_version = super::SPEC_VERSION.to_string();
+        assert_eq!(super::api_version_mismatch(Some(spec_version.clone())), None);
+        assert_eq!(super::api_version_mismatch(Some(format!("v{}", spec_version))), None);
+        assert_eq!(super::api_version_mismatch(Some("".to_string())), None);
+        assert_eq!(super::api_version_mismatch(None), None);
 
         assert_eq!(
-            file,
-            format!("{}/oxide", std::env::temp_dir().to_str().unwrap().trim_end_matches('/'))
+            super::api_version_mismatch(Some("999.0.0".to_string())),
+            Some("999.0.0".to_string())
         );
     }
 
@@ -294,10 +425,41 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_get_release_download_url() {
+        let mut release = super::ReleaseInfo {
+            version: "v0.1.0".to_string(),
+            url: "".to_string(),
+            published_at: chrono::Utc::now(),
+            assets: vec![],
+        };
+        assert_eq!(
+            super::get_release_download_url(&release),
+            format!(
+                "https://dl.oxide.computer/releases/cli/v0.1.0/oxide-{}",
+                crate::built_info::TARGET
+            )
+        );
+
+        release.assets = vec![
+            super::ReleaseAsset {
+                name: "oxide-some-other-target".to_string(),
+                browser_download_url: "https://example.com/other".to_string(),
+            },
+            super::ReleaseAsset {
+                name: format!("oxide-{}", crate::built_info::TARGET),
+                browser_download_url: "https://example.com/ours".to_string(),
+            },
+        ];
+        assert_eq!(super::get_release_download_url(&release), "https://example.com/ours");
+    }
+
     #[tokio::test]
     #[serial_test::serial]
     async fn test_check_for_update() {
-        let result = super::check_for_update("0.0.1", true).await.unwrap();
+        let result = super::check_for_update("0.0.1", true, std::time::Duration::ZERO)
+            .await
+            .unwrap();
         assert_eq!(result.is_some(), true);
 
         let latest_release = result.unwrap();